@@ -1242,6 +1242,7 @@
         fragment = {
           module,
           entryPoint: descriptor.fragment.entryPoint,
+          constants: descriptor.fragment.constants,
           targets: descriptor.fragment.targets,
         };
       }
@@ -1253,6 +1254,7 @@
         vertex: {
           module,
           entryPoint: descriptor.vertex.entryPoint,
+          constants: descriptor.vertex.constants,
           buffers: descriptor.vertex.buffers,
         },
         primitive: descriptor.primitive,
@@ -8,6 +8,7 @@ use deno_core::ResourceId;
 use serde::Deserialize;
 use serde::Serialize;
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 use super::error::WebGpuError;
 use super::error::WebGpuResult;
@@ -40,7 +41,8 @@ impl Resource for WebGpuRenderPipeline {
 pub struct GpuProgrammableStage {
     module: ResourceId,
     entry_point: String,
-    // constants: HashMap<String, GPUPipelineConstantValue>
+    #[serde(default)]
+    constants: HashMap<String, f64>,
 }
 
 #[op]
@@ -74,8 +76,9 @@ pub fn op_webgpu_create_compute_pipeline(
         stage: wgpu_core::pipeline::ProgrammableStageDescriptor {
             module: compute_shader_module_resource.0,
             entry_point: Cow::from(compute.entry_point),
-            // TODO(lucacasonato): support args.compute.constants
+            constants: Cow::Owned(compute.constants.into_iter().collect()),
         },
+        cache: None,
     };
     let implicit_pipelines = match layout {
         Some(_) => None,
@@ -236,6 +239,8 @@ impl<'a> From<GpuVertexBufferLayout> for wgpu_core::pipeline::VertexBufferLayout
 struct GpuVertexState {
     module: ResourceId,
     entry_point: String,
+    #[serde(default)]
+    constants: HashMap<String, f64>,
     buffers: Vec<Option<GpuVertexBufferLayout>>,
 }
 
@@ -263,7 +268,8 @@ struct GpuFragmentState {
     targets: Vec<wgpu_types::ColorTargetState>,
     module: u32,
     entry_point: String,
-    // TODO(lucacasonato): constants
+    #[serde(default)]
+    constants: HashMap<String, f64>,
 }
 
 #[derive(Deserialize)]
@@ -311,6 +317,7 @@ pub fn op_webgpu_create_render_pipeline(
             stage: wgpu_core::pipeline::ProgrammableStageDescriptor {
                 module: fragment_shader_module_resource.0,
                 entry_point: Cow::from(fragment.entry_point),
+                constants: Cow::Owned(fragment.constants.into_iter().collect()),
             },
             targets: Cow::from(fragment.targets),
         })
@@ -333,6 +340,7 @@ pub fn op_webgpu_create_render_pipeline(
             stage: wgpu_core::pipeline::ProgrammableStageDescriptor {
                 module: vertex_shader_module_resource.0,
                 entry_point: Cow::Owned(args.vertex.entry_point),
+                constants: Cow::Owned(args.vertex.constants.into_iter().collect()),
             },
             buffers: Cow::Owned(vertex_buffers),
         },
@@ -341,6 +349,7 @@ pub fn op_webgpu_create_render_pipeline(
         multisample: args.multisample,
         fragment,
         multiview: None,
+        cache: None,
     };
 
     let implicit_pipelines = match args.layout {
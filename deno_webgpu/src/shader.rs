@@ -35,6 +35,7 @@ pub fn op_webgpu_create_shader_module(
     let descriptor = wgpu_core::pipeline::ShaderModuleDescriptor {
         label: label.map(Cow::from),
         shader_bound_checks: wgpu_types::ShaderBoundChecks::default(),
+        source_hash: None,
     };
 
     gfx_put!(device => instance.device_create_shader_module(
@@ -49,7 +49,11 @@ fn main() {
         global.instance_create_surface(&window, wgc::id::TypedId::zip(0, 1, wgt::Backend::Empty));
 
     let device = match actions.pop() {
-        Some(trace::Action::Init { desc, backend }) => {
+        Some(trace::Action::Init {
+            desc,
+            queues,
+            backend,
+        }) => {
             log::info!("Initializing the device for backend: {:?}", backend);
             let adapter = global
                 .request_adapter(
@@ -71,9 +75,10 @@ fn main() {
             let info = gfx_select!(adapter => global.adapter_get_info(adapter)).unwrap();
             log::info!("Picked '{}'", info.name);
             let id = wgc::id::TypedId::zip(1, 0, backend);
-            let (_, error) = gfx_select!(adapter => global.adapter_request_device(
+            let (_, error) = gfx_select!(adapter => global.adapter_request_device_with_queues(
                 adapter,
                 &desc,
+                &queues,
                 None,
                 id
             ));
@@ -95,7 +100,7 @@ fn main() {
         }
 
         gfx_select!(device => global.device_stop_capture(device));
-        gfx_select!(device => global.device_poll(device, true)).unwrap();
+        gfx_select!(device => global.device_poll(device, wgt::Maintain::Wait)).unwrap();
     }
     #[cfg(feature = "winit")]
     {
@@ -181,7 +186,7 @@ fn main() {
                 },
                 Event::LoopDestroyed => {
                     log::info!("Closing");
-                    gfx_select!(device => global.device_poll(device, true)).unwrap();
+                    gfx_select!(device => global.device_poll(device, wgt::Maintain::Wait)).unwrap();
                 }
                 _ => {}
             }
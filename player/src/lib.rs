@@ -117,20 +117,31 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
                 trace::Command::InsertDebugMarker(marker) => self
                     .command_encoder_insert_debug_marker::<A>(encoder, &marker)
                     .unwrap(),
-                trace::Command::RunComputePass { base } => {
-                    self.command_encoder_run_compute_pass_impl::<A>(encoder, base.as_ref())
-                        .unwrap();
+                trace::Command::RunComputePass {
+                    base,
+                    timestamp_writes,
+                } => {
+                    self.command_encoder_run_compute_pass_impl::<A>(
+                        encoder,
+                        base.as_ref(),
+                        timestamp_writes.as_ref(),
+                    )
+                    .unwrap();
                 }
                 trace::Command::RunRenderPass {
                     base,
                     target_colors,
                     target_depth_stencil,
+                    timestamp_writes,
+                    occlusion_query_set,
                 } => {
                     self.command_encoder_run_render_pass_impl::<A>(
                         encoder,
                         base.as_ref(),
                         &target_colors,
                         target_depth_stencil.as_ref(),
+                        timestamp_writes.as_ref(),
+                        occlusion_query_set,
                     )
                     .unwrap();
                 }
@@ -249,14 +260,39 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
             Action::DestroyBindGroup(id) => {
                 self.bind_group_drop::<A>(id);
             }
+            Action::CreateDescriptorTable { id, desc } => {
+                self.device_maintain_ids::<A>(device).unwrap();
+                let (_, error) = self.device_create_descriptor_table::<A>(device, &desc, id);
+                if let Some(e) = error {
+                    panic!("{:?}", e);
+                }
+            }
+            Action::UpdateDescriptorTable {
+                id,
+                first_slot,
+                views,
+            } => {
+                // the table may still be in use by a replayed submission
+                self.device_poll::<A>(device, wgt::Maintain::Wait).unwrap();
+                self.descriptor_table_update_slots::<A>(id, first_slot, &views)
+                    .unwrap();
+            }
+            Action::ClearDescriptorTableSlots { id, slots } => {
+                self.device_poll::<A>(device, wgt::Maintain::Wait).unwrap();
+                self.descriptor_table_clear_slots::<A>(id, slots).unwrap();
+            }
             Action::CreateShaderModule { id, desc, data } => {
                 log::info!("Creating shader from {}", data);
-                let code = fs::read_to_string(dir.join(&data)).unwrap();
                 let source = if data.ends_with(".wgsl") {
+                    let code = fs::read_to_string(dir.join(&data)).unwrap();
                     wgc::pipeline::ShaderModuleSource::Wgsl(Cow::Owned(code))
                 } else if data.ends_with(".ron") {
+                    let code = fs::read_to_string(dir.join(&data)).unwrap();
                     let module = ron::de::from_str(&code).unwrap();
                     wgc::pipeline::ShaderModuleSource::Naga(module)
+                } else if data.ends_with(".ir") {
+                    let bytes = fs::read(dir.join(&data)).unwrap();
+                    wgc::pipeline::ShaderModuleSource::SerializedIr(Cow::Owned(bytes))
                 } else {
                     panic!("Unknown shader {}", data);
                 };
@@ -337,6 +373,16 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
             Action::DestroyQuerySet(id) => {
                 self.query_set_drop::<A>(id);
             }
+            Action::CreatePipelineCache { id, desc } => {
+                self.device_maintain_ids::<A>(device).unwrap();
+                let (_, error) = self.device_create_pipeline_cache::<A>(device, &desc, id);
+                if let Some(e) = error {
+                    panic!("{:?}", e);
+                }
+            }
+            Action::DestroyPipelineCache(id) => {
+                self.pipeline_cache_drop::<A>(id);
+            }
             Action::WriteBuffer {
                 id,
                 data,
@@ -379,6 +425,23 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
                 let cmdbuf = self.encode_commands::<A>(encoder, commands);
                 self.queue_submit::<A>(device, &[cmdbuf]).unwrap();
             }
+            Action::SubmitToQueue {
+                index: _,
+                queue_index,
+                commands,
+            } => {
+                let (encoder, error) = self.device_create_command_encoder::<A>(
+                    device,
+                    &wgt::CommandEncoderDescriptor { label: None },
+                    comb_manager.alloc(device.backend()),
+                );
+                if let Some(e) = error {
+                    panic!("{:?}", e);
+                }
+                let cmdbuf = self.encode_commands::<A>(encoder, commands);
+                self.queue_submit_indexed::<A>(device, queue_index, &[cmdbuf])
+                    .unwrap();
+            }
         }
     }
 }
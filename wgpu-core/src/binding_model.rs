@@ -2,7 +2,10 @@ use crate::{
     device::{DeviceError, MissingDownlevelFlags, MissingFeatures, SHADER_STAGE_COUNT},
     error::{ErrorFormatter, PrettyError},
     hub::Resource,
-    id::{BindGroupLayoutId, BufferId, DeviceId, SamplerId, TextureViewId, Valid},
+    id::{
+        BindGroupId, BindGroupLayoutId, BufferId, DeviceId, SamplerId, TextureId, TextureViewId,
+        Valid,
+    },
     init_tracker::{BufferInitTrackerAction, TextureInitTrackerAction},
     track::{TrackerSet, UsageConflict, DUMMY_SELECTOR},
     validation::{MissingBufferUsageError, MissingTextureUsageError},
@@ -19,6 +22,7 @@ use serde::Serialize;
 use std::{
     borrow::{Borrow, Cow},
     ops::Range,
+    sync::{Arc, Weak},
 };
 
 use thiserror::Error;
@@ -193,6 +197,49 @@ impl PrettyError for CreateBindGroupError {
     }
 }
 
+#[derive(Clone, Debug, Error)]
+pub enum CreateDescriptorTableError {
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+    #[error("bind group layout is invalid")]
+    InvalidLayout,
+    #[error(transparent)]
+    MissingFeatures(#[from] MissingFeatures),
+    #[error("descriptor table layouts must have exactly one entry, which is an array of textures or storage textures")]
+    InvalidTableLayout,
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum UpdateDescriptorTableError {
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+    #[error("descriptor table {0:?} is invalid")]
+    InvalidTable(BindGroupId),
+    #[error("bind group {0:?} is not a descriptor table")]
+    NotATable(BindGroupId),
+    #[error("slots {start}..{end} are out of bounds of a table with {count} slots")]
+    SlotsOutOfBounds { start: u32, end: u32, count: u32 },
+    #[error("descriptor table is in use by a submission that has not completed yet")]
+    InUse,
+    #[error(transparent)]
+    Binding(#[from] CreateBindGroupError),
+}
+
+impl PrettyError for UpdateDescriptorTableError {
+    fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
+        if let Self::Binding(ref error) = *self {
+            return error.fmt_pretty(fmt);
+        }
+        fmt.error(self);
+        match *self {
+            Self::InvalidTable(id) | Self::NotATable(id) => {
+                fmt.bind_group_label(&id);
+            }
+            _ => {}
+        };
+    }
+}
+
 #[derive(Clone, Debug, Error)]
 pub enum BindingZone {
     #[error("stage {0:?}")]
@@ -408,6 +455,19 @@ pub struct BindGroupDescriptor<'a> {
     pub entries: Cow<'a, [BindGroupEntry<'a>]>,
 }
 
+/// Describes a descriptor table: a bind group whose single texture array
+/// binding starts out empty and is filled in slot by slot.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
+pub struct DescriptorTableDescriptor<'a> {
+    /// Debug label of the table. This will show up in graphics debuggers for easy identification.
+    pub label: Label<'a>,
+    /// The [`BindGroupLayout`] of the table. It must have a single entry
+    /// with a `count`, binding textures or storage textures.
+    pub layout: BindGroupLayoutId,
+}
+
 /// Describes a [`BindGroupLayout`].
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "trace", derive(serde::Serialize))]
@@ -421,6 +481,124 @@ pub struct BindGroupLayoutDescriptor<'a> {
 
 pub(crate) type BindEntryMap = FastHashMap<u32, wgt::BindGroupLayoutEntry>;
 
+/// Get the entries of a layout sorted by binding, as they are interned.
+pub(crate) fn sorted_entries(entry_map: &BindEntryMap) -> Vec<wgt::BindGroupLayoutEntry> {
+    let mut entries = entry_map.values().cloned().collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.binding);
+    entries
+}
+
+/// Identifies the contents of a bind group layout within its device.
+///
+/// Equivalent bind group layouts have the same key, and bind groups are
+/// compatible with pipeline layouts by key rather than by ID.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct LayoutKey(u64);
+
+struct PooledBindGroupLayout<A: hal::Api> {
+    raw: Weak<A::BindGroupLayout>,
+    key: LayoutKey,
+    /// One of the registered layouts with these entries, to be returned by
+    /// deduplication instead of registering a new one.
+    id: Option<Valid<BindGroupLayoutId>>,
+}
+
+/// Hal layouts of a device, interned by their contents.
+///
+/// Equivalent layouts share a single hal object. The pool only holds weak
+/// references: the hal object is destroyed along with the last layout using it.
+pub(crate) struct LayoutPool<A: hal::Api> {
+    next_key: u64,
+    bind_group_layouts: FastHashMap<Vec<wgt::BindGroupLayoutEntry>, PooledBindGroupLayout<A>>,
+    pipeline_layouts:
+        FastHashMap<(Vec<LayoutKey>, Vec<wgt::PushConstantRange>), Weak<A::PipelineLayout>>,
+}
+
+impl<A: hal::Api> LayoutPool<A> {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_key: 0,
+            bind_group_layouts: FastHashMap::default(),
+            pipeline_layouts: FastHashMap::default(),
+        }
+    }
+
+    /// Get the hal bind group layout with the given entries, sorted by binding,
+    /// creating it with `create` if there is none alive.
+    pub(crate) fn bind_group_layout<E>(
+        &mut self,
+        entries: &[wgt::BindGroupLayoutEntry],
+        create: impl FnOnce() -> Result<A::BindGroupLayout, E>,
+    ) -> Result<(Arc<A::BindGroupLayout>, LayoutKey), E> {
+        if let Some(pooled) = self.bind_group_layouts.get(entries) {
+            if let Some(raw) = pooled.raw.upgrade() {
+                return Ok((raw, pooled.key));
+            }
+        }
+
+        let raw = Arc::new(create()?);
+        let key = LayoutKey(self.next_key);
+        self.next_key += 1;
+        self.bind_group_layouts
+            .retain(|_, pooled| pooled.raw.strong_count() != 0);
+        self.bind_group_layouts.insert(
+            entries.to_vec(),
+            PooledBindGroupLayout {
+                raw: Arc::downgrade(&raw),
+                key,
+                id: None,
+            },
+        );
+        Ok((raw, key))
+    }
+
+    /// Get the registered bind group layout with the given entries, if any.
+    pub(crate) fn bind_group_layout_id(
+        &self,
+        entries: &[wgt::BindGroupLayoutEntry],
+    ) -> Option<(Valid<BindGroupLayoutId>, LayoutKey)> {
+        let pooled = self.bind_group_layouts.get(entries)?;
+        Some((pooled.id?, pooled.key))
+    }
+
+    /// Record `id` as a registered bind group layout to deduplicate against.
+    pub(crate) fn set_bind_group_layout_id(
+        &mut self,
+        entries: &[wgt::BindGroupLayoutEntry],
+        key: LayoutKey,
+        id: Valid<BindGroupLayoutId>,
+    ) {
+        if let Some(pooled) = self.bind_group_layouts.get_mut(entries) {
+            if pooled.key == key {
+                pooled.id = Some(id);
+            }
+        }
+    }
+
+    /// Get the hal pipeline layout with the given bind group layouts and push
+    /// constant ranges, creating it with `create` if there is none alive.
+    pub(crate) fn pipeline_layout<E>(
+        &mut self,
+        bind_group_layout_keys: &[LayoutKey],
+        push_constant_ranges: &[wgt::PushConstantRange],
+        create: impl FnOnce() -> Result<A::PipelineLayout, E>,
+    ) -> Result<Arc<A::PipelineLayout>, E> {
+        let key = (
+            bind_group_layout_keys.to_vec(),
+            push_constant_ranges.to_vec(),
+        );
+        if let Some(raw) = self.pipeline_layouts.get(&key).and_then(Weak::upgrade) {
+            return Ok(raw);
+        }
+
+        let raw = Arc::new(create()?);
+        self.pipeline_layouts
+            .retain(|_, pooled| pooled.strong_count() != 0);
+        self.pipeline_layouts.insert(key, Arc::downgrade(&raw));
+        Ok(raw)
+    }
+}
+
 /// Bind group layout.
 ///
 /// The lifetime of BGLs is a bit special. They are only referenced on CPU
@@ -431,7 +609,9 @@ pub(crate) type BindEntryMap = FastHashMap<u32, wgt::BindGroupLayoutEntry>;
 ///  - pipelines with implicit layouts
 #[derive(Debug)]
 pub struct BindGroupLayout<A: hal::Api> {
-    pub(crate) raw: A::BindGroupLayout,
+    /// Shared with the equivalent layouts of the device.
+    pub(crate) raw: Arc<A::BindGroupLayout>,
+    pub(crate) key: LayoutKey,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) multi_ref_count: MultiRefCount,
     pub(crate) entries: BindEntryMap,
@@ -525,6 +705,19 @@ pub enum PushConstantUploadError {
     },
     #[error("provided push constant offset {0} does not respect `PUSH_CONSTANT_ALIGNMENT`")]
     Unaligned(u32),
+    #[error("provided push constant with indices {offset}..{end_offset} exceeds the `max_push_constant_size` limit {limit}")]
+    ExceedsLimit {
+        offset: u32,
+        end_offset: u32,
+        limit: u32,
+    },
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+#[error("push constant at offset {offset} is used by stage(s) {stages:?} of the current pipeline layout, but has not been set")]
+pub struct MissingPushConstants {
+    pub stages: wgt::ShaderStages,
+    pub offset: u32,
 }
 
 /// Describes a pipeline layout.
@@ -549,10 +742,12 @@ pub struct PipelineLayoutDescriptor<'a> {
 
 #[derive(Debug)]
 pub struct PipelineLayout<A: hal::Api> {
-    pub(crate) raw: A::PipelineLayout,
+    /// Shared with the equivalent layouts of the device.
+    pub(crate) raw: Arc<A::PipelineLayout>,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) life_guard: LifeGuard,
     pub(crate) bind_group_layout_ids: ArrayVec<Valid<BindGroupLayoutId>, { hal::MAX_BIND_GROUPS }>,
+    pub(crate) bind_group_layout_keys: ArrayVec<LayoutKey, { hal::MAX_BIND_GROUPS }>,
     pub(crate) push_constant_ranges: ArrayVec<wgt::PushConstantRange, { SHADER_STAGE_COUNT }>,
 }
 
@@ -714,6 +909,7 @@ pub struct BindGroup<A: hal::Api> {
     pub(crate) raw: A::BindGroup,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) layout_id: Valid<BindGroupLayoutId>,
+    pub(crate) layout_key: LayoutKey,
     pub(crate) life_guard: LifeGuard,
     pub(crate) used: TrackerSet,
     pub(crate) used_buffer_ranges: Vec<BufferInitTrackerAction>,
@@ -722,9 +918,47 @@ pub struct BindGroup<A: hal::Api> {
     /// Actual binding sizes for buffers that don't have `min_binding_size`
     /// specified in BGL. Listed in the order of iteration of `BGL.entries`.
     pub(crate) late_buffer_binding_sizes: Vec<wgt::BufferSize>,
+    /// Slot contents, if this group was created as a descriptor table.
+    pub(crate) table: Option<DescriptorTable>,
+}
+
+/// The mutable part of a descriptor table.
+///
+/// The usages of the table are tracked per distinct view, so that updating
+/// slots only costs as much as the slots and views involved.
+#[derive(Debug)]
+pub(crate) struct DescriptorTable {
+    /// The binding holding the texture array.
+    pub(crate) binding: u32,
+    pub(crate) slots: Vec<Option<Valid<TextureViewId>>>,
+    /// Distinct views in the slots.
+    pub(crate) views: FastHashMap<Valid<TextureViewId>, TableView>,
+    /// Views of `views`, in the order of `BindGroup::used_texture_ranges`.
+    pub(crate) ordered_views: Vec<Valid<TextureViewId>>,
+    /// Distinct views in the slots, by texture.
+    pub(crate) texture_views: FastHashMap<Valid<TextureId>, Vec<Valid<TextureViewId>>>,
+    /// Bumped on every slot update. Command buffers record the version
+    /// they were encoded against, and submitting one that saw an older
+    /// version is an error.
+    pub(crate) version: u64,
+}
+
+/// A view bound to slots of a [`DescriptorTable`].
+#[derive(Debug)]
+pub(crate) struct TableView {
+    pub(crate) slot_count: u32,
+    /// Index of the view in `DescriptorTable::ordered_views`.
+    pub(crate) index: usize,
+    pub(crate) usage: hal::TextureUses,
 }
 
 impl<A: hal::Api> BindGroup<A> {
+    /// Version of the group contents. Always zero for regular bind groups,
+    /// which are immutable.
+    pub(crate) fn version(&self) -> u64 {
+        self.table.as_ref().map_or(0, |table| table.version)
+    }
+
     pub(crate) fn validate_dynamic_bindings(
         &self,
         offsets: &[wgt::DynamicOffset],
@@ -1,8 +1,11 @@
 use crate::{
-    binding_model::{BindGroup, LateMinBufferBindingSizeMismatch, PipelineLayout},
+    binding_model::{
+        BindGroup, LateMinBufferBindingSizeMismatch, LayoutKey, MissingPushConstants,
+        PipelineLayout,
+    },
     device::SHADER_STAGE_COUNT,
     hub::{HalApi, Storage},
-    id::{BindGroupId, BindGroupLayoutId, PipelineLayoutId, Valid},
+    id::{BindGroupId, PipelineLayoutId, Valid},
     pipeline::LateSizedBufferGroup,
     Stored,
 };
@@ -160,7 +163,8 @@ impl EntryPayload {
 #[derive(Debug)]
 pub(super) struct Binder {
     pub(super) pipeline_layout_id: Option<Valid<PipelineLayoutId>>, //TODO: strongly `Stored`
-    manager: compat::Manager<Valid<BindGroupLayoutId>>,
+    /// Compatibility is checked by bind group layout contents rather than IDs.
+    manager: compat::Manager<LayoutKey>,
     payloads: [EntryPayload; hal::MAX_BIND_GROUPS],
 }
 
@@ -190,7 +194,9 @@ impl Binder {
         let old_id_opt = self.pipeline_layout_id.replace(new_id);
         let new = &guard[new_id];
 
-        let mut bind_range = self.manager.update_expectations(&new.bind_group_layout_ids);
+        let mut bind_range = self
+            .manager
+            .update_expectations(&new.bind_group_layout_keys);
 
         // Update the buffer binding sizes that are required by shaders.
         for (payload, late_group) in self.payloads.iter_mut().zip(late_sized_buffer_groups) {
@@ -263,7 +269,7 @@ impl Binder {
             }
         }
 
-        let bind_range = self.manager.assign(index, bind_group.layout_id);
+        let bind_range = self.manager.assign(index, bind_group.layout_key);
         &self.payloads[bind_range]
     }
 
@@ -352,3 +358,88 @@ pub fn compute_nonoverlapping_ranges(
 
     output_ranges
 }
+
+/// Shadow copy of the push constant memory of a pass.
+///
+/// Values are kept across pipeline changes, and the ranges of a newly bound
+/// pipeline layout are re-applied right before the next draw or dispatch.
+#[derive(Debug, Default)]
+pub(super) struct PushConstantTracker {
+    /// Last value written to each push constant word.
+    data: Vec<u32>,
+    /// Shader stages each push constant word has been written for.
+    defined: Vec<wgt::ShaderStages>,
+    /// Non-overlapping ranges of the current pipeline layout,
+    /// if they haven't been re-applied yet.
+    pending: Option<ArrayVec<wgt::PushConstantRange, { SHADER_STAGE_COUNT * 2 }>>,
+}
+
+impl PushConstantTracker {
+    /// Record `values` written at `offset` for `stages`.
+    ///
+    /// Returns `true` if the values need to be written to the encoder right away,
+    /// or `false` if they will be picked up by the next `flush`.
+    pub(super) fn write(&mut self, stages: wgt::ShaderStages, offset: u32, values: &[u32]) -> bool {
+        let start = (offset / wgt::PUSH_CONSTANT_ALIGNMENT) as usize;
+        let end = start + values.len();
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+            self.defined.resize(end, wgt::ShaderStages::NONE);
+        }
+        self.data[start..end].copy_from_slice(values);
+        for defined in self.defined[start..end].iter_mut() {
+            *defined |= stages;
+        }
+        self.pending.is_none()
+    }
+
+    /// Notify the tracker that a pipeline layout with the given ranges was bound.
+    pub(super) fn change_pipeline_layout(&mut self, ranges: &[wgt::PushConstantRange]) {
+        self.pending = if ranges.is_empty() {
+            None
+        } else {
+            Some(compute_nonoverlapping_ranges(ranges))
+        };
+    }
+
+    /// Re-apply the recorded values to the ranges of the current pipeline layout,
+    /// if they haven't been yet.
+    ///
+    /// Fails if any word of these ranges hasn't been written for all of its stages.
+    pub(super) fn flush<PushFn>(&mut self, mut push_fn: PushFn) -> Result<(), MissingPushConstants>
+    where
+        PushFn: FnMut(wgt::ShaderStages, u32, &[u32]),
+    {
+        let ranges = match self.pending {
+            Some(ref ranges) => ranges,
+            None => return Ok(()),
+        };
+
+        for range in ranges.iter() {
+            let start = (range.range.start / wgt::PUSH_CONSTANT_ALIGNMENT) as usize;
+            let end = (range.range.end / wgt::PUSH_CONSTANT_ALIGNMENT) as usize;
+            for index in start..end {
+                let defined = self
+                    .defined
+                    .get(index)
+                    .cloned()
+                    .unwrap_or(wgt::ShaderStages::NONE);
+                if !defined.contains(range.stages) {
+                    return Err(MissingPushConstants {
+                        stages: range.stages - defined,
+                        offset: index as u32 * wgt::PUSH_CONSTANT_ALIGNMENT,
+                    });
+                }
+            }
+        }
+
+        for range in ranges.iter() {
+            let start = (range.range.start / wgt::PUSH_CONSTANT_ALIGNMENT) as usize;
+            let end = (range.range.end / wgt::PUSH_CONSTANT_ALIGNMENT) as usize;
+            push_fn(range.stages, range.range.start, &self.data[start..end]);
+        }
+
+        self.pending = None;
+        Ok(())
+    }
+}
@@ -34,20 +34,21 @@ invalidations or index format changes.
 #![allow(clippy::reversed_empty_ranges)]
 
 use crate::{
-    binding_model::buffer_binding_type_alignment,
+    binding_model::{buffer_binding_type_alignment, LayoutKey},
     command::{
         BasePass, DrawError, MapPassErr, PassErrorScope, RenderCommand, RenderCommandError,
         StateChange,
     },
     conv,
     device::{
-        AttachmentData, Device, DeviceError, MissingDownlevelFlags, RenderPassContext,
-        SHADER_STAGE_COUNT,
+        AttachmentData, Device, DeviceError, DeviceErrorSource, MissingDownlevelFlags,
+        MissingFeatures, RenderPassContext, SHADER_STAGE_COUNT,
     },
     error::{ErrorFormatter, PrettyError},
     hub::{GlobalIdentityHandlerFactory, HalApi, Hub, Resource, Storage, Token},
     id,
     init_tracker::{BufferInitTrackerAction, MemoryInitKind, TextureInitTrackerAction},
+    instance::Adapter,
     pipeline::PipelineFlags,
     track::{TrackerSet, UsageConflict},
     validation::check_buffer_usage,
@@ -86,6 +87,8 @@ pub struct RenderBundleEncoder {
     parent_id: id::DeviceId,
     pub(crate) context: RenderPassContext,
     pub(crate) is_ds_read_only: bool,
+    pub(crate) is_depth_read_only: bool,
+    pub(crate) is_stencil_read_only: bool,
 }
 
 impl RenderBundleEncoder {
@@ -94,8 +97,34 @@ impl RenderBundleEncoder {
         parent_id: id::DeviceId,
         base: Option<BasePass<RenderCommand>>,
     ) -> Result<Self, CreateRenderBundleError> {
-        //TODO: validate that attachment formats are renderable,
-        // have expected aspects, support multisampling.
+        if desc.color_formats.is_empty() && desc.depth_stencil.is_none() {
+            return Err(CreateRenderBundleError::MissingAttachments);
+        }
+        for &format in desc.color_formats.iter() {
+            if !hal::FormatAspects::from(format).contains(hal::FormatAspects::COLOR) {
+                return Err(CreateRenderBundleError::InvalidColorAttachmentFormat(
+                    format,
+                ));
+            }
+        }
+        // Renderability and multisampling support depend on the device,
+        // they are validated when the bundle is finished.
+        let (is_depth_read_only, is_stencil_read_only) = match desc.depth_stencil {
+            Some(ds) => {
+                let aspects = hal::FormatAspects::from(ds.format);
+                if !aspects.intersects(hal::FormatAspects::DEPTH | hal::FormatAspects::STENCIL) {
+                    return Err(
+                        CreateRenderBundleError::InvalidDepthStencilAttachmentFormat(ds.format),
+                    );
+                }
+                (
+                    !aspects.contains(hal::FormatAspects::DEPTH) || ds.depth_read_only,
+                    !aspects.contains(hal::FormatAspects::STENCIL) || ds.stencil_read_only,
+                )
+            }
+            None => (false, false),
+        };
+
         Ok(Self {
             base: base.unwrap_or_else(|| BasePass::new(&desc.label)),
             parent_id,
@@ -118,14 +147,9 @@ impl RenderBundleEncoder {
                 },
                 multiview: desc.multiview,
             },
-            is_ds_read_only: match desc.depth_stencil {
-                Some(ds) => {
-                    let aspects = hal::FormatAspects::from(ds.format);
-                    (!aspects.contains(hal::FormatAspects::DEPTH) || ds.depth_read_only)
-                        && (!aspects.contains(hal::FormatAspects::STENCIL) || ds.stencil_read_only)
-                }
-                None => false,
-            },
+            is_ds_read_only: is_depth_read_only && is_stencil_read_only,
+            is_depth_read_only,
+            is_stencil_read_only,
         })
     }
 
@@ -143,6 +167,8 @@ impl RenderBundleEncoder {
                 multiview: None,
             },
             is_ds_read_only: false,
+            is_depth_read_only: false,
+            is_stencil_read_only: false,
         }
     }
 
@@ -155,13 +181,17 @@ impl RenderBundleEncoder {
         self.parent_id
     }
 
-    pub(crate) fn finish<A: hal::Api, G: GlobalIdentityHandlerFactory>(
+    pub(crate) fn finish<A: HalApi, G: GlobalIdentityHandlerFactory>(
         self,
         desc: &RenderBundleDescriptor,
         device: &Device<A>,
+        adapter: &Adapter<A>,
         hub: &Hub<A, G>,
         token: &mut Token<Device<A>>,
     ) -> Result<RenderBundle, RenderBundleError> {
+        self.validate_attachment_formats(device, adapter)
+            .map_pass_err(PassErrorScope::Bundle)?;
+
         let (pipeline_layout_guard, mut token) = hub.pipeline_layouts.read(token);
         let (bind_group_guard, mut token) = hub.bind_groups.read(&mut token);
         let (pipeline_guard, mut token) = hub.render_pipelines.read(&mut token);
@@ -187,6 +217,7 @@ impl RenderBundleEncoder {
         let mut pipeline_layout_id = None::<id::Valid<id::PipelineLayoutId>>;
         let mut buffer_memory_init_actions = Vec::new();
         let mut texture_memory_init_actions = Vec::new();
+        let mut dynamic_state = RenderBundleDynamicState::default();
 
         for &command in base.commands {
             match command {
@@ -212,7 +243,7 @@ impl RenderBundleEncoder {
                     let bind_group = state
                         .trackers
                         .bind_groups
-                        .use_extend(&*bind_group_guard, bind_group_id, (), ())
+                        .use_current(&*bind_group_guard, bind_group_id)
                         .map_err(|_| RenderCommandError::InvalidBindGroup(bind_group_id))
                         .map_pass_err(scope)?;
                     if bind_group.dynamic_binding_info.len() != offsets.len() {
@@ -242,7 +273,7 @@ impl RenderBundleEncoder {
                     buffer_memory_init_actions.extend_from_slice(&bind_group.used_buffer_ranges);
                     texture_memory_init_actions.extend_from_slice(&bind_group.used_texture_ranges);
 
-                    state.set_bind_group(index, bind_group_id, bind_group.layout_id, offsets);
+                    state.set_bind_group(index, bind_group_id, bind_group.layout_key, offsets);
                     state
                         .trackers
                         .merge_extend_stateful(&bind_group.used)
@@ -280,7 +311,7 @@ impl RenderBundleEncoder {
                     state.set_pipeline(
                         pipeline.strip_index_format,
                         &pipeline.vertex_strides,
-                        &layout.bind_group_layout_ids,
+                        &layout.bind_group_layout_keys,
                         &layout.push_constant_ranges,
                     );
                     commands.push(command);
@@ -507,12 +538,49 @@ impl RenderBundleEncoder {
                 RenderCommand::PopDebugGroup => unimplemented!(),
                 RenderCommand::WriteTimestamp { .. }
                 | RenderCommand::BeginPipelineStatisticsQuery { .. }
-                | RenderCommand::EndPipelineStatisticsQuery => unimplemented!(),
-                RenderCommand::ExecuteBundle(_)
-                | RenderCommand::SetBlendConstant(_)
-                | RenderCommand::SetStencilReference(_)
-                | RenderCommand::SetViewport { .. }
-                | RenderCommand::SetScissor(_) => unreachable!("not supported by a render bundle"),
+                | RenderCommand::EndPipelineStatisticsQuery
+                | RenderCommand::BeginOcclusionQuery { .. }
+                | RenderCommand::EndOcclusionQuery => unimplemented!(),
+                RenderCommand::SetViewport {
+                    ref rect,
+                    depth_min,
+                    depth_max,
+                } => {
+                    let scope = PassErrorScope::SetViewport;
+                    if rect.w <= 0.0
+                        || rect.h <= 0.0
+                        || depth_min < 0.0
+                        || depth_min > 1.0
+                        || depth_max < 0.0
+                        || depth_max > 1.0
+                    {
+                        return Err(RenderCommandError::InvalidViewport).map_pass_err(scope);
+                    }
+                    dynamic_state.viewport = true;
+                    commands.push(command);
+                }
+                RenderCommand::SetScissor(ref rect) => {
+                    let scope = PassErrorScope::SetScissorRect;
+                    // The bounds are checked against the extent of the pass the bundle is executed in.
+                    let edges = rect.x.checked_add(rect.w).zip(rect.y.checked_add(rect.h));
+                    let (rect_right, rect_bottom) = match edges {
+                        Some(edges) if rect.w != 0 && rect.h != 0 => edges,
+                        _ => {
+                            return Err(RenderCommandError::InvalidScissorRect).map_pass_err(scope)
+                        }
+                    };
+                    let (right, bottom) = dynamic_state.scissor_bounds.unwrap_or((0, 0));
+                    dynamic_state.scissor_bounds =
+                        Some((right.max(rect_right), bottom.max(rect_bottom)));
+                    commands.push(command);
+                }
+                RenderCommand::SetStencilReference(_) => {
+                    dynamic_state.stencil_reference = true;
+                    commands.push(command);
+                }
+                RenderCommand::ExecuteBundle(_) | RenderCommand::SetBlendConstant(_) => {
+                    unreachable!("not supported by a render bundle")
+                }
             }
         }
 
@@ -525,6 +593,9 @@ impl RenderBundleEncoder {
                 push_constant_data: Vec::new(),
             },
             is_ds_read_only: self.is_ds_read_only,
+            is_depth_read_only: self.is_depth_read_only,
+            is_stencil_read_only: self.is_stencil_read_only,
+            dynamic_state,
             device_id: Stored {
                 value: id::Valid(self.parent_id),
                 ref_count: device.life_guard.add_ref(),
@@ -537,6 +608,35 @@ impl RenderBundleEncoder {
         })
     }
 
+    fn validate_attachment_formats<A: HalApi>(
+        &self,
+        device: &Device<A>,
+        adapter: &Adapter<A>,
+    ) -> Result<(), CreateRenderBundleError> {
+        let attachments = &self.context.attachments;
+        for &format in attachments
+            .colors
+            .iter()
+            .chain(attachments.depth_stencil.iter())
+        {
+            let format_features = device.describe_format_features(adapter, format)?;
+            if !format_features
+                .allowed_usages
+                .contains(wgt::TextureUsages::RENDER_ATTACHMENT)
+            {
+                return Err(CreateRenderBundleError::FormatNotRenderable(format));
+            }
+            if self.context.sample_count > 1
+                && !format_features
+                    .flags
+                    .contains(wgt::TextureFormatFeatureFlags::MULTISAMPLE)
+            {
+                return Err(CreateRenderBundleError::FormatNotMultisampled(format));
+            }
+        }
+        Ok(())
+    }
+
     pub fn set_index_buffer(
         &mut self,
         buffer_id: id::BufferId,
@@ -553,13 +653,25 @@ impl RenderBundleEncoder {
     }
 }
 
-/// Error type returned from `RenderBundleEncoder::new` if the sample count is invalid.
+/// Error type returned from `RenderBundleEncoder::new` if the attachments are invalid.
 #[derive(Clone, Debug, Error)]
 pub enum CreateRenderBundleError {
     #[error("invalid number of samples {0}")]
     InvalidSampleCount(u32),
     #[error("number of color attachments exceeds the limit")]
     TooManyColorAttachments,
+    #[error("render bundle must have at least one color or depth-stencil attachment")]
+    MissingAttachments,
+    #[error("attachment format {0:?} is not a color format")]
+    InvalidColorAttachmentFormat(wgt::TextureFormat),
+    #[error("attachment format {0:?} is not a depth-stencil format")]
+    InvalidDepthStencilAttachmentFormat(wgt::TextureFormat),
+    #[error("attachment format {0:?} is not renderable")]
+    FormatNotRenderable(wgt::TextureFormat),
+    #[error("attachment format {0:?} does not support multisampling")]
+    FormatNotMultisampled(wgt::TextureFormat),
+    #[error(transparent)]
+    MissingFeatures(#[from] MissingFeatures),
 }
 
 /// Error type returned from `RenderBundleEncoder::new` if the sample count is invalid.
@@ -593,6 +705,9 @@ pub struct RenderBundle {
     // without re-binding anything on the pipeline change.
     base: BasePass<RenderCommand>,
     pub(super) is_ds_read_only: bool,
+    pub(super) is_depth_read_only: bool,
+    pub(super) is_stencil_read_only: bool,
+    pub(super) dynamic_state: RenderBundleDynamicState,
     pub(crate) device_id: Stored<id::DeviceId>,
     pub(crate) used: TrackerSet,
     pub(super) buffer_memory_init_actions: Vec<BufferInitTrackerAction>,
@@ -601,6 +716,18 @@ pub struct RenderBundle {
     pub(crate) life_guard: LifeGuard,
 }
 
+/// Render pass state that is changed by executing a render bundle,
+/// and needs to be restored afterwards.
+#[derive(Clone, Debug, Default)]
+pub(super) struct RenderBundleDynamicState {
+    /// True if the bundle sets the viewport.
+    pub viewport: bool,
+    /// Furthest right and bottom edges of the scissor rects set by the bundle, if any.
+    pub scissor_bounds: Option<(u32, u32)>,
+    /// True if the bundle sets the stencil reference.
+    pub stencil_reference: bool,
+}
+
 unsafe impl Send for RenderBundle {}
 unsafe impl Sync for RenderBundle {}
 
@@ -784,14 +911,39 @@ impl RenderBundle {
                 }
                 RenderCommand::WriteTimestamp { .. }
                 | RenderCommand::BeginPipelineStatisticsQuery { .. }
-                | RenderCommand::EndPipelineStatisticsQuery => {
+                | RenderCommand::EndPipelineStatisticsQuery
+                | RenderCommand::BeginOcclusionQuery { .. }
+                | RenderCommand::EndOcclusionQuery => {
                     return Err(ExecutionError::Unimplemented("queries"))
                 }
-                RenderCommand::ExecuteBundle(_)
-                | RenderCommand::SetBlendConstant(_)
-                | RenderCommand::SetStencilReference(_)
-                | RenderCommand::SetViewport { .. }
-                | RenderCommand::SetScissor(_) => unreachable!(),
+                RenderCommand::SetViewport {
+                    ref rect,
+                    depth_min,
+                    depth_max,
+                } => {
+                    let r = hal::Rect {
+                        x: rect.x,
+                        y: rect.y,
+                        w: rect.w,
+                        h: rect.h,
+                    };
+                    raw.set_viewport(&r, depth_min..depth_max);
+                }
+                RenderCommand::SetScissor(ref rect) => {
+                    let r = hal::Rect {
+                        x: rect.x,
+                        y: rect.y,
+                        w: rect.w,
+                        h: rect.h,
+                    };
+                    raw.set_scissor_rect(&r);
+                }
+                RenderCommand::SetStencilReference(value) => {
+                    raw.set_stencil_reference(value);
+                }
+                RenderCommand::ExecuteBundle(_) | RenderCommand::SetBlendConstant(_) => {
+                    unreachable!()
+                }
             }
         }
 
@@ -911,7 +1063,7 @@ impl VertexState {
 
 #[derive(Debug)]
 struct BindState {
-    bind_group: Option<(id::BindGroupId, id::BindGroupLayoutId)>,
+    bind_group: Option<(id::BindGroupId, LayoutKey)>,
     dynamic_offsets: Range<usize>,
     is_dirty: bool,
 }
@@ -928,14 +1080,14 @@ impl BindState {
     fn set_group(
         &mut self,
         bind_group_id: id::BindGroupId,
-        layout_id: id::BindGroupLayoutId,
+        layout_key: LayoutKey,
         dyn_offset: usize,
         dyn_count: usize,
     ) -> bool {
         match self.bind_group {
             Some((bg_id, _)) if bg_id == bind_group_id && dyn_count == 0 => false,
             _ => {
-                self.bind_group = Some((bind_group_id, layout_id));
+                self.bind_group = Some((bind_group_id, layout_key));
                 self.dynamic_offsets = dyn_offset..dyn_offset + dyn_count;
                 self.is_dirty = true;
                 true
@@ -1036,12 +1188,12 @@ impl State {
         &mut self,
         slot: u8,
         bind_group_id: id::BindGroupId,
-        layout_id: id::Valid<id::BindGroupLayoutId>,
+        layout_key: LayoutKey,
         offsets: &[wgt::DynamicOffset],
     ) {
         if self.bind[slot as usize].set_group(
             bind_group_id,
-            layout_id.0,
+            layout_key,
             self.raw_dynamic_offsets.len(),
             offsets.len(),
         ) {
@@ -1054,7 +1206,7 @@ impl State {
         &mut self,
         index_format: Option<wgt::IndexFormat>,
         vertex_strides: &[(wgt::BufferAddress, wgt::VertexStepMode)],
-        layout_ids: &[id::Valid<id::BindGroupLayoutId>],
+        layout_keys: &[LayoutKey],
         push_constant_layouts: &[wgt::PushConstantRange],
     ) {
         self.index.pipeline_format = index_format;
@@ -1071,15 +1223,15 @@ impl State {
             .push_constant_ranges
             .set_push_constants(push_constant_layouts);
 
-        self.used_bind_groups = layout_ids.len();
+        self.used_bind_groups = layout_keys.len();
         let invalid_from = if push_constants_changed {
             Some(0)
         } else {
             self.bind
                 .iter()
-                .zip(layout_ids)
-                .position(|(bs, layout_id)| match bs.bind_group {
-                    Some((_, bgl_id)) => bgl_id != layout_id.0,
+                .zip(layout_keys)
+                .position(|(bs, layout_key)| match bs.bind_group {
+                    Some((_, bgl_key)) => bgl_key != *layout_key,
                     None => false,
                 })
         };
@@ -1157,6 +1309,8 @@ pub(super) enum RenderBundleErrorInner {
     Draw(#[from] DrawError),
     #[error(transparent)]
     MissingDownlevelFlags(#[from] MissingDownlevelFlags),
+    #[error(transparent)]
+    Attachment(#[from] CreateRenderBundleError),
 }
 
 impl<T> From<T> for RenderBundleErrorInner
@@ -1183,6 +1337,14 @@ impl RenderBundleError {
         inner: RenderBundleErrorInner::Device(DeviceError::Invalid),
     };
 }
+impl DeviceErrorSource for RenderBundleError {
+    fn device_error(&self) -> Option<&DeviceError> {
+        match self.inner {
+            RenderBundleErrorInner::Device(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
 impl PrettyError for RenderBundleError {
     fn fmt_pretty(&self, fmt: &mut ErrorFormatter) {
         // This error is wrapper for the inner error,
@@ -1206,7 +1368,7 @@ where
 
 pub mod bundle_ffi {
     use super::{RenderBundleEncoder, RenderCommand};
-    use crate::{id, RawString};
+    use crate::{command::Rect, id, RawString};
     use std::{convert::TryInto, slice};
     use wgt::{BufferAddress, BufferSize, DynamicOffset};
 
@@ -1303,6 +1465,48 @@ pub mod bundle_ffi {
         });
     }
 
+    #[no_mangle]
+    pub extern "C" fn wgpu_render_bundle_set_stencil_reference(
+        bundle: &mut RenderBundleEncoder,
+        value: u32,
+    ) {
+        bundle
+            .base
+            .commands
+            .push(RenderCommand::SetStencilReference(value));
+    }
+
+    #[no_mangle]
+    pub extern "C" fn wgpu_render_bundle_set_viewport(
+        bundle: &mut RenderBundleEncoder,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        depth_min: f32,
+        depth_max: f32,
+    ) {
+        bundle.base.commands.push(RenderCommand::SetViewport {
+            rect: Rect { x, y, w, h },
+            depth_min,
+            depth_max,
+        });
+    }
+
+    #[no_mangle]
+    pub extern "C" fn wgpu_render_bundle_set_scissor_rect(
+        bundle: &mut RenderBundleEncoder,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+    ) {
+        bundle
+            .base
+            .commands
+            .push(RenderCommand::SetScissor(Rect { x, y, w, h }));
+    }
+
     #[no_mangle]
     pub extern "C" fn wgpu_render_bundle_draw(
         bundle: &mut RenderBundleEncoder,
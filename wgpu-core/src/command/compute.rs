@@ -1,15 +1,16 @@
 use crate::{
     binding_model::{
-        BindError, BindGroup, LateMinBufferBindingSizeMismatch, PushConstantUploadError,
+        BindError, BindGroup, LateMinBufferBindingSizeMismatch, MissingPushConstants,
+        PipelineLayout, PushConstantUploadError,
     },
     command::{
-        bind::Binder,
+        bind::{Binder, PushConstantTracker},
         end_pipeline_statistics_query,
         memory_init::{fixup_discarded_surfaces, SurfacesInDiscardState},
         BasePass, BasePassRef, CommandBuffer, CommandEncoderError, CommandEncoderStatus,
-        MapPassErr, PassErrorScope, QueryUseError, StateChange,
+        MapPassErr, PassErrorScope, PassTimestampWrites, QueryResetMap, QueryUseError, StateChange,
     },
-    device::MissingDownlevelFlags,
+    device::{MissingDownlevelFlags, MissingFeatures},
     error::{ErrorFormatter, PrettyError},
     hub::{Global, GlobalIdentityHandlerFactory, HalApi, Storage, Token},
     id,
@@ -76,6 +77,7 @@ pub enum ComputeCommand {
 pub struct ComputePass {
     base: BasePass<ComputeCommand>,
     parent_id: id::CommandEncoderId,
+    timestamp_writes: Option<PassTimestampWrites>,
 }
 
 impl ComputePass {
@@ -83,6 +85,7 @@ impl ComputePass {
         Self {
             base: BasePass::new(&desc.label),
             parent_id,
+            timestamp_writes: desc.timestamp_writes.cloned(),
         }
     }
 
@@ -92,7 +95,10 @@ impl ComputePass {
 
     #[cfg(feature = "trace")]
     pub fn into_command(self) -> crate::device::trace::Command {
-        crate::device::trace::Command::RunComputePass { base: self.base }
+        crate::device::trace::Command::RunComputePass {
+            base: self.base,
+            timestamp_writes: self.timestamp_writes,
+        }
     }
 }
 
@@ -111,6 +117,8 @@ impl fmt::Debug for ComputePass {
 #[derive(Clone, Debug, Default)]
 pub struct ComputePassDescriptor<'a> {
     pub label: Label<'a>,
+    /// Timestamps to write at the beginning and the end of the pass, if any.
+    pub timestamp_writes: Option<&'a PassTimestampWrites>,
 }
 
 #[derive(Clone, Debug, Error, PartialEq)]
@@ -129,6 +137,8 @@ pub enum DispatchError {
     InvalidGroupSize { current: [u32; 3], limit: u32 },
     #[error(transparent)]
     BindingSizeTooSmall(#[from] LateMinBufferBindingSizeMismatch),
+    #[error(transparent)]
+    MissingPushConstants(#[from] MissingPushConstants),
 }
 
 /// Error encountered when performing a compute pass.
@@ -169,6 +179,8 @@ pub enum ComputePassErrorInner {
     #[error(transparent)]
     QueryUse(#[from] QueryUseError),
     #[error(transparent)]
+    MissingFeatures(#[from] MissingFeatures),
+    #[error(transparent)]
     MissingDownlevelFlags(#[from] MissingDownlevelFlags),
 }
 
@@ -223,6 +235,7 @@ where
 struct State {
     binder: Binder,
     pipeline: StateChange<id::ComputePipelineId>,
+    push_constants: PushConstantTracker,
     trackers: StatefulTrackerSubset,
     debug_scope_depth: u32,
 }
@@ -244,6 +257,20 @@ impl State {
         Ok(())
     }
 
+    fn flush_push_constants<A: HalApi>(
+        &mut self,
+        raw_encoder: &mut A::CommandEncoder,
+        pipeline_layout_guard: &Storage<PipelineLayout<A>, id::PipelineLayoutId>,
+    ) -> Result<(), DispatchError> {
+        if let Some(pipeline_layout_id) = self.binder.pipeline_layout_id {
+            let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id].raw;
+            self.push_constants.flush(|stages, offset, data| unsafe {
+                raw_encoder.set_push_constants(pipeline_layout, stages, offset, data);
+            })?;
+        }
+        Ok(())
+    }
+
     fn flush_states<A: HalApi>(
         &mut self,
         raw_encoder: &mut A::CommandEncoder,
@@ -282,7 +309,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         encoder_id: id::CommandEncoderId,
         pass: &ComputePass,
     ) -> Result<(), ComputePassError> {
-        self.command_encoder_run_compute_pass_impl::<A>(encoder_id, pass.base.as_ref())
+        self.command_encoder_run_compute_pass_impl::<A>(
+            encoder_id,
+            pass.base.as_ref(),
+            pass.timestamp_writes.as_ref(),
+        )
     }
 
     #[doc(hidden)]
@@ -290,6 +321,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         &self,
         encoder_id: id::CommandEncoderId,
         base: BasePassRef<ComputeCommand>,
+        timestamp_writes: Option<&PassTimestampWrites>,
     ) -> Result<(), ComputePassError> {
         profiling::scope!("run_compute_pass", "CommandEncoder");
         let init_scope = PassErrorScope::Pass(encoder_id);
@@ -312,6 +344,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         if let Some(ref mut list) = cmd_buf.commands {
             list.push(crate::device::trace::Command::RunComputePass {
                 base: BasePass::from_ref(base),
+                timestamp_writes: timestamp_writes.cloned(),
             });
         }
 
@@ -326,6 +359,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let mut state = State {
             binder: Binder::new(),
             pipeline: StateChange::new(),
+            push_constants: PushConstantTracker::default(),
             trackers: StatefulTrackerSubset::new(A::VARIANT),
             debug_scope_depth: 0,
         };
@@ -334,7 +368,38 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let mut string_offset = 0;
         let mut active_query = None;
 
-        let hal_desc = hal::ComputePassDescriptor { label: base.label };
+        let hal_timestamp_writes = match timestamp_writes {
+            Some(timestamp_writes) => {
+                device
+                    .require_features(wgt::Features::TIMESTAMP_QUERY)
+                    .map_pass_err(init_scope)?;
+
+                let query_set_id = timestamp_writes.query_set;
+                let query_set = cmd_buf
+                    .trackers
+                    .query_sets
+                    .use_extend(&*query_set_guard, query_set_id, (), ())
+                    .map_err(|_| ComputePassErrorInner::InvalidQuerySet(query_set_id))
+                    .map_pass_err(init_scope)?;
+
+                // The pass queries are reset right before the pass begins.
+                let mut query_reset_state = QueryResetMap::new();
+                let hal_timestamp_writes = query_set
+                    .validate_pass_timestamp_writes(timestamp_writes, &mut query_reset_state)
+                    .map_pass_err(init_scope)?;
+                query_reset_state
+                    .reset_queries(raw, &*query_set_guard, A::VARIANT)
+                    .map_err(ComputePassErrorInner::InvalidQuerySet)
+                    .map_pass_err(init_scope)?;
+                Some(hal_timestamp_writes)
+            }
+            None => None,
+        };
+
+        let hal_desc = hal::ComputePassDescriptor {
+            label: base.label,
+            timestamp_writes: hal_timestamp_writes,
+        };
         unsafe {
             raw.begin_compute_pass(&hal_desc);
         }
@@ -370,7 +435,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     let bind_group = cmd_buf
                         .trackers
                         .bind_groups
-                        .use_extend(&*bind_group_guard, bind_group_id, (), ())
+                        .use_current(&*bind_group_guard, bind_group_id)
                         .map_err(|_| ComputePassErrorInner::InvalidBindGroup(bind_group_id))
                         .map_pass_err(scope)?;
                     bind_group
@@ -459,26 +524,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             }
                         }
 
-                        // Clear push constant ranges
-                        let non_overlapping = super::bind::compute_nonoverlapping_ranges(
-                            &pipeline_layout.push_constant_ranges,
-                        );
-                        for range in non_overlapping {
-                            let offset = range.range.start;
-                            let size_bytes = range.range.end - offset;
-                            super::push_constant_clear(
-                                offset,
-                                size_bytes,
-                                |clear_offset, clear_data| unsafe {
-                                    raw.set_push_constants(
-                                        &pipeline_layout.raw,
-                                        wgt::ShaderStages::COMPUTE,
-                                        clear_offset,
-                                        clear_data,
-                                    );
-                                },
-                            );
-                        }
+                        // Push constants are re-applied before the next dispatch
+                        state
+                            .push_constants
+                            .change_pipeline_layout(&pipeline_layout.push_constant_ranges);
                     }
                 }
                 ComputeCommand::SetPushConstant {
@@ -494,31 +543,44 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     let data_slice =
                         &base.push_constant_data[(values_offset as usize)..values_end_offset];
 
-                    let pipeline_layout_id = state
-                        .binder
-                        .pipeline_layout_id
-                        //TODO: don't error here, lazily update the push constants
-                        .ok_or(ComputePassErrorInner::Dispatch(
-                            DispatchError::MissingPipeline,
-                        ))
-                        .map_pass_err(scope)?;
-                    let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
-
-                    pipeline_layout
-                        .validate_push_constant_ranges(
-                            wgt::ShaderStages::COMPUTE,
-                            offset,
-                            end_offset_bytes,
-                        )
-                        .map_pass_err(scope)?;
+                    let pipeline_layout = match state.binder.pipeline_layout_id {
+                        Some(pipeline_layout_id) => {
+                            let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
+                            pipeline_layout
+                                .validate_push_constant_ranges(
+                                    wgt::ShaderStages::COMPUTE,
+                                    offset,
+                                    end_offset_bytes,
+                                )
+                                .map_pass_err(scope)?;
+                            Some(pipeline_layout)
+                        }
+                        // Without a pipeline, the values are only recorded,
+                        // and get validated against the layout once one is set.
+                        None => {
+                            super::validate_push_constant_limit(
+                                offset,
+                                end_offset_bytes,
+                                cmd_buf.limits.max_push_constant_size,
+                            )
+                            .map_pass_err(scope)?;
+                            None
+                        }
+                    };
 
-                    unsafe {
-                        raw.set_push_constants(
-                            &pipeline_layout.raw,
-                            wgt::ShaderStages::COMPUTE,
-                            offset,
-                            data_slice,
-                        );
+                    let write_now =
+                        state
+                            .push_constants
+                            .write(wgt::ShaderStages::COMPUTE, offset, data_slice);
+                    if let (Some(pipeline_layout), true) = (pipeline_layout, write_now) {
+                        unsafe {
+                            raw.set_push_constants(
+                                &pipeline_layout.raw,
+                                wgt::ShaderStages::COMPUTE,
+                                offset,
+                                data_slice,
+                            );
+                        }
                     }
                 }
                 ComputeCommand::Dispatch(groups) => {
@@ -536,6 +598,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     );
 
                     state.is_ready().map_pass_err(scope)?;
+                    state
+                        .flush_push_constants(raw, &*pipeline_layout_guard)
+                        .map_pass_err(scope)?;
                     state
                         .flush_states(
                             raw,
@@ -572,6 +637,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     };
 
                     state.is_ready().map_pass_err(scope)?;
+                    state
+                        .flush_push_constants(raw, &*pipeline_layout_guard)
+                        .map_pass_err(scope)?;
 
                     device
                         .require_downlevel_flags(wgt::DownlevelFlags::INDIRECT_EXECUTION)
@@ -2,7 +2,9 @@
 !*/
 
 use crate::{
-    binding_model::{LateMinBufferBindingSizeMismatch, PushConstantUploadError},
+    binding_model::{
+        LateMinBufferBindingSizeMismatch, MissingPushConstants, PushConstantUploadError,
+    },
     error::ErrorFormatter,
     id,
     track::UseExtendError,
@@ -55,6 +57,8 @@ pub enum DrawError {
     },
     #[error(transparent)]
     BindingSizeTooSmall(#[from] LateMinBufferBindingSizeMismatch),
+    #[error(transparent)]
+    MissingPushConstants(#[from] MissingPushConstants),
 }
 
 /// Error encountered when encoding a render command.
@@ -223,5 +227,9 @@ pub enum RenderCommand {
         query_index: u32,
     },
     EndPipelineStatisticsQuery,
+    BeginOcclusionQuery {
+        query_index: u32,
+    },
+    EndOcclusionQuery,
     ExecuteBundle(id::RenderBundleId),
 }
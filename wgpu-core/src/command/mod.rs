@@ -18,6 +18,7 @@ use self::memory_init::CommandBufferTextureMemoryActions;
 use crate::error::{ErrorFormatter, PrettyError};
 use crate::init_tracker::BufferInitTrackerAction;
 use crate::{
+    binding_model::PushConstantUploadError,
     hub::{Global, GlobalIdentityHandlerFactory, HalApi, Storage, Token},
     id,
     resource::{Buffer, Texture},
@@ -385,6 +386,25 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
     }
 }
 
+/// Validate a push constant upload that can't be checked against a pipeline layout yet.
+fn validate_push_constant_limit(
+    offset: u32,
+    end_offset: u32,
+    limit: u32,
+) -> Result<(), PushConstantUploadError> {
+    if offset % wgt::PUSH_CONSTANT_ALIGNMENT != 0 {
+        return Err(PushConstantUploadError::Unaligned(offset));
+    }
+    if end_offset > limit {
+        return Err(PushConstantUploadError::ExceedsLimit {
+            offset,
+            end_offset,
+            limit,
+        });
+    }
+    Ok(())
+}
+
 fn push_constant_clear<PushFn>(offset: u32, size_bytes: u32, mut push_fn: PushFn)
 where
     PushFn: FnMut(u32, &[u32]),
@@ -467,6 +487,10 @@ pub enum PassErrorScope {
     BeginPipelineStatisticsQuery,
     #[error("In a end_pipeline_statistics_query command")]
     EndPipelineStatisticsQuery,
+    #[error("In a begin_occlusion_query command")]
+    BeginOcclusionQuery,
+    #[error("In a end_occlusion_query command")]
+    EndOcclusionQuery,
     #[error("In a execute_bundle command")]
     ExecuteBundle,
     #[error("In a dispatch command, indirect:{indirect}")]
@@ -82,6 +82,26 @@ impl<A: hal::Api> QueryResetMap<A> {
     }
 }
 
+/// Describes the timestamps written at the boundaries of a render or compute pass.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    any(feature = "serial-pass", feature = "trace"),
+    derive(serde::Serialize)
+)]
+#[cfg_attr(
+    any(feature = "serial-pass", feature = "replay"),
+    derive(serde::Deserialize)
+)]
+pub struct PassTimestampWrites {
+    /// The query set to write the timestamps into.
+    pub query_set: id::QuerySetId,
+    /// The index of the query written when the pass begins, if any.
+    pub beginning_of_pass_write_index: Option<u32>,
+    /// The index of the query written when the pass ends, if any.
+    pub end_of_pass_write_index: Option<u32>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SimplifiedQueryType {
     Occlusion,
@@ -130,6 +150,10 @@ pub enum QueryUseError {
     },
     #[error("Query was stopped while there was no active query")]
     AlreadyStopped,
+    #[error("Query {query_index} was still active at the end of the pass")]
+    Unterminated { query_index: u32 },
+    #[error("Pass timestamp writes must specify the beginning of pass index, the end of pass index, or both")]
+    MissingTimestampWriteIndices,
     #[error("A query of type {query_type:?} was started using a query set of type {set_type:?}")]
     IncompatibleType {
         set_type: SimplifiedQueryType,
@@ -169,14 +193,6 @@ impl<A: HalApi> QuerySet<A> {
         query_index: u32,
         reset_state: Option<&mut QueryResetMap<A>>,
     ) -> Result<&A::QuerySet, QueryUseError> {
-        // We need to defer our resets because we are in a renderpass, add the usage to the reset map.
-        if let Some(reset) = reset_state {
-            let used = reset.use_query_set(query_set_id, self, query_index);
-            if used {
-                return Err(QueryUseError::UsedTwiceInsideRenderpass { query_index });
-            }
-        }
-
         let simple_set_type = SimplifiedQueryType::from(self.desc.ty);
         if simple_set_type != query_type {
             return Err(QueryUseError::IncompatibleType {
@@ -192,6 +208,14 @@ impl<A: HalApi> QuerySet<A> {
             });
         }
 
+        // We need to defer our resets because we are in a renderpass, add the usage to the reset map.
+        if let Some(reset) = reset_state {
+            let used = reset.use_query_set(query_set_id, self, query_index);
+            if used {
+                return Err(QueryUseError::UsedTwiceInsideRenderpass { query_index });
+            }
+        }
+
         Ok(&self.raw)
     }
 
@@ -221,21 +245,47 @@ impl<A: HalApi> QuerySet<A> {
         Ok(())
     }
 
-    pub(super) fn validate_and_begin_pipeline_statistics_query(
+    /// Validate the timestamp writes of a pass, deferring the resets of the used
+    /// queries to `reset_state`, and build their hal counterpart.
+    pub(super) fn validate_pass_timestamp_writes(
+        &self,
+        timestamp_writes: &PassTimestampWrites,
+        reset_state: &mut QueryResetMap<A>,
+    ) -> Result<hal::PassTimestampWrites<A>, QueryUseError> {
+        let query_set_id = timestamp_writes.query_set;
+        let beginning_index = timestamp_writes.beginning_of_pass_write_index;
+        let end_index = timestamp_writes.end_of_pass_write_index;
+        if beginning_index.is_none() && end_index.is_none() {
+            return Err(QueryUseError::MissingTimestampWriteIndices);
+        }
+
+        for &query_index in beginning_index.iter().chain(end_index.iter()) {
+            self.validate_query(
+                query_set_id,
+                SimplifiedQueryType::Timestamp,
+                query_index,
+                Some(&mut *reset_state),
+            )?;
+        }
+
+        Ok(hal::PassTimestampWrites {
+            query_set: &self.raw,
+            beginning_of_pass_write_index: beginning_index,
+            end_of_pass_write_index: end_index,
+        })
+    }
+
+    fn validate_and_begin_query(
         &self,
         raw_encoder: &mut A::CommandEncoder,
         query_set_id: id::QuerySetId,
+        query_type: SimplifiedQueryType,
         query_index: u32,
         reset_state: Option<&mut QueryResetMap<A>>,
         active_query: &mut Option<(id::QuerySetId, u32)>,
     ) -> Result<(), QueryUseError> {
         let needs_reset = reset_state.is_none();
-        let query_set = self.validate_query(
-            query_set_id,
-            SimplifiedQueryType::PipelineStatistics,
-            query_index,
-            reset_state,
-        )?;
+        let query_set = self.validate_query(query_set_id, query_type, query_index, reset_state)?;
 
         if let Some((_old_id, old_idx)) = active_query.replace((query_set_id, query_index)) {
             return Err(QueryUseError::AlreadyStarted {
@@ -254,9 +304,46 @@ impl<A: HalApi> QuerySet<A> {
 
         Ok(())
     }
+
+    pub(super) fn validate_and_begin_pipeline_statistics_query(
+        &self,
+        raw_encoder: &mut A::CommandEncoder,
+        query_set_id: id::QuerySetId,
+        query_index: u32,
+        reset_state: Option<&mut QueryResetMap<A>>,
+        active_query: &mut Option<(id::QuerySetId, u32)>,
+    ) -> Result<(), QueryUseError> {
+        self.validate_and_begin_query(
+            raw_encoder,
+            query_set_id,
+            SimplifiedQueryType::PipelineStatistics,
+            query_index,
+            reset_state,
+            active_query,
+        )
+    }
+
+    pub(super) fn validate_and_begin_occlusion_query(
+        &self,
+        raw_encoder: &mut A::CommandEncoder,
+        query_set_id: id::QuerySetId,
+        query_index: u32,
+        reset_state: &mut QueryResetMap<A>,
+        active_query: &mut Option<(id::QuerySetId, u32)>,
+    ) -> Result<(), QueryUseError> {
+        // Occlusion queries only exist within render passes, so their resets are always deferred.
+        self.validate_and_begin_query(
+            raw_encoder,
+            query_set_id,
+            SimplifiedQueryType::Occlusion,
+            query_index,
+            Some(reset_state),
+            active_query,
+        )
+    }
 }
 
-pub(super) fn end_pipeline_statistics_query<A: HalApi>(
+fn end_query<A: HalApi>(
     raw_encoder: &mut A::CommandEncoder,
     storage: &Storage<QuerySet<A>, id::QuerySetId>,
     active_query: &mut Option<(id::QuerySetId, u32)>,
@@ -273,6 +360,22 @@ pub(super) fn end_pipeline_statistics_query<A: HalApi>(
     }
 }
 
+pub(super) fn end_pipeline_statistics_query<A: HalApi>(
+    raw_encoder: &mut A::CommandEncoder,
+    storage: &Storage<QuerySet<A>, id::QuerySetId>,
+    active_query: &mut Option<(id::QuerySetId, u32)>,
+) -> Result<(), QueryUseError> {
+    end_query(raw_encoder, storage, active_query)
+}
+
+pub(super) fn end_occlusion_query<A: HalApi>(
+    raw_encoder: &mut A::CommandEncoder,
+    storage: &Storage<QuerySet<A>, id::QuerySetId>,
+    active_query: &mut Option<(id::QuerySetId, u32)>,
+) -> Result<(), QueryUseError> {
+    end_query(raw_encoder, storage, active_query)
+}
+
 impl<G: GlobalIdentityHandlerFactory> Global<G> {
     pub fn command_encoder_write_timestamp<A: HalApi>(
         &self,
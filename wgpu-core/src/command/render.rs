@@ -1,12 +1,12 @@
 use crate::{
-    binding_model::BindError,
+    binding_model::{BindError, PipelineLayout},
     command::{
-        bind::Binder,
-        end_pipeline_statistics_query,
+        bind::{Binder, PushConstantTracker},
+        end_occlusion_query, end_pipeline_statistics_query,
         memory_init::{fixup_discarded_surfaces, SurfacesInDiscardState},
         BasePass, BasePassRef, CommandBuffer, CommandEncoderError, CommandEncoderStatus, DrawError,
-        ExecutionError, MapPassErr, PassErrorScope, QueryResetMap, QueryUseError, RenderCommand,
-        RenderCommandError, StateChange,
+        ExecutionError, MapPassErr, PassErrorScope, PassTimestampWrites, QueryResetMap,
+        QueryUseError, RenderCommand, RenderCommandError, SimplifiedQueryType, StateChange,
     },
     device::{
         AttachmentData, Device, MissingDownlevelFlags, MissingFeatures,
@@ -17,7 +17,7 @@ use crate::{
     id,
     init_tracker::{MemoryInitKind, TextureInitRange, TextureInitTrackerAction},
     pipeline::PipelineFlags,
-    resource::{Texture, TextureView},
+    resource::{QuerySet, Texture, TextureView},
     track::{StatefulTrackerSubset, TextureSelector, UsageConflict},
     validation::{
         check_buffer_usage, check_texture_usage, MissingBufferUsageError, MissingTextureUsageError,
@@ -156,6 +156,10 @@ pub struct RenderPassDescriptor<'a> {
     pub color_attachments: Cow<'a, [RenderPassColorAttachment]>,
     /// The depth and stencil attachment of the render pass, if any.
     pub depth_stencil_attachment: Option<&'a RenderPassDepthStencilAttachment>,
+    /// Timestamps to write at the beginning and the end of the pass, if any.
+    pub timestamp_writes: Option<&'a PassTimestampWrites>,
+    /// The query set occlusion queries of the pass are written to, if any.
+    pub occlusion_query_set: Option<id::QuerySetId>,
 }
 
 #[cfg_attr(feature = "serial-pass", derive(Deserialize, Serialize))]
@@ -164,6 +168,8 @@ pub struct RenderPass {
     parent_id: id::CommandEncoderId,
     color_targets: ArrayVec<RenderPassColorAttachment, { hal::MAX_COLOR_TARGETS }>,
     depth_stencil_target: Option<RenderPassDepthStencilAttachment>,
+    timestamp_writes: Option<PassTimestampWrites>,
+    occlusion_query_set: Option<id::QuerySetId>,
 }
 
 impl RenderPass {
@@ -173,6 +179,8 @@ impl RenderPass {
             parent_id,
             color_targets: desc.color_attachments.iter().cloned().collect(),
             depth_stencil_target: desc.depth_stencil_attachment.cloned(),
+            timestamp_writes: desc.timestamp_writes.cloned(),
+            occlusion_query_set: desc.occlusion_query_set,
         }
     }
 
@@ -186,6 +194,8 @@ impl RenderPass {
             base: self.base,
             target_colors: self.color_targets.into_iter().collect(),
             target_depth_stencil: self.depth_stencil_target,
+            timestamp_writes: self.timestamp_writes,
+            occlusion_query_set: self.occlusion_query_set,
         }
     }
 
@@ -209,10 +219,12 @@ impl fmt::Debug for RenderPass {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "RenderPass {{ encoder_id: {:?}, color_targets: {:?}, depth_stencil_target: {:?}, data: {:?} commands, {:?} dynamic offsets, and {:?} push constant u32s }}",
+            "RenderPass {{ encoder_id: {:?}, color_targets: {:?}, depth_stencil_target: {:?}, timestamp_writes: {:?}, occlusion_query_set: {:?}, data: {:?} commands, {:?} dynamic offsets, and {:?} push constant u32s }}",
             self.parent_id,
             self.color_targets,
             self.depth_stencil_target,
+            self.timestamp_writes,
+            self.occlusion_query_set,
             self.base.commands.len(),
             self.base.dynamic_offsets.len(),
             self.base.push_constant_data.len(),
@@ -337,7 +349,11 @@ struct State {
     binder: Binder,
     blend_constant: OptionalState,
     stencil_reference: u32,
+    viewport: hal::Rect<f32>,
+    viewport_depth: Range<f32>,
+    scissor: hal::Rect<u32>,
     pipeline: StateChange<id::RenderPipelineId>,
+    push_constants: PushConstantTracker,
     index: IndexState,
     vertex: VertexState,
     debug_scope_depth: u32,
@@ -389,6 +405,20 @@ impl State {
         Ok(())
     }
 
+    fn flush_push_constants<A: HalApi>(
+        &mut self,
+        raw_encoder: &mut A::CommandEncoder,
+        pipeline_layout_guard: &Storage<PipelineLayout<A>, id::PipelineLayoutId>,
+    ) -> Result<(), DrawError> {
+        if let Some(pipeline_layout_id) = self.binder.pipeline_layout_id {
+            let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id].raw;
+            self.push_constants.flush(|stages, offset, data| unsafe {
+                raw_encoder.set_push_constants(pipeline_layout, stages, offset, data);
+            })?;
+        }
+        Ok(())
+    }
+
     /// Reset the `RenderBundle`-related states.
     fn reset_bundle(&mut self) {
         self.binder.reset();
@@ -465,8 +495,13 @@ pub enum RenderPassErrorInner {
     ResourceUsageConflict(#[from] UsageConflict),
     #[error("render bundle has incompatible targets, {0}")]
     IncompatibleBundleTargets(#[from] RenderPassCompatibilityError),
-    #[error("render bundle has an incompatible read-only depth/stencil flag: bundle is {bundle}, while the pass is {pass}")]
-    IncompatibleBundleRods { pass: bool, bundle: bool },
+    #[error("render bundle has incompatible read-only flags: bundle has depth {bundle_depth} and stencil {bundle_stencil}, while the pass has depth {pass_depth} and stencil {pass_stencil}")]
+    IncompatibleBundleRods {
+        pass_depth: bool,
+        pass_stencil: bool,
+        bundle_depth: bool,
+        bundle_stencil: bool,
+    },
     #[error(transparent)]
     RenderCommand(#[from] RenderCommandError),
     #[error(transparent)]
@@ -475,6 +510,8 @@ pub enum RenderPassErrorInner {
     Bind(#[from] BindError),
     #[error(transparent)]
     QueryUse(#[from] QueryUseError),
+    #[error("occlusion query was started in a render pass without an occlusion query set")]
+    MissingOcclusionQuerySet,
     #[error("multiview layer count must match")]
     MultiViewMismatch,
     #[error(
@@ -557,6 +594,8 @@ struct RenderPassInfo<'a, A: hal::Api> {
     trackers: StatefulTrackerSubset,
     render_attachments: AttachmentDataVec<RenderAttachment<'a>>, // All render attachments, including depth/stencil
     is_ds_read_only: bool,
+    is_depth_read_only: bool,
+    is_stencil_read_only: bool,
     extent: wgt::Extent3d,
     _phantom: PhantomData<A>,
 
@@ -607,9 +646,13 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
         label: Option<&str>,
         color_attachments: &[RenderPassColorAttachment],
         depth_stencil_attachment: Option<&RenderPassDepthStencilAttachment>,
+        timestamp_writes: Option<&PassTimestampWrites>,
+        occlusion_query_set: Option<id::QuerySetId>,
         cmd_buf: &mut CommandBuffer<A>,
         view_guard: &'a Storage<TextureView<A>, id::TextureViewId>,
         texture_guard: &'a Storage<Texture<A>, id::TextureId>,
+        query_set_guard: &Storage<QuerySet<A>, id::QuerySetId>,
+        query_reset_state: &mut QueryResetMap<A>,
     ) -> Result<Self, RenderPassErrorInner> {
         profiling::scope!("start", "RenderPassInfo");
 
@@ -617,6 +660,8 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
         // This allows us to use the primary raw pipeline in `RenderPipeline`,
         // instead of the special read-only one, which would be `None`.
         let mut is_ds_read_only = false;
+        let mut is_depth_read_only = false;
+        let mut is_stencil_read_only = false;
 
         let mut render_attachments = AttachmentDataVec::<RenderAttachment>::new();
         let mut discarded_surfaces = AttachmentDataVec::new();
@@ -776,6 +821,10 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
                 }
             }
 
+            is_depth_read_only =
+                !ds_aspects.contains(hal::FormatAspects::DEPTH) || at.depth.read_only;
+            is_stencil_read_only =
+                !ds_aspects.contains(hal::FormatAspects::STENCIL) || at.stencil.read_only;
             let usage = if at.is_read_only(ds_aspects)? {
                 is_ds_read_only = true;
                 hal::TextureUses::DEPTH_STENCIL_READ | hal::TextureUses::RESOURCE
@@ -911,6 +960,44 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
             multiview,
         };
 
+        let hal_timestamp_writes = match timestamp_writes {
+            Some(timestamp_writes) => {
+                device.require_features(wgt::Features::TIMESTAMP_QUERY)?;
+
+                let query_set_id = timestamp_writes.query_set;
+                let query_set = cmd_buf
+                    .trackers
+                    .query_sets
+                    .use_extend(query_set_guard, query_set_id, (), ())
+                    .map_err(|_| RenderCommandError::InvalidQuerySet(query_set_id))?;
+                Some(
+                    query_set
+                        .validate_pass_timestamp_writes(timestamp_writes, query_reset_state)?,
+                )
+            }
+            None => None,
+        };
+
+        let occlusion_query_set = match occlusion_query_set {
+            Some(query_set_id) => {
+                let query_set = cmd_buf
+                    .trackers
+                    .query_sets
+                    .use_extend(query_set_guard, query_set_id, (), ())
+                    .map_err(|_| RenderCommandError::InvalidQuerySet(query_set_id))?;
+                let set_type = SimplifiedQueryType::from(query_set.desc.ty);
+                if set_type != SimplifiedQueryType::Occlusion {
+                    return Err(QueryUseError::IncompatibleType {
+                        set_type,
+                        query_type: SimplifiedQueryType::Occlusion,
+                    }
+                    .into());
+                }
+                Some(&query_set.raw)
+            }
+            None => None,
+        };
+
         let hal_desc = hal::RenderPassDescriptor {
             label,
             extent,
@@ -918,6 +1005,8 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
             color_attachments: &colors,
             depth_stencil_attachment: depth_stencil,
             multiview,
+            timestamp_writes: hal_timestamp_writes,
+            occlusion_query_set,
         };
         unsafe {
             cmd_buf.encoder.raw.begin_render_pass(&hal_desc);
@@ -928,6 +1017,8 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
             trackers: StatefulTrackerSubset::new(A::VARIANT),
             render_attachments,
             is_ds_read_only,
+            is_depth_read_only,
+            is_stencil_read_only,
             extent,
             _phantom: PhantomData,
             pending_discard_init_fixups,
@@ -996,6 +1087,8 @@ impl<'a, A: HalApi> RenderPassInfo<'a, A> {
                     clear_value: (0.0, 0),
                 }),
                 multiview: self.multiview,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             };
             unsafe {
                 raw.begin_render_pass(&desc);
@@ -1020,6 +1113,8 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             pass.base.as_ref(),
             &pass.color_targets,
             pass.depth_stencil_target.as_ref(),
+            pass.timestamp_writes.as_ref(),
+            pass.occlusion_query_set,
         )
     }
 
@@ -1030,6 +1125,8 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         base: BasePassRef<RenderCommand>,
         color_attachments: &[RenderPassColorAttachment],
         depth_stencil_attachment: Option<&RenderPassDepthStencilAttachment>,
+        timestamp_writes: Option<&PassTimestampWrites>,
+        occlusion_query_set_id: Option<id::QuerySetId>,
     ) -> Result<(), RenderPassError> {
         profiling::scope!("run_render_pass", "CommandEncoder");
         let init_scope = PassErrorScope::Pass(encoder_id);
@@ -1054,6 +1151,8 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     base: BasePass::from_ref(base),
                     target_colors: color_attachments.to_vec(),
                     target_depth_stencil: depth_stencil_attachment.cloned(),
+                    timestamp_writes: timestamp_writes.cloned(),
+                    occlusion_query_set: occlusion_query_set_id,
                 });
             }
 
@@ -1074,14 +1173,20 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 encoder_id
             );
 
+            let mut query_reset_state = QueryResetMap::new();
+
             let mut info = RenderPassInfo::start(
                 device,
                 base.label,
                 color_attachments,
                 depth_stencil_attachment,
+                timestamp_writes,
+                occlusion_query_set_id,
                 cmd_buf,
                 &*view_guard,
                 &*texture_guard,
+                &*query_set_guard,
+                &mut query_reset_state,
             )
             .map_pass_err(init_scope)?;
 
@@ -1092,7 +1197,21 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 binder: Binder::new(),
                 blend_constant: OptionalState::Unused,
                 stencil_reference: 0,
+                viewport: hal::Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: info.extent.width as f32,
+                    h: info.extent.height as f32,
+                },
+                viewport_depth: 0.0..1.0,
+                scissor: hal::Rect {
+                    x: 0,
+                    y: 0,
+                    w: info.extent.width,
+                    h: info.extent.height,
+                },
                 pipeline: StateChange::new(),
+                push_constants: PushConstantTracker::default(),
                 index: IndexState::default(),
                 vertex: VertexState::default(),
                 debug_scope_depth: 0,
@@ -1101,7 +1220,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             let mut dynamic_offset_count = 0;
             let mut string_offset = 0;
             let mut active_query = None;
-            let mut query_reset_state = QueryResetMap::new();
+            let mut active_occlusion_query = None;
 
             for command in base.commands {
                 match *command {
@@ -1130,7 +1249,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         let bind_group = cmd_buf
                             .trackers
                             .bind_groups
-                            .use_extend(&*bind_group_guard, bind_group_id, (), ())
+                            .use_current(&*bind_group_guard, bind_group_id)
                             .map_err(|_| RenderCommandError::InvalidBindGroup(bind_group_id))
                             .map_pass_err(scope)?;
                         bind_group
@@ -1251,26 +1370,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                                 }
                             }
 
-                            // Clear push constant ranges
-                            let non_overlapping = super::bind::compute_nonoverlapping_ranges(
-                                &pipeline_layout.push_constant_ranges,
-                            );
-                            for range in non_overlapping {
-                                let offset = range.range.start;
-                                let size_bytes = range.range.end - offset;
-                                super::push_constant_clear(
-                                    offset,
-                                    size_bytes,
-                                    |clear_offset, clear_data| unsafe {
-                                        raw.set_push_constants(
-                                            &pipeline_layout.raw,
-                                            range.stages,
-                                            clear_offset,
-                                            clear_data,
-                                        );
-                                    },
-                                );
-                            }
+                            // Push constants are re-applied before the next draw
+                            state
+                                .push_constants
+                                .change_pipeline_layout(&pipeline_layout.push_constant_ranges);
                         }
 
                         state.index.pipeline_format = pipeline.strip_index_format;
@@ -1433,14 +1536,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         {
                             return Err(RenderCommandError::InvalidViewport).map_pass_err(scope);
                         }
-                        let r = hal::Rect {
+                        state.viewport = hal::Rect {
                             x: rect.x,
                             y: rect.y,
                             w: rect.w,
                             h: rect.h,
                         };
+                        state.viewport_depth = depth_min..depth_max;
                         unsafe {
-                            raw.set_viewport(&r, depth_min..depth_max);
+                            raw.set_viewport(&state.viewport, depth_min..depth_max);
                         }
                     }
                     RenderCommand::SetPushConstant {
@@ -1460,39 +1564,64 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         let data_slice =
                             &base.push_constant_data[(values_offset as usize)..values_end_offset];
 
-                        let pipeline_layout_id = state
-                            .binder
-                            .pipeline_layout_id
-                            .ok_or(DrawError::MissingPipeline)
-                            .map_pass_err(scope)?;
-                        let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
-
-                        pipeline_layout
-                            .validate_push_constant_ranges(stages, offset, end_offset_bytes)
-                            .map_err(RenderCommandError::from)
-                            .map_pass_err(scope)?;
+                        let pipeline_layout = match state.binder.pipeline_layout_id {
+                            Some(pipeline_layout_id) => {
+                                let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
+                                pipeline_layout
+                                    .validate_push_constant_ranges(stages, offset, end_offset_bytes)
+                                    .map_err(RenderCommandError::from)
+                                    .map_pass_err(scope)?;
+                                Some(pipeline_layout)
+                            }
+                            // Without a pipeline, the values are only recorded,
+                            // and get validated against the layout once one is set.
+                            None => {
+                                super::validate_push_constant_limit(
+                                    offset,
+                                    end_offset_bytes,
+                                    device.limits.max_push_constant_size,
+                                )
+                                .map_err(RenderCommandError::from)
+                                .map_pass_err(scope)?;
+                                None
+                            }
+                        };
 
-                        unsafe {
-                            raw.set_push_constants(&pipeline_layout.raw, stages, offset, data_slice)
+                        let write_now = state.push_constants.write(stages, offset, data_slice);
+                        if let (Some(pipeline_layout), true) = (pipeline_layout, write_now) {
+                            unsafe {
+                                raw.set_push_constants(
+                                    &pipeline_layout.raw,
+                                    stages,
+                                    offset,
+                                    data_slice,
+                                )
+                            }
                         }
                     }
                     RenderCommand::SetScissor(ref rect) => {
                         let scope = PassErrorScope::SetScissorRect;
                         if rect.w == 0
                             || rect.h == 0
-                            || rect.x + rect.w > info.extent.width
-                            || rect.y + rect.h > info.extent.height
+                            || rect
+                                .x
+                                .checked_add(rect.w)
+                                .map_or(true, |right| right > info.extent.width)
+                            || rect
+                                .y
+                                .checked_add(rect.h)
+                                .map_or(true, |bottom| bottom > info.extent.height)
                         {
                             return Err(RenderCommandError::InvalidScissorRect).map_pass_err(scope);
                         }
-                        let r = hal::Rect {
+                        state.scissor = hal::Rect {
                             x: rect.x,
                             y: rect.y,
                             w: rect.w,
                             h: rect.h,
                         };
                         unsafe {
-                            raw.set_scissor_rect(&r);
+                            raw.set_scissor_rect(&state.scissor);
                         }
                     }
                     RenderCommand::Draw {
@@ -1508,6 +1637,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             pipeline: state.pipeline.last_state,
                         };
                         state.is_ready(indexed).map_pass_err(scope)?;
+                        state
+                            .flush_push_constants(raw, &*pipeline_layout_guard)
+                            .map_pass_err(scope)?;
 
                         let last_vertex = first_vertex + vertex_count;
                         let vertex_limit = state.vertex.vertex_limit;
@@ -1548,6 +1680,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             pipeline: state.pipeline.last_state,
                         };
                         state.is_ready(indexed).map_pass_err(scope)?;
+                        state
+                            .flush_push_constants(raw, &*pipeline_layout_guard)
+                            .map_pass_err(scope)?;
 
                         //TODO: validate that base_vertex + max_index() is within the provided range
                         let last_index = first_index + index_count;
@@ -1592,6 +1727,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             pipeline: state.pipeline.last_state,
                         };
                         state.is_ready(indexed).map_pass_err(scope)?;
+                        state
+                            .flush_push_constants(raw, &*pipeline_layout_guard)
+                            .map_pass_err(scope)?;
 
                         let stride = match indexed {
                             false => mem::size_of::<wgt::DrawIndirectArgs>(),
@@ -1665,6 +1803,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             pipeline: state.pipeline.last_state,
                         };
                         state.is_ready(indexed).map_pass_err(scope)?;
+                        state
+                            .flush_push_constants(raw, &*pipeline_layout_guard)
+                            .map_pass_err(scope)?;
 
                         let stride = match indexed {
                             false => mem::size_of::<wgt::DrawIndirectArgs>(),
@@ -1859,6 +2000,31 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         end_pipeline_statistics_query(raw, &*query_set_guard, &mut active_query)
                             .map_pass_err(scope)?;
                     }
+                    RenderCommand::BeginOcclusionQuery { query_index } => {
+                        let scope = PassErrorScope::BeginOcclusionQuery;
+
+                        let query_set_id = occlusion_query_set_id
+                            .ok_or(RenderPassErrorInner::MissingOcclusionQuerySet)
+                            .map_pass_err(scope)?;
+                        // The query set was validated and tracked when the pass was started.
+                        let query_set = &query_set_guard[query_set_id];
+
+                        query_set
+                            .validate_and_begin_occlusion_query(
+                                raw,
+                                query_set_id,
+                                query_index,
+                                &mut query_reset_state,
+                                &mut active_occlusion_query,
+                            )
+                            .map_pass_err(scope)?;
+                    }
+                    RenderCommand::EndOcclusionQuery => {
+                        let scope = PassErrorScope::EndOcclusionQuery;
+
+                        end_occlusion_query(raw, &*query_set_guard, &mut active_occlusion_query)
+                            .map_pass_err(scope)?;
+                    }
                     RenderCommand::ExecuteBundle(bundle_id) => {
                         let scope = PassErrorScope::ExecuteBundle;
                         let bundle = cmd_buf
@@ -1873,14 +2039,27 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             .map_err(RenderPassErrorInner::IncompatibleBundleTargets)
                             .map_pass_err(scope)?;
 
-                        if info.is_ds_read_only != bundle.is_ds_read_only {
+                        // A read-only aspect of the pass may not be written by the bundle,
+                        // while a bundle that only reads an aspect can be used in any pass.
+                        if (info.is_depth_read_only && !bundle.is_depth_read_only)
+                            || (info.is_stencil_read_only && !bundle.is_stencil_read_only)
+                        {
                             return Err(RenderPassErrorInner::IncompatibleBundleRods {
-                                pass: info.is_ds_read_only,
-                                bundle: bundle.is_ds_read_only,
+                                pass_depth: info.is_depth_read_only,
+                                pass_stencil: info.is_stencil_read_only,
+                                bundle_depth: bundle.is_depth_read_only,
+                                bundle_stencil: bundle.is_stencil_read_only,
                             })
                             .map_pass_err(scope);
                         }
 
+                        if let Some((right, bottom)) = bundle.dynamic_state.scissor_bounds {
+                            if right > info.extent.width || bottom > info.extent.height {
+                                return Err(RenderCommandError::InvalidScissorRect)
+                                    .map_pass_err(scope);
+                            }
+                        }
+
                         cmd_buf.buffer_memory_init_actions.extend(
                             bundle
                                 .buffer_memory_init_actions
@@ -1928,11 +2107,29 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             .bind_groups
                             .merge_extend(&bundle.used.bind_groups)
                             .unwrap();
+
+                        // Restore the pass state the bundle may have changed. Bundles can't
+                        // set the blend constant, so it never needs restoring.
+                        unsafe {
+                            if bundle.dynamic_state.viewport {
+                                raw.set_viewport(&state.viewport, state.viewport_depth.clone());
+                            }
+                            if bundle.dynamic_state.scissor_bounds.is_some() {
+                                raw.set_scissor_rect(&state.scissor);
+                            }
+                            if bundle.dynamic_state.stencil_reference {
+                                raw.set_stencil_reference(state.stencil_reference);
+                            }
+                        }
                         state.reset_bundle();
                     }
                 }
             }
 
+            if let Some((_, query_index)) = active_occlusion_query {
+                return Err(QueryUseError::Unterminated { query_index }).map_pass_err(init_scope);
+            }
+
             log::trace!("Merging {:?} with the render pass", encoder_id);
             let (trackers, pending_discard_init_fixups) =
                 info.finish(raw, &*texture_guard).map_pass_err(init_scope)?;
@@ -2345,6 +2542,21 @@ pub mod render_ffi {
             .push(RenderCommand::EndPipelineStatisticsQuery);
     }
 
+    #[no_mangle]
+    pub extern "C" fn wgpu_render_pass_begin_occlusion_query(
+        pass: &mut RenderPass,
+        query_index: u32,
+    ) {
+        pass.base
+            .commands
+            .push(RenderCommand::BeginOcclusionQuery { query_index });
+    }
+
+    #[no_mangle]
+    pub extern "C" fn wgpu_render_pass_end_occlusion_query(pass: &mut RenderPass) {
+        pass.base.commands.push(RenderCommand::EndOcclusionQuery);
+    }
+
     /// # Safety
     ///
     /// This function is unsafe as there is no guarantee that the given pointer is
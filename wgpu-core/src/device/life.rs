@@ -17,7 +17,7 @@ use hal::Device as _;
 use parking_lot::Mutex;
 use thiserror::Error;
 
-use std::mem;
+use std::{mem, sync::Arc};
 
 /// A struct that keeps lists of resources that are no longer needed by the user.
 #[derive(Debug, Default)]
@@ -94,6 +94,10 @@ struct NonReferencedResources<A: hal::Api> {
     bind_group_layouts: Vec<A::BindGroupLayout>,
     pipeline_layouts: Vec<A::PipelineLayout>,
     query_sets: Vec<A::QuerySet>,
+    shader_modules: Vec<A::ShaderModule>,
+    pipeline_caches: Vec<A::PipelineCache>,
+    /// Memory use of the staging buffers in `buffers`, released once they're destroyed.
+    staging_memory: Vec<resource::MemoryUse>,
 }
 
 impl<A: hal::Api> NonReferencedResources<A> {
@@ -109,6 +113,9 @@ impl<A: hal::Api> NonReferencedResources<A> {
             bind_group_layouts: Vec::new(),
             pipeline_layouts: Vec::new(),
             query_sets: Vec::new(),
+            shader_modules: Vec::new(),
+            pipeline_caches: Vec::new(),
+            staging_memory: Vec::new(),
         }
     }
 
@@ -121,7 +128,10 @@ impl<A: hal::Api> NonReferencedResources<A> {
         self.compute_pipes.extend(other.compute_pipes);
         self.render_pipes.extend(other.render_pipes);
         self.query_sets.extend(other.query_sets);
+        self.staging_memory.extend(other.staging_memory);
         assert!(other.bind_group_layouts.is_empty());
+        assert!(other.shader_modules.is_empty());
+        assert!(other.pipeline_caches.is_empty());
         assert!(other.pipeline_layouts.is_empty());
     }
 
@@ -131,6 +141,7 @@ impl<A: hal::Api> NonReferencedResources<A> {
             for raw in self.buffers.drain(..) {
                 device.destroy_buffer(raw);
             }
+            self.staging_memory.clear();
         }
         if !self.textures.is_empty() {
             profiling::scope!("destroy_textures");
@@ -186,6 +197,18 @@ impl<A: hal::Api> NonReferencedResources<A> {
                 device.destroy_query_set(raw);
             }
         }
+        if !self.shader_modules.is_empty() {
+            profiling::scope!("destroy_shader_modules");
+            for raw in self.shader_modules.drain(..) {
+                device.destroy_shader_module(raw);
+            }
+        }
+        if !self.pipeline_caches.is_empty() {
+            profiling::scope!("destroy_pipeline_caches");
+            for raw in self.pipeline_caches.drain(..) {
+                device.destroy_pipeline_cache(raw);
+            }
+        }
     }
 }
 
@@ -193,10 +216,16 @@ impl<A: hal::Api> NonReferencedResources<A> {
 struct ActiveSubmission<A: hal::Api> {
     /// The index of the submission we track.
     ///
-    /// When `Device::fence`'s value is greater than or equal to this, our queue
-    /// submission has completed.
+    /// When the fence of the hardware queue it was submitted to reaches
+    /// a value greater than or equal to this, our queue submission has completed.
     index: SubmissionIndex,
 
+    /// The hardware queue this submission went to, where `0` is `Device::queue`.
+    ///
+    /// Submission indices are shared by all the queues of a device, but each
+    /// queue only signals its own fence.
+    queue: usize,
+
     /// Resources to be freed once this queue submission has completed.
     ///
     /// When the device is polled, for completed submissions,
@@ -220,6 +249,8 @@ struct ActiveSubmission<A: hal::Api> {
 pub enum WaitIdleError {
     #[error(transparent)]
     Device(#[from] DeviceError),
+    #[error("tried to wait using a submission index from the wrong device. Submission index is from device {0:?}. Called poll on device {1:?}.")]
+    WrongSubmissionIndex(id::QueueId, id::DeviceId),
     #[error("GPU got stuck :(")]
     StuckGpu,
 }
@@ -294,6 +325,15 @@ pub(super) struct LifetimeTracker<A: hal::Api> {
     /// Buffers the user has asked us to map, and which are not used by any
     /// queue submission still in flight.
     ready_to_map: Vec<id::Valid<id::BufferId>>,
+
+    /// Dropped shader modules that pipelines are still being compiled from.
+    ///
+    /// The compilations only hold the other references, and never destroy
+    /// anything, so these are freed by `LifetimeTracker::cleanup` once unique.
+    released_shader_modules: Vec<Arc<A::ShaderModule>>,
+
+    /// Dropped pipeline caches that pipelines are still being compiled with.
+    released_pipeline_caches: Vec<Arc<A::PipelineCache>>,
 }
 
 impl<A: hal::Api> LifetimeTracker<A> {
@@ -306,6 +346,8 @@ impl<A: hal::Api> LifetimeTracker<A> {
             active: Vec::new(),
             free_resources: NonReferencedResources::new(),
             ready_to_map: Vec::new(),
+            released_shader_modules: Vec::new(),
+            released_pipeline_caches: Vec::new(),
         }
     }
 
@@ -318,6 +360,7 @@ impl<A: hal::Api> LifetimeTracker<A> {
     pub fn track_submission(
         &mut self,
         index: SubmissionIndex,
+        queue: usize,
         temp_resources: impl Iterator<Item = TempResource<A>>,
         encoders: Vec<EncoderInFlight<A>>,
     ) {
@@ -325,6 +368,10 @@ impl<A: hal::Api> LifetimeTracker<A> {
         for res in temp_resources {
             match res {
                 TempResource::Buffer(raw) => last_resources.buffers.push(raw),
+                TempResource::StagingBuffer(raw, memory) => {
+                    last_resources.buffers.push(raw);
+                    last_resources.staging_memory.push(memory);
+                }
                 TempResource::Texture(raw, views) => {
                     last_resources.textures.push(raw);
                     last_resources.texture_views.extend(views);
@@ -334,6 +381,7 @@ impl<A: hal::Api> LifetimeTracker<A> {
 
         self.active.alloc().init(ActiveSubmission {
             index,
+            queue,
             last_resources,
             mapped: Vec::new(),
             encoders,
@@ -341,6 +389,43 @@ impl<A: hal::Api> LifetimeTracker<A> {
         });
     }
 
+    /// Return the index of the last submission that completed along with all
+    /// the submissions before it, given the values reached by the fence of
+    /// each hardware queue.
+    ///
+    /// Queues complete their work independently, but resources are only retired
+    /// in submission order.
+    pub fn last_done(
+        &self,
+        fence_values: &[SubmissionIndex],
+        last_submission: SubmissionIndex,
+    ) -> SubmissionIndex {
+        self.active
+            .iter()
+            .find(|a| a.index > fence_values[a.queue])
+            .map_or(last_submission, |a| a.index - 1)
+    }
+
+    /// Return the hardware queue of the submission `index`, if it is still in flight.
+    pub fn submission_queue(&self, index: SubmissionIndex) -> Option<usize> {
+        self.active
+            .iter()
+            .find(|a| a.index == index)
+            .map(|a| a.queue)
+    }
+
+    /// Return the index and hardware queue of the submissions that are not done yet,
+    /// given the values reached by the fence of each hardware queue.
+    pub fn in_flight<'a>(
+        &'a self,
+        fence_values: &'a [SubmissionIndex],
+    ) -> impl Iterator<Item = (SubmissionIndex, usize)> + 'a {
+        self.active
+            .iter()
+            .filter(move |a| a.index > fence_values[a.queue])
+            .map(|a| (a.index, a.queue))
+    }
+
     pub fn post_submit(&mut self) {
         self.suspected_resources.buffers.extend(
             self.future_suspected_buffers
@@ -409,8 +494,30 @@ impl<A: hal::Api> LifetimeTracker<A> {
         work_done_closures
     }
 
+    /// Destroy a dropped shader module, once no pipeline is compiled from it.
+    pub(super) fn release_shader_module(&mut self, raw: Arc<A::ShaderModule>) {
+        match Arc::try_unwrap(raw) {
+            Ok(raw) => self.free_resources.shader_modules.push(raw),
+            Err(raw) => self.released_shader_modules.push(raw),
+        }
+    }
+
+    /// Destroy a dropped pipeline cache, once no pipeline is compiled with it.
+    pub(super) fn release_pipeline_cache(&mut self, raw: Arc<A::PipelineCache>) {
+        match Arc::try_unwrap(raw) {
+            Ok(raw) => self.free_resources.pipeline_caches.push(raw),
+            Err(raw) => self.released_pipeline_caches.push(raw),
+        }
+    }
+
     pub fn cleanup(&mut self, device: &A::Device) {
         profiling::scope!("cleanup", "LifetimeTracker");
+        for raw in mem::take(&mut self.released_shader_modules) {
+            self.release_shader_module(raw);
+        }
+        for raw in mem::take(&mut self.released_pipeline_caches) {
+            self.release_pipeline_cache(raw);
+        }
         unsafe {
             self.free_resources.clean(device);
         }
@@ -428,6 +535,10 @@ impl<A: hal::Api> LifetimeTracker<A> {
             .map_or(&mut self.free_resources, |a| &mut a.last_resources);
         match temp_resource {
             TempResource::Buffer(raw) => resources.buffers.push(raw),
+            TempResource::StagingBuffer(raw, memory) => {
+                resources.buffers.push(raw);
+                resources.staging_memory.push(memory);
+            }
             TempResource::Texture(raw, views) => {
                 resources.texture_views.extend(views);
                 resources.textures.push(raw);
@@ -435,8 +546,12 @@ impl<A: hal::Api> LifetimeTracker<A> {
         }
     }
 
-    pub fn add_work_done_closure(&mut self, closure: SubmittedWorkDoneClosure) -> bool {
-        match self.active.last_mut() {
+    pub fn add_work_done_closure(
+        &mut self,
+        queue: usize,
+        closure: SubmittedWorkDoneClosure,
+    ) -> bool {
+        match self.active.iter_mut().rev().find(|a| a.queue == queue) {
             Some(active) => {
                 active.work_done_closures.push(closure);
                 true
@@ -735,7 +850,10 @@ impl<A: HalApi> LifetimeTracker<A> {
                         self.suspected_resources
                             .bind_group_layouts
                             .extend_from_slice(&lay.bind_group_layout_ids);
-                        self.free_resources.pipeline_layouts.push(lay.raw);
+                        // The hal object may be shared with equivalent layouts.
+                        if let Ok(raw) = Arc::try_unwrap(lay.raw) {
+                            self.free_resources.pipeline_layouts.push(raw);
+                        }
                     }
                 }
             }
@@ -756,7 +874,9 @@ impl<A: HalApi> LifetimeTracker<A> {
                         t.lock().add(trace::Action::DestroyBindGroupLayout(id.0));
                     }
                     if let Some(lay) = hub.bind_group_layouts.unregister_locked(id.0, &mut *guard) {
-                        self.free_resources.bind_group_layouts.push(lay.raw);
+                        if let Ok(raw) = Arc::try_unwrap(lay.raw) {
+                            self.free_resources.bind_group_layouts.push(raw);
+                        }
                     }
                 }
             }
@@ -785,6 +905,128 @@ impl<A: HalApi> LifetimeTracker<A> {
         }
     }
 
+    /// Free the objects of the destroyed device `device_id`, other than its
+    /// buffers, textures and command buffers.
+    ///
+    /// Their ids become invalid, and are unregistered once the user drops them.
+    /// Nothing can run on the device anymore, and the suspected resources must
+    /// have been triaged already, since they may refer to these objects.
+    pub(super) fn free_destroyed_device_objects<G: GlobalIdentityHandlerFactory>(
+        &mut self,
+        hub: &Hub<A, G>,
+        device_id: id::DeviceId,
+        trackers: &Mutex<TrackerSet>,
+        token: &mut Token<super::Device<A>>,
+    ) {
+        profiling::scope!("free_destroyed_device_objects");
+
+        {
+            let (mut guard, _) = hub.render_bundles.write(token);
+            let mut trackers = trackers.lock();
+            for (id, _) in guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.bundles.remove(id::Valid(id));
+            }
+        }
+        {
+            let (mut guard, _) = hub.bind_groups.write(token);
+            let mut trackers = trackers.lock();
+            for (id, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.bind_groups.remove(id::Valid(id));
+                self.free_resources.bind_groups.push(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.texture_views.write(token);
+            let mut trackers = trackers.lock();
+            for (id, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.views.remove(id::Valid(id));
+                self.free_resources.texture_views.push(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.samplers.write(token);
+            let mut trackers = trackers.lock();
+            for (id, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.samplers.remove(id::Valid(id));
+                self.free_resources.samplers.push(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.compute_pipelines.write(token);
+            let mut trackers = trackers.lock();
+            for (id, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.compute_pipes.remove(id::Valid(id));
+                self.free_resources.compute_pipes.push(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.render_pipelines.write(token);
+            let mut trackers = trackers.lock();
+            for (id, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.render_pipes.remove(id::Valid(id));
+                self.free_resources.render_pipes.push(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.pipeline_layouts.write(token);
+            for (_, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                // The hal object may be shared with equivalent layouts.
+                if let Ok(raw) = Arc::try_unwrap(res.raw) {
+                    self.free_resources.pipeline_layouts.push(raw);
+                }
+            }
+        }
+        {
+            let (mut guard, _) = hub.bind_group_layouts.write(token);
+            for (_, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                if let Ok(raw) = Arc::try_unwrap(res.raw) {
+                    self.free_resources.bind_group_layouts.push(raw);
+                }
+            }
+        }
+        {
+            let (mut guard, _) = hub.query_sets.write(token);
+            let mut trackers = trackers.lock();
+            for (id, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                trackers.query_sets.remove(id::Valid(id));
+                self.free_resources.query_sets.push(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.shader_modules.write(token);
+            for (_, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                self.release_shader_module(res.raw);
+            }
+        }
+        {
+            let (mut guard, _) = hub.pipeline_caches.write(token);
+            for (_, res) in
+                guard.take_matching(A::VARIANT, |res| res.device_id.value.0 == device_id)
+            {
+                self.release_pipeline_cache(res.raw);
+            }
+        }
+    }
+
     /// Determine which buffers are ready to map, and which must wait for the
     /// GPU.
     ///
@@ -852,43 +1094,44 @@ impl<A: HalApi> LifetimeTracker<A> {
                     self.free_resources.buffers.extend(buf.raw);
                 }
             } else {
-                let mapping = match std::mem::replace(
+                let mut mapping = match std::mem::replace(
                     &mut buffer.map_state,
                     resource::BufferMapState::Idle,
                 ) {
-                    resource::BufferMapState::Waiting(pending_mapping) => pending_mapping,
+                    resource::BufferMapState::Mapped(mapping) => mapping,
                     // Mapping cancelled
                     resource::BufferMapState::Idle => continue,
-                    // Mapping queued at least twice by map -> unmap -> map
-                    // and was already successfully mapped below
-                    active @ resource::BufferMapState::Active { .. } => {
-                        buffer.map_state = active;
-                        continue;
-                    }
                     _ => panic!("No pending mapping."),
                 };
-                let status = if mapping.range.start != mapping.range.end {
-                    log::debug!("Buffer {:?} map state -> Active", buffer_id);
-                    let host = mapping.op.host;
-                    let size = mapping.range.end - mapping.range.start;
-                    match super::map_buffer(raw, buffer, mapping.range.start, size, host) {
-                        Ok(ptr) => {
-                            buffer.map_state = resource::BufferMapState::Active {
-                                ptr,
-                                range: mapping.range.start..mapping.range.start + size,
-                                host,
-                            };
-                            resource::BufferMapAsyncStatus::Success
-                        }
-                        Err(e) => {
-                            log::error!("Mapping failed {:?}", e);
-                            resource::BufferMapAsyncStatus::Error
+                // Ranges requested after an earlier request was queued are
+                // mapped along with it, so later entries may find nothing pending.
+                for pending in mem::take(&mut mapping.pending) {
+                    let status = if pending.range.start != pending.range.end {
+                        log::debug!("Buffer {:?} range {:?} -> Active", buffer_id, pending.range);
+                        let host = pending.op.host;
+                        let size = pending.range.end - pending.range.start;
+                        match super::map_buffer(
+                            raw,
+                            buffer,
+                            &mut mapping,
+                            pending.range.start,
+                            size,
+                            host,
+                        ) {
+                            Ok(()) => resource::BufferMapAsyncStatus::Success,
+                            Err(e) => {
+                                log::error!("Mapping failed {:?}", e);
+                                resource::BufferMapAsyncStatus::Error
+                            }
                         }
-                    }
-                } else {
-                    resource::BufferMapAsyncStatus::Success
-                };
-                pending_callbacks.push((mapping.op, status));
+                    } else {
+                        resource::BufferMapAsyncStatus::Success
+                    };
+                    pending_callbacks.push((pending.op, status));
+                }
+                if !mapping.active.is_empty() {
+                    buffer.map_state = resource::BufferMapState::Mapped(mapping);
+                }
             }
         }
         pending_callbacks
@@ -7,10 +7,12 @@ use crate::{
         BufferInitTracker, BufferInitTrackerAction, MemoryInitKind, TextureInitRange,
         TextureInitTracker, TextureInitTrackerAction,
     },
-    instance, pipeline, present, resource,
-    track::{BufferState, TextureSelector, TextureState, TrackerSet, UsageConflict},
+    instance, pipeline, pipeline_cache, present, resource,
+    track::{
+        BindGroupState, BufferState, TextureSelector, TextureState, TrackerSet, UsageConflict,
+    },
     validation::{self, check_buffer_usage, check_texture_usage},
-    FastHashMap, Label, LabelHelpers as _, LifeGuard, MultiRefCount, RefCount, Stored,
+    FastHashMap, Label, LabelHelpers as _, LifeGuard, MultiRefCount, RawString, RefCount, Stored,
     SubmissionIndex, DOWNLEVEL_ERROR_MESSAGE,
 };
 
@@ -22,10 +24,27 @@ use smallvec::SmallVec;
 use thiserror::Error;
 use wgt::{BufferAddress, TextureFormat, TextureViewDimension};
 
-use std::{borrow::Cow, iter, marker::PhantomData, mem, num::NonZeroU32, ops::Range, ptr};
+use std::{
+    borrow::Cow,
+    ffi::CString,
+    fmt,
+    hash::{Hash as _, Hasher as _},
+    iter,
+    marker::PhantomData,
+    mem,
+    num::NonZeroU32,
+    ops::Range,
+    ptr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 mod life;
 pub mod queue;
+pub mod timeline;
 #[cfg(any(feature = "trace", feature = "replay"))]
 pub mod trace;
 
@@ -37,8 +56,12 @@ const CLEANUP_WAIT_MS: u32 = 5000;
 
 const IMPLICIT_FAILURE: &str = "failed implicit";
 const EP_FAILURE: &str = "EP is invalid";
+const DEVICE_LOST_MESSAGE: &str = "device was lost by the backend";
+const TABLE_BINDING_TYPES: &str =
+    "SampledTextureArray, ReadonlyStorageTextureArray or WriteonlyStorageTextureArray";
 
 pub type DeviceDescriptor<'a> = wgt::DeviceDescriptor<Label<'a>>;
+pub type QueueDescriptor<'a> = wgt::QueueDescriptor<Label<'a>>;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -133,48 +156,290 @@ pub type BufferMapPendingClosure = (resource::BufferMapOperation, resource::Buff
 pub struct UserClosures {
     pub mappings: Vec<BufferMapPendingClosure>,
     pub submissions: SmallVec<[queue::SubmittedWorkDoneClosure; 1]>,
+    pub device_lost_invocations: SmallVec<[DeviceLostInvocation; 1]>,
 }
 
 impl UserClosures {
     fn extend(&mut self, other: Self) {
         self.mappings.extend(other.mappings);
         self.submissions.extend(other.submissions);
+        self.device_lost_invocations
+            .extend(other.device_lost_invocations);
     }
 
     unsafe fn fire(self) {
         //Note: this logic is specifically moved out of `handle_mapping()` in order to
         // have nothing locked by the time we execute users callback code.
         for (operation, status) in self.mappings {
-            (operation.callback)(status, operation.user_data);
+            operation.callback.call(status);
         }
         for closure in self.submissions {
             (closure.callback)(closure.user_data);
         }
+        for invocation in self.device_lost_invocations {
+            invocation.call();
+        }
+    }
+}
+
+#[repr(C)]
+pub struct DeviceLostClosureC {
+    pub callback:
+        unsafe extern "C" fn(user_data: *mut u8, reason: wgt::DeviceLostReason, message: RawString),
+    pub user_data: *mut u8,
+}
+
+unsafe impl Send for DeviceLostClosureC {}
+
+/// Callback invoked once a device is lost, with the reason and a message.
+///
+/// It's called at most once, without any internal locks held.
+pub struct DeviceLostClosure {
+    // We wrap this so creating the enum in the C variant can be unsafe,
+    // allowing our call function to be safe.
+    inner: DeviceLostClosureInner,
+}
+
+enum DeviceLostClosureInner {
+    Rust {
+        callback: Box<dyn FnOnce(wgt::DeviceLostReason, String) + Send + 'static>,
+    },
+    C {
+        inner: DeviceLostClosureC,
+    },
+}
+
+impl DeviceLostClosure {
+    pub fn from_rust(
+        callback: Box<dyn FnOnce(wgt::DeviceLostReason, String) + Send + 'static>,
+    ) -> Self {
+        Self {
+            inner: DeviceLostClosureInner::Rust { callback },
+        }
+    }
+
+    /// # Safety
+    ///
+    /// - The callback pointer must be valid to call with the provided `user_data` pointer.
+    /// - Both pointers must point to `'static` data, as the callback may be called at
+    ///   an unspecified time, on any thread.
+    pub unsafe fn from_c(inner: DeviceLostClosureC) -> Self {
+        Self {
+            inner: DeviceLostClosureInner::C { inner },
+        }
+    }
+
+    pub(crate) fn call(self, reason: wgt::DeviceLostReason, message: String) {
+        match self.inner {
+            DeviceLostClosureInner::Rust { callback } => callback(reason, message),
+            // SAFETY: the contract of `from_c` guarantees this is sound
+            DeviceLostClosureInner::C { inner } => unsafe {
+                let message = CString::new(message).unwrap_or_default();
+                (inner.callback)(inner.user_data, reason, message.as_ptr())
+            },
+        }
+    }
+}
+
+impl fmt::Debug for DeviceLostClosure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            DeviceLostClosureInner::Rust { .. } => f.write_str("DeviceLostClosure::Rust"),
+            DeviceLostClosureInner::C { .. } => f.write_str("DeviceLostClosure::C"),
+        }
+    }
+}
+
+/// A device lost callback, along with the arguments to call it with.
+#[derive(Debug)]
+pub struct DeviceLostInvocation {
+    closure: DeviceLostClosure,
+    reason: wgt::DeviceLostReason,
+    message: String,
+}
+
+impl DeviceLostInvocation {
+    fn call(self) {
+        self.closure.call(self.reason, self.message);
+    }
+}
+
+/// Callback invoked with the memory report of a device, see
+/// [`Global::device_set_memory_threshold_callback`].
+pub type MemoryThresholdCallback = Box<dyn FnMut(wgt::MemoryReport) + Send + 'static>;
+
+struct MemoryThreshold {
+    threshold: u64,
+    callback: Arc<Mutex<MemoryThresholdCallback>>,
+    /// Whether the memory used was above the threshold when last checked.
+    above: bool,
+    /// Whether the callback was busy when the threshold was last crossed,
+    /// so it's invoked on the next check instead.
+    missed: bool,
+}
+
+/// Running totals of the memory of the live resources of a device, kept
+/// up to date by the [`resource::MemoryUse`] of each resource.
+#[derive(Debug, Default)]
+struct MemoryTotals {
+    buffers: Arc<AtomicU64>,
+    textures: Arc<AtomicU64>,
+    /// Staging buffers of the queue writes until the GPU is done with them,
+    /// and of the buffers mapped at creation.
+    staging: Arc<AtomicU64>,
+    query_sets: Arc<AtomicU64>,
+}
+
+/// Size of the memory of a query set, as described by `desc`.
+fn query_set_memory_size(desc: &wgt::QuerySetDescriptor<()>) -> u64 {
+    let values_per_query = match desc.ty {
+        wgt::QueryType::PipelineStatistics(types) => types.bits().count_ones(),
+        wgt::QueryType::Occlusion | wgt::QueryType::Timestamp => 1,
+    };
+    desc.count as u64 * values_per_query as u64 * wgt::QUERY_SIZE as u64
+}
+
+/// A render pipeline left to compile by a [`pipeline::PendingPipeline`].
+struct DeferredRenderPipeline<A: hal::Api> {
+    id: id::RenderPipelineId,
+    pending: pipeline::PendingRenderPipeline<A>,
+    abandoned: pipeline::AbandonedPipelines<A>,
+}
+
+impl<G: GlobalIdentityHandlerFactory, A: HalApi>
+    pipeline::DeferredPipeline<G, pipeline::CreateRenderPipelineError>
+    for DeferredRenderPipeline<A>
+{
+    fn compile(self: Box<Self>, global: &Global<G>) -> Option<pipeline::CreateRenderPipelineError> {
+        global.compile_render_pipeline::<A>(self.id, self.pending)
+    }
+
+    fn abandon(self: Box<Self>) {
+        let this = *self;
+        this.abandoned
+            .lock()
+            .push(pipeline::AbandonedPipeline::Render(this.id, this.pending));
+    }
+}
+
+/// A compute pipeline left to compile by a [`pipeline::PendingPipeline`].
+struct DeferredComputePipeline<A: hal::Api> {
+    id: id::ComputePipelineId,
+    pending: pipeline::PendingComputePipeline<A>,
+    abandoned: pipeline::AbandonedPipelines<A>,
+}
+
+impl<G: GlobalIdentityHandlerFactory, A: HalApi>
+    pipeline::DeferredPipeline<G, pipeline::CreateComputePipelineError>
+    for DeferredComputePipeline<A>
+{
+    fn compile(
+        self: Box<Self>,
+        global: &Global<G>,
+    ) -> Option<pipeline::CreateComputePipelineError> {
+        global.compile_compute_pipeline::<A>(self.id, self.pending)
+    }
+
+    fn abandon(self: Box<Self>) {
+        let this = *self;
+        this.abandoned
+            .lock()
+            .push(pipeline::AbandonedPipeline::Compute(this.id, this.pending));
+    }
+}
+
+/// Whether a device is lost, and who to tell about it.
+#[derive(Debug, Default)]
+struct DeviceLost {
+    closure: Option<DeviceLostClosure>,
+    /// Set once the device is lost, after which it can't be used anymore.
+    reason: Option<(wgt::DeviceLostReason, String)>,
+    /// Callback of a loss noticed while the hub was locked, invoked by the
+    /// next [`Device::maintain`].
+    pending_invocation: Option<DeviceLostInvocation>,
+}
+
+impl DeviceLost {
+    /// Mark the device as lost.
+    ///
+    /// Returns the user callback to invoke once nothing is locked anymore,
+    /// unless the device was already lost or has no callback.
+    fn lose(
+        &mut self,
+        reason: wgt::DeviceLostReason,
+        message: &str,
+    ) -> Option<DeviceLostInvocation> {
+        if self.reason.is_some() {
+            return None;
+        }
+        log::info!("Device {:?} is lost: {}", reason, message);
+        self.reason = Some((reason, message.to_string()));
+        self.closure.take().map(|closure| DeviceLostInvocation {
+            closure,
+            reason,
+            message: message.to_string(),
+        })
+    }
+
+    /// Mark the device as lost, keeping the user callback for
+    /// [`DeviceLost::take_pending`].
+    fn lose_later(&mut self, reason: wgt::DeviceLostReason, message: &str) {
+        if let Some(invocation) = self.lose(reason, message) {
+            self.pending_invocation = Some(invocation);
+        }
+    }
+
+    /// Take the user callback left by [`DeviceLost::lose_later`].
+    fn take_pending(&mut self) -> Option<DeviceLostInvocation> {
+        self.pending_invocation.take()
+    }
+
+    /// Replace the callback invoked once the device is lost.
+    ///
+    /// If the device is already lost, returns the invocation of `closure` instead.
+    fn set_closure(&mut self, closure: DeviceLostClosure) -> Option<DeviceLostInvocation> {
+        match self.reason {
+            Some((reason, ref message)) => Some(DeviceLostInvocation {
+                closure,
+                reason,
+                message: message.clone(),
+            }),
+            None => {
+                self.closure = Some(closure);
+                None
+            }
+        }
     }
 }
 
+/// Make `offset..offset + size` of `buffer` accessible from the host.
+///
+/// `mapping` is the state of `buffer`, taken out of it by the caller.
 fn map_buffer<A: hal::Api>(
     raw: &A::Device,
     buffer: &mut resource::Buffer<A>,
+    mapping: &mut resource::BufferMapping,
     offset: BufferAddress,
     size: BufferAddress,
     kind: HostMap,
-) -> Result<ptr::NonNull<u8>, resource::BufferAccessError> {
-    let mapping = unsafe {
+) -> Result<(), resource::BufferAccessError> {
+    let hal_mapping = unsafe {
         raw.map_buffer(buffer.raw.as_ref().unwrap(), offset..offset + size)
             .map_err(DeviceError::from)?
     };
+    mapping.is_coherent = hal_mapping.is_coherent;
+    let ptr = hal_mapping.ptr;
 
-    buffer.sync_mapped_writes = match kind {
+    let needs_flush = match kind {
         HostMap::Read if !mapping.is_coherent => unsafe {
             raw.invalidate_mapped_ranges(
                 buffer.raw.as_ref().unwrap(),
                 iter::once(offset..offset + size),
             );
-            None
+            false
         },
-        HostMap::Write if !mapping.is_coherent => Some(offset..offset + size),
-        _ => None,
+        HostMap::Write => !mapping.is_coherent,
+        HostMap::Read => false,
     };
 
     assert_eq!(offset % wgt::COPY_BUFFER_ALIGNMENT, 0);
@@ -187,15 +452,13 @@ fn map_buffer<A: hal::Api>(
     // we instead just initialize the memory here and make sure it is GPU visible, so this happens at max only once for every buffer region.
     //
     // If this is a write mapping zeroing out the memory here is the only reasonable way as all data is pushed to GPU anyways.
-    let zero_init_needs_flush_now = mapping.is_coherent && buffer.sync_mapped_writes.is_none(); // No need to flush if it is flushed later anyways.
+    let zero_init_needs_flush_now = mapping.is_coherent && !needs_flush; // No need to flush if it is flushed later anyways.
     for uninitialized_range in buffer.initialization_status.drain(offset..(size + offset)) {
         let num_bytes = uninitialized_range.end - uninitialized_range.start;
         unsafe {
             ptr::write_bytes(
-                mapping
-                    .ptr
-                    .as_ptr()
-                    .offset(uninitialized_range.start as isize),
+                ptr.as_ptr()
+                    .offset((uninitialized_range.start - offset) as isize),
                 0,
                 num_bytes as usize,
             )
@@ -210,7 +473,13 @@ fn map_buffer<A: hal::Api>(
         }
     }
 
-    Ok(mapping.ptr)
+    mapping.active.push(resource::BufferActiveMapping {
+        range: offset..offset + size,
+        ptr,
+        host: kind,
+        needs_flush,
+    });
+    Ok(())
 }
 
 struct CommandAllocator<A: hal::Api> {
@@ -256,6 +525,9 @@ impl<A: hal::Api> CommandAllocator<A> {
 /// 1. `life_tracker` is locked after `hub.devices`, enforced by the type system
 /// 1. `self.trackers` is locked last (unenforced)
 /// 1. `self.trace` is locked last (unenforced)
+/// 1. `self.layout_pool` is locked last (unenforced)
+/// 1. `self.lost` is locked last (unenforced)
+/// 1. `self.memory_threshold` is locked last (unenforced)
 pub struct Device<A: hal::Api> {
     pub(crate) raw: A::Device,
     pub(crate) adapter_id: Stored<id::AdapterId>,
@@ -275,13 +547,34 @@ pub struct Device<A: hal::Api> {
     ref_count: RefCount,
 
     command_allocator: Mutex<CommandAllocator<A>>,
+    /// Index of the last submission, shared by all the queues of this device.
     pub(crate) active_submission_index: SubmissionIndex,
     fence: A::Fence,
+    /// Hardware queues opened in addition to `queue`.
+    extra_queues: Vec<queue::HardwareQueue<A>>,
+    /// For each logical queue, the hardware queue it submits to, where `0` is `queue`.
+    ///
+    /// The first logical queue is always `queue`. If fewer hardware queues
+    /// were available than requested, several logical queues share one.
+    queue_map: Vec<usize>,
+    /// For each hardware queue, the index of its last submission.
+    last_submissions: Vec<SubmissionIndex>,
+    /// For each hardware queue, the last submission of every other hardware
+    /// queue it waits for on the GPU, which its new submissions are ordered after.
+    waited_submissions: Vec<Vec<SubmissionIndex>>,
+    /// Timeline values to wait for before the next submission of a logical queue.
+    timeline_waits: Mutex<Vec<(u32, Arc<timeline::Timeline>, u64)>>,
+    lost: Mutex<DeviceLost>,
+    memory_threshold: Mutex<Option<MemoryThreshold>>,
+    memory_totals: MemoryTotals,
+    abandoned_pipelines: pipeline::AbandonedPipelines<A>,
 
     /// All live resources allocated with this [`Device`].
     ///
     /// Has to be locked temporarily only (locked last)
     pub(crate) trackers: Mutex<TrackerSet>,
+    /// Hal layouts of this device, shared between equivalent layouts.
+    layout_pool: Mutex<binding_model::LayoutPool<A>>,
     // Life tracker should be locked right after the device and before anything else.
     life_tracker: Mutex<life::LifetimeTracker<A>>,
     /// Temporary storage for resource management functions. Cleared at the end
@@ -327,6 +620,77 @@ impl<A: hal::Api> Device<A> {
     }
 }
 
+/// Parse the source of a shader module and validate it with the given capabilities.
+pub(crate) fn validate_shader_source(
+    desc: &pipeline::ShaderModuleDescriptor,
+    source: pipeline::ShaderModuleSource,
+    caps: naga::valid::Capabilities,
+) -> Result<(naga::Module, naga::valid::ModuleInfo), pipeline::CreateShaderModuleError> {
+    let (module, source, info) = match source {
+        pipeline::ShaderModuleSource::Wgsl(code) => {
+            profiling::scope!("naga::wgsl::parse_str");
+            let module = naga::front::wgsl::parse_str(&code).map_err(|inner| {
+                pipeline::CreateShaderModuleError::Parsing(pipeline::ShaderError {
+                    source: code.to_string(),
+                    label: desc.label.as_ref().map(|l| l.to_string()),
+                    inner,
+                })
+            })?;
+            (module, code.into_owned(), None)
+        }
+        pipeline::ShaderModuleSource::Naga(module) => (module, String::new(), None),
+        #[cfg(feature = "serialized-ir")]
+        pipeline::ShaderModuleSource::SerializedIr(data) => {
+            profiling::scope!("serialized_ir::decode");
+            let validated = crate::serialized_ir::decode(&data)?;
+            // The validation results only hold if the module doesn't rely on
+            // capabilities that are missing here, otherwise validate it again.
+            let info = if caps.contains(validated.capabilities) {
+                Some(validated.info)
+            } else {
+                None
+            };
+            (validated.module, String::new(), info)
+        }
+    };
+
+    let info = match info {
+        Some(info) => info,
+        None => {
+            profiling::scope!("naga::validate");
+            naga::valid::Validator::new(naga::valid::ValidationFlags::all(), caps)
+                .validate(&module)
+                .map_err(|inner| {
+                    pipeline::CreateShaderModuleError::Validation(pipeline::ShaderError {
+                        source,
+                        label: desc.label.as_ref().map(|l| l.to_string()),
+                        inner,
+                    })
+                })?
+        }
+    };
+    Ok((module, info))
+}
+
+pub(crate) fn naga_capabilities(features: wgt::Features) -> naga::valid::Capabilities {
+    use naga::valid::Capabilities as Caps;
+
+    let mut caps = Caps::empty();
+    caps.set(
+        Caps::PUSH_CONSTANT,
+        features.contains(wgt::Features::PUSH_CONSTANTS),
+    );
+    caps.set(
+        Caps::FLOAT64,
+        features.contains(wgt::Features::SHADER_FLOAT64),
+    );
+    caps.set(
+        Caps::PRIMITIVE_INDEX,
+        features.contains(wgt::Features::SHADER_PRIMITIVE_INDEX),
+    );
+    caps
+}
+
 impl<A: HalApi> Device<A> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
@@ -335,6 +699,7 @@ impl<A: HalApi> Device<A> {
         alignments: hal::Alignments,
         downlevel: wgt::DownlevelCapabilities,
         desc: &DeviceDescriptor,
+        extra_queue_descs: &[QueueDescriptor],
         trace_path: Option<&std::path::Path>,
     ) -> Result<Self, CreateDeviceError> {
         #[cfg(not(feature = "trace"))]
@@ -344,6 +709,25 @@ impl<A: HalApi> Device<A> {
         let fence =
             unsafe { open.device.create_fence() }.map_err(|_| CreateDeviceError::OutOfMemory)?;
 
+        let mut extra_queues = Vec::with_capacity(open.extra_queues.len());
+        for raw in open.extra_queues {
+            let fence = unsafe { open.device.create_fence() }
+                .map_err(|_| CreateDeviceError::OutOfMemory)?;
+            extra_queues.push(queue::HardwareQueue { raw, fence });
+        }
+        let extra_queue_count = extra_queue_descs.len();
+        if extra_queues.len() < extra_queue_count {
+            log::info!(
+                "Requested {} extra queues, mapping them onto {} hardware ones",
+                extra_queue_count,
+                extra_queues.len()
+            );
+        }
+        let hardware_queue_count = 1 + extra_queues.len();
+        let queue_map = (0..=extra_queue_count)
+            .map(|index| index % hardware_queue_count)
+            .collect();
+
         let mut com_alloc = CommandAllocator {
             free_encoders: Vec::new(),
         };
@@ -394,7 +778,17 @@ impl<A: HalApi> Device<A> {
             command_allocator: Mutex::new(com_alloc),
             active_submission_index: 0,
             fence,
+            extra_queues,
+            queue_map,
+            last_submissions: vec![0; hardware_queue_count],
+            waited_submissions: vec![vec![0; hardware_queue_count]; hardware_queue_count],
+            timeline_waits: Mutex::new(Vec::new()),
+            lost: Mutex::new(DeviceLost::default()),
+            memory_threshold: Mutex::new(None),
+            memory_totals: MemoryTotals::default(),
+            abandoned_pipelines: Arc::default(),
             trackers: Mutex::new(TrackerSet::new(A::VARIANT)),
+            layout_pool: Mutex::new(binding_model::LayoutPool::new()),
             life_tracker: Mutex::new(life::LifetimeTracker::new()),
             temp_suspected: life::SuspectedResources::default(),
             #[cfg(feature = "trace")]
@@ -402,6 +796,7 @@ impl<A: HalApi> Device<A> {
                 Ok(mut trace) => {
                     trace.add(trace::Action::Init {
                         desc: desc.clone(),
+                        queues: extra_queue_descs.to_vec(),
                         backend: A::VARIANT,
                     });
                     Some(Mutex::new(trace))
@@ -427,9 +822,308 @@ impl<A: HalApi> Device<A> {
         self.life_tracker.lock()
     }
 
+    /// Return the hardware queue the logical queue `index` submits to, if any.
+    fn hardware_queue_index(&self, index: u32) -> Option<usize> {
+        self.queue_map.get(index as usize).copied()
+    }
+
+    /// Return whether the logical queue `index` is the only one submitting
+    /// to its hardware queue, if it exists.
+    fn has_own_hardware_queue(&self, index: u32) -> Option<bool> {
+        let hardware_index = self.hardware_queue_index(index)?;
+        Some(
+            self.queue_map
+                .iter()
+                .filter(|&&i| i == hardware_index)
+                .count()
+                == 1,
+        )
+    }
+
+    fn hardware_fence(&self, hardware_index: usize) -> &A::Fence {
+        match hardware_index {
+            0 => &self.fence,
+            _ => &self.extra_queues[hardware_index - 1].fence,
+        }
+    }
+
+    /// Return the values currently reached by the fence of each hardware queue.
+    fn fence_values(&self) -> Result<Vec<SubmissionIndex>, DeviceError> {
+        (0..self.last_submissions.len())
+            .map(|index| unsafe {
+                self.raw
+                    .get_fence_value(self.hardware_fence(index))
+                    .map_err(DeviceError::from)
+            })
+            .collect()
+    }
+
+    /// Return the index of the last submission that is done, along with all the
+    /// submissions before it.
+    fn last_done_index(
+        &self,
+        life_tracker: &life::LifetimeTracker<A>,
+    ) -> Result<SubmissionIndex, DeviceError> {
+        if self.extra_queues.is_empty() {
+            return unsafe {
+                self.raw
+                    .get_fence_value(&self.fence)
+                    .map_err(DeviceError::from)
+            };
+        }
+        let fence_values = self.fence_values()?;
+        Ok(life_tracker.last_done(&fence_values, self.active_submission_index))
+    }
+
+    /// Check if the submission `index` and all the ones before it are done.
+    fn is_done(&self, index: SubmissionIndex) -> Result<bool, DeviceError> {
+        for (hardware_index, &last) in self.last_submissions.iter().enumerate() {
+            let value = unsafe {
+                self.raw
+                    .get_fence_value(self.hardware_fence(hardware_index))
+            }
+            .map_err(DeviceError::from)?;
+            if value < index.min(last) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Return the index of the last submission to the hardware queue of the
+    /// logical queue `queue_index`.
+    fn last_submission(&self, queue_index: u32) -> Option<SubmissionIndex> {
+        self.hardware_queue_index(queue_index)
+            .map(|hardware_index| self.last_submissions[hardware_index])
+    }
+
+    /// Check if the submission `index` is done, regardless of the earlier ones.
+    fn is_submission_done<'this, 'token: 'this>(
+        &'this self,
+        index: SubmissionIndex,
+        token: &mut Token<'token, Self>,
+    ) -> Result<bool, DeviceError> {
+        // Submissions are only retired once done.
+        let hardware_index = match self.lock_life(token).submission_queue(index) {
+            Some(hardware_index) => hardware_index,
+            None => return Ok(true),
+        };
+        let value = unsafe {
+            self.raw
+                .get_fence_value(self.hardware_fence(hardware_index))
+        }
+        .map_err(DeviceError::from)?;
+        Ok(value >= index)
+    }
+
+    /// Wait for the submission `index` to be done, regardless of the earlier ones.
+    ///
+    /// Returns false if the timeout was reached.
+    fn wait_for_submission<'this, 'token: 'this>(
+        &'this self,
+        index: SubmissionIndex,
+        timeout_ms: u32,
+        token: &mut Token<'token, Self>,
+    ) -> Result<bool, DeviceError> {
+        let hardware_index = match self.lock_life(token).submission_queue(index) {
+            Some(hardware_index) => hardware_index,
+            None => return Ok(true),
+        };
+        unsafe {
+            self.raw
+                .wait(self.hardware_fence(hardware_index), index, timeout_ms)
+                .map_err(DeviceError::from)
+        }
+    }
+
+    /// Make the next submission to the logical queue `queue_index` wait on the GPU
+    /// for the submission `index` to be done.
+    ///
+    /// Returns false if the backend can't wait on the GPU.
+    fn queue_wait_for_submission<'this, 'token: 'this>(
+        &'this mut self,
+        queue_index: u32,
+        index: SubmissionIndex,
+        token: &mut Token<'token, Self>,
+    ) -> Result<bool, DeviceError> {
+        let hardware_index = self
+            .hardware_queue_index(queue_index)
+            .ok_or(DeviceError::Invalid)?;
+        let source_index = match self.lock_life(token).submission_queue(index) {
+            Some(source_index) => source_index,
+            None => return Ok(true),
+        };
+        // Submissions to the same hardware queue are executed in order.
+        if source_index == hardware_index {
+            return Ok(true);
+        }
+
+        let Self {
+            ref mut queue,
+            ref fence,
+            ref mut extra_queues,
+            ..
+        } = *self;
+        let mut hardware_queues = iter::once((queue, fence))
+            .chain(
+                extra_queues
+                    .iter_mut()
+                    .map(|extra| (&mut extra.raw, &extra.fence)),
+            )
+            .collect::<Vec<_>>();
+        let fence = hardware_queues[source_index].1;
+        let queue = &mut *hardware_queues[hardware_index].0;
+        let waited = unsafe { queue.wait_for_fence(fence, index) }.map_err(DeviceError::from)?;
+        if waited {
+            let last_waited = &mut self.waited_submissions[hardware_index][source_index];
+            *last_waited = index.max(*last_waited);
+        }
+        Ok(waited)
+    }
+
+    fn add_timeline_wait(
+        &self,
+        queue_index: u32,
+        timeline: Arc<timeline::Timeline>,
+        value: u64,
+    ) -> Result<(), DeviceError> {
+        if self.hardware_queue_index(queue_index).is_none() {
+            return Err(DeviceError::Invalid);
+        }
+        self.timeline_waits
+            .lock()
+            .push((queue_index, timeline, value));
+        Ok(())
+    }
+
+    fn take_timeline_waits(&self, queue_index: u32) -> Vec<(Arc<timeline::Timeline>, u64)> {
+        let mut waits = self.timeline_waits.lock();
+        let mut taken = Vec::new();
+        waits.retain(|&(index, ref timeline, value)| {
+            if index == queue_index {
+                taken.push((Arc::clone(timeline), value));
+                false
+            } else {
+                true
+            }
+        });
+        taken
+    }
+
+    /// Returns false once the device is lost, after which it can't be used.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.lost.lock().reason.is_none()
+    }
+
+    /// Return the device `id` from `storage`, unless it's invalid or lost.
+    fn get_usable(
+        storage: &Storage<Self, id::DeviceId>,
+        id: id::DeviceId,
+    ) -> Result<&Self, DeviceError> {
+        let device = storage.get(id).map_err(|_| DeviceError::Invalid)?;
+        if device.is_valid() {
+            Ok(device)
+        } else {
+            Err(DeviceError::Lost)
+        }
+    }
+
+    /// Mutable version of [`Device::get_usable`].
+    fn get_usable_mut(
+        storage: &mut Storage<Self, id::DeviceId>,
+        id: id::DeviceId,
+    ) -> Result<&mut Self, DeviceError> {
+        let device = storage.get_mut(id).map_err(|_| DeviceError::Invalid)?;
+        if device.is_valid() {
+            Ok(device)
+        } else {
+            Err(DeviceError::Lost)
+        }
+    }
+
+    /// Report the memory of the live resources, without the heaps.
+    fn memory_totals(&self) -> wgt::MemoryReport {
+        let totals = &self.memory_totals;
+        wgt::MemoryReport {
+            buffers: totals.buffers.load(Ordering::Relaxed),
+            textures: totals.textures.load(Ordering::Relaxed),
+            staging: totals.staging.load(Ordering::Relaxed),
+            query_sets: totals.query_sets.load(Ordering::Relaxed),
+            heaps: Vec::new(),
+        }
+    }
+
+    /// Mark the device as lost, see [`DeviceLost::lose`].
+    fn lose(&self, reason: wgt::DeviceLostReason, message: &str) -> Option<DeviceLostInvocation> {
+        self.lost.lock().lose(reason, message)
+    }
+
+    /// Mark the device `id` as lost if `error` says it is.
+    ///
+    /// The caller holds the hub locks, so the user callback is only invoked
+    /// by the next [`Device::maintain`].
+    fn lose_on_error(
+        storage: &Storage<Self, id::DeviceId>,
+        id: id::DeviceId,
+        error: &impl DeviceErrorSource,
+    ) {
+        if let Some(&DeviceError::Lost) = error.device_error() {
+            if let Ok(device) = storage.get(id) {
+                device
+                    .lost
+                    .lock()
+                    .lose_later(wgt::DeviceLostReason::Unknown, DEVICE_LOST_MESSAGE);
+            }
+        }
+    }
+
+    /// Replace the callback invoked once the device is lost, see
+    /// [`DeviceLost::set_closure`].
+    fn set_lost_closure(&self, closure: DeviceLostClosure) -> Option<DeviceLostInvocation> {
+        self.lost.lock().set_closure(closure)
+    }
+
+    /// Wait for the submission `index` and all the ones before it to be done,
+    /// on every hardware queue.
+    ///
+    /// The timeout covers the waits on all the queues together, `!0` waits
+    /// without a limit. Returns false if the timeout was reached.
+    fn wait_for_index(&self, index: SubmissionIndex, timeout_ms: u32) -> Result<bool, DeviceError> {
+        // Only keep track of the time when there are several queues to wait on.
+        let deadline = if timeout_ms != !0 && self.last_submissions.len() > 1 {
+            Some(Instant::now() + Duration::from_millis(timeout_ms.into()))
+        } else {
+            None
+        };
+        // Fences are signaled with the indices of their own queue's submissions only,
+        // so never wait past the last one.
+        for (hardware_index, &last) in self.last_submissions.iter().enumerate() {
+            let remaining_ms = match deadline {
+                // Never more than `timeout_ms`, so it fits.
+                Some(deadline) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_millis() as u32,
+                None => timeout_ms,
+            };
+            let done = unsafe {
+                self.raw
+                    .wait(
+                        self.hardware_fence(hardware_index),
+                        index.min(last),
+                        remaining_ms,
+                    )
+                    .map_err(DeviceError::from)?
+            };
+            if !done {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// Check this device for completed commands.
     ///
-    /// Return a pair `(closures, queue_empty)`, where:
+    /// Return a triple `(closures, queue_empty, result)`, where:
     ///
     /// - `closures` is a list of actions to take: mapping buffers, notifying the user
     ///
@@ -437,13 +1131,22 @@ impl<A: HalApi> Device<A> {
     ///   submissions still in flight. (We have to take the locks needed to
     ///   produce this information for other reasons, so we might as well just
     ///   return it to our callers.)
+    ///
+    /// - `result` tells if the wait requested by `maintain` timed out.
     fn maintain<'this, 'token: 'this, G: GlobalIdentityHandlerFactory>(
         &'this self,
         hub: &Hub<A, G>,
-        force_wait: bool,
+        maintain: wgt::Maintain<SubmissionIndex>,
         token: &mut Token<'token, Self>,
-    ) -> Result<(UserClosures, bool), WaitIdleError> {
+    ) -> Result<(UserClosures, bool, wgt::MaintainResult), WaitIdleError> {
         profiling::scope!("maintain", "Device");
+        let (render_pipeline_ids, compute_pipeline_ids) = self.release_abandoned_pipelines();
+        for id in render_pipeline_ids {
+            hub.render_pipelines.resume(id).assign_error("", token);
+        }
+        for id in compute_pipeline_ids {
+            hub.compute_pipelines.resume(id).assign_error("", token);
+        }
         let mut life_tracker = self.lock_life(token);
 
         // Normally, `temp_suspected` exists only to save heap
@@ -464,20 +1167,21 @@ impl<A: HalApi> Device<A> {
         );
         life_tracker.triage_mapped(hub, token);
 
-        let last_done_index = if force_wait {
-            let current_index = self.active_submission_index;
-            unsafe {
-                self.raw
-                    .wait(&self.fence, current_index, CLEANUP_WAIT_MS)
-                    .map_err(DeviceError::from)?
-            };
-            current_index
-        } else {
-            unsafe {
-                self.raw
-                    .get_fence_value(&self.fence)
-                    .map_err(DeviceError::from)?
+        let mut device_lost_invocations = SmallVec::new();
+        device_lost_invocations.extend(self.lost.lock().take_pending());
+        // Nothing runs on a lost device anymore, so all the submissions are done.
+        let lost = (self.active_submission_index, wgt::MaintainResult::Complete);
+        let (last_done_index, result) = if self.is_valid() {
+            match self.wait_for_maintain(maintain, &life_tracker) {
+                Err(DeviceError::Lost) => {
+                    device_lost_invocations
+                        .extend(self.lose(wgt::DeviceLostReason::Unknown, DEVICE_LOST_MESSAGE));
+                    lost
+                }
+                other => other?,
             }
+        } else {
+            lost
         };
 
         let submission_closures =
@@ -488,8 +1192,45 @@ impl<A: HalApi> Device<A> {
         let closures = UserClosures {
             mappings: mapping_closures,
             submissions: submission_closures,
+            device_lost_invocations,
         };
-        Ok((closures, life_tracker.queue_empty()))
+        Ok((closures, life_tracker.queue_empty(), result))
+    }
+
+    /// Wait for the submissions `maintain` asks for.
+    ///
+    /// Returns the index of the last submission done, and whether the wait timed out.
+    fn wait_for_maintain(
+        &self,
+        maintain: wgt::Maintain<SubmissionIndex>,
+        life_tracker: &life::LifetimeTracker<A>,
+    ) -> Result<(SubmissionIndex, wgt::MaintainResult), DeviceError> {
+        Ok(match maintain {
+            wgt::Maintain::Wait => {
+                let current_index = self.active_submission_index;
+                self.wait_for_index(current_index, CLEANUP_WAIT_MS)?;
+                (current_index, wgt::MaintainResult::Complete)
+            }
+            wgt::Maintain::WaitForSubmissionIndex(index) => {
+                self.wait_for_index(index, !0)?;
+                let last_done_index = index.max(self.last_done_index(life_tracker)?);
+                (last_done_index, wgt::MaintainResult::Complete)
+            }
+            wgt::Maintain::WaitForSubmissionIndexTimeout(index, timeout) => {
+                // `!0` means no timeout to hal.
+                let timeout_ms = timeout.as_millis().min(u32::MAX as u128 - 1) as u32;
+                let result = if self.wait_for_index(index, timeout_ms)? {
+                    wgt::MaintainResult::Complete
+                } else {
+                    wgt::MaintainResult::TimedOut
+                };
+                (self.last_done_index(life_tracker)?, result)
+            }
+            wgt::Maintain::Poll => (
+                self.last_done_index(life_tracker)?,
+                wgt::MaintainResult::Complete,
+            ),
+        })
     }
 
     fn untrack<'this, 'token: 'this, G: GlobalIdentityHandlerFactory>(
@@ -622,9 +1363,12 @@ impl<A: HalApi> Device<A> {
             usage: desc.usage,
             size: desc.size,
             initialization_status: BufferInitTracker::new(desc.size),
-            sync_mapped_writes: None,
             map_state: resource::BufferMapState::Idle,
             life_guard: LifeGuard::new(desc.label.borrow_or_default()),
+            memory: Some(resource::MemoryUse::new(
+                &self.memory_totals.buffers,
+                desc.size,
+            )),
         })
     }
 
@@ -639,6 +1383,7 @@ impl<A: HalApi> Device<A> {
     ) -> resource::Texture<A> {
         debug_assert_eq!(self_id.backend(), A::VARIANT);
 
+        let memory = resource::MemoryUse::new(&self.memory_totals.textures, desc.memory_size());
         resource::Texture {
             inner: resource::TextureInner::Native {
                 raw: Some(hal_texture),
@@ -660,6 +1405,7 @@ impl<A: HalApi> Device<A> {
             },
             life_guard: LifeGuard::new(desc.label.borrow_or_default()),
             clear_mode,
+            memory: Some(memory),
         }
     }
 
@@ -1110,50 +1856,46 @@ impl<A: HalApi> Device<A> {
         desc: &pipeline::ShaderModuleDescriptor<'a>,
         source: pipeline::ShaderModuleSource<'a>,
     ) -> Result<pipeline::ShaderModule<A>, pipeline::CreateShaderModuleError> {
-        let (module, source) = match source {
-            pipeline::ShaderModuleSource::Wgsl(code) => {
-                profiling::scope!("naga::wgsl::parse_str");
-                let module = naga::front::wgsl::parse_str(&code).map_err(|inner| {
-                    pipeline::CreateShaderModuleError::Parsing(pipeline::ShaderError {
-                        source: code.to_string(),
-                        label: desc.label.as_ref().map(|l| l.to_string()),
-                        inner,
-                    })
-                })?;
-                (module, code.into_owned())
+        let mut hasher = pipeline_cache::KeyHasher::default();
+        desc.shader_bound_checks.runtime_checks().hash(&mut hasher);
+        let cacheable = match source {
+            pipeline::ShaderModuleSource::Wgsl(ref code) => {
+                code.hash(&mut hasher);
+                true
             }
-            pipeline::ShaderModuleSource::Naga(module) => (module, String::new()),
+            // The IR can only be identified by the code it was parsed from.
+            pipeline::ShaderModuleSource::Naga(_) => match desc.source_hash {
+                Some(source_hash) => {
+                    hasher.write_u64(source_hash);
+                    true
+                }
+                None => false,
+            },
+            #[cfg(feature = "serialized-ir")]
+            pipeline::ShaderModuleSource::SerializedIr(ref data) => {
+                data.hash(&mut hasher);
+                true
+            }
+        };
+        let cache_hash = if cacheable {
+            Some(hasher.finish())
+        } else {
+            None
         };
 
-        use naga::valid::Capabilities as Caps;
-        profiling::scope!("naga::validate");
-
-        let mut caps = Caps::empty();
-        caps.set(
-            Caps::PUSH_CONSTANT,
-            self.features.contains(wgt::Features::PUSH_CONSTANTS),
-        );
-        caps.set(
-            Caps::FLOAT64,
-            self.features.contains(wgt::Features::SHADER_FLOAT64),
-        );
-        caps.set(
-            Caps::PRIMITIVE_INDEX,
-            self.features
-                .contains(wgt::Features::SHADER_PRIMITIVE_INDEX),
-        );
-        let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), caps)
-            .validate(&module)
-            .map_err(|inner| {
-                pipeline::CreateShaderModuleError::Validation(pipeline::ShaderError {
-                    source,
-                    label: desc.label.as_ref().map(|l| l.to_string()),
-                    inner,
-                })
-            })?;
-        let interface =
-            validation::Interface::new(&module, &info, self.features, self.limits.clone());
-        let hal_shader = hal::ShaderInput::Naga(hal::NagaShader { module, info });
+        let (module, info) =
+            validate_shader_source(desc, source, naga_capabilities(self.features))?;
+        let interface =
+            validation::Interface::new(&module, &info, self.features, self.limits.clone());
+        let specialization = if interface.is_specializable() {
+            Some(pipeline::SpecializationSource {
+                module: module.clone(),
+                runtime_checks: desc.shader_bound_checks.runtime_checks(),
+            })
+        } else {
+            None
+        };
+        let hal_shader = hal::ShaderInput::Naga(hal::NagaShader { module, info });
 
         let hal_desc = hal::ShaderModuleDescriptor {
             label: desc.label.borrow_option(),
@@ -1175,17 +1917,101 @@ impl<A: HalApi> Device<A> {
         };
 
         Ok(pipeline::ShaderModule {
-            raw,
+            raw: Arc::new(raw),
             device_id: Stored {
                 value: id::Valid(self_id),
                 ref_count: self.life_guard.add_ref(),
             },
             interface: Some(interface),
+            specialization,
+            cache_hash,
             #[cfg(debug_assertions)]
             label: desc.label.borrow_or_default().to_string(),
         })
     }
 
+    /// Validate the pipeline-overridable constants of a stage, returning the values
+    /// to specialize the shader module with, if there are any.
+    fn resolve_pipeline_constants(
+        shader_module: &pipeline::ShaderModule<A>,
+        constants: &pipeline::PipelineConstants,
+        stage: wgt::ShaderStages,
+        entry_point: &str,
+    ) -> Result<validation::StageSpecialization, validation::StageError> {
+        if constants.is_empty() {
+            return Ok(validation::StageSpecialization::default());
+        }
+        match shader_module.interface {
+            Some(ref interface) => {
+                interface.resolve_pipeline_constants(constants, stage, entry_point)
+            }
+            // Pass-through modules don't expose any overridable constants.
+            None => Err(validation::StageError::UnknownPipelineConstant(
+                constants[0].0.clone(),
+            )),
+        }
+    }
+
+    /// Returns the key of a pipeline stage's translation in pipeline caches,
+    /// if the shader module can be cached.
+    fn stage_cache_key(
+        shader_module: &pipeline::ShaderModule<A>,
+        entry_point: &str,
+        specialization: &validation::StageSpecialization,
+        stage: wgt::ShaderStages,
+        layout_hash: u64,
+        topology: Option<wgt::PrimitiveTopology>,
+    ) -> Option<pipeline::StageCacheKey> {
+        let mut hasher = pipeline_cache::KeyHasher::default();
+        hasher.write_u64(shader_module.cache_hash?);
+        specialization.hash(&mut hasher);
+        let module = hasher.finish();
+
+        let mut hasher = pipeline_cache::KeyHasher::default();
+        hasher.write_u64(layout_hash);
+        hasher.write_u32(stage.bits());
+        topology.hash(&mut hasher);
+        Some(pipeline::StageCacheKey {
+            module,
+            variant: hasher.finish(),
+            entry_point: entry_point.to_string(),
+        })
+    }
+
+    /// Compile a copy of `shader_module` with the overridden constants of `specialization`.
+    ///
+    /// The returned module is only needed for pipeline creation,
+    /// and has to be destroyed right after.
+    fn create_specialized_shader_module(
+        &self,
+        shader_module: &pipeline::ShaderModule<A>,
+        specialization: &validation::StageSpecialization,
+    ) -> Result<A::ShaderModule, validation::StageError> {
+        let source = shader_module
+            .specialization
+            .as_ref()
+            .ok_or(validation::StageError::InvalidModule)?;
+        let module = validation::specialize_module(&source.module, specialization);
+
+        profiling::scope!("naga::validate");
+        let caps = naga_capabilities(self.features);
+        let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), caps)
+            .validate(&module)
+            .map_err(|err| validation::StageError::Specialization(err.to_string()))?;
+
+        let hal_desc = hal::ShaderModuleDescriptor {
+            label: None,
+            runtime_checks: source.runtime_checks,
+        };
+        let hal_shader = hal::ShaderInput::Naga(hal::NagaShader { module, info });
+        unsafe { self.raw.create_shader_module(&hal_desc, hal_shader) }.map_err(|err| match err {
+            hal::ShaderError::Device(err) => {
+                validation::StageError::Specialization(err.to_string())
+            }
+            hal::ShaderError::Compilation(msg) => validation::StageError::Specialization(msg),
+        })
+    }
+
     #[allow(unused_unsafe)]
     unsafe fn create_shader_module_spirv<'a>(
         &self,
@@ -1215,29 +2041,49 @@ impl<A: HalApi> Device<A> {
         };
 
         Ok(pipeline::ShaderModule {
-            raw,
+            raw: Arc::new(raw),
             device_id: Stored {
                 value: id::Valid(self_id),
                 ref_count: self.life_guard.add_ref(),
             },
             interface: None,
+            specialization: None,
+            cache_hash: None,
             #[cfg(debug_assertions)]
             label: desc.label.borrow_or_default().to_string(),
         })
     }
 
     fn deduplicate_bind_group_layout(
-        self_id: id::DeviceId,
+        &self,
         entry_map: &binding_model::BindEntryMap,
         guard: &Storage<binding_model::BindGroupLayout<A>, id::BindGroupLayoutId>,
     ) -> Option<id::BindGroupLayoutId> {
-        guard
-            .iter(self_id.backend())
-            .find(|&(_, bgl)| bgl.device_id.value.0 == self_id && bgl.entries == *entry_map)
-            .map(|(id, value)| {
-                value.multi_ref_count.inc();
-                id
-            })
+        let entries = binding_model::sorted_entries(entry_map);
+        let (id, key) = self.layout_pool.lock().bind_group_layout_id(&entries)?;
+        // The layout may have been destroyed since it was recorded.
+        if !guard.contains(id.0) {
+            return None;
+        }
+        match guard.get(id.0) {
+            Ok(bgl) if bgl.key == key => {
+                bgl.multi_ref_count.inc();
+                Some(id.0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Record a newly registered bind group layout for deduplication.
+    fn register_bind_group_layout(
+        &self,
+        id: id::Valid<id::BindGroupLayoutId>,
+        bgl: &binding_model::BindGroupLayout<A>,
+    ) {
+        let entries = binding_model::sorted_entries(&bgl.entries);
+        self.layout_pool
+            .lock()
+            .set_bind_group_layout_id(&entries, bgl.key, id);
     }
 
     fn get_introspection_bind_group_layouts<'a>(
@@ -1421,21 +2267,6 @@ impl<A: HalApi> Device<A> {
                 })?;
         }
 
-        let bgl_flags = conv::bind_group_layout_flags(self.features);
-
-        let mut hal_bindings = entry_map.values().cloned().collect::<Vec<_>>();
-        hal_bindings.sort_by_key(|b| b.binding);
-        let hal_desc = hal::BindGroupLayoutDescriptor {
-            label,
-            flags: bgl_flags,
-            entries: &hal_bindings,
-        };
-        let raw = unsafe {
-            self.raw
-                .create_bind_group_layout(&hal_desc)
-                .map_err(DeviceError::from)?
-        };
-
         let mut count_validator = binding_model::BindingTypeMaxCountValidator::default();
         for entry in entry_map.values() {
             count_validator.add_binding(entry);
@@ -1446,8 +2277,27 @@ impl<A: HalApi> Device<A> {
             .validate(&self.limits)
             .map_err(binding_model::CreateBindGroupLayoutError::TooManyBindings)?;
 
+        let bgl_flags = conv::bind_group_layout_flags(self.features);
+
+        let hal_bindings = binding_model::sorted_entries(&entry_map);
+        let hal_desc = hal::BindGroupLayoutDescriptor {
+            label,
+            flags: bgl_flags,
+            entries: &hal_bindings,
+        };
+        // Equivalent layouts share the hal object, labeled by the first of them.
+        let (raw, key) = self
+            .layout_pool
+            .lock()
+            .bind_group_layout(&hal_bindings, || unsafe {
+                self.raw
+                    .create_bind_group_layout(&hal_desc)
+                    .map_err(DeviceError::from)
+            })?;
+
         Ok(binding_model::BindGroupLayout {
             raw,
+            key,
             device_id: Stored {
                 value: id::Valid(self_id),
                 ref_count: self.life_guard.add_ref(),
@@ -1845,7 +2695,7 @@ impl<A: HalApi> Device<A> {
 
         let hal_desc = hal::BindGroupDescriptor {
             label: desc.label.borrow_option(),
-            layout: &layout.raw,
+            layout: &*layout.raw,
             entries: &hal_entries,
             buffers: &hal_buffers,
             samplers: &hal_samplers,
@@ -1867,6 +2717,7 @@ impl<A: HalApi> Device<A> {
                 ref_count: self.life_guard.add_ref(),
             },
             layout_id: id::Valid(desc.layout),
+            layout_key: layout.key,
             life_guard: LifeGuard::new(desc.label.borrow_or_default()),
             used,
             used_buffer_ranges,
@@ -1878,9 +2729,307 @@ impl<A: HalApi> Device<A> {
                 .keys()
                 .flat_map(|binding| late_buffer_binding_sizes.get(binding).cloned())
                 .collect(),
+            table: None,
+        })
+    }
+
+    fn create_descriptor_table(
+        &self,
+        self_id: id::DeviceId,
+        layout: &binding_model::BindGroupLayout<A>,
+        desc: &binding_model::DescriptorTableDescriptor,
+    ) -> Result<binding_model::BindGroup<A>, binding_model::CreateDescriptorTableError> {
+        use crate::binding_model::CreateDescriptorTableError as Error;
+
+        self.require_features(
+            wgt::Features::TEXTURE_BINDING_ARRAY | wgt::Features::PARTIALLY_BOUND_BINDING_ARRAY,
+        )?;
+
+        let entry = match layout.entries.values().next() {
+            Some(entry) if layout.entries.len() == 1 => entry,
+            _ => return Err(Error::InvalidTableLayout),
+        };
+        let count = match (entry.count, entry.ty) {
+            (Some(count), wgt::BindingType::Texture { .. })
+            | (Some(count), wgt::BindingType::StorageTexture { .. }) => count.get(),
+            _ => return Err(Error::InvalidTableLayout),
+        };
+
+        // All the slots start out unbound, which the partially bound
+        // layout allows.
+        let hal_desc = hal::BindGroupDescriptor {
+            label: desc.label.borrow_option(),
+            layout: &*layout.raw,
+            entries: &[],
+            buffers: &[],
+            samplers: &[],
+            textures: &[],
+        };
+        let raw = unsafe {
+            self.raw
+                .create_bind_group(&hal_desc)
+                .map_err(DeviceError::from)?
+        };
+
+        // manually add a dependency on BGL
+        layout.multi_ref_count.inc();
+
+        Ok(binding_model::BindGroup {
+            raw,
+            device_id: Stored {
+                value: id::Valid(self_id),
+                ref_count: self.life_guard.add_ref(),
+            },
+            layout_id: id::Valid(desc.layout),
+            layout_key: layout.key,
+            life_guard: LifeGuard::new(desc.label.borrow_or_default()),
+            used: TrackerSet::new(A::VARIANT),
+            used_buffer_ranges: Vec::new(),
+            used_texture_ranges: Vec::new(),
+            dynamic_binding_info: Vec::new(),
+            late_buffer_binding_sizes: Vec::new(),
+            table: Some(binding_model::DescriptorTable {
+                binding: entry.binding,
+                slots: vec![None; count as usize],
+                views: FastHashMap::default(),
+                ordered_views: Vec::new(),
+                texture_views: FastHashMap::default(),
+                version: 0,
+            }),
         })
     }
 
+    /// Write `views` into the slots of a descriptor table starting at
+    /// `first_slot`, clearing the slots given `None`.
+    ///
+    /// Only the written slots and the views they held are visited. Returns
+    /// the views and textures the table no longer references, so that the
+    /// caller can suspect them.
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    fn update_descriptor_table(
+        &self,
+        table_id: id::BindGroupId,
+        bind_group: &mut binding_model::BindGroup<A>,
+        layout: &binding_model::BindGroupLayout<A>,
+        first_slot: u32,
+        views: &[Option<id::TextureViewId>],
+        texture_guard: &parking_lot::lock_api::RwLockReadGuard<
+            parking_lot::RawRwLock,
+            Storage<resource::Texture<A>, id::TextureId>,
+        >,
+        texture_view_guard: &Storage<resource::TextureView<A>, id::TextureViewId>,
+    ) -> Result<
+        (
+            Vec<id::Valid<id::TextureViewId>>,
+            Vec<id::Valid<id::TextureId>>,
+        ),
+        binding_model::UpdateDescriptorTableError,
+    > {
+        use crate::binding_model::{CreateBindGroupError, UpdateDescriptorTableError as Error};
+
+        let binding_model::BindGroup {
+            ref mut raw,
+            ref life_guard,
+            ref mut used,
+            ref mut used_texture_ranges,
+            ref mut table,
+            ..
+        } = *bind_group;
+        let table = match *table {
+            Some(ref mut table) => table,
+            None => return Err(Error::NotATable(table_id)),
+        };
+        let end = first_slot as usize + views.len();
+        if end > table.slots.len() {
+            return Err(Error::SlotsOutOfBounds {
+                start: first_slot,
+                end: end.min(u32::MAX as usize) as u32,
+                count: table.slots.len() as u32,
+            });
+        }
+
+        if !self.is_done(life_guard.life_count())? {
+            return Err(Error::InUse);
+        }
+
+        // Validate the new views against the layout before touching the table.
+        let decl = &layout.entries[&table.binding];
+        for &id in views.iter().flatten() {
+            let view = texture_view_guard
+                .get(id)
+                .map_err(|_| CreateBindGroupError::InvalidTextureView(id))?;
+            let (pub_usage, _) =
+                Self::texture_use_parameters(table.binding, decl, view, TABLE_BINDING_TYPES)?;
+            check_texture_usage(texture_guard[view.parent_id.value].desc.usage, pub_usage)
+                .map_err(CreateBindGroupError::from)?;
+        }
+
+        let mut released_views = Vec::new();
+        let mut released_textures = Vec::new();
+        for (offset, &new) in views.iter().enumerate() {
+            let new = new.map(id::Valid);
+            if let Some(id) = new {
+                Self::add_table_view(
+                    table,
+                    decl,
+                    id,
+                    used,
+                    used_texture_ranges,
+                    texture_guard,
+                    texture_view_guard,
+                )?;
+            }
+            let slot = first_slot as usize + offset;
+            if let Some(old) = mem::replace(&mut table.slots[slot], new) {
+                Self::release_table_view(
+                    table,
+                    old,
+                    used,
+                    used_texture_ranges,
+                    texture_view_guard,
+                    &mut released_views,
+                    &mut released_textures,
+                )?;
+            }
+        }
+
+        // Cleared slots are left as they are in the backend, since shaders
+        // aren't allowed to access them.
+        let mut slot = first_slot;
+        for run in views.split(Option::is_none) {
+            if !run.is_empty() {
+                let hal_textures = run
+                    .iter()
+                    .flatten()
+                    .map(|&id| {
+                        let view = &texture_view_guard[id::Valid(id)];
+                        hal::TextureBinding {
+                            view: &view.raw,
+                            usage: table.views[&id::Valid(id)].usage,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                unsafe {
+                    self.raw.update_bind_group(
+                        raw,
+                        &*layout.raw,
+                        table.binding,
+                        slot,
+                        &hal_textures,
+                    );
+                }
+            }
+            slot += run.len() as u32 + 1;
+        }
+
+        table.version += 1;
+        Ok((released_views, released_textures))
+    }
+
+    /// Count one more slot of `table` holding `view_id`, adding the view
+    /// to the usages of the table if it's new to it.
+    #[allow(clippy::too_many_arguments)]
+    fn add_table_view(
+        table: &mut binding_model::DescriptorTable,
+        decl: &wgt::BindGroupLayoutEntry,
+        view_id: id::Valid<id::TextureViewId>,
+        used: &mut TrackerSet,
+        used_texture_ranges: &mut Vec<TextureInitTrackerAction>,
+        texture_guard: &parking_lot::lock_api::RwLockReadGuard<
+            parking_lot::RawRwLock,
+            Storage<resource::Texture<A>, id::TextureId>,
+        >,
+        texture_view_guard: &Storage<resource::TextureView<A>, id::TextureViewId>,
+    ) -> Result<(), binding_model::CreateBindGroupError> {
+        if let Some(table_view) = table.views.get_mut(&view_id) {
+            table_view.slot_count += 1;
+            return Ok(());
+        }
+
+        let view = used
+            .views
+            .use_extend(texture_view_guard, view_id.0, (), ())
+            .map_err(|_| binding_model::CreateBindGroupError::InvalidTextureView(view_id.0))?;
+        let (pub_usage, internal_use) =
+            Self::texture_use_parameters(table.binding, decl, view, TABLE_BINDING_TYPES)?;
+        Self::create_texture_binding(
+            view,
+            texture_guard,
+            internal_use,
+            pub_usage,
+            used,
+            used_texture_ranges,
+        )?;
+
+        table.views.insert(
+            view_id,
+            binding_model::TableView {
+                slot_count: 1,
+                index: table.ordered_views.len(),
+                usage: internal_use,
+            },
+        );
+        table.ordered_views.push(view_id);
+        table
+            .texture_views
+            .entry(view.parent_id.value)
+            .or_default()
+            .push(view_id);
+        Ok(())
+    }
+
+    /// Count one less slot of `table` holding `view_id`, removing the view
+    /// from the usages of the table once no slot holds it anymore.
+    #[allow(clippy::too_many_arguments)]
+    fn release_table_view(
+        table: &mut binding_model::DescriptorTable,
+        view_id: id::Valid<id::TextureViewId>,
+        used: &mut TrackerSet,
+        used_texture_ranges: &mut Vec<TextureInitTrackerAction>,
+        texture_view_guard: &Storage<resource::TextureView<A>, id::TextureViewId>,
+        released_views: &mut Vec<id::Valid<id::TextureViewId>>,
+        released_textures: &mut Vec<id::Valid<id::TextureId>>,
+    ) -> Result<(), binding_model::CreateBindGroupError> {
+        let table_view = table.views.get_mut(&view_id).unwrap();
+        table_view.slot_count -= 1;
+        if table_view.slot_count != 0 {
+            return Ok(());
+        }
+
+        let index = table.views.remove(&view_id).unwrap().index;
+        table.ordered_views.swap_remove(index);
+        used_texture_ranges.swap_remove(index);
+        if let Some(&moved) = table.ordered_views.get(index) {
+            table.views.get_mut(&moved).unwrap().index = index;
+        }
+        used.views.remove(view_id);
+        released_views.push(view_id);
+
+        // Texture usages are merged across views, so rebuild them from the
+        // views of the texture that are left.
+        let texture_id = texture_view_guard[view_id].parent_id.value;
+        used.textures.remove(texture_id);
+        let texture_views = table.texture_views.get_mut(&texture_id).unwrap();
+        texture_views.retain(|&id| id != view_id);
+        if texture_views.is_empty() {
+            table.texture_views.remove(&texture_id);
+            released_textures.push(texture_id);
+            return Ok(());
+        }
+        for &id in texture_views.iter() {
+            let view = &texture_view_guard[id];
+            used.textures
+                .change_extend(
+                    texture_id,
+                    &view.parent_id.ref_count,
+                    view.selector.clone(),
+                    table.views[&id].usage,
+                )
+                .map_err(UsageConflict::from)?;
+        }
+        Ok(())
+    }
+
     fn check_array_binding(
         features: wgt::Features,
         count: Option<NonZeroU32>,
@@ -2109,8 +3258,13 @@ impl<A: HalApi> Device<A> {
         let bgl_vec = desc
             .bind_group_layouts
             .iter()
-            .map(|&id| &bgl_guard.get(id).unwrap().raw)
+            .map(|&id| &*bgl_guard.get(id).unwrap().raw)
             .collect::<Vec<_>>();
+        let bind_group_layout_keys = desc
+            .bind_group_layouts
+            .iter()
+            .map(|&id| bgl_guard.get(id).unwrap().key)
+            .collect::<ArrayVec<_, { hal::MAX_BIND_GROUPS }>>();
         let hal_desc = hal::PipelineLayoutDescriptor {
             label: desc.label.borrow_option(),
             flags: hal::PipelineLayoutFlags::BASE_VERTEX_INSTANCE,
@@ -2118,11 +3272,15 @@ impl<A: HalApi> Device<A> {
             push_constant_ranges: desc.push_constant_ranges.as_ref(),
         };
 
-        let raw = unsafe {
-            self.raw
-                .create_pipeline_layout(&hal_desc)
-                .map_err(DeviceError::from)?
-        };
+        let raw = self.layout_pool.lock().pipeline_layout(
+            &bind_group_layout_keys,
+            &desc.push_constant_ranges,
+            || unsafe {
+                self.raw
+                    .create_pipeline_layout(&hal_desc)
+                    .map_err(DeviceError::from)
+            },
+        )?;
 
         Ok(binding_model::PipelineLayout {
             raw,
@@ -2131,6 +3289,7 @@ impl<A: HalApi> Device<A> {
                 ref_count: self.life_guard.add_ref(),
             },
             life_guard: LifeGuard::new(desc.label.borrow_or_default()),
+            bind_group_layout_keys,
             bind_group_layout_ids: desc
                 .bind_group_layouts
                 .iter()
@@ -2172,13 +3331,15 @@ impl<A: HalApi> Device<A> {
         }
 
         for (bgl_id, map) in ids.group_ids.iter_mut().zip(derived_group_layouts) {
-            match Device::deduplicate_bind_group_layout(self_id, &map, bgl_guard) {
+            match self.deduplicate_bind_group_layout(&map, bgl_guard) {
                 Some(dedup_id) => {
                     *bgl_id = dedup_id;
                 }
                 None => {
                     let bgl = self.create_bind_group_layout(self_id, None, map)?;
                     bgl_guard.force_replace(*bgl_id, bgl);
+                    let valid_id = id::Valid(*bgl_id);
+                    self.register_bind_group_layout(valid_id, &bgl_guard[valid_id]);
                 }
             };
         }
@@ -2193,14 +3354,15 @@ impl<A: HalApi> Device<A> {
         Ok(ids.root_id)
     }
 
-    fn create_compute_pipeline<G: GlobalIdentityHandlerFactory>(
+    /// Validate a compute pipeline, and gather what its compilation needs.
+    fn prepare_compute_pipeline<G: GlobalIdentityHandlerFactory>(
         &self,
         self_id: id::DeviceId,
         desc: &pipeline::ComputePipelineDescriptor,
         implicit_context: Option<ImplicitPipelineContext>,
         hub: &Hub<A, G>,
         token: &mut Token<Self>,
-    ) -> Result<pipeline::ComputePipeline<A>, pipeline::CreateComputePipelineError> {
+    ) -> Result<pipeline::PendingComputePipeline<A>, pipeline::CreateComputePipelineError> {
         //TODO: only lock mutable if the layout is derived
         let (mut pipeline_layout_guard, mut token) = hub.pipeline_layouts.write(token);
         let (mut bgl_guard, mut token) = hub.bind_group_layouts.write(&mut token);
@@ -2221,11 +3383,20 @@ impl<A: HalApi> Device<A> {
         let mut shader_binding_sizes = FastHashMap::default();
 
         let io = validation::StageIo::default();
-        let (shader_module_guard, _) = hub.shader_modules.read(&mut token);
+        let (shader_module_guard, mut token) = hub.shader_modules.read(&mut token);
+        let (pipeline_cache_guard, _) = hub.pipeline_caches.read(&mut token);
 
         let shader_module = shader_module_guard
             .get(desc.stage.module)
             .map_err(|_| validation::StageError::InvalidModule)?;
+        let cache = match desc.cache {
+            Some(cache_id) => Some(
+                pipeline_cache_guard
+                    .get(cache_id)
+                    .map_err(|_| pipeline::CreateComputePipelineError::InvalidCache)?,
+            ),
+            None => None,
+        };
 
         {
             let flag = wgt::ShaderStages::COMPUTE;
@@ -2254,6 +3425,12 @@ impl<A: HalApi> Device<A> {
                 )?;
             }
         }
+        let specialization = Self::resolve_pipeline_constants(
+            shader_module,
+            &desc.stage.constants,
+            wgt::ShaderStages::COMPUTE,
+            &desc.stage.entry_point,
+        )?;
 
         let pipeline_layout_id = match desc.layout {
             Some(id) => id,
@@ -2272,32 +3449,34 @@ impl<A: HalApi> Device<A> {
         let late_sized_buffer_groups =
             Device::make_late_sized_buffer_groups(&shader_binding_sizes, layout, &*bgl_guard);
 
-        let pipeline_desc = hal::ComputePipelineDescriptor {
-            label: desc.label.borrow_option(),
-            layout: &layout.raw,
-            stage: hal::ProgrammableStage {
-                entry_point: desc.stage.entry_point.as_ref(),
-                module: &shader_module.raw,
-            },
-        };
-
-        let raw =
-            unsafe { self.raw.create_compute_pipeline(&pipeline_desc) }.map_err(
-                |err| match err {
-                    hal::PipelineError::Device(error) => {
-                        pipeline::CreateComputePipelineError::Device(error.into())
-                    }
-                    hal::PipelineError::Linkage(_stages, msg) => {
-                        pipeline::CreateComputePipelineError::Internal(msg)
-                    }
-                    hal::PipelineError::EntryPoint(_stage) => {
-                        pipeline::CreateComputePipelineError::Internal(EP_FAILURE.to_string())
-                    }
-                },
+        let stage_cache = cache.and_then(|cache| {
+            let key = Self::stage_cache_key(
+                shader_module,
+                &desc.stage.entry_point,
+                &specialization,
+                wgt::ShaderStages::COMPUTE,
+                pipeline_cache::hash_layout(layout, &*bgl_guard),
+                None,
             )?;
+            Some(cache.stage(key))
+        });
 
-        let pipeline = pipeline::ComputePipeline {
-            raw,
+        let specialized_module = if specialization.is_empty() {
+            None
+        } else {
+            Some(self.create_specialized_shader_module(shader_module, &specialization)?)
+        };
+
+        Ok(pipeline::PendingComputePipeline {
+            label: desc.label.borrow_option().map(str::to_string),
+            layout: Arc::clone(&layout.raw),
+            stage: pipeline::PendingStage {
+                module: Arc::clone(&shader_module.raw),
+                specialized_module,
+                entry_point: desc.stage.entry_point.to_string(),
+                cache: stage_cache,
+            },
+            cache: cache.map(|cache| Arc::clone(&cache.raw)),
             layout_id: Stored {
                 value: id::Valid(pipeline_layout_id),
                 ref_count: layout.life_guard.add_ref(),
@@ -2307,12 +3486,62 @@ impl<A: HalApi> Device<A> {
                 ref_count: self.life_guard.add_ref(),
             },
             late_sized_buffer_groups,
-            life_guard: LifeGuard::new(desc.label.borrow_or_default()),
+        })
+    }
+
+    /// Compile a compute pipeline that passed validation.
+    ///
+    /// This only calls into the backend, without locking any storage.
+    fn compile_compute_pipeline(
+        &self,
+        pending: pipeline::PendingComputePipeline<A>,
+    ) -> Result<pipeline::ComputePipeline<A>, pipeline::CreateComputePipelineError> {
+        let pipeline::PendingComputePipeline {
+            label,
+            layout,
+            stage,
+            cache,
+            layout_id,
+            device_id,
+            late_sized_buffer_groups,
+        } = pending;
+
+        let pipeline_desc = hal::ComputePipelineDescriptor {
+            label: label.as_deref(),
+            layout: &*layout,
+            stage: stage.to_hal(),
+            cache: cache.as_deref(),
+        };
+        let raw = unsafe { self.raw.create_compute_pipeline(&pipeline_desc) };
+        // The layout has to be released before the reference keeping it registered.
+        drop(layout);
+        if let Some(module) = stage.specialized_module {
+            unsafe { self.raw.destroy_shader_module(module) };
+        }
+        let raw = raw.map_err(|err| match err {
+            hal::PipelineError::Device(error) => {
+                pipeline::CreateComputePipelineError::Device(error.into())
+            }
+            hal::PipelineError::Linkage(_stages, msg) => {
+                pipeline::CreateComputePipelineError::Internal(msg)
+            }
+            hal::PipelineError::EntryPoint(_stage) => {
+                pipeline::CreateComputePipelineError::Internal(EP_FAILURE.to_string())
+            }
+        })?;
+
+        let pipeline = pipeline::ComputePipeline {
+            raw,
+            layout_id,
+            device_id,
+            late_sized_buffer_groups,
+            life_guard: LifeGuard::new(label.as_deref().unwrap_or_default()),
         };
         Ok(pipeline)
     }
 
-    fn create_render_pipeline<G: GlobalIdentityHandlerFactory>(
+    /// Validate a render pipeline, and gather what its compilation needs.
+    fn prepare_render_pipeline<G: GlobalIdentityHandlerFactory>(
         &self,
         self_id: id::DeviceId,
         adapter: &crate::instance::Adapter<A>,
@@ -2320,7 +3549,7 @@ impl<A: HalApi> Device<A> {
         implicit_context: Option<ImplicitPipelineContext>,
         hub: &Hub<A, G>,
         token: &mut Token<Self>,
-    ) -> Result<pipeline::RenderPipeline<A>, pipeline::CreateRenderPipelineError> {
+    ) -> Result<pipeline::PendingRenderPipeline<A>, pipeline::CreateRenderPipelineError> {
         use wgt::TextureFormatFeatureFlags as Tfff;
 
         //TODO: only lock mutable if the layout is derived
@@ -2382,10 +3611,10 @@ impl<A: HalApi> Device<A> {
                     stride: vb_state.array_stride,
                 });
             }
-            vertex_buffers.alloc().init(hal::VertexBufferLayout {
+            vertex_buffers.alloc().init(pipeline::VertexBufferLayout {
                 array_stride: vb_state.array_stride,
                 step_mode: vb_state.step_mode,
-                attributes: vb_state.attributes.as_ref(),
+                attributes: Cow::Owned(vb_state.attributes.to_vec()),
             });
 
             for attribute in vb_state.attributes.iter() {
@@ -2535,7 +3764,16 @@ impl<A: HalApi> Device<A> {
             sc
         };
 
-        let (shader_module_guard, _) = hub.shader_modules.read(&mut token);
+        let (shader_module_guard, mut token) = hub.shader_modules.read(&mut token);
+        let (pipeline_cache_guard, _) = hub.pipeline_caches.read(&mut token);
+        let cache = match desc.cache {
+            Some(cache_id) => Some(
+                pipeline_cache_guard
+                    .get(cache_id)
+                    .map_err(|_| pipeline::CreateRenderPipelineError::InvalidCache)?,
+            ),
+            None => None,
+        };
 
         let vertex_stage = {
             let stage = &desc.vertex.stage;
@@ -2578,10 +3816,14 @@ impl<A: HalApi> Device<A> {
                 validated_stages |= flag;
             }
 
-            hal::ProgrammableStage {
-                module: &shader_module.raw,
-                entry_point: stage.entry_point.as_ref(),
-            }
+            let specialization = Self::resolve_pipeline_constants(
+                shader_module,
+                &stage.constants,
+                flag,
+                &stage.entry_point,
+            )
+            .map_err(|error| pipeline::CreateRenderPipelineError::Stage { stage: flag, error })?;
+            (shader_module, stage.entry_point.as_ref(), specialization)
         };
 
         let fragment_stage = match desc.fragment {
@@ -2625,10 +3867,21 @@ impl<A: HalApi> Device<A> {
                     }
                 }
 
-                Some(hal::ProgrammableStage {
-                    module: &shader_module.raw,
-                    entry_point: fragment.stage.entry_point.as_ref(),
-                })
+                let specialization = Self::resolve_pipeline_constants(
+                    shader_module,
+                    &fragment.stage.constants,
+                    flag,
+                    &fragment.stage.entry_point,
+                )
+                .map_err(|error| pipeline::CreateRenderPipelineError::Stage {
+                    stage: flag,
+                    error,
+                })?;
+                Some((
+                    shader_module,
+                    fragment.stage.entry_point.as_ref(),
+                    specialization,
+                ))
             }
             None => None,
         };
@@ -2690,35 +3943,66 @@ impl<A: HalApi> Device<A> {
         let late_sized_buffer_groups =
             Device::make_late_sized_buffer_groups(&shader_binding_sizes, layout, &*bgl_guard);
 
-        let pipeline_desc = hal::RenderPipelineDescriptor {
-            label: desc.label.borrow_option(),
-            layout: &layout.raw,
-            vertex_buffers: &vertex_buffers,
-            vertex_stage,
-            primitive: desc.primitive,
-            depth_stencil: desc.depth_stencil.clone(),
-            multisample: desc.multisample,
-            fragment_stage,
-            color_targets,
-            multiview: desc.multiview,
+        let (vertex_stage_cache, fragment_stage_cache) = match cache {
+            Some(cache) => {
+                let layout_hash = pipeline_cache::hash_layout(layout, &*bgl_guard);
+                let topology = Some(desc.primitive.topology);
+                let vertex_key = Self::stage_cache_key(
+                    vertex_stage.0,
+                    vertex_stage.1,
+                    &vertex_stage.2,
+                    wgt::ShaderStages::VERTEX,
+                    layout_hash,
+                    topology,
+                );
+                let fragment_key = fragment_stage.as_ref().and_then(
+                    |&(shader_module, entry_point, ref specialization)| {
+                        Self::stage_cache_key(
+                            shader_module,
+                            entry_point,
+                            specialization,
+                            wgt::ShaderStages::FRAGMENT,
+                            layout_hash,
+                            topology,
+                        )
+                    },
+                );
+                (
+                    vertex_key.map(|key| cache.stage(key)),
+                    fragment_key.map(|key| cache.stage(key)),
+                )
+            }
+            None => (None, None),
+        };
+
+        let specialized_vertex_module = if vertex_stage.2.is_empty() {
+            None
+        } else {
+            let module = self
+                .create_specialized_shader_module(vertex_stage.0, &vertex_stage.2)
+                .map_err(|error| pipeline::CreateRenderPipelineError::Stage {
+                    stage: wgt::ShaderStages::VERTEX,
+                    error,
+                })?;
+            Some(module)
+        };
+        let specialized_fragment_module = match fragment_stage {
+            Some((shader_module, _, ref specialization)) if !specialization.is_empty() => {
+                match self.create_specialized_shader_module(shader_module, specialization) {
+                    Ok(module) => Some(module),
+                    Err(error) => {
+                        if let Some(module) = specialized_vertex_module {
+                            unsafe { self.raw.destroy_shader_module(module) };
+                        }
+                        return Err(pipeline::CreateRenderPipelineError::Stage {
+                            stage: wgt::ShaderStages::FRAGMENT,
+                            error,
+                        });
+                    }
+                }
+            }
+            _ => None,
         };
-        let raw =
-            unsafe { self.raw.create_render_pipeline(&pipeline_desc) }.map_err(
-                |err| match err {
-                    hal::PipelineError::Device(error) => {
-                        pipeline::CreateRenderPipelineError::Device(error.into())
-                    }
-                    hal::PipelineError::Linkage(stage, msg) => {
-                        pipeline::CreateRenderPipelineError::Internal { stage, error: msg }
-                    }
-                    hal::PipelineError::EntryPoint(stage) => {
-                        pipeline::CreateRenderPipelineError::Internal {
-                            stage: hal::auxil::map_naga_stage(stage),
-                            error: EP_FAILURE.to_string(),
-                        }
-                    }
-                },
-            )?;
 
         let pass_context = RenderPassContext {
             attachments: AttachmentData {
@@ -2747,8 +4031,30 @@ impl<A: HalApi> Device<A> {
             }
         }
 
-        let pipeline = pipeline::RenderPipeline {
-            raw,
+        Ok(pipeline::PendingRenderPipeline {
+            label: desc.label.borrow_option().map(str::to_string),
+            layout: Arc::clone(&layout.raw),
+            vertex_buffers,
+            vertex_stage: pipeline::PendingStage {
+                module: Arc::clone(&vertex_stage.0.raw),
+                specialized_module: specialized_vertex_module,
+                entry_point: vertex_stage.1.to_string(),
+                cache: vertex_stage_cache,
+            },
+            fragment_stage: fragment_stage.map(|(shader_module, entry_point, _)| {
+                pipeline::PendingStage {
+                    module: Arc::clone(&shader_module.raw),
+                    specialized_module: specialized_fragment_module,
+                    entry_point: entry_point.to_string(),
+                    cache: fragment_stage_cache,
+                }
+            }),
+            primitive: desc.primitive,
+            depth_stencil: desc.depth_stencil.clone(),
+            multisample: desc.multisample,
+            color_targets: color_targets.to_vec(),
+            multiview: desc.multiview,
+            cache: cache.map(|cache| Arc::clone(&cache.raw)),
             layout_id: Stored {
                 value: id::Valid(pipeline_layout_id),
                 ref_count: layout.life_guard.add_ref(),
@@ -2759,15 +4065,99 @@ impl<A: HalApi> Device<A> {
             },
             pass_context,
             flags,
-            strip_index_format: desc.primitive.strip_index_format,
             vertex_strides,
             late_sized_buffer_groups,
-            life_guard: LifeGuard::new(desc.label.borrow_or_default()),
+        })
+    }
+
+    /// Compile a render pipeline that passed validation.
+    ///
+    /// This only calls into the backend, without locking any storage.
+    fn compile_render_pipeline(
+        &self,
+        pending: pipeline::PendingRenderPipeline<A>,
+    ) -> Result<pipeline::RenderPipeline<A>, pipeline::CreateRenderPipelineError> {
+        let pipeline::PendingRenderPipeline {
+            label,
+            layout,
+            vertex_buffers,
+            vertex_stage,
+            fragment_stage,
+            primitive,
+            depth_stencil,
+            multisample,
+            color_targets,
+            multiview,
+            cache,
+            layout_id,
+            device_id,
+            pass_context,
+            flags,
+            vertex_strides,
+            late_sized_buffer_groups,
+        } = pending;
+
+        let hal_vertex_buffers = vertex_buffers
+            .iter()
+            .map(|vb_state| hal::VertexBufferLayout {
+                array_stride: vb_state.array_stride,
+                step_mode: vb_state.step_mode,
+                attributes: vb_state.attributes.as_ref(),
+            })
+            .collect::<Vec<_>>();
+        let pipeline_desc = hal::RenderPipelineDescriptor {
+            label: label.as_deref(),
+            layout: &*layout,
+            vertex_buffers: &hal_vertex_buffers,
+            vertex_stage: vertex_stage.to_hal(),
+            primitive,
+            depth_stencil,
+            multisample,
+            fragment_stage: fragment_stage.as_ref().map(|stage| stage.to_hal()),
+            color_targets: &color_targets,
+            multiview,
+            cache: cache.as_deref(),
+        };
+        let raw = unsafe { self.raw.create_render_pipeline(&pipeline_desc) };
+        // The layout has to be released before the reference keeping it registered.
+        drop(layout);
+        for module in vertex_stage
+            .specialized_module
+            .into_iter()
+            .chain(fragment_stage.and_then(|stage| stage.specialized_module))
+        {
+            unsafe { self.raw.destroy_shader_module(module) };
+        }
+        let raw = raw.map_err(|err| match err {
+            hal::PipelineError::Device(error) => {
+                pipeline::CreateRenderPipelineError::Device(error.into())
+            }
+            hal::PipelineError::Linkage(stage, msg) => {
+                pipeline::CreateRenderPipelineError::Internal { stage, error: msg }
+            }
+            hal::PipelineError::EntryPoint(stage) => {
+                pipeline::CreateRenderPipelineError::Internal {
+                    stage: hal::auxil::map_naga_stage(stage),
+                    error: EP_FAILURE.to_string(),
+                }
+            }
+        })?;
+
+        let pipeline = pipeline::RenderPipeline {
+            raw,
+            layout_id,
+            device_id,
+            pass_context,
+            flags,
+            strip_index_format: primitive.strip_index_format,
+            vertex_strides,
+            late_sized_buffer_groups,
+            life_guard: LifeGuard::new(label.as_deref().unwrap_or_default()),
         };
         Ok(pipeline)
     }
 
-    fn describe_format_features(
+    pub(crate) fn describe_format_features(
         &self,
         adapter: &crate::instance::Adapter<A>,
         format: TextureFormat,
@@ -2784,7 +4174,7 @@ impl<A: HalApi> Device<A> {
         if using_device_features || downlevel {
             Ok(adapter.get_texture_format_features(format))
         } else {
-            Ok(format_desc.guaranteed_format_features)
+            Ok(adapter.restrict_format_features(format, format_desc.guaranteed_format_features))
         }
     }
 
@@ -2793,18 +4183,9 @@ impl<A: HalApi> Device<A> {
         submission_index: SubmissionIndex,
         token: &mut Token<Self>,
     ) -> Result<(), WaitIdleError> {
-        let last_done_index = unsafe {
-            self.raw
-                .get_fence_value(&self.fence)
-                .map_err(DeviceError::from)?
-        };
-        if last_done_index < submission_index {
+        if !self.is_done(submission_index)? {
             log::info!("Waiting for submission {:?}", submission_index);
-            unsafe {
-                self.raw
-                    .wait(&self.fence, submission_index, !0)
-                    .map_err(DeviceError::from)?
-            };
+            self.wait_for_index(submission_index, !0)?;
             let closures = self
                 .lock_life(token)
                 .triage_submissions(submission_index, &self.command_allocator);
@@ -2845,6 +4226,7 @@ impl<A: HalApi> Device<A> {
         }
 
         let hal_desc = desc.map_label(super::LabelHelpers::borrow_option);
+        let desc = desc.map_label(|_| ());
         Ok(resource::QuerySet {
             raw: unsafe { self.raw.create_query_set(&hal_desc).unwrap() },
             device_id: Stored {
@@ -2852,7 +4234,54 @@ impl<A: HalApi> Device<A> {
                 ref_count: self.life_guard.add_ref(),
             },
             life_guard: LifeGuard::new(""),
-            desc: desc.map_label(|_| ()),
+            memory: resource::MemoryUse::new(
+                &self.memory_totals.query_sets,
+                query_set_memory_size(&desc),
+            ),
+            desc,
+        })
+    }
+
+    fn create_pipeline_cache(
+        &self,
+        self_id: id::DeviceId,
+        adapter: &crate::instance::Adapter<A>,
+        desc: &pipeline::PipelineCacheDescriptor,
+    ) -> Result<pipeline::PipelineCache<A>, pipeline::CreatePipelineCacheError> {
+        let header = pipeline_cache::Header::new(&adapter.raw.info);
+        let contents = match desc.data {
+            Some(ref data) => match pipeline_cache::decode(data, &header) {
+                Ok(contents) => contents,
+                Err(error) if desc.fallback => {
+                    log::warn!("Discarding pipeline cache data: {}", error);
+                    pipeline_cache::Contents::default()
+                }
+                Err(error) => return Err(error.into()),
+            },
+            None => pipeline_cache::Contents::default(),
+        };
+
+        let hal_desc = hal::PipelineCacheDescriptor {
+            label: desc.label.borrow_option(),
+            data: contents.backend_data.as_deref(),
+        };
+        let raw =
+            unsafe { self.raw.create_pipeline_cache(&hal_desc) }.map_err(DeviceError::from)?;
+        let stages = contents
+            .stages
+            .into_iter()
+            .map(|(key, data)| (key, Arc::new(hal::StageCache::new(Some(data)))))
+            .collect();
+
+        Ok(pipeline::PipelineCache {
+            raw: Arc::new(raw),
+            device_id: Stored {
+                value: id::Valid(self_id),
+                ref_count: self.life_guard.add_ref(),
+            },
+            stages: Mutex::new(stages),
+            hits: AtomicU64::new(0),
+            life_guard: LifeGuard::new(desc.label.borrow_or_default()),
         })
     }
 }
@@ -2876,12 +4305,44 @@ impl<A: hal::Api> Device<A> {
         }
     }
 
+    /// Free what the pipelines dropped before being compiled hold, returning their IDs.
+    pub(crate) fn release_abandoned_pipelines(
+        &self,
+    ) -> (Vec<id::RenderPipelineId>, Vec<id::ComputePipelineId>) {
+        let mut render_pipeline_ids = Vec::new();
+        let mut compute_pipeline_ids = Vec::new();
+        let abandoned = mem::take(&mut *self.abandoned_pipelines.lock());
+        for abandoned_pipeline in abandoned {
+            // Layouts have to be released before the references keeping them registered.
+            let stages = match abandoned_pipeline {
+                pipeline::AbandonedPipeline::Render(id, pending) => {
+                    render_pipeline_ids.push(id);
+                    drop(pending.layout);
+                    iter::once(pending.vertex_stage)
+                        .chain(pending.fragment_stage)
+                        .collect::<ArrayVec<_, 2>>()
+                }
+                pipeline::AbandonedPipeline::Compute(id, pending) => {
+                    compute_pipeline_ids.push(id);
+                    drop(pending.layout);
+                    iter::once(pending.stage).collect()
+                }
+            };
+            for stage in stages {
+                if let Some(module) = stage.specialized_module {
+                    unsafe { self.raw.destroy_shader_module(module) };
+                }
+            }
+        }
+        (render_pipeline_ids, compute_pipeline_ids)
+    }
+
     /// Wait for idle and remove resources that we can, before we die.
     pub(crate) fn prepare_to_die(&mut self) {
         self.pending_writes.deactivate();
         let mut life_tracker = self.life_tracker.lock();
         let current_index = self.active_submission_index;
-        if let Err(error) = unsafe { self.raw.wait(&self.fence, current_index, CLEANUP_WAIT_MS) } {
+        if let Err(error) = self.wait_for_index(current_index, CLEANUP_WAIT_MS) {
             log::error!("failed to wait for the device: {:?}", error);
         }
         let _ = life_tracker.triage_submissions(current_index, &self.command_allocator);
@@ -2898,6 +4359,10 @@ impl<A: hal::Api> Device<A> {
         unsafe {
             self.raw.destroy_buffer(self.zero_buffer);
             self.raw.destroy_fence(self.fence);
+            for queue in self.extra_queues {
+                self.raw.destroy_fence(queue.fence);
+                self.raw.destroy_queue(queue.raw);
+            }
             self.raw.exit(self.queue);
         }
     }
@@ -2934,6 +4399,45 @@ impl From<hal::DeviceError> for DeviceError {
     }
 }
 
+/// Errors which can report a failure of the device itself.
+pub(crate) trait DeviceErrorSource {
+    fn device_error(&self) -> Option<&DeviceError>;
+}
+
+impl DeviceErrorSource for DeviceError {
+    fn device_error(&self) -> Option<&DeviceError> {
+        Some(self)
+    }
+}
+
+macro_rules! impl_device_error_source {
+    ($($error:ty),*) => {$(
+        impl DeviceErrorSource for $error {
+            fn device_error(&self) -> Option<&DeviceError> {
+                match *self {
+                    Self::Device(ref error) => Some(error),
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+impl_device_error_source!(
+    resource::CreateBufferError,
+    resource::CreateTextureError,
+    resource::CreateSamplerError,
+    resource::CreateQuerySetError,
+    binding_model::CreateBindGroupLayoutError,
+    binding_model::CreatePipelineLayoutError,
+    binding_model::CreateBindGroupError,
+    binding_model::CreateDescriptorTableError,
+    pipeline::CreateShaderModuleError,
+    pipeline::CreateRenderPipelineError,
+    pipeline::CreateComputePipelineError,
+    pipeline::CreatePipelineCacheError
+);
+
 #[derive(Clone, Debug, Error)]
 #[error("Features {0:?} are required but not enabled on the device")]
 pub struct MissingFeatures(pub wgt::Features);
@@ -3011,6 +4515,27 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         surface.get_preferred_format(adapter)
     }
 
+    pub fn surface_get_supported_formats<A: HalApi>(
+        &self,
+        surface_id: id::SurfaceId,
+        adapter_id: id::AdapterId,
+    ) -> Result<Vec<TextureFormat>, instance::GetSurfacePreferredFormatError> {
+        profiling::scope!("surface_get_supported_formats");
+        let hub = A::hub(self);
+        let mut token = Token::root();
+
+        let (surface_guard, mut token) = self.surfaces.read(&mut token);
+        let (adapter_guard, mut _token) = hub.adapters.read(&mut token);
+        let adapter = adapter_guard
+            .get(adapter_id)
+            .map_err(|_| instance::GetSurfacePreferredFormatError::InvalidAdapter)?;
+        let surface = surface_guard
+            .get(surface_id)
+            .map_err(|_| instance::GetSurfacePreferredFormatError::InvalidSurface)?;
+
+        surface.get_supported_formats(adapter)
+    }
+
     pub fn device_features<A: HalApi>(
         &self,
         device_id: id::DeviceId,
@@ -3052,6 +4577,19 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         device_id: id::DeviceId,
         desc: &resource::BufferDescriptor,
         id_in: Input<G, id::BufferId>,
+    ) -> (id::BufferId, Option<resource::CreateBufferError>) {
+        let (id, error) = self.device_create_buffer_inner::<A>(device_id, desc, id_in);
+        if error.is_none() {
+            self.check_memory_threshold::<A>(device_id);
+        }
+        (id, error)
+    }
+
+    fn device_create_buffer_inner<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &resource::BufferDescriptor,
+        id_in: Input<G, id::BufferId>,
     ) -> (id::BufferId, Option<resource::CreateBufferError>) {
         profiling::scope!("create_buffer", "Device");
 
@@ -3061,9 +4599,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -3088,21 +4626,22 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             } else if desc.usage.contains(wgt::BufferUsages::MAP_WRITE) {
                 // buffer is mappable, so we are just doing that at start
                 let map_size = buffer.size;
-                let ptr = match map_buffer(&device.raw, &mut buffer, 0, map_size, HostMap::Write) {
-                    Ok(ptr) => ptr,
-                    Err(e) => {
-                        let raw = buffer.raw.unwrap();
-                        device
-                            .lock_life(&mut token)
-                            .schedule_resource_destruction(queue::TempResource::Buffer(raw), !0);
-                        break e.into();
-                    }
-                };
-                buffer.map_state = resource::BufferMapState::Active {
-                    ptr,
-                    range: 0..map_size,
-                    host: HostMap::Write,
-                };
+                let mut mapping = resource::BufferMapping::default();
+                if let Err(e) = map_buffer(
+                    &device.raw,
+                    &mut buffer,
+                    &mut mapping,
+                    0,
+                    map_size,
+                    HostMap::Write,
+                ) {
+                    let raw = buffer.raw.unwrap();
+                    device
+                        .lock_life(&mut token)
+                        .schedule_resource_destruction(queue::TempResource::Buffer(raw), !0);
+                    break e.into();
+                }
+                buffer.map_state = resource::BufferMapState::Mapped(mapping);
                 hal::BufferUses::MAP_WRITE
             } else {
                 // buffer needs staging area for initialization only
@@ -3149,6 +4688,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     ptr: mapping.ptr,
                     needs_flush: !mapping.is_coherent,
                     stage_buffer,
+                    stage_memory: resource::MemoryUse::new(
+                        &device.memory_totals.staging,
+                        buffer.size,
+                    ),
                 };
                 hal::BufferUses::COPY_DST
             };
@@ -3165,6 +4708,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -3328,9 +4872,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .raw
             .take()
             .ok_or(resource::DestroyError::AlreadyDestroyed)?;
+        buffer.memory = None;
         let temp = queue::TempResource::Buffer(raw);
 
-        if device.pending_writes.dst_buffers.contains(&buffer_id) {
+        if device.pending_writes.dst_buffers.contains_key(&buffer_id) {
             device.pending_writes.temp_resources.push(temp);
         } else {
             let last_submit_index = buffer.life_guard.life_count();
@@ -3369,7 +4914,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let device = &device_guard[device_id];
         {
             let mut life_lock = device.lock_life(&mut token);
-            if device.pending_writes.dst_buffers.contains(&buffer_id) {
+            if device.pending_writes.dst_buffers.contains_key(&buffer_id) {
                 life_lock.future_suspected_buffers.push(Stored {
                     value: id::Valid(buffer_id),
                     ref_count,
@@ -3396,6 +4941,19 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         device_id: id::DeviceId,
         desc: &resource::TextureDescriptor,
         id_in: Input<G, id::TextureId>,
+    ) -> (id::TextureId, Option<resource::CreateTextureError>) {
+        let (id, error) = self.device_create_texture_inner::<A>(device_id, desc, id_in);
+        if error.is_none() {
+            self.check_memory_threshold::<A>(device_id);
+        }
+        (id, error)
+    }
+
+    fn device_create_texture_inner<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &resource::TextureDescriptor,
+        id_in: Input<G, id::TextureId>,
     ) -> (id::TextureId, Option<resource::CreateTextureError>) {
         profiling::scope!("create_texture", "Device");
 
@@ -3406,9 +4964,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (adapter_guard, mut token) = hub.adapters.read(&mut token);
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -3438,6 +4996,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -3463,9 +5022,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (adapter_guard, mut token) = hub.adapters.read(&mut token);
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
 
             // NB: Any change done through the raw texture handle will not be recorded in the replay
@@ -3516,6 +5075,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -3561,9 +5121,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         match texture.inner {
             resource::TextureInner::Native { ref mut raw } => {
                 let raw = raw.take().ok_or(resource::DestroyError::AlreadyDestroyed)?;
+                texture.memory = None;
                 let temp = queue::TempResource::Texture(raw, clear_views);
 
-                if device.pending_writes.dst_textures.contains(&texture_id) {
+                if device.pending_writes.dst_textures.contains_key(&texture_id) {
                     device.pending_writes.temp_resources.push(temp);
                 } else {
                     drop(texture_guard);
@@ -3612,7 +5173,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let device = &device_guard[device_id];
         {
             let mut life_lock = device.lock_life(&mut token);
-            if device.pending_writes.dst_textures.contains(&texture_id) {
+            if device.pending_writes.dst_textures.contains_key(&texture_id) {
                 life_lock.future_suspected_textures.push(Stored {
                     value: id::Valid(texture_id),
                     ref_count,
@@ -3750,9 +5311,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -3777,6 +5338,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -3832,9 +5394,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let error = 'outer: loop {
             let (device_guard, mut token) = hub.devices.read(&mut token);
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -3857,9 +5419,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             // so their inputs are `PhantomData` of size 0.
             if mem::size_of::<Input<G, id::BindGroupLayoutId>>() == 0 {
                 let (bgl_guard, _) = hub.bind_group_layouts.read(&mut token);
-                if let Some(id) =
-                    Device::deduplicate_bind_group_layout(device_id, &entry_map, &*bgl_guard)
-                {
+                if let Some(id) = device.deduplicate_bind_group_layout(&entry_map, &*bgl_guard) {
                     return (id, None);
                 }
             }
@@ -3874,9 +5434,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             };
 
             let id = fid.assign(layout, &mut token);
+            let (bgl_guard, _) = hub.bind_group_layouts.read(&mut token);
+            device.register_bind_group_layout(id, &bgl_guard[id]);
             return (id.0, None);
         };
 
+        {
+            let (device_guard, _) = hub.devices.read(&mut token);
+            Device::lose_on_error(&device_guard, device_id, &error);
+        }
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -3928,9 +5494,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -3951,6 +5517,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -4007,9 +5574,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (bind_group_layout_guard, mut token) = hub.bind_group_layouts.read(&mut token);
 
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -4041,15 +5608,177 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 .trackers
                 .lock()
                 .bind_groups
-                .init(id, ref_count, PhantomData)
+                .init(id, ref_count, BindGroupState::default())
+                .unwrap();
+            return (id.0, None);
+        };
+
+        Device::lose_on_error(&device_guard, device_id, &error);
+        let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
+        (id, Some(error))
+    }
+
+    /// Create a descriptor table, a bind group with a single texture array
+    /// binding whose slots are filled in with
+    /// [`Global::descriptor_table_update_slots`].
+    ///
+    /// Tables are dropped with [`Global::bind_group_drop`].
+    pub fn device_create_descriptor_table<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &binding_model::DescriptorTableDescriptor,
+        id_in: Input<G, id::BindGroupId>,
+    ) -> (
+        id::BindGroupId,
+        Option<binding_model::CreateDescriptorTableError>,
+    ) {
+        profiling::scope!("create_descriptor_table", "Device");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let fid = hub.bind_groups.prepare(id_in);
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let (bind_group_layout_guard, mut token) = hub.bind_group_layouts.read(&mut token);
+
+        let error = loop {
+            let device = match Device::get_usable(&device_guard, device_id) {
+                Ok(device) => device,
+                Err(e) => break e.into(),
+            };
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                trace.lock().add(trace::Action::CreateDescriptorTable {
+                    id: fid.id(),
+                    desc: desc.clone(),
+                });
+            }
+
+            let bind_group_layout = match bind_group_layout_guard.get(desc.layout) {
+                Ok(layout) => layout,
+                Err(_) => break binding_model::CreateDescriptorTableError::InvalidLayout,
+            };
+            let table = match device.create_descriptor_table(device_id, bind_group_layout, desc) {
+                Ok(table) => table,
+                Err(e) => break e,
+            };
+            let ref_count = table.life_guard.add_ref();
+
+            let id = fid.assign(table, &mut token);
+            log::debug!("Descriptor table {:?}", id);
+
+            device
+                .trackers
+                .lock()
+                .bind_groups
+                .init(id, ref_count, BindGroupState::default())
                 .unwrap();
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
 
+    /// Write `views` into consecutive slots of a descriptor table, starting
+    /// at `first_slot`.
+    ///
+    /// The table must not be in use by a submission that hasn't completed.
+    /// Command buffers recorded with the table before the update can no
+    /// longer be submitted.
+    pub fn descriptor_table_update_slots<A: HalApi>(
+        &self,
+        table_id: id::BindGroupId,
+        first_slot: u32,
+        views: &[id::TextureViewId],
+    ) -> Result<(), binding_model::UpdateDescriptorTableError> {
+        profiling::scope!("update_slots", "DescriptorTable");
+
+        let views = views.iter().cloned().map(Some).collect::<Vec<_>>();
+        self.write_descriptor_table_slots::<A>(table_id, first_slot, &views)
+    }
+
+    /// Clear the `slots` of a descriptor table, releasing the views they held.
+    ///
+    /// Shaders must not access cleared slots. The same restrictions as for
+    /// [`Global::descriptor_table_update_slots`] apply.
+    pub fn descriptor_table_clear_slots<A: HalApi>(
+        &self,
+        table_id: id::BindGroupId,
+        slots: Range<u32>,
+    ) -> Result<(), binding_model::UpdateDescriptorTableError> {
+        profiling::scope!("clear_slots", "DescriptorTable");
+
+        let views = vec![None; slots.end.saturating_sub(slots.start) as usize];
+        self.write_descriptor_table_slots::<A>(table_id, slots.start, &views)
+    }
+
+    fn write_descriptor_table_slots<A: HalApi>(
+        &self,
+        table_id: id::BindGroupId,
+        first_slot: u32,
+        views: &[Option<id::TextureViewId>],
+    ) -> Result<(), binding_model::UpdateDescriptorTableError> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+
+        let (device_id, (released_views, released_textures)) = {
+            let (device_guard, mut token) = hub.devices.read(&mut token);
+            let (bind_group_layout_guard, mut token) = hub.bind_group_layouts.read(&mut token);
+            let (mut bind_group_guard, mut token) = hub.bind_groups.write(&mut token);
+            let (_, mut token) = hub.buffers.read(&mut token);
+            let (texture_guard, mut token) = hub.textures.read(&mut token);
+            let (texture_view_guard, _) = hub.texture_views.read(&mut token);
+
+            let bind_group = bind_group_guard
+                .get_mut(table_id)
+                .map_err(|_| binding_model::UpdateDescriptorTableError::InvalidTable(table_id))?;
+            let device = &device_guard[bind_group.device_id.value];
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                let action = if views.iter().all(Option::is_none) {
+                    trace::Action::ClearDescriptorTableSlots {
+                        id: table_id,
+                        slots: first_slot..first_slot + views.len() as u32,
+                    }
+                } else {
+                    trace::Action::UpdateDescriptorTable {
+                        id: table_id,
+                        first_slot,
+                        views: views.iter().flatten().cloned().collect(),
+                    }
+                };
+                trace.lock().add(action);
+            }
+
+            let layout = &bind_group_layout_guard[bind_group.layout_id];
+            let released = device.update_descriptor_table(
+                table_id,
+                bind_group,
+                layout,
+                first_slot,
+                views,
+                &texture_guard,
+                &texture_view_guard,
+            )?;
+            (bind_group.device_id.value, released)
+        };
+
+        // The views that were replaced may now be unreferenced.
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let mut life_lock = device_guard[device_id].lock_life(&mut token);
+        life_lock
+            .suspected_resources
+            .texture_views
+            .extend(released_views);
+        life_lock
+            .suspected_resources
+            .textures
+            .extend(released_textures);
+        Ok(())
+    }
+
     pub fn bind_group_label<A: HalApi>(&self, id: id::BindGroupId) -> String {
         A::hub(self).bind_groups.label_for_resource(id)
     }
@@ -4102,9 +5831,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -4119,6 +5848,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                                 .unwrap();
                         trace.make_binary("ron", string.as_bytes())
                     }
+                    #[cfg(feature = "serialized-ir")]
+                    pipeline::ShaderModuleSource::SerializedIr(ref data) => {
+                        trace.make_binary("ir", data)
+                    }
                 };
                 trace.add(trace::Action::CreateShaderModule {
                     id: fid.id(),
@@ -4135,7 +5868,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
+        hub.shader_module_errors
+            .lock()
+            .insert(id, error.compilation_info());
         (id, Some(error))
     }
 
@@ -4162,9 +5899,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -4187,10 +5924,32 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
+        hub.shader_module_errors
+            .lock()
+            .insert(id, error.compilation_info());
         (id, Some(error))
     }
 
+    /// Get the messages produced while compiling a shader module.
+    ///
+    /// For a shader module that failed to be created, the messages describe why.
+    pub fn shader_module_get_compilation_info<A: HalApi>(
+        &self,
+        shader_module_id: id::ShaderModuleId,
+    ) -> wgt::CompilationInfo {
+        profiling::scope!("get_compilation_info", "ShaderModule");
+
+        let hub = A::hub(self);
+        // Naga doesn't report warnings, so valid modules have no messages.
+        hub.shader_module_errors
+            .lock()
+            .get(&shader_module_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn shader_module_label<A: HalApi>(&self, id: id::ShaderModuleId) -> String {
         A::hub(self).shader_modules.label_for_resource(id)
     }
@@ -4203,6 +5962,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let mut token = Token::root();
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let (module, _) = hub.shader_modules.unregister(shader_module_id, &mut token);
+        hub.shader_module_errors.lock().remove(&shader_module_id);
         if let Some(module) = module {
             let device = &device_guard[module.device_id.value];
             #[cfg(feature = "trace")]
@@ -4211,9 +5971,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     .lock()
                     .add(trace::Action::DestroyShaderModule(shader_module_id));
             }
-            unsafe {
-                device.raw.destroy_shader_module(module.raw);
-            }
+            device
+                .lock_life(&mut token)
+                .release_shader_module(module.raw);
         }
     }
 
@@ -4231,9 +5991,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid,
+                Err(e) => break e,
             };
             let dev_stored = Stored {
                 value: id::Valid(device_id),
@@ -4262,6 +6022,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -4320,10 +6081,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = A::hub(self);
         let mut token = Token::root();
         let fid = hub.render_bundles.prepare(id_in);
+        let device_id = bundle_encoder.parent();
 
+        let (adapter_guard, mut token) = hub.adapters.read(&mut token);
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(bundle_encoder.parent()) {
+            let device = match device_guard.get(device_id) {
                 Ok(device) => device,
                 Err(_) => break command::RenderBundleError::INVALID_DEVICE,
             };
@@ -4334,13 +6097,16 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     desc: trace::new_render_bundle_encoder_descriptor(
                         desc.label.clone(),
                         &bundle_encoder.context,
-                        bundle_encoder.is_ds_read_only,
+                        bundle_encoder.is_depth_read_only,
+                        bundle_encoder.is_stencil_read_only,
                     ),
                     base: bundle_encoder.to_base_pass(),
                 });
             }
 
-            let render_bundle = match bundle_encoder.finish(desc, device, hub, &mut token) {
+            let adapter = &adapter_guard[device.adapter_id.value];
+            let render_bundle = match bundle_encoder.finish(desc, device, adapter, hub, &mut token)
+            {
                 Ok(bundle) => bundle,
                 Err(e) => break e,
             };
@@ -4358,6 +6124,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
         (id, Some(error))
     }
@@ -4400,6 +6167,19 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         device_id: id::DeviceId,
         desc: &resource::QuerySetDescriptor,
         id_in: Input<G, id::QuerySetId>,
+    ) -> (id::QuerySetId, Option<resource::CreateQuerySetError>) {
+        let (id, error) = self.device_create_query_set_inner::<A>(device_id, desc, id_in);
+        if error.is_none() {
+            self.check_memory_threshold::<A>(device_id);
+        }
+        (id, error)
+    }
+
+    fn device_create_query_set_inner<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &resource::QuerySetDescriptor,
+        id_in: Input<G, id::QuerySetId>,
     ) -> (id::QuerySetId, Option<resource::CreateQuerySetError>) {
         profiling::scope!("create_query_set", "Device");
 
@@ -4409,9 +6189,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -4439,6 +6219,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return (id.0, None);
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error("", &mut token);
         (id, Some(error))
     }
@@ -4452,9 +6233,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let device_id = {
             let (mut query_set_guard, _) = hub.query_sets.write(&mut token);
-            let query_set = query_set_guard.get_mut(query_set_id).unwrap();
-            query_set.life_guard.ref_count.take();
-            query_set.device_id.value
+            match query_set_guard.get_mut(query_set_id) {
+                Ok(query_set) => {
+                    query_set.life_guard.ref_count.take();
+                    query_set.device_id.value
+                }
+                Err(InvalidId) => {
+                    hub.query_sets
+                        .unregister_locked(query_set_id, &mut *query_set_guard);
+                    return;
+                }
+            }
         };
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
@@ -4486,6 +6275,68 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
     ) {
         profiling::scope!("create_render_pipeline", "Device");
 
+        let (id, pending) =
+            self.prepare_render_pipeline::<A>(device_id, desc, id_in, implicit_pipeline_ids);
+        let error = match pending {
+            Ok(pending) => self.compile_render_pipeline::<A>(id, pending),
+            Err(error) => Some(error),
+        };
+        (id, error)
+    }
+
+    /// Returns where the pipelines of `device_id` dropped before being compiled go.
+    fn abandoned_pipelines<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+    ) -> pipeline::AbandonedPipelines<A> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        Arc::clone(&device_guard[device_id].abandoned_pipelines)
+    }
+
+    /// Validate a render pipeline, leaving its compilation to the returned
+    /// [`pipeline::PendingPipeline`], which can be run on another thread.
+    ///
+    /// Validation errors are returned right away, and the ID is then an error ID.
+    pub fn device_create_render_pipeline_deferred<A: HalApi + 'static>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &pipeline::RenderPipelineDescriptor,
+        id_in: Input<G, id::RenderPipelineId>,
+        implicit_pipeline_ids: Option<ImplicitPipelineIds<G>>,
+    ) -> (
+        id::RenderPipelineId,
+        Result<
+            pipeline::PendingPipeline<G, pipeline::CreateRenderPipelineError>,
+            pipeline::CreateRenderPipelineError,
+        >,
+    ) {
+        profiling::scope!("create_render_pipeline_deferred", "Device");
+
+        let (id, pending) =
+            self.prepare_render_pipeline::<A>(device_id, desc, id_in, implicit_pipeline_ids);
+        let pending = pending.map(|pending| {
+            let abandoned = self.abandoned_pipelines::<A>(pending.device_id.value);
+            pipeline::PendingPipeline::new(Box::new(DeferredRenderPipeline {
+                id,
+                pending,
+                abandoned,
+            }))
+        });
+        (id, pending)
+    }
+
+    fn prepare_render_pipeline<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &pipeline::RenderPipelineDescriptor,
+        id_in: Input<G, id::RenderPipelineId>,
+        implicit_pipeline_ids: Option<ImplicitPipelineIds<G>>,
+    ) -> (
+        id::RenderPipelineId,
+        Result<pipeline::PendingRenderPipeline<A>, pipeline::CreateRenderPipelineError>,
+    ) {
         let hub = A::hub(self);
         let mut token = Token::root();
 
@@ -4495,9 +6346,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (adapter_guard, mut token) = hub.adapters.read(&mut token);
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             let adapter = &adapter_guard[device.adapter_id.value];
             #[cfg(feature = "trace")]
@@ -4509,7 +6360,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 });
             }
 
-            let pipeline = match device.create_render_pipeline(
+            match device.prepare_render_pipeline(
                 device_id,
                 adapter,
                 desc,
@@ -4517,25 +6368,49 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 hub,
                 &mut token,
             ) {
-                Ok(pair) => pair,
+                Ok(pending) => return (fid.into_id(), Ok(pending)),
                 Err(e) => break e,
-            };
-            let ref_count = pipeline.life_guard.add_ref();
-
-            let id = fid.assign(pipeline, &mut token);
-            log::info!("Created render pipeline {:?} with {:?}", id, desc);
-
-            device
-                .trackers
-                .lock()
-                .render_pipes
-                .init(id, ref_count, PhantomData)
-                .unwrap();
-            return (id.0, None);
+            }
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
-        (id, Some(error))
+        (id, Err(error))
+    }
+
+    fn compile_render_pipeline<A: HalApi>(
+        &self,
+        id: id::RenderPipelineId,
+        pending: pipeline::PendingRenderPipeline<A>,
+    ) -> Option<pipeline::CreateRenderPipelineError> {
+        profiling::scope!("compile_render_pipeline", "Device");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let fid = hub.render_pipelines.resume(id);
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let device = &device_guard[pending.device_id.value];
+        let label = pending.label.clone();
+        match device.compile_render_pipeline(pending) {
+            Ok(pipeline) => {
+                let ref_count = pipeline.life_guard.add_ref();
+                let id = fid.assign(pipeline, &mut token);
+                log::info!("Created render pipeline {:?} ({:?})", id, label);
+
+                device
+                    .trackers
+                    .lock()
+                    .render_pipes
+                    .init(id, ref_count, PhantomData)
+                    .unwrap();
+                None
+            }
+            Err(error) => {
+                fid.assign_error(label.as_deref().unwrap_or_default(), &mut token);
+                Some(error)
+            }
+        }
     }
 
     /// Get an ID of one of the bind group layouts. The ID adds a refcount,
@@ -4630,6 +6505,57 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
     ) {
         profiling::scope!("create_compute_pipeline", "Device");
 
+        let (id, pending) =
+            self.prepare_compute_pipeline::<A>(device_id, desc, id_in, implicit_pipeline_ids);
+        let error = match pending {
+            Ok(pending) => self.compile_compute_pipeline::<A>(id, pending),
+            Err(error) => Some(error),
+        };
+        (id, error)
+    }
+
+    /// Validate a compute pipeline, leaving its compilation to the returned
+    /// [`pipeline::PendingPipeline`], which can be run on another thread.
+    ///
+    /// Validation errors are returned right away, and the ID is then an error ID.
+    pub fn device_create_compute_pipeline_deferred<A: HalApi + 'static>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &pipeline::ComputePipelineDescriptor,
+        id_in: Input<G, id::ComputePipelineId>,
+        implicit_pipeline_ids: Option<ImplicitPipelineIds<G>>,
+    ) -> (
+        id::ComputePipelineId,
+        Result<
+            pipeline::PendingPipeline<G, pipeline::CreateComputePipelineError>,
+            pipeline::CreateComputePipelineError,
+        >,
+    ) {
+        profiling::scope!("create_compute_pipeline_deferred", "Device");
+
+        let (id, pending) =
+            self.prepare_compute_pipeline::<A>(device_id, desc, id_in, implicit_pipeline_ids);
+        let pending = pending.map(|pending| {
+            let abandoned = self.abandoned_pipelines::<A>(pending.device_id.value);
+            pipeline::PendingPipeline::new(Box::new(DeferredComputePipeline {
+                id,
+                pending,
+                abandoned,
+            }))
+        });
+        (id, pending)
+    }
+
+    fn prepare_compute_pipeline<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &pipeline::ComputePipelineDescriptor,
+        id_in: Input<G, id::ComputePipelineId>,
+        implicit_pipeline_ids: Option<ImplicitPipelineIds<G>>,
+    ) -> (
+        id::ComputePipelineId,
+        Result<pipeline::PendingComputePipeline<A>, pipeline::CreateComputePipelineError>,
+    ) {
         let hub = A::hub(self);
         let mut token = Token::root();
 
@@ -4638,9 +6564,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -4651,32 +6577,56 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 });
             }
 
-            let pipeline = match device.create_compute_pipeline(
+            match device.prepare_compute_pipeline(
                 device_id,
                 desc,
                 implicit_context,
                 hub,
                 &mut token,
             ) {
-                Ok(pair) => pair,
+                Ok(pending) => return (fid.into_id(), Ok(pending)),
                 Err(e) => break e,
-            };
-            let ref_count = pipeline.life_guard.add_ref();
-
-            let id = fid.assign(pipeline, &mut token);
-            log::info!("Created compute pipeline {:?} with {:?}", id, desc);
-
-            device
-                .trackers
-                .lock()
-                .compute_pipes
-                .init(id, ref_count, PhantomData)
-                .unwrap();
-            return (id.0, None);
+            }
         };
 
+        Device::lose_on_error(&device_guard, device_id, &error);
         let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
-        (id, Some(error))
+        (id, Err(error))
+    }
+
+    fn compile_compute_pipeline<A: HalApi>(
+        &self,
+        id: id::ComputePipelineId,
+        pending: pipeline::PendingComputePipeline<A>,
+    ) -> Option<pipeline::CreateComputePipelineError> {
+        profiling::scope!("compile_compute_pipeline", "Device");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let fid = hub.compute_pipelines.resume(id);
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let device = &device_guard[pending.device_id.value];
+        let label = pending.label.clone();
+        match device.compile_compute_pipeline(pending) {
+            Ok(pipeline) => {
+                let ref_count = pipeline.life_guard.add_ref();
+                let id = fid.assign(pipeline, &mut token);
+                log::info!("Created compute pipeline {:?} ({:?})", id, label);
+
+                device
+                    .trackers
+                    .lock()
+                    .compute_pipes
+                    .init(id, ref_count, PhantomData)
+                    .unwrap();
+                None
+            }
+            Err(error) => {
+                fid.assign_error(label.as_deref().unwrap_or_default(), &mut token);
+                Some(error)
+            }
+        }
     }
 
     /// Get an ID of one of the bind group layouts. The ID adds a refcount,
@@ -4759,6 +6709,124 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .push(layout_id);
     }
 
+    pub fn device_create_pipeline_cache<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        desc: &pipeline::PipelineCacheDescriptor,
+        id_in: Input<G, id::PipelineCacheId>,
+    ) -> (
+        id::PipelineCacheId,
+        Option<pipeline::CreatePipelineCacheError>,
+    ) {
+        profiling::scope!("create_pipeline_cache", "Device");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let fid = hub.pipeline_caches.prepare(id_in);
+
+        let (adapter_guard, mut token) = hub.adapters.read(&mut token);
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let error = loop {
+            let device = match Device::get_usable(&device_guard, device_id) {
+                Ok(device) => device,
+                Err(e) => break e.into(),
+            };
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                trace.lock().add(trace::Action::CreatePipelineCache {
+                    id: fid.id(),
+                    desc: desc.clone(),
+                });
+            }
+
+            let adapter = &adapter_guard[device.adapter_id.value];
+            let cache = match device.create_pipeline_cache(device_id, adapter, desc) {
+                Ok(cache) => cache,
+                Err(e) => break e,
+            };
+
+            let id = fid.assign(cache, &mut token);
+            log::info!("Created pipeline cache {:?}", id);
+            return (id.0, None);
+        };
+
+        Device::lose_on_error(&device_guard, device_id, &error);
+        let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
+        (id, Some(error))
+    }
+
+    /// Serialize the contents of a pipeline cache, so that it can be
+    /// recreated in a later run. Returns `None` if the cache is invalid.
+    pub fn pipeline_cache_get_data<A: HalApi>(
+        &self,
+        pipeline_cache_id: id::PipelineCacheId,
+    ) -> Option<Vec<u8>> {
+        profiling::scope!("get_data", "PipelineCache");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (adapter_guard, mut token) = hub.adapters.read(&mut token);
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let (cache_guard, _) = hub.pipeline_caches.read(&mut token);
+
+        let cache = cache_guard.get(pipeline_cache_id).ok()?;
+        let device = &device_guard[cache.device_id.value];
+        let adapter = &adapter_guard[device.adapter_id.value];
+
+        let contents = pipeline_cache::Contents {
+            stages: cache
+                .stages
+                .lock()
+                .iter()
+                .filter_map(|(key, stage)| Some((key.clone(), stage.load()?)))
+                .collect(),
+            backend_data: unsafe { device.raw.pipeline_cache_get_data(&cache.raw) },
+        };
+        let header = pipeline_cache::Header::new(&adapter.raw.info);
+        Some(pipeline_cache::encode(&header, &contents))
+    }
+
+    /// Returns the number of pipeline stages whose translation was found in the cache,
+    /// or 0 if the cache is invalid.
+    pub fn pipeline_cache_hit_count<A: HalApi>(
+        &self,
+        pipeline_cache_id: id::PipelineCacheId,
+    ) -> u64 {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (cache_guard, _) = hub.pipeline_caches.read(&mut token);
+        cache_guard
+            .get(pipeline_cache_id)
+            .map_or(0, |cache| cache.hit_count())
+    }
+
+    pub fn pipeline_cache_drop<A: HalApi>(&self, pipeline_cache_id: id::PipelineCacheId) {
+        profiling::scope!("drop", "PipelineCache");
+        log::debug!("pipeline cache {:?} is dropped", pipeline_cache_id);
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+
+        // Pipelines don't keep the cache alive, so it can be destroyed
+        // as soon as no pipeline is being compiled with it.
+        let (cache, _) = hub
+            .pipeline_caches
+            .unregister(pipeline_cache_id, &mut token);
+        if let Some(cache) = cache {
+            let device = &device_guard[cache.device_id.value];
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                trace
+                    .lock()
+                    .add(trace::Action::DestroyPipelineCache(pipeline_cache_id));
+            }
+            device
+                .lock_life(&mut token)
+                .release_pipeline_cache(cache.raw);
+        }
+    }
+
     pub fn surface_configure<A: HalApi>(
         &self,
         surface_id: id::SurfaceId,
@@ -4818,9 +6886,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (device_guard, _token) = hub.devices.read(&mut token);
 
         let error = loop {
-            let device = match device_guard.get(device_id) {
+            let device = match Device::get_usable(&device_guard, device_id) {
                 Ok(device) => device,
-                Err(_) => break DeviceError::Invalid.into(),
+                Err(e) => break e.into(),
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
@@ -4925,26 +6993,193 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
     }
 
     /// Check `device_id` for freeable resources and completed buffer mappings.
+    ///
+    /// With [`wgt::Maintain::WaitForSubmissionIndexTimeout`], returns
+    /// [`wgt::MaintainResult::TimedOut`] if the submission wasn't done in time.
     pub fn device_poll<A: HalApi>(
         &self,
         device_id: id::DeviceId,
-        force_wait: bool,
-    ) -> Result<(), WaitIdleError> {
-        let (closures, _) = {
+        maintain: wgt::Maintain<queue::WrappedSubmissionIndex>,
+    ) -> Result<wgt::MaintainResult, WaitIdleError> {
+        let (closures, _, result) = {
+            let mut wrong_index = None;
+            let maintain = maintain.map_index(|submission| {
+                if submission.queue_id != device_id {
+                    wrong_index = Some(submission.queue_id);
+                }
+                submission.index
+            });
+            if let Some(queue_id) = wrong_index {
+                return Err(WaitIdleError::WrongSubmissionIndex(queue_id, device_id));
+            }
+
             let hub = A::hub(self);
             let mut token = Token::root();
             let (device_guard, mut token) = hub.devices.read(&mut token);
             device_guard
                 .get(device_id)
                 .map_err(|_| DeviceError::Invalid)?
-                .maintain(hub, force_wait, &mut token)?
+                .maintain(hub, maintain, &mut token)?
         };
         unsafe {
             closures.fire();
         }
+        self.check_memory_threshold::<A>(device_id);
+        Ok(result)
+    }
+
+    /// Report the GPU memory allocated by `device_id`.
+    pub fn device_memory_report<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+    ) -> Result<wgt::MemoryReport, InvalidDevice> {
+        profiling::scope!("memory_report", "Device");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard.get(device_id).map_err(|_| InvalidDevice)?;
+        Ok(wgt::MemoryReport {
+            heaps: unsafe { device.raw.memory_heaps() },
+            ..device.memory_totals()
+        })
+    }
+
+    /// Set `callback` to be invoked with the memory report of `device_id` whenever
+    /// its total goes from below `threshold` to above, replacing the previous one.
+    ///
+    /// Memory is checked when buffers, textures and query sets are created,
+    /// when the device is polled, and after submissions.
+    pub fn device_set_memory_threshold_callback<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        threshold: u64,
+        callback: MemoryThresholdCallback,
+    ) -> Result<(), InvalidDevice> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard.get(device_id).map_err(|_| InvalidDevice)?;
+        *device.memory_threshold.lock() = Some(MemoryThreshold {
+            threshold,
+            callback: Arc::new(Mutex::new(callback)),
+            above: false,
+            missed: false,
+        });
         Ok(())
     }
 
+    /// Invoke the memory threshold callback of `device_id` if its memory
+    /// went above the threshold since last checked.
+    pub(crate) fn check_memory_threshold<A: HalApi>(&self, device_id: id::DeviceId) {
+        let (callback, report) = {
+            let hub = A::hub(self);
+            let mut token = Token::root();
+            let (device_guard, _) = hub.devices.read(&mut token);
+            let device = match device_guard.get(device_id) {
+                Ok(device) => device,
+                Err(_) => return,
+            };
+            let mut slot = device.memory_threshold.lock();
+            let memory_threshold = match *slot {
+                Some(ref mut memory_threshold) => memory_threshold,
+                None => return,
+            };
+            let report = device.memory_totals();
+            let above = report.total() >= memory_threshold.threshold;
+            let crossed = (above && !memory_threshold.above) || memory_threshold.missed;
+            memory_threshold.above = above;
+            memory_threshold.missed = false;
+            if !crossed {
+                return;
+            }
+            let report = wgt::MemoryReport {
+                heaps: unsafe { device.raw.memory_heaps() },
+                ..report
+            };
+            (Arc::clone(&memory_threshold.callback), report)
+        };
+
+        // The callback may poll the device again, in which case a crossing
+        // found meanwhile is invoked on the next check.
+        match callback.try_lock() {
+            Some(mut callback) => (*callback)(report),
+            None => {
+                let hub = A::hub(self);
+                let mut token = Token::root();
+                let (device_guard, _) = hub.devices.read(&mut token);
+                if let Ok(device) = device_guard.get(device_id) {
+                    if let Some(ref mut memory_threshold) = *device.memory_threshold.lock() {
+                        // Unless the callback was replaced since.
+                        if Arc::ptr_eq(&memory_threshold.callback, &callback) {
+                            memory_threshold.missed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Set the callback invoked once `device_id` is lost, replacing the previous one.
+    ///
+    /// If the device is already lost, the callback is invoked right away.
+    pub fn device_set_device_lost_closure<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        closure: DeviceLostClosure,
+    ) -> Result<(), InvalidDevice> {
+        let invocation = {
+            let hub = A::hub(self);
+            let mut token = Token::root();
+            let (device_guard, _) = hub.devices.read(&mut token);
+            device_guard
+                .get(device_id)
+                .map_err(|_| InvalidDevice)?
+                .set_lost_closure(closure)
+        };
+        if let Some(invocation) = invocation {
+            invocation.call();
+        }
+        Ok(())
+    }
+
+    /// Mark `device_id` as lost, so that using it fails from now on,
+    /// and invoke its device lost callback.
+    pub(crate) fn lose_device<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        reason: wgt::DeviceLostReason,
+        message: &str,
+    ) {
+        let invocation = {
+            let hub = A::hub(self);
+            let mut token = Token::root();
+            let (device_guard, _) = hub.devices.read(&mut token);
+            match device_guard.get(device_id) {
+                Ok(device) => device.lose(reason, message),
+                Err(_) => None,
+            }
+        };
+        if let Some(invocation) = invocation {
+            invocation.call();
+        }
+    }
+
+    /// Mark `device_id` as lost if `error` says it is.
+    pub(crate) fn lose_device_on_error<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        error: &DeviceError,
+    ) {
+        if let DeviceError::Lost = *error {
+            self.lose_device::<A>(
+                device_id,
+                wgt::DeviceLostReason::Unknown,
+                DEVICE_LOST_MESSAGE,
+            );
+        }
+    }
+
     /// Poll all devices belonging to the backend `A`.
     ///
     /// If `force_wait` is true, block until all buffer mappings are done.
@@ -4962,7 +7197,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             let (device_guard, mut token) = hub.devices.read(&mut token);
 
             for (id, device) in device_guard.iter(A::VARIANT) {
-                let (cbs, queue_empty) = device.maintain(hub, force_wait, &mut token)?;
+                let maintain = if force_wait {
+                    wgt::Maintain::Wait
+                } else {
+                    wgt::Maintain::Poll
+                };
+                let (cbs, queue_empty, _) = device.maintain(hub, maintain, &mut token)?;
 
                 // If the device's own `RefCount` clone is the only one left, and
                 // its submission queue is empty, then it can be freed.
@@ -5036,6 +7276,160 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         }
     }
 
+    /// Destroy `device_id` right away, instead of once it's dropped and unused.
+    ///
+    /// Waits for the GPU to be idle, frees the memory of all the buffers and
+    /// textures of the device, and resolves their pending mappings with
+    /// [`resource::BufferMapAsyncStatus::ContextLost`]. The device is then lost
+    /// with [`wgt::DeviceLostReason::Destroyed`], so using it is an error.
+    ///
+    /// Buffers with mapped ranges keep their memory until they're unmapped,
+    /// since the mapped memory may still be accessed. All the other objects of
+    /// the device are freed as well, and their ids become invalid until the
+    /// user drops them.
+    pub fn device_destroy<A: HalApi>(&self, device_id: id::DeviceId) -> Result<(), WaitIdleError> {
+        profiling::scope!("destroy", "Device");
+        log::info!("Device {:?} is destroyed", device_id);
+
+        let closures = {
+            let hub = A::hub(self);
+            let mut token = Token::root();
+            let (mut device_guard, mut token) = hub.devices.write(&mut token);
+            let device = device_guard
+                .get_mut(device_id)
+                .map_err(|_| DeviceError::Invalid)?;
+
+            if device.is_valid() {
+                match device.wait_for_index(device.active_submission_index, CLEANUP_WAIT_MS) {
+                    Ok(true) => (),
+                    Ok(false) => return Err(WaitIdleError::StuckGpu),
+                    // Nothing runs on the GPU anymore either way.
+                    Err(DeviceError::Lost) => (),
+                    Err(error) => return Err(error.into()),
+                }
+            }
+
+            let mut closures = UserClosures::default();
+            closures
+                .device_lost_invocations
+                .extend(device.lose(wgt::DeviceLostReason::Destroyed, "device was destroyed"));
+
+            let mut temp_resources = Vec::new();
+            {
+                let (mut buffer_guard, mut token) = hub.buffers.write(&mut token);
+                let buffer_ids = buffer_guard
+                    .iter(A::VARIANT)
+                    .filter(|&(_, buffer)| buffer.device_id.value.0 == device_id)
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                for buffer_id in buffer_ids {
+                    let buffer = buffer_guard.get_mut(buffer_id).unwrap();
+                    // Views of mapped ranges may still be alive, so mapped buffers
+                    // are only freed once unmapped, see `buffer_unmap_inner`.
+                    match mem::replace(&mut buffer.map_state, resource::BufferMapState::Idle) {
+                        state @ resource::BufferMapState::Init { .. } => {
+                            buffer.map_state = state;
+                            continue;
+                        }
+                        resource::BufferMapState::Mapped(mut mapping) => {
+                            closures.mappings.extend(
+                                mem::take(&mut mapping.pending)
+                                    .into_iter()
+                                    .map(|p| (p.op, resource::BufferMapAsyncStatus::ContextLost)),
+                            );
+                            if !mapping.active.is_empty() {
+                                buffer.map_state = resource::BufferMapState::Mapped(mapping);
+                                continue;
+                            }
+                        }
+                        resource::BufferMapState::Idle => {}
+                    }
+                    if let Some(raw) = buffer.raw.take() {
+                        buffer.memory = None;
+                        #[cfg(feature = "trace")]
+                        if let Some(ref trace) = device.trace {
+                            trace.lock().add(trace::Action::FreeBuffer(buffer_id));
+                        }
+                        temp_resources.push((
+                            queue::TempResource::Buffer(raw),
+                            buffer.life_guard.life_count(),
+                        ));
+                    }
+                }
+
+                let (mut texture_guard, _) = hub.textures.write(&mut token);
+                let texture_ids = texture_guard
+                    .iter(A::VARIANT)
+                    .filter(|&(_, texture)| texture.device_id.value.0 == device_id)
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                for texture_id in texture_ids {
+                    let texture = texture_guard.get_mut(texture_id).unwrap();
+                    // Surface textures belong to the surface.
+                    let raw = match texture.inner {
+                        resource::TextureInner::Native { ref mut raw } => match raw.take() {
+                            Some(raw) => raw,
+                            None => continue,
+                        },
+                        resource::TextureInner::Surface { .. } => continue,
+                    };
+                    texture.memory = None;
+                    #[cfg(feature = "trace")]
+                    if let Some(ref trace) = device.trace {
+                        trace.lock().add(trace::Action::FreeTexture(texture_id));
+                    }
+                    let clear_views = match mem::replace(
+                        &mut texture.clear_mode,
+                        resource::TextureClearMode::None,
+                    ) {
+                        resource::TextureClearMode::RenderPass { clear_views, .. } => clear_views,
+                        _ => SmallVec::new(),
+                    };
+                    temp_resources.push((
+                        queue::TempResource::Texture(raw, clear_views),
+                        texture.life_guard.life_count(),
+                    ));
+                }
+            }
+
+            {
+                let mut life_tracker = device.lock_life(&mut token);
+                for (temp, last_submit_index) in temp_resources {
+                    life_tracker.schedule_resource_destruction(temp, last_submit_index);
+                }
+            }
+            // The device is lost now, so this considers all the submissions done
+            // and frees the resources.
+            let (maintain_closures, _, _) =
+                device.maintain(hub, wgt::Maintain::Poll, &mut token)?;
+            closures.extend(maintain_closures);
+
+            // Free the other objects the user still holds, whose ids become invalid.
+            {
+                let (mut cmd_buf_guard, _) = hub.command_buffers.write(&mut token);
+                for (_, cmd_buf) in cmd_buf_guard
+                    .take_matching(A::VARIANT, |cmd_buf| cmd_buf.device_id.value.0 == device_id)
+                {
+                    device.destroy_command_buffer(cmd_buf);
+                }
+            }
+            let mut life_tracker = device.lock_life(&mut token);
+            life_tracker.free_destroyed_device_objects(
+                hub,
+                device_id,
+                &device.trackers,
+                &mut token,
+            );
+            life_tracker.cleanup(&device.raw);
+            closures
+        };
+
+        unsafe {
+            closures.fire();
+        }
+        Ok(())
+    }
+
     pub fn device_drop<A: HalApi>(&self, device_id: id::DeviceId) {
         profiling::scope!("drop", "Device");
         log::debug!("device {:?} is dropped", device_id);
@@ -5104,32 +7498,65 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return Err(resource::BufferAccessError::UnalignedRange);
         }
 
-        let (device_id, ref_count) = {
+        let mapped = {
             let (mut buffer_guard, _) = hub.buffers.write(&mut token);
             let buffer = buffer_guard
                 .get_mut(buffer_id)
                 .map_err(|_| resource::BufferAccessError::Invalid)?;
 
             check_buffer_usage(buffer.usage, pub_usage)?;
-            buffer.map_state = match buffer.map_state {
-                resource::BufferMapState::Init { .. } | resource::BufferMapState::Active { .. } => {
+            if range.end > buffer.size {
+                return Err(resource::BufferAccessError::OutOfBoundsOverrun {
+                    index: range.end - 1,
+                    max: buffer.size,
+                });
+            }
+            let pending = resource::BufferPendingMapping {
+                range,
+                op,
+                _parent_ref_count: buffer.life_guard.add_ref(),
+            };
+            match buffer.map_state {
+                resource::BufferMapState::Init { .. } => {
                     return Err(resource::BufferAccessError::AlreadyMapped);
                 }
-                resource::BufferMapState::Waiting(_) => {
-                    op.call_error();
-                    return Ok(());
+                resource::BufferMapState::Mapped(ref mut mapping) => {
+                    if mapping.active.iter().any(|active| {
+                        active.range.start < pending.range.end
+                            && pending.range.start < active.range.end
+                    }) {
+                        return Err(resource::BufferAccessError::RangeAlreadyMapped(
+                            pending.range,
+                        ));
+                    }
+                    if mapping.overlaps(&pending.range) {
+                        Err(pending.op)
+                    } else {
+                        mapping.pending.push(pending);
+                        Ok(())
+                    }
                 }
                 resource::BufferMapState::Idle => {
-                    resource::BufferMapState::Waiting(resource::BufferPendingMapping {
-                        range,
-                        op,
-                        _parent_ref_count: buffer.life_guard.add_ref(),
-                    })
+                    buffer.map_state = resource::BufferMapState::Mapped(resource::BufferMapping {
+                        pending: vec![pending],
+                        ..Default::default()
+                    });
+                    Ok(())
                 }
-            };
-            log::debug!("Buffer {:?} map state -> Waiting", buffer_id);
-
-            (buffer.device_id.value, buffer.life_guard.add_ref())
+            }
+            .map(|()| {
+                log::debug!("Buffer {:?} map state -> Waiting", buffer_id);
+                (buffer.device_id.value, buffer.life_guard.add_ref())
+            })
+        };
+        let (device_id, ref_count) = match mapped {
+            Ok(mapped) => mapped,
+            Err(op) => {
+                // Callbacks are never called with locks held.
+                drop(device_guard);
+                op.callback.call_error();
+                return Ok(());
+            }
         };
 
         let device = &device_guard[device_id];
@@ -5188,31 +7615,54 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 }
                 unsafe { Ok((ptr.as_ptr().offset(offset as isize), range_size)) }
             }
-            resource::BufferMapState::Active { ptr, ref range, .. } => {
-                if offset < range.start {
-                    return Err(resource::BufferAccessError::OutOfBoundsUnderrun {
-                        index: offset,
-                        min: range.start,
-                    });
+            resource::BufferMapState::Mapped(ref mapping) => {
+                if mapping.active.is_empty() {
+                    return Err(resource::BufferAccessError::NotMapped);
                 }
-                if offset + range_size > range.end {
+                // Find the active range the access starts in.
+                let active = match mapping
+                    .active
+                    .iter()
+                    .filter(|active| active.range.start <= offset)
+                    .max_by_key(|active| active.range.start)
+                {
+                    Some(active) => active,
+                    None => {
+                        let min = mapping
+                            .active
+                            .iter()
+                            .map(|active| active.range.start)
+                            .min()
+                            .unwrap_or(buffer.size);
+                        return Err(resource::BufferAccessError::OutOfBoundsUnderrun {
+                            index: offset,
+                            min,
+                        });
+                    }
+                };
+                if offset + range_size > active.range.end {
                     return Err(resource::BufferAccessError::OutOfBoundsOverrun {
                         index: offset + range_size - 1,
-                        max: range.end,
+                        max: active.range.end,
                     });
                 }
-                unsafe { Ok((ptr.as_ptr().offset(offset as isize), range_size)) }
-            }
-            resource::BufferMapState::Idle | resource::BufferMapState::Waiting(_) => {
-                Err(resource::BufferAccessError::NotMapped)
+                let ptr = active.ptr.as_ptr();
+                unsafe {
+                    Ok((
+                        ptr.offset((offset - active.range.start) as isize),
+                        range_size,
+                    ))
+                }
             }
+            resource::BufferMapState::Idle => Err(resource::BufferAccessError::NotMapped),
         }
     }
 
     fn buffer_unmap_inner<A: HalApi>(
         &self,
         buffer_id: id::BufferId,
-    ) -> Result<Option<BufferMapPendingClosure>, resource::BufferAccessError> {
+        range: Option<Range<BufferAddress>>,
+    ) -> Result<Vec<BufferMapPendingClosure>, resource::BufferAccessError> {
         profiling::scope!("unmap", "Buffer");
 
         let hub = A::hub(self);
@@ -5225,13 +7675,23 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .map_err(|_| resource::BufferAccessError::Invalid)?;
         let device = &mut device_guard[buffer.device_id.value];
 
-        log::debug!("Buffer {:?} map state -> Idle", buffer_id);
+        let mut closures = Vec::new();
         match mem::replace(&mut buffer.map_state, resource::BufferMapState::Idle) {
             resource::BufferMapState::Init {
                 ptr,
                 stage_buffer,
+                stage_memory,
                 needs_flush,
             } => {
+                if let Some(range) = range.filter(|range| *range != (0..buffer.size)) {
+                    buffer.map_state = resource::BufferMapState::Init {
+                        ptr,
+                        stage_buffer,
+                        stage_memory,
+                        needs_flush,
+                    };
+                    return Err(resource::BufferAccessError::RangeNotMapped(range));
+                }
                 #[cfg(feature = "trace")]
                 if let Some(ref trace) = device.trace {
                     let mut trace = trace.lock();
@@ -5259,6 +7719,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     .as_ref()
                     .ok_or(resource::BufferAccessError::Destroyed)?;
 
+                let last_use = buffer.life_guard.life_count();
                 buffer.life_guard.use_at(device.active_submission_index + 1);
                 let region = wgt::BufferSize::new(buffer.size).map(|size| hal::BufferCopy {
                     src_offset: 0,
@@ -5284,42 +7745,113 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 }
                 device
                     .pending_writes
-                    .consume_temp(queue::TempResource::Buffer(stage_buffer));
-                device.pending_writes.dst_buffers.insert(buffer_id);
+                    .consume_temp(queue::TempResource::StagingBuffer(
+                        stage_buffer,
+                        stage_memory,
+                    ));
+                device
+                    .pending_writes
+                    .dst_buffers
+                    .entry(buffer_id)
+                    .or_insert(last_use);
             }
             resource::BufferMapState::Idle => {
                 return Err(resource::BufferAccessError::NotMapped);
             }
-            resource::BufferMapState::Waiting(pending) => {
-                return Ok(Some((pending.op, resource::BufferMapAsyncStatus::Aborted)));
-            }
-            resource::BufferMapState::Active { ptr, range, host } => {
-                if host == HostMap::Write {
-                    #[cfg(feature = "trace")]
-                    if let Some(ref trace) = device.trace {
-                        let mut trace = trace.lock();
-                        let size = range.end - range.start;
-                        let data = trace.make_binary("bin", unsafe {
-                            std::slice::from_raw_parts(ptr.as_ptr(), size as usize)
-                        });
-                        trace.add(trace::Action::WriteBuffer {
-                            id: buffer_id,
-                            data,
-                            range: range.clone(),
-                            queued: false,
-                        });
+            resource::BufferMapState::Mapped(mut mapping) => {
+                let (pending, active) = match range {
+                    None => (
+                        mem::take(&mut mapping.pending),
+                        mem::take(&mut mapping.active),
+                    ),
+                    Some(range) => {
+                        if let Some(index) = mapping.pending.iter().position(|p| p.range == range) {
+                            (vec![mapping.pending.swap_remove(index)], Vec::new())
+                        } else if let Some(index) =
+                            mapping.active.iter().position(|a| a.range == range)
+                        {
+                            (Vec::new(), vec![mapping.active.swap_remove(index)])
+                        } else {
+                            buffer.map_state = resource::BufferMapState::Mapped(mapping);
+                            return Err(resource::BufferAccessError::RangeNotMapped(range));
+                        }
                     }
-                    let _ = (ptr, range);
-                }
-                unsafe {
-                    device
-                        .raw
-                        .unmap_buffer(buffer.raw.as_ref().unwrap())
-                        .map_err(DeviceError::from)?
                 };
+
+                closures.extend(
+                    pending
+                        .into_iter()
+                        .map(|p| (p.op, resource::BufferMapAsyncStatus::Aborted)),
+                );
+
+                if !active.is_empty() {
+                    let raw_buf = buffer.raw.as_ref().unwrap();
+                    for active in active {
+                        if active.host == HostMap::Write {
+                            #[cfg(feature = "trace")]
+                            if let Some(ref trace) = device.trace {
+                                let mut trace = trace.lock();
+                                let size = active.range.end - active.range.start;
+                                let data = trace.make_binary("bin", unsafe {
+                                    std::slice::from_raw_parts(active.ptr.as_ptr(), size as usize)
+                                });
+                                trace.add(trace::Action::WriteBuffer {
+                                    id: buffer_id,
+                                    data,
+                                    range: active.range.clone(),
+                                    queued: false,
+                                });
+                            }
+                        }
+                        if active.needs_flush {
+                            unsafe {
+                                device
+                                    .raw
+                                    .flush_mapped_ranges(raw_buf, iter::once(active.range));
+                            }
+                        }
+                    }
+                    if mapping.active.is_empty() {
+                        unsafe {
+                            device
+                                .raw
+                                .unmap_buffer(raw_buf)
+                                .map_err(DeviceError::from)?
+                        };
+                    }
+                }
+
+                if !mapping.pending.is_empty() || !mapping.active.is_empty() {
+                    buffer.map_state = resource::BufferMapState::Mapped(mapping);
+                }
+            }
+        }
+        if let resource::BufferMapState::Idle = buffer.map_state {
+            log::debug!("Buffer {:?} map state -> Idle", buffer_id);
+        }
+
+        // Buffers mapped when their device got destroyed are freed once unmapped.
+        let freed = match (&buffer.map_state, device.is_valid()) {
+            (&resource::BufferMapState::Idle, false) => {
+                buffer.memory = None;
+                buffer
+                    .raw
+                    .take()
+                    .map(|raw| (raw, buffer.life_guard.life_count()))
+            }
+            _ => None,
+        };
+        drop(buffer_guard);
+        if let Some((raw, last_submit_index)) = freed {
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                trace.lock().add(trace::Action::FreeBuffer(buffer_id));
             }
+            device
+                .lock_life(&mut token)
+                .schedule_resource_destruction(queue::TempResource::Buffer(raw), last_submit_index);
         }
-        Ok(None)
+        Ok(closures)
     }
 
     pub fn buffer_unmap<A: HalApi>(
@@ -5327,12 +7859,82 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         buffer_id: id::BufferId,
     ) -> Result<(), resource::BufferAccessError> {
         //Note: outside inner function so no locks are held when calling the callback
-        let closure = self.buffer_unmap_inner::<A>(buffer_id)?;
-        if let Some((operation, status)) = closure {
-            unsafe {
-                (operation.callback)(status, operation.user_data);
-            }
+        let closures = self.buffer_unmap_inner::<A>(buffer_id, None)?;
+        for (operation, status) in closures {
+            operation.callback.call(status);
+        }
+        Ok(())
+    }
+
+    /// Unmap a single range of the buffer.
+    ///
+    /// The range has to match one given to [`Self::buffer_map_async`] exactly.
+    /// Other ranges stay mapped or pending.
+    pub fn buffer_unmap_range<A: HalApi>(
+        &self,
+        buffer_id: id::BufferId,
+        range: Range<BufferAddress>,
+    ) -> Result<(), resource::BufferAccessError> {
+        let closures = self.buffer_unmap_inner::<A>(buffer_id, Some(range))?;
+        for (operation, status) in closures {
+            operation.callback.call(status);
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgt::DeviceLostReason;
+
+    fn recording_closure(calls: &Arc<Mutex<Vec<DeviceLostReason>>>) -> DeviceLostClosure {
+        let calls = Arc::clone(calls);
+        DeviceLostClosure::from_rust(Box::new(move |reason, _message| calls.lock().push(reason)))
+    }
+
+    #[test]
+    fn device_lost_calls_back_once() {
+        let calls = Arc::default();
+        let mut lost = DeviceLost::default();
+        assert!(lost.set_closure(recording_closure(&calls)).is_none());
+
+        lost.lose(DeviceLostReason::Destroyed, "destroyed")
+            .unwrap()
+            .call();
+        assert!(lost
+            .lose(DeviceLostReason::Unknown, DEVICE_LOST_MESSAGE)
+            .is_none());
+        assert_eq!(*calls.lock(), [DeviceLostReason::Destroyed]);
+
+        // Callbacks set on a lost device get the first reason right away.
+        lost.set_closure(recording_closure(&calls)).unwrap().call();
+        assert_eq!(
+            *calls.lock(),
+            [DeviceLostReason::Destroyed, DeviceLostReason::Destroyed]
+        );
+    }
+
+    #[test]
+    fn device_lost_on_error_defers_callback() {
+        let calls = Arc::default();
+        let mut lost = DeviceLost::default();
+        lost.set_closure(recording_closure(&calls));
+
+        lost.lose_later(DeviceLostReason::Unknown, DEVICE_LOST_MESSAGE);
+        assert!(lost.reason.is_some());
+        assert!(calls.lock().is_empty());
+
+        lost.take_pending().unwrap().call();
+        assert!(lost.take_pending().is_none());
+        assert_eq!(*calls.lock(), [DeviceLostReason::Unknown]);
+    }
+
+    #[test]
+    fn creation_errors_report_device_loss() {
+        let lost = resource::CreateBufferError::from(DeviceError::Lost);
+        assert!(matches!(lost.device_error(), Some(&DeviceError::Lost)));
+        let other = resource::CreateBufferError::EmptyUsage;
+        assert!(other.device_error().is_none());
+    }
+}
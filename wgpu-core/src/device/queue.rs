@@ -6,13 +6,13 @@ use crate::{
         ClearError, CommandBuffer, CopySide, ImageCopyTexture, TransferError,
     },
     conv,
-    device::{DeviceError, WaitIdleError},
+    device::{timeline::TimelineError, Device, DeviceError, WaitIdleError},
     get_lowest_common_denom,
     hub::{Global, GlobalIdentityHandlerFactory, HalApi, Token},
     id,
     init_tracker::{has_copy_partial_init_tracker_coverage, TextureInitRange},
-    resource::{BufferAccessError, BufferMapState, TextureInner},
-    track, FastHashSet,
+    resource::{BufferAccessError, BufferMapState, MemoryUse, TextureInner},
+    track, FastHashMap, SubmissionIndex,
 };
 
 use hal::{CommandEncoder as _, Device as _, Queue as _};
@@ -41,6 +41,7 @@ unsafe impl Sync for SubmittedWorkDoneClosure {}
 
 struct StagingData<A: hal::Api> {
     buffer: A::Buffer,
+    memory: MemoryUse,
 }
 
 impl<A: hal::Api> StagingData<A> {
@@ -61,9 +62,22 @@ impl<A: hal::Api> StagingData<A> {
     }
 }
 
+/// A submission index, along with the queue it was submitted to.
+///
+/// Returned by [`Global::queue_submit`], and passed to [`Global::device_poll`]
+/// to wait for the submission.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrappedSubmissionIndex {
+    pub queue_id: id::QueueId,
+    pub index: SubmissionIndex,
+}
+
 #[derive(Debug)]
 pub enum TempResource<A: hal::Api> {
     Buffer(A::Buffer),
+    /// A staging buffer, counted in the staging memory until it's destroyed.
+    StagingBuffer(A::Buffer, MemoryUse),
     Texture(A::Texture, SmallVec<[A::TextureView; 1]>),
 }
 
@@ -80,13 +94,22 @@ impl<A: hal::Api> EncoderInFlight<A> {
     }
 }
 
+/// A hardware queue opened in addition to `Device::queue`, with its own fence.
+#[derive(Debug)]
+pub(crate) struct HardwareQueue<A: hal::Api> {
+    pub raw: A::Queue,
+    pub fence: A::Fence,
+}
+
 #[derive(Debug)]
 pub(crate) struct PendingWrites<A: hal::Api> {
     pub command_encoder: A::CommandEncoder,
     pub is_active: bool,
     pub temp_resources: Vec<TempResource<A>>,
-    pub dst_buffers: FastHashSet<id::BufferId>,
-    pub dst_textures: FastHashSet<id::TextureId>,
+    /// Destinations of the pending writes, with the submission that last
+    /// used them before they were written to.
+    pub dst_buffers: FastHashMap<id::BufferId, SubmissionIndex>,
+    pub dst_textures: FastHashMap<id::TextureId, SubmissionIndex>,
     pub executing_command_buffers: Vec<A::CommandBuffer>,
 }
 
@@ -96,8 +119,8 @@ impl<A: hal::Api> PendingWrites<A> {
             command_encoder,
             is_active: false,
             temp_resources: Vec::new(),
-            dst_buffers: FastHashSet::default(),
-            dst_textures: FastHashSet::default(),
+            dst_buffers: FastHashMap::default(),
+            dst_textures: FastHashMap::default(),
             executing_command_buffers: Vec::new(),
         }
     }
@@ -114,7 +137,7 @@ impl<A: hal::Api> PendingWrites<A> {
 
         for resource in self.temp_resources {
             match resource {
-                TempResource::Buffer(buffer) => unsafe {
+                TempResource::Buffer(buffer) | TempResource::StagingBuffer(buffer, _) => unsafe {
                     device.destroy_buffer(buffer);
                 },
                 TempResource::Texture(texture, views) => unsafe {
@@ -132,7 +155,8 @@ impl<A: hal::Api> PendingWrites<A> {
     }
 
     fn consume(&mut self, stage: StagingData<A>) {
-        self.temp_resources.push(TempResource::Buffer(stage.buffer));
+        self.temp_resources
+            .push(TempResource::StagingBuffer(stage.buffer, stage.memory));
     }
 
     #[must_use]
@@ -202,7 +226,8 @@ impl<A: hal::Api> super::Device<A> {
             memory_flags: hal::MemoryFlags::TRANSIENT,
         };
         let buffer = unsafe { self.raw.create_buffer(&stage_desc)? };
-        Ok(StagingData { buffer })
+        let memory = MemoryUse::new(&self.memory_totals.staging, size);
+        Ok(StagingData { buffer, memory })
     }
 }
 
@@ -230,10 +255,20 @@ pub enum QueueSubmitError {
     DestroyedTexture(id::TextureId),
     #[error(transparent)]
     Unmap(#[from] BufferAccessError),
+    #[error("descriptor table {0:?} was updated after the command buffer using it was recorded")]
+    OutdatedDescriptorTable(id::BindGroupId),
     #[error("surface output was dropped before the command buffer got submitted")]
     SurfaceOutputDropped,
     #[error("surface was unconfigured before the command buffer got submitted")]
     SurfaceUnconfigured,
+    #[error("buffer {0:?} is still in use by a submission to another queue")]
+    BufferInUseOnOtherQueue(id::BufferId),
+    #[error("texture {0:?} is still in use by a submission to another queue")]
+    TextureInUseOnOtherQueue(id::TextureId),
+    #[error("surface texture {0:?} can only be used on the main queue")]
+    SurfaceTextureOnOtherQueue(id::TextureId),
+    #[error(transparent)]
+    Timeline(#[from] TimelineError),
     #[error("GPU got stuck :(")]
     StuckGpu,
 }
@@ -253,9 +288,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = A::hub(self);
         let mut token = Token::root();
         let (mut device_guard, mut token) = hub.devices.write(&mut token);
-        let device = device_guard
-            .get_mut(queue_id)
-            .map_err(|_| DeviceError::Invalid)?;
+        let device = Device::get_usable_mut(&mut device_guard, queue_id)?;
         let (buffer_guard, _) = hub.buffers.read(&mut token);
 
         #[cfg(feature = "trace")]
@@ -295,6 +328,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         if !dst.usage.contains(wgt::BufferUsages::COPY_DST) {
             return Err(TransferError::MissingCopyDstUsageFlag(Some(buffer_id), None).into());
         }
+        let last_use = dst.life_guard.life_count();
         dst.life_guard.use_at(device.active_submission_index + 1);
 
         if data_size % wgt::COPY_BUFFER_ALIGNMENT != 0 {
@@ -330,7 +364,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         }
 
         device.pending_writes.consume(stage);
-        device.pending_writes.dst_buffers.insert(buffer_id);
+        device
+            .pending_writes
+            .dst_buffers
+            .entry(buffer_id)
+            .or_insert(last_use);
 
         // Ensure the overwritten bytes are marked as initialized so they don't need to be nulled prior to mapping or binding.
         {
@@ -358,9 +396,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = A::hub(self);
         let mut token = Token::root();
         let (mut device_guard, mut token) = hub.devices.write(&mut token);
-        let device = device_guard
-            .get_mut(queue_id)
-            .map_err(|_| DeviceError::Invalid)?;
+        let device = Device::get_usable_mut(&mut device_guard, queue_id)?;
 
         #[cfg(feature = "trace")]
         if let Some(ref trace) = device.trace {
@@ -483,6 +519,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (hal_copy_size, array_layer_count) =
             validate_texture_copy_range(destination, &dst.desc, CopySide::Destination, size)?;
+        let last_use = dst.life_guard.life_count();
         dst.life_guard.use_at(device.active_submission_index + 1);
 
         let bytes_per_row = if let Some(bytes_per_row) = data_layout.bytes_per_row {
@@ -570,7 +607,8 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         device
             .pending_writes
             .dst_textures
-            .insert(destination.texture);
+            .entry(destination.texture)
+            .or_insert(last_use);
 
         Ok(())
     }
@@ -579,17 +617,77 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         &self,
         queue_id: id::QueueId,
         command_buffer_ids: &[id::CommandBufferId],
-    ) -> Result<(), QueueSubmitError> {
+    ) -> Result<WrappedSubmissionIndex, QueueSubmitError> {
+        self.queue_submit_indexed::<A>(queue_id, 0, command_buffer_ids)
+    }
+
+    /// Submit to the queue `queue_index` of the device, see
+    /// [`Global::adapter_request_device_with_queues`].
+    ///
+    /// Pending writes from `queue_write_*` go along with the next submission
+    /// to any queue of the device.
+    pub fn queue_submit_indexed<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+        command_buffer_ids: &[id::CommandBufferId],
+    ) -> Result<WrappedSubmissionIndex, QueueSubmitError> {
+        let result = self.submit_to_queue::<A>(queue_id, queue_index, command_buffer_ids);
+        match result {
+            Ok(_) => self.check_memory_threshold::<A>(queue_id),
+            Err(QueueSubmitError::Queue(ref error)) => {
+                self.lose_device_on_error::<A>(queue_id, error)
+            }
+            Err(_) => (),
+        }
+        result
+    }
+
+    fn submit_to_queue<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+        command_buffer_ids: &[id::CommandBufferId],
+    ) -> Result<WrappedSubmissionIndex, QueueSubmitError> {
         profiling::scope!("submit", "Queue");
 
-        let callbacks = {
+        self.wait_timelines_before_submit::<A>(queue_id, queue_index)?;
+
+        let (submit_index, callbacks) = {
             let hub = A::hub(self);
             let mut token = Token::root();
 
             let (mut device_guard, mut token) = hub.devices.write(&mut token);
-            let device = device_guard
-                .get_mut(queue_id)
-                .map_err(|_| DeviceError::Invalid)?;
+            let device = Device::get_usable_mut(&mut device_guard, queue_id)?;
+            let hardware_index = device
+                .hardware_queue_index(queue_index)
+                .ok_or(DeviceError::Invalid)?;
+            // Submissions still in flight on other hardware queues, whose
+            // resources can't be synchronized with by barriers on this one,
+            // unless this queue waits for them on the GPU.
+            let foreign_submissions = if device.extra_queues.is_empty() {
+                Vec::new()
+            } else {
+                let fence_values = device.fence_values()?;
+                let waited = &device.waited_submissions[hardware_index];
+                device
+                    .lock_life(&mut token)
+                    .in_flight(&fence_values)
+                    .filter(|&(index, queue)| queue != hardware_index && index > waited[queue])
+                    .map(|(index, _)| index)
+                    .collect()
+            };
+            // The pending writes are submitted to this queue as well.
+            for (&id, last_use) in device.pending_writes.dst_buffers.iter() {
+                if foreign_submissions.contains(last_use) {
+                    return Err(QueueSubmitError::BufferInUseOnOtherQueue(id));
+                }
+            }
+            for (&id, last_use) in device.pending_writes.dst_textures.iter() {
+                if foreign_submissions.contains(last_use) {
+                    return Err(QueueSubmitError::TextureInUseOnOtherQueue(id));
+                }
+            }
             device.temp_suspected.clear();
             device.active_submission_index += 1;
             let submit_index = device.active_submission_index;
@@ -631,10 +729,16 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         };
                         #[cfg(feature = "trace")]
                         if let Some(ref trace) = device.trace {
-                            trace.lock().add(Action::Submit(
-                                submit_index,
-                                cmdbuf.commands.take().unwrap(),
-                            ));
+                            let commands = cmdbuf.commands.take().unwrap();
+                            trace.lock().add(if queue_index == 0 {
+                                Action::Submit(submit_index, commands)
+                            } else {
+                                Action::SubmitToQueue {
+                                    index: submit_index,
+                                    queue_index,
+                                    commands,
+                                }
+                            });
                         }
                         if !cmdbuf.is_finished() {
                             device.destroy_command_buffer(cmdbuf);
@@ -644,6 +748,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         // optimize the tracked states
                         cmdbuf.trackers.optimize();
 
+                        // the recorded usages of descriptor tables are only
+                        // valid for the contents they had at recording time
+                        for id in cmdbuf.trackers.bind_groups.used() {
+                            let version = bind_group_guard[id].version();
+                            if cmdbuf.trackers.bind_groups.query(id, ()) != Some(version) {
+                                return Err(QueueSubmitError::OutdatedDescriptorTable(id.0));
+                            }
+                        }
+
                         // update submission IDs
                         for id in cmdbuf.trackers.buffers.used() {
                             let buffer = &mut buffer_guard[id];
@@ -653,11 +766,19 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                                     return Err(QueueSubmitError::DestroyedBuffer(id.0));
                                 }
                             };
+                            if foreign_submissions.contains(&buffer.life_guard.life_count()) {
+                                return Err(QueueSubmitError::BufferInUseOnOtherQueue(id.0));
+                            }
                             if !buffer.life_guard.use_at(submit_index) {
-                                if let BufferMapState::Active { .. } = buffer.map_state {
-                                    log::warn!("Dropped buffer has a pending mapping.");
-                                    unsafe { device.raw.unmap_buffer(raw_buf) }
-                                        .map_err(DeviceError::from)?;
+                                match buffer.map_state {
+                                    BufferMapState::Mapped(ref mapping)
+                                        if !mapping.active.is_empty() =>
+                                    {
+                                        log::warn!("Dropped buffer has a pending mapping.");
+                                        unsafe { device.raw.unmap_buffer(raw_buf) }
+                                            .map_err(DeviceError::from)?;
+                                    }
+                                    _ => {}
                                 }
                                 device.temp_suspected.buffers.push(id);
                             } else {
@@ -669,6 +790,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         }
                         for id in cmdbuf.trackers.textures.used() {
                             let texture = &mut texture_guard[id];
+                            if foreign_submissions.contains(&texture.life_guard.life_count()) {
+                                return Err(QueueSubmitError::TextureInUseOnOtherQueue(id.0));
+                            }
                             match texture.inner {
                                 TextureInner::Native { raw: None } => {
                                     return Err(QueueSubmitError::DestroyedTexture(id.0));
@@ -679,6 +803,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                                 } => {
                                     use track::ResourceState as _;
 
+                                    // Surfaces are presented from the main queue.
+                                    if hardware_index != 0 {
+                                        return Err(QueueSubmitError::SurfaceTextureOnOtherQueue(
+                                            id.0,
+                                        ));
+                                    }
                                     *has_work = true;
                                     let ref_count = cmdbuf.trackers.textures.get_ref_count(id);
                                     //TODO: better error handling here?
@@ -817,8 +947,16 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     ref mut pending_writes,
                     ref mut queue,
                     ref mut fence,
+                    ref mut extra_queues,
                     ..
                 } = *device;
+                let (queue, fence) = match hardware_index {
+                    0 => (queue, fence),
+                    _ => {
+                        let extra = &mut extra_queues[hardware_index - 1];
+                        (&mut extra.raw, &mut extra.fence)
+                    }
+                };
 
                 {
                     //TODO: these blocks have a few organizational issues and should be refactored
@@ -830,7 +968,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     let (_, mut token) = hub.buffers.read(&mut token); // skip token
                     let (mut texture_guard, _) = hub.textures.write(&mut token);
 
-                    for &id in pending_writes.dst_textures.iter() {
+                    for &id in pending_writes.dst_textures.keys() {
                         let texture = texture_guard.get_mut(id).unwrap();
                         match texture.inner {
                             TextureInner::Native { raw: None } => {
@@ -891,6 +1029,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         .map_err(DeviceError::from)?;
                 }
             }
+            device.last_submissions[hardware_index] = submit_index;
 
             profiling::scope!("cleanup");
             if let Some(pending_execution) = device.pending_writes.post_submit(
@@ -905,30 +1044,35 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             let mut pending_write_resources = mem::take(&mut device.pending_writes.temp_resources);
             device.lock_life(&mut token).track_submission(
                 submit_index,
+                hardware_index,
                 pending_write_resources.drain(..),
                 active_executions,
             );
 
             // This will schedule destruction of all resources that are no longer needed
             // by the user but used in the command stream, among other things.
-            let (closures, _) = match device.maintain(hub, false, &mut token) {
+            let (closures, _, _) = match device.maintain(hub, wgt::Maintain::Poll, &mut token) {
                 Ok(closures) => closures,
                 Err(WaitIdleError::Device(err)) => return Err(QueueSubmitError::Queue(err)),
                 Err(WaitIdleError::StuckGpu) => return Err(QueueSubmitError::StuckGpu),
+                Err(WaitIdleError::WrongSubmissionIndex(..)) => unreachable!(),
             };
 
             device.pending_writes.temp_resources = pending_write_resources;
             device.temp_suspected.clear();
             device.lock_life(&mut token).post_submit();
 
-            closures
+            (submit_index, closures)
         };
 
         // the closures should execute with nothing locked!
         unsafe {
             callbacks.fire();
         }
-        Ok(())
+        Ok(WrappedSubmissionIndex {
+            queue_id,
+            index: submit_index,
+        })
     }
 
     pub fn queue_get_timestamp_period<A: HalApi>(
@@ -944,20 +1088,51 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         }
     }
 
+    /// Return whether the queue `queue_index` of the device has a hardware
+    /// queue of its own, rather than sharing one with other queues of the
+    /// device and executing in order with them.
+    pub fn queue_is_independent<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+    ) -> Result<bool, InvalidQueue> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        device_guard
+            .get(queue_id)
+            .ok()
+            .and_then(|device| device.has_own_hardware_queue(queue_index))
+            .ok_or(InvalidQueue)
+    }
+
     pub fn queue_on_submitted_work_done<A: HalApi>(
         &self,
         queue_id: id::QueueId,
         closure: SubmittedWorkDoneClosure,
+    ) -> Result<(), InvalidQueue> {
+        self.queue_on_submitted_work_done_indexed::<A>(queue_id, 0, closure)
+    }
+
+    /// Like [`Global::queue_on_submitted_work_done`], for the queue `queue_index` of the device.
+    pub fn queue_on_submitted_work_done_indexed<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+        closure: SubmittedWorkDoneClosure,
     ) -> Result<(), InvalidQueue> {
         //TODO: flush pending writes
         let added = {
             let hub = A::hub(self);
             let mut token = Token::root();
             let (device_guard, mut token) = hub.devices.read(&mut token);
-            match device_guard.get(queue_id) {
-                Ok(device) => device.lock_life(&mut token).add_work_done_closure(closure),
-                Err(_) => return Err(InvalidQueue),
-            }
+            let device = device_guard.get(queue_id).map_err(|_| InvalidQueue)?;
+            let hardware_index = device
+                .hardware_queue_index(queue_index)
+                .ok_or(InvalidQueue)?;
+            device
+                .lock_life(&mut token)
+                .add_work_done_closure(hardware_index, closure)
         };
         if !added {
             unsafe {
@@ -0,0 +1,317 @@
+use crate::{
+    device::DeviceError,
+    hub::{Global, GlobalIdentityHandlerFactory, HalApi, Token},
+    id, SubmissionIndex,
+};
+
+use parking_lot::{Condvar, Mutex};
+use thiserror::Error;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A point of a timeline: `value` is reached once the submission
+/// `submission_index` of `device` is done.
+#[derive(Clone, Copy, Debug)]
+struct TimelinePoint {
+    device: id::DeviceId,
+    submission_index: SubmissionIndex,
+    value: u64,
+}
+
+#[derive(Debug)]
+struct TimelineState {
+    /// Largest value known to be reached.
+    completed: u64,
+    /// Largest value signaled so far, reached or not.
+    signaled: u64,
+    /// Signaled values that aren't known to be reached yet, in ascending order.
+    pending: Vec<TimelinePoint>,
+}
+
+/// A monotonically increasing counter, advanced by queue submissions completing.
+///
+/// Queues signal values of the timeline once their work submitted so far is done,
+/// and queues of any device of the adapter the timeline was created from can wait
+/// for a value before their next submission. The host can wait for values too.
+///
+/// The value only grows: reaching a value also reaches all the smaller ones,
+/// even if they were signaled on another queue that hasn't caught up yet.
+#[derive(Debug)]
+pub struct Timeline {
+    /// Only devices of this adapter can use the timeline.
+    adapter_id: id::Valid<id::AdapterId>,
+    state: Mutex<TimelineState>,
+    condvar: Condvar,
+}
+
+impl Timeline {
+    fn new(adapter_id: id::Valid<id::AdapterId>, initial_value: u64) -> Self {
+        Self {
+            adapter_id,
+            state: Mutex::new(TimelineState {
+                completed: initial_value,
+                signaled: initial_value,
+                pending: Vec::new(),
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Mark `value` as reached, along with the smaller ones.
+    fn complete(&self, value: u64) {
+        let mut state = self.state.lock();
+        if value > state.completed {
+            state.completed = value;
+            state.pending.retain(|point| point.value > value);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum TimelineError {
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+    #[error("timeline value {value} is not greater than the last signaled value {signaled}")]
+    NonIncreasingValue { value: u64, signaled: u64 },
+    #[error("timeline value {0} is never signaled")]
+    NeverSignaled(u64),
+    #[error("timeline was created from another adapter than the queue")]
+    WrongAdapter,
+}
+
+impl<G: GlobalIdentityHandlerFactory> Global<G> {
+    /// Create a timeline starting at `initial_value`, for the queues of
+    /// all the devices of the adapter of `device_id`.
+    pub fn device_create_timeline<A: HalApi>(
+        &self,
+        device_id: id::DeviceId,
+        initial_value: u64,
+    ) -> Result<Timeline, DeviceError> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard
+            .get(device_id)
+            .map_err(|_| DeviceError::Invalid)?;
+        Ok(Timeline::new(device.adapter_id.value, initial_value))
+    }
+
+    /// Signal `value` on `timeline` once all the work submitted so far to
+    /// the queue `queue_index` of the device is done.
+    pub fn queue_signal_timeline<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+        timeline: &Timeline,
+        value: u64,
+    ) -> Result<(), TimelineError> {
+        profiling::scope!("signal_timeline", "Queue");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let device = device_guard
+            .get(queue_id)
+            .map_err(|_| DeviceError::Invalid)?;
+        if device.adapter_id.value != timeline.adapter_id {
+            return Err(TimelineError::WrongAdapter);
+        }
+        let submission_index = device
+            .last_submission(queue_index)
+            .ok_or(DeviceError::Invalid)?;
+
+        let mut state = timeline.state.lock();
+        if value <= state.signaled {
+            return Err(TimelineError::NonIncreasingValue {
+                value,
+                signaled: state.signaled,
+            });
+        }
+        state.signaled = value;
+        if device.is_submission_done(submission_index, &mut token)? {
+            state.completed = value;
+            state.pending.clear();
+        } else {
+            state.pending.push(TimelinePoint {
+                device: queue_id,
+                submission_index,
+                value,
+            });
+        }
+        timeline.condvar.notify_all();
+        Ok(())
+    }
+
+    /// Make the next submission to the queue `queue_index` of the device wait
+    /// until `timeline` reaches `value`.
+    ///
+    /// The wait happens on the GPU if `value` is signaled by a queue of the same
+    /// device and the backend supports it. Otherwise the submission blocks on the
+    /// host until the work signaling `value` is done.
+    pub fn queue_wait_timeline<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+        timeline: Arc<Timeline>,
+        value: u64,
+    ) -> Result<(), TimelineError> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard
+            .get(queue_id)
+            .map_err(|_| DeviceError::Invalid)?;
+        if device.adapter_id.value != timeline.adapter_id {
+            return Err(TimelineError::WrongAdapter);
+        }
+        Ok(device.add_timeline_wait(queue_index, timeline, value)?)
+    }
+
+    /// Return the largest value reached by `timeline`, without blocking.
+    pub fn timeline_value<A: HalApi>(&self, timeline: &Timeline) -> Result<u64, TimelineError> {
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let pending = timeline.state.lock().pending.clone();
+        // Points are signaled in increasing order, so the last one done wins.
+        for point in pending.iter().rev() {
+            let (device_guard, mut token) = hub.devices.read(&mut token);
+            // Points are only signaled by devices of the timeline's adapter,
+            // so they live in this hub, unless they were dropped. Devices wait
+            // for their work to finish before going away.
+            let done = match device_guard.get(point.device) {
+                Ok(device) => device.is_submission_done(point.submission_index, &mut token)?,
+                Err(_) => true,
+            };
+            if done {
+                timeline.complete(point.value);
+                break;
+            }
+        }
+        Ok(timeline.state.lock().completed)
+    }
+
+    /// Wait for `timeline` to reach `value`, for up to `timeout`.
+    ///
+    /// If `value` isn't signaled yet, waits for it to be signaled from another thread.
+    /// Returns false if the timeout was reached.
+    pub fn timeline_wait<A: HalApi>(
+        &self,
+        timeline: &Timeline,
+        value: u64,
+        timeout: Option<Duration>,
+    ) -> Result<bool, TimelineError> {
+        profiling::scope!("wait", "Timeline");
+
+        let hub = A::hub(self);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let point = {
+                let mut state = timeline.state.lock();
+                loop {
+                    if state.completed >= value {
+                        return Ok(true);
+                    }
+                    let found = state
+                        .pending
+                        .iter()
+                        .find(|point| point.value >= value)
+                        .copied();
+                    match found {
+                        Some(point) => break point,
+                        None => match deadline {
+                            Some(deadline) => {
+                                if timeline
+                                    .condvar
+                                    .wait_until(&mut state, deadline)
+                                    .timed_out()
+                                {
+                                    return Ok(false);
+                                }
+                            }
+                            None => timeline.condvar.wait(&mut state),
+                        },
+                    }
+                }
+            };
+
+            let timeout_ms = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    remaining.as_millis().min(u32::MAX as u128 - 1) as u32
+                }
+                None => !0,
+            };
+            let mut token = Token::root();
+            let (device_guard, mut token) = hub.devices.read(&mut token);
+            let done = match device_guard.get(point.device) {
+                Ok(device) => {
+                    device.wait_for_submission(point.submission_index, timeout_ms, &mut token)?
+                }
+                Err(_) => true,
+            };
+            drop(device_guard);
+            if !done {
+                return Ok(false);
+            }
+            timeline.complete(point.value);
+        }
+    }
+
+    /// Wait for the timeline values the next submission to the queue
+    /// `queue_index` of the device depends on.
+    pub(crate) fn wait_timelines_before_submit<A: HalApi>(
+        &self,
+        queue_id: id::QueueId,
+        queue_index: u32,
+    ) -> Result<(), TimelineError> {
+        let hub = A::hub(self);
+        let waits = {
+            let mut token = Token::root();
+            let (device_guard, _) = hub.devices.read(&mut token);
+            let device = device_guard
+                .get(queue_id)
+                .map_err(|_| DeviceError::Invalid)?;
+            device.take_timeline_waits(queue_index)
+        };
+        for (timeline, value) in waits {
+            let point = {
+                let state = timeline.state.lock();
+                if value > state.signaled {
+                    return Err(TimelineError::NeverSignaled(value));
+                }
+                if state.completed >= value {
+                    continue;
+                }
+                state
+                    .pending
+                    .iter()
+                    .find(|point| point.value >= value)
+                    .copied()
+            };
+
+            if let Some(point) = point.filter(|point| point.device == queue_id) {
+                let mut token = Token::root();
+                let (mut device_guard, mut token) = hub.devices.write(&mut token);
+                let device = device_guard
+                    .get_mut(queue_id)
+                    .map_err(|_| DeviceError::Invalid)?;
+                if device.queue_wait_for_submission(
+                    queue_index,
+                    point.submission_index,
+                    &mut token,
+                )? {
+                    continue;
+                }
+            }
+
+            // Fences of other devices can't be waited for on the GPU, neither can
+            // any fence on some backends. `value` is signaled already, so this
+            // only waits for GPU work that was submitted.
+            self.timeline_wait::<A>(&timeline, value, None)?;
+        }
+        Ok(())
+    }
+}
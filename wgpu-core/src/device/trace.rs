@@ -13,7 +13,8 @@ pub const FILE_NAME: &str = "trace.ron";
 pub(crate) fn new_render_bundle_encoder_descriptor<'a>(
     label: crate::Label<'a>,
     context: &'a super::RenderPassContext,
-    is_ds_read_only: bool,
+    is_depth_read_only: bool,
+    is_stencil_read_only: bool,
 ) -> crate::command::RenderBundleEncoderDescriptor<'a> {
     crate::command::RenderBundleEncoderDescriptor {
         label,
@@ -22,8 +23,9 @@ pub(crate) fn new_render_bundle_encoder_descriptor<'a>(
             let aspects = hal::FormatAspects::from(format);
             wgt::RenderBundleDepthStencil {
                 format,
-                depth_read_only: is_ds_read_only && aspects.contains(hal::FormatAspects::DEPTH),
-                stencil_read_only: is_ds_read_only && aspects.contains(hal::FormatAspects::STENCIL),
+                depth_read_only: is_depth_read_only && aspects.contains(hal::FormatAspects::DEPTH),
+                stencil_read_only: is_stencil_read_only
+                    && aspects.contains(hal::FormatAspects::STENCIL),
             }
         }),
         sample_count: context.sample_count,
@@ -38,6 +40,9 @@ pub(crate) fn new_render_bundle_encoder_descriptor<'a>(
 pub enum Action<'a> {
     Init {
         desc: crate::device::DeviceDescriptor<'a>,
+        /// Additional queues of the device.
+        #[cfg_attr(feature = "replay", serde(default))]
+        queues: Vec<crate::device::QueueDescriptor<'a>>,
         backend: wgt::Backend,
     },
     ConfigureSurface(id::SurfaceId, wgt::SurfaceConfiguration),
@@ -76,6 +81,19 @@ pub enum Action<'a> {
         crate::binding_model::BindGroupDescriptor<'a>,
     ),
     DestroyBindGroup(id::BindGroupId),
+    CreateDescriptorTable {
+        id: id::BindGroupId,
+        desc: crate::binding_model::DescriptorTableDescriptor<'a>,
+    },
+    UpdateDescriptorTable {
+        id: id::BindGroupId,
+        first_slot: u32,
+        views: Vec<id::TextureViewId>,
+    },
+    ClearDescriptorTableSlots {
+        id: id::BindGroupId,
+        slots: Range<u32>,
+    },
     CreateShaderModule {
         id: id::ShaderModuleId,
         desc: crate::pipeline::ShaderModuleDescriptor<'a>,
@@ -107,6 +125,11 @@ pub enum Action<'a> {
         desc: crate::resource::QuerySetDescriptor<'a>,
     },
     DestroyQuerySet(id::QuerySetId),
+    CreatePipelineCache {
+        id: id::PipelineCacheId,
+        desc: crate::pipeline::PipelineCacheDescriptor<'a>,
+    },
+    DestroyPipelineCache(id::PipelineCacheId),
     WriteBuffer {
         id: id::BufferId,
         data: FileName,
@@ -120,6 +143,12 @@ pub enum Action<'a> {
         size: wgt::Extent3d,
     },
     Submit(crate::SubmissionIndex, Vec<Command>),
+    /// A submission to one of the additional queues of the device.
+    SubmitToQueue {
+        index: crate::SubmissionIndex,
+        queue_index: u32,
+        commands: Vec<Command>,
+    },
 }
 
 #[derive(Debug)]
@@ -173,11 +202,17 @@ pub enum Command {
     InsertDebugMarker(String),
     RunComputePass {
         base: crate::command::BasePass<crate::command::ComputeCommand>,
+        #[cfg_attr(feature = "replay", serde(default))]
+        timestamp_writes: Option<crate::command::PassTimestampWrites>,
     },
     RunRenderPass {
         base: crate::command::BasePass<crate::command::RenderCommand>,
         target_colors: Vec<crate::command::RenderPassColorAttachment>,
         target_depth_stencil: Option<crate::command::RenderPassDepthStencilAttachment>,
+        #[cfg_attr(feature = "replay", serde(default))]
+        timestamp_writes: Option<crate::command::PassTimestampWrites>,
+        #[cfg_attr(feature = "replay", serde(default))]
+        occlusion_query_set: Option<id::QuerySetId>,
     },
 }
 
@@ -116,6 +116,11 @@ pub fn format_pretty_any(
     if let Some(pretty_err) = error.downcast_ref::<crate::binding_model::CreateBindGroupError>() {
         return pretty_err.fmt_pretty(&mut fmt);
     }
+    if let Some(pretty_err) =
+        error.downcast_ref::<crate::binding_model::UpdateDescriptorTableError>()
+    {
+        return pretty_err.fmt_pretty(&mut fmt);
+    }
     if let Some(pretty_err) =
         error.downcast_ref::<crate::binding_model::CreatePipelineLayoutError>()
     {
@@ -142,6 +147,9 @@ pub fn format_pretty_any(
     if let Some(pretty_err) = error.downcast_ref::<crate::command::TransferError>() {
         return pretty_err.fmt_pretty(&mut fmt);
     }
+    if let Some(pretty_err) = error.downcast_ref::<crate::offline::CheckStageError>() {
+        return pretty_err.fmt_pretty(&mut fmt);
+    }
 
     // default
     fmt.error(error)
@@ -4,9 +4,9 @@ use crate::{
     device::Device,
     id,
     instance::{Adapter, HalSurface, Instance, Surface},
-    pipeline::{ComputePipeline, RenderPipeline, ShaderModule},
+    pipeline::{ComputePipeline, PipelineCache, RenderPipeline, ShaderModule},
     resource::{Buffer, QuerySet, Sampler, Texture, TextureClearMode, TextureView},
-    Epoch, Index,
+    Epoch, FastHashMap, Index,
 };
 
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -14,7 +14,7 @@ use wgt::Backend;
 
 #[cfg(debug_assertions)]
 use std::cell::Cell;
-use std::{fmt::Debug, marker::PhantomData, mem, ops};
+use std::{fmt::Debug, marker::PhantomData, mem, ops, sync::Arc};
 
 /// A simple structure to allocate [`Id`] identifiers.
 ///
@@ -263,6 +263,33 @@ impl<T, I: id::TypedId> Storage<T, I> {
             })
     }
 
+    /// Take out the values matching `predicate`, leaving errors in their place.
+    ///
+    /// Their ids stay allocated but invalid, until they're unregistered.
+    pub(crate) fn take_matching(
+        &mut self,
+        backend: Backend,
+        mut predicate: impl FnMut(&T) -> bool,
+    ) -> Vec<(I, T)>
+    where
+        T: Resource,
+    {
+        let mut taken = Vec::new();
+        for (index, element) in self.map.iter_mut().enumerate() {
+            let (epoch, label) = match *element {
+                Element::Occupied(ref value, epoch) if predicate(value) => {
+                    (epoch, value.label().to_string())
+                }
+                _ => continue,
+            };
+            if let Element::Occupied(value, _) = mem::replace(element, Element::Error(epoch, label))
+            {
+                taken.push((I::zip(index as Index, epoch, backend), value));
+            }
+        }
+        taken
+    }
+
     fn generate_report(&self) -> StorageReport {
         let mut report = StorageReport {
             element_size: mem::size_of::<T>(),
@@ -328,6 +355,9 @@ impl<A: hal::Api> Access<QuerySet<A>> for ComputePipeline<A> {}
 impl<A: hal::Api> Access<QuerySet<A>> for Sampler<A> {}
 impl<A: hal::Api> Access<ShaderModule<A>> for Device<A> {}
 impl<A: hal::Api> Access<ShaderModule<A>> for BindGroupLayout<A> {}
+impl<A: hal::Api> Access<PipelineCache<A>> for Root {}
+impl<A: hal::Api> Access<PipelineCache<A>> for Device<A> {}
+impl<A: hal::Api> Access<PipelineCache<A>> for ShaderModule<A> {}
 impl<A: hal::Api> Access<Buffer<A>> for Root {}
 impl<A: hal::Api> Access<Buffer<A>> for Device<A> {}
 impl<A: hal::Api> Access<Buffer<A>> for BindGroupLayout<A> {}
@@ -435,6 +465,7 @@ pub trait GlobalIdentityHandlerFactory:
     + IdentityHandlerFactory<id::RenderBundleId>
     + IdentityHandlerFactory<id::RenderPipelineId>
     + IdentityHandlerFactory<id::ComputePipelineId>
+    + IdentityHandlerFactory<id::PipelineCacheId>
     + IdentityHandlerFactory<id::QuerySetId>
     + IdentityHandlerFactory<id::BufferId>
     + IdentityHandlerFactory<id::TextureId>
@@ -530,6 +561,15 @@ impl<T: Resource, I: id::TypedId + Copy, F: IdentityHandlerFactory<I>> Registry<
         }
     }
 
+    /// Resume the registration of an ID that was prepared earlier,
+    /// and taken out with [`FutureId::into_id`].
+    pub(crate) fn resume(&self, id: I) -> FutureId<I, T> {
+        FutureId {
+            id,
+            data: &self.data,
+        }
+    }
+
     pub(crate) fn read<'a, A: Access<T>>(
         &'a self,
         _token: &'a mut Token<A>,
@@ -598,6 +638,7 @@ pub struct HubReport {
     pub render_bundles: StorageReport,
     pub render_pipelines: StorageReport,
     pub compute_pipelines: StorageReport,
+    pub pipeline_caches: StorageReport,
     pub query_sets: StorageReport,
     pub buffers: StorageReport,
     pub textures: StorageReport,
@@ -616,12 +657,16 @@ pub struct Hub<A: hal::Api, F: GlobalIdentityHandlerFactory> {
     pub devices: Registry<Device<A>, id::DeviceId, F>,
     pub pipeline_layouts: Registry<PipelineLayout<A>, id::PipelineLayoutId, F>,
     pub shader_modules: Registry<ShaderModule<A>, id::ShaderModuleId, F>,
+    /// Compilation messages of the shader modules that failed to be created,
+    /// since their storage elements can't hold anything but a label.
+    pub(crate) shader_module_errors: Mutex<FastHashMap<id::ShaderModuleId, wgt::CompilationInfo>>,
     pub bind_group_layouts: Registry<BindGroupLayout<A>, id::BindGroupLayoutId, F>,
     pub bind_groups: Registry<BindGroup<A>, id::BindGroupId, F>,
     pub command_buffers: Registry<CommandBuffer<A>, id::CommandBufferId, F>,
     pub render_bundles: Registry<RenderBundle, id::RenderBundleId, F>,
     pub render_pipelines: Registry<RenderPipeline<A>, id::RenderPipelineId, F>,
     pub compute_pipelines: Registry<ComputePipeline<A>, id::ComputePipelineId, F>,
+    pub pipeline_caches: Registry<PipelineCache<A>, id::PipelineCacheId, F>,
     pub query_sets: Registry<QuerySet<A>, id::QuerySetId, F>,
     pub buffers: Registry<Buffer<A>, id::BufferId, F>,
     pub textures: Registry<Texture<A>, id::TextureId, F>,
@@ -636,12 +681,14 @@ impl<A: HalApi, F: GlobalIdentityHandlerFactory> Hub<A, F> {
             devices: Registry::new(A::VARIANT, factory),
             pipeline_layouts: Registry::new(A::VARIANT, factory),
             shader_modules: Registry::new(A::VARIANT, factory),
+            shader_module_errors: Mutex::new(FastHashMap::default()),
             bind_group_layouts: Registry::new(A::VARIANT, factory),
             bind_groups: Registry::new(A::VARIANT, factory),
             command_buffers: Registry::new(A::VARIANT, factory),
             render_bundles: Registry::new(A::VARIANT, factory),
             render_pipelines: Registry::new(A::VARIANT, factory),
             compute_pipelines: Registry::new(A::VARIANT, factory),
+            pipeline_caches: Registry::new(A::VARIANT, factory),
             query_sets: Registry::new(A::VARIANT, factory),
             buffers: Registry::new(A::VARIANT, factory),
             textures: Registry::new(A::VARIANT, factory),
@@ -660,6 +707,8 @@ impl<A: HalApi, F: GlobalIdentityHandlerFactory> Hub<A, F> {
         let mut devices = self.devices.data.write();
         for element in devices.map.iter_mut() {
             if let Element::Occupied(ref mut device, _) = *element {
+                // Abandoned pipelines share the shader modules, layouts and caches freed below.
+                device.release_abandoned_pipelines();
                 device.prepare_to_die();
             }
         }
@@ -726,24 +775,37 @@ impl<A: HalApi, F: GlobalIdentityHandlerFactory> Hub<A, F> {
         for element in self.shader_modules.data.write().map.drain(..) {
             if let Element::Occupied(module, _) = element {
                 let device = &devices[module.device_id.value];
-                unsafe {
-                    device.raw.destroy_shader_module(module.raw);
+                match Arc::try_unwrap(module.raw) {
+                    Ok(raw) => unsafe {
+                        device.raw.destroy_shader_module(raw);
+                    },
+                    // Only a pending pipeline outliving the hub can still hold it.
+                    Err(_) => log::warn!("Leaking a shader module used by a pending pipeline"),
                 }
             }
         }
+        self.shader_module_errors.lock().clear();
         for element in self.bind_group_layouts.data.write().map.drain(..) {
             if let Element::Occupied(bgl, _) = element {
                 let device = &devices[bgl.device_id.value];
-                unsafe {
-                    device.raw.destroy_bind_group_layout(bgl.raw);
+                match Arc::try_unwrap(bgl.raw) {
+                    Ok(raw) => unsafe {
+                        device.raw.destroy_bind_group_layout(raw);
+                    },
+                    // Only a pending pipeline outliving the hub can still hold it.
+                    Err(_) => log::warn!("Leaking a bind group layout used by a pending pipeline"),
                 }
             }
         }
         for element in self.pipeline_layouts.data.write().map.drain(..) {
             if let Element::Occupied(pipeline_layout, _) = element {
                 let device = &devices[pipeline_layout.device_id.value];
-                unsafe {
-                    device.raw.destroy_pipeline_layout(pipeline_layout.raw);
+                match Arc::try_unwrap(pipeline_layout.raw) {
+                    Ok(raw) => unsafe {
+                        device.raw.destroy_pipeline_layout(raw);
+                    },
+                    // Only a pending pipeline outliving the hub can still hold it.
+                    Err(_) => log::warn!("Leaking a pipeline layout used by a pending pipeline"),
                 }
             }
         }
@@ -763,6 +825,18 @@ impl<A: HalApi, F: GlobalIdentityHandlerFactory> Hub<A, F> {
                 }
             }
         }
+        for element in self.pipeline_caches.data.write().map.drain(..) {
+            if let Element::Occupied(cache, _) = element {
+                let device = &devices[cache.device_id.value];
+                match Arc::try_unwrap(cache.raw) {
+                    Ok(raw) => unsafe {
+                        device.raw.destroy_pipeline_cache(raw);
+                    },
+                    // Only a pending pipeline outliving the hub can still hold it.
+                    Err(_) => log::warn!("Leaking a pipeline cache used by a pending pipeline"),
+                }
+            }
+        }
 
         for element in surface_guard.map.iter_mut() {
             if let Element::Occupied(ref mut surface, _epoch) = *element {
@@ -818,6 +892,7 @@ impl<A: HalApi, F: GlobalIdentityHandlerFactory> Hub<A, F> {
             render_bundles: self.render_bundles.data.read().generate_report(),
             render_pipelines: self.render_pipelines.data.read().generate_report(),
             compute_pipelines: self.compute_pipelines.data.read().generate_report(),
+            pipeline_caches: self.pipeline_caches.data.read().generate_report(),
             query_sets: self.query_sets.data.read().generate_report(),
             buffers: self.buffers.data.read().generate_report(),
             textures: self.textures.data.read().generate_report(),
@@ -178,6 +178,7 @@ pub type BindGroupId = Id<crate::binding_model::BindGroup<Dummy>>;
 pub type ShaderModuleId = Id<crate::pipeline::ShaderModule<Dummy>>;
 pub type RenderPipelineId = Id<crate::pipeline::RenderPipeline<Dummy>>;
 pub type ComputePipelineId = Id<crate::pipeline::ComputePipeline<Dummy>>;
+pub type PipelineCacheId = Id<crate::pipeline::PipelineCache<Dummy>>;
 // Command
 pub type CommandEncoderId = CommandBufferId;
 pub type CommandBufferId = Id<crate::command::CommandBuffer<Dummy>>;
@@ -1,9 +1,9 @@
 use crate::{
-    device::{Device, DeviceDescriptor},
+    device::{Device, DeviceDescriptor, QueueDescriptor},
     hub::{Global, GlobalIdentityHandlerFactory, HalApi, Input, Token},
     id::{AdapterId, DeviceId, SurfaceId, Valid},
     present::Presentation,
-    LabelHelpers, LifeGuard, Stored, DOWNLEVEL_WARNING_MESSAGE,
+    FastHashMap, LabelHelpers, LifeGuard, Stored, DOWNLEVEL_WARNING_MESSAGE,
 };
 
 use wgt::{Backend, Backends, PowerPreference};
@@ -11,6 +11,8 @@ use wgt::{Backend, Backends, PowerPreference};
 use hal::{Adapter as _, Instance as _};
 use thiserror::Error;
 
+use std::sync::Arc;
+
 pub type RequestAdapterOptions = wgt::RequestAdapterOptions<SurfaceId>;
 type HalInstance<A> = <A as hal::Api>::Instance;
 //TODO: remove this
@@ -152,6 +154,23 @@ impl crate::hub::Resource for Surface {
 }
 
 impl Surface {
+    pub fn get_supported_formats<A: HalApi>(
+        &self,
+        adapter: &Adapter<A>,
+    ) -> Result<Vec<wgt::TextureFormat>, GetSurfacePreferredFormatError> {
+        let suf = A::get_surface(self);
+        let caps = unsafe {
+            profiling::scope!("surface_capabilities");
+            adapter
+                .raw
+                .adapter
+                .surface_capabilities(&suf.raw)
+                .ok_or(GetSurfacePreferredFormatError::UnsupportedQueueFamily)?
+        };
+
+        Ok(caps.formats)
+    }
+
     pub fn get_preferred_format<A: HalApi>(
         &self,
         adapter: &Adapter<A>,
@@ -167,37 +186,104 @@ impl Surface {
             wgt::TextureFormat::Rgba16Float,
         ];
 
-        let suf = A::get_surface(self);
-        let caps = unsafe {
-            profiling::scope!("surface_capabilities");
-            adapter
-                .raw
-                .adapter
-                .surface_capabilities(&suf.raw)
-                .ok_or(GetSurfacePreferredFormatError::UnsupportedQueueFamily)?
-        };
+        let formats = self.get_supported_formats(adapter)?;
 
         preferred_formats
             .iter()
             .cloned()
-            .find(|preferred| caps.formats.contains(preferred))
+            .find(|preferred| formats.contains(preferred))
             .ok_or(GetSurfacePreferredFormatError::NotFound)
     }
 }
 
 pub struct Adapter<A: hal::Api> {
-    pub(crate) raw: hal::ExposedAdapter<A>,
+    /// Shared with the adapters derived by [`Adapter::emulate_profile`], so its
+    /// features and capabilities may be wider than the ones exposed.
+    pub(crate) raw: Arc<hal::ExposedAdapter<A>>,
+    features: wgt::Features,
+    capabilities: hal::Capabilities,
+    /// Texture format features of the emulated profiles.
+    profile_format_features: FastHashMap<wgt::TextureFormat, wgt::TextureFormatFeatures>,
     life_guard: LifeGuard,
 }
 
-impl<A: HalApi> Adapter<A> {
+impl<A: hal::Api> Adapter<A> {
     fn new(raw: hal::ExposedAdapter<A>) -> Self {
         Self {
-            raw,
+            features: raw.features,
+            capabilities: raw.capabilities.clone(),
+            raw: Arc::new(raw),
+            profile_format_features: FastHashMap::default(),
             life_guard: LifeGuard::new("<Adapter>"),
         }
     }
 
+    /// Derive an adapter restricted to the capabilities of `profile`.
+    ///
+    /// Devices created from the derived adapter validate against the profile,
+    /// while this one is left untouched. Profiles can only narrow the
+    /// capabilities, so deriving from an adapter that already emulates one
+    /// keeps the texture format features both of them allow.
+    fn emulate_profile(&self, profile: &wgt::AdapterProfile) -> Result<Self, EmulateProfileError> {
+        let caps = &self.capabilities;
+        if !self.features.contains(profile.features) {
+            return Err(EmulateProfileError::UnsupportedFeatures(
+                profile.features - self.features,
+            ));
+        }
+        if let Some(failed) = check_limits(&profile.limits, &caps.limits).pop() {
+            return Err(EmulateProfileError::LimitsExceeded(failed));
+        }
+        if !caps.downlevel.flags.contains(profile.downlevel_caps.flags) {
+            return Err(EmulateProfileError::UnsupportedDownlevelFlags(
+                profile.downlevel_caps.flags - caps.downlevel.flags,
+            ));
+        }
+        if profile.downlevel_caps.shader_model > caps.downlevel.shader_model {
+            return Err(EmulateProfileError::UnsupportedShaderModel(
+                profile.downlevel_caps.shader_model,
+            ));
+        }
+
+        log::info!("Emulating adapter profile {:#?}", profile);
+        let mut profile_format_features = self.profile_format_features.clone();
+        for format_profile in profile.texture_format_features.iter() {
+            let features = profile_format_features
+                .entry(format_profile.format)
+                .or_insert(format_profile.features);
+            features.allowed_usages &= format_profile.features.allowed_usages;
+            features.flags &= format_profile.features.flags;
+        }
+        Ok(Self {
+            raw: Arc::clone(&self.raw),
+            features: profile.features,
+            capabilities: hal::Capabilities {
+                limits: profile.limits.clone(),
+                alignments: caps.alignments.clone(),
+                downlevel: profile.downlevel_caps.clone(),
+            },
+            profile_format_features,
+            life_guard: LifeGuard::new("<Adapter>"),
+        })
+    }
+
+    /// Narrow `features` of `format` down to what the emulated profiles allow.
+    pub(crate) fn restrict_format_features(
+        &self,
+        format: wgt::TextureFormat,
+        features: wgt::TextureFormatFeatures,
+    ) -> wgt::TextureFormatFeatures {
+        match self.profile_format_features.get(&format) {
+            Some(profile) => wgt::TextureFormatFeatures {
+                allowed_usages: features.allowed_usages & profile.allowed_usages,
+                flags: features.flags & profile.flags,
+            },
+            None => features,
+        }
+    }
+}
+
+impl<A: HalApi> Adapter<A> {
     pub fn is_surface_supported(&self, surface: &Surface) -> bool {
         let suf = A::get_surface(surface);
         unsafe { self.raw.adapter.surface_capabilities(&suf.raw) }.is_some()
@@ -254,10 +340,13 @@ impl<A: HalApi> Adapter<A> {
             caps.contains(Tfc::MULTISAMPLE_RESOLVE),
         );
 
-        wgt::TextureFormatFeatures {
-            allowed_usages,
-            flags,
-        }
+        self.restrict_format_features(
+            format,
+            wgt::TextureFormatFeatures {
+                allowed_usages,
+                flags,
+            },
+        )
     }
 
     fn create_device_from_hal(
@@ -265,9 +354,10 @@ impl<A: HalApi> Adapter<A> {
         self_id: AdapterId,
         open: hal::OpenDevice<A>,
         desc: &DeviceDescriptor,
+        extra_queues: &[QueueDescriptor],
         trace_path: Option<&std::path::Path>,
     ) -> Result<Device<A>, RequestDeviceError> {
-        let caps = &self.raw.capabilities;
+        let caps = &self.capabilities;
         Device::new(
             open,
             Stored {
@@ -277,6 +367,7 @@ impl<A: HalApi> Adapter<A> {
             caps.alignments.clone(),
             caps.downlevel.clone(),
             desc,
+            extra_queues,
             trace_path,
         )
         .or(Err(RequestDeviceError::OutOfMemory))
@@ -286,16 +377,17 @@ impl<A: HalApi> Adapter<A> {
         &self,
         self_id: AdapterId,
         desc: &DeviceDescriptor,
+        extra_queues: &[QueueDescriptor],
         trace_path: Option<&std::path::Path>,
     ) -> Result<Device<A>, RequestDeviceError> {
         // Verify all features were exposed by the adapter
-        if !self.raw.features.contains(desc.features) {
+        if !self.features.contains(desc.features) {
             return Err(RequestDeviceError::UnsupportedFeature(
-                desc.features - self.raw.features,
+                desc.features - self.features,
             ));
         }
 
-        let caps = &self.raw.capabilities;
+        let caps = &self.capabilities;
         if wgt::Backends::PRIMARY.contains(wgt::Backends::from(A::VARIANT))
             && !caps.downlevel.is_webgpu_compliant()
         {
@@ -325,14 +417,21 @@ impl<A: HalApi> Adapter<A> {
             return Err(RequestDeviceError::LimitsExceeded(failed));
         }
 
-        let open = unsafe { self.raw.adapter.open(desc.features, &desc.limits) }.map_err(
-            |err| match err {
-                hal::DeviceError::Lost => RequestDeviceError::DeviceLost,
-                hal::DeviceError::OutOfMemory => RequestDeviceError::OutOfMemory,
-            },
-        )?;
+        let extra_queue_labels = extra_queues
+            .iter()
+            .map(|desc| desc.label.borrow_option())
+            .collect::<Vec<_>>();
+        let open = unsafe {
+            self.raw
+                .adapter
+                .open(desc.features, &desc.limits, &extra_queue_labels)
+        }
+        .map_err(|err| match err {
+            hal::DeviceError::Lost => RequestDeviceError::DeviceLost,
+            hal::DeviceError::OutOfMemory => RequestDeviceError::OutOfMemory,
+        })?;
 
-        self.create_device_from_hal(self_id, open, desc, trace_path)
+        self.create_device_from_hal(self_id, open, desc, extra_queues, trace_path)
     }
 }
 
@@ -383,6 +482,21 @@ pub enum RequestDeviceError {
     UnsupportedFeature(wgt::Features),
 }
 
+#[derive(Clone, Debug, Error)]
+/// Error when restricting an adapter to a profile it can't provide
+pub enum EmulateProfileError {
+    #[error("adapter is invalid")]
+    InvalidAdapter,
+    #[error("profile requires unsupported features: {0:?}")]
+    UnsupportedFeatures(wgt::Features),
+    #[error(transparent)]
+    LimitsExceeded(#[from] FailedLimit),
+    #[error("profile requires unsupported downlevel flags: {0:?}")]
+    UnsupportedDownlevelFlags(wgt::DownlevelFlags),
+    #[error("profile requires shader model {0:?}, which is unsupported")]
+    UnsupportedShaderModel(wgt::ShaderModel),
+}
+
 pub enum AdapterInputs<'a, I> {
     IdSet(&'a [I], fn(&I) -> Backend),
     Mask(Backends, fn(Backend) -> I),
@@ -820,6 +934,27 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .map_err(|_| InvalidAdapter)
     }
 
+    pub fn adapter_emulate_profile<A: HalApi>(
+        &self,
+        adapter_id: AdapterId,
+        profile: &wgt::AdapterProfile,
+        id_in: Input<G, AdapterId>,
+    ) -> Result<AdapterId, EmulateProfileError> {
+        profiling::scope!("emulate_profile", "Adapter");
+
+        let hub = A::hub(self);
+        let mut token = Token::root();
+        let adapter = {
+            let (adapter_guard, _) = hub.adapters.read(&mut token);
+            adapter_guard
+                .get(adapter_id)
+                .map_err(|_| EmulateProfileError::InvalidAdapter)?
+                .emulate_profile(profile)?
+        };
+        let id = hub.adapters.prepare(id_in).assign(adapter, &mut token);
+        Ok(id.0)
+    }
+
     pub fn adapter_features<A: HalApi>(
         &self,
         adapter_id: AdapterId,
@@ -829,7 +964,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (adapter_guard, _) = hub.adapters.read(&mut token);
         adapter_guard
             .get(adapter_id)
-            .map(|adapter| adapter.raw.features)
+            .map(|adapter| adapter.features)
             .map_err(|_| InvalidAdapter)
     }
 
@@ -842,7 +977,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (adapter_guard, _) = hub.adapters.read(&mut token);
         adapter_guard
             .get(adapter_id)
-            .map(|adapter| adapter.raw.capabilities.limits.clone())
+            .map(|adapter| adapter.capabilities.limits.clone())
             .map_err(|_| InvalidAdapter)
     }
 
@@ -855,7 +990,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (adapter_guard, _) = hub.adapters.read(&mut token);
         adapter_guard
             .get(adapter_id)
-            .map(|adapter| adapter.raw.capabilities.downlevel.clone())
+            .map(|adapter| adapter.capabilities.downlevel.clone())
             .map_err(|_| InvalidAdapter)
     }
 
@@ -884,6 +1019,22 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         desc: &DeviceDescriptor,
         trace_path: Option<&std::path::Path>,
         id_in: Input<G, DeviceId>,
+    ) -> (DeviceId, Option<RequestDeviceError>) {
+        self.adapter_request_device_with_queues::<A>(adapter_id, desc, &[], trace_path, id_in)
+    }
+
+    /// Request a device with additional queues besides the main one.
+    ///
+    /// The queues are addressed by their index with the device ID, the main
+    /// queue being `0` and the queues of `extra_queues` following it. If the
+    /// adapter has fewer hardware queues, several of them share one.
+    pub fn adapter_request_device_with_queues<A: HalApi>(
+        &self,
+        adapter_id: AdapterId,
+        desc: &DeviceDescriptor,
+        extra_queues: &[QueueDescriptor],
+        trace_path: Option<&std::path::Path>,
+        id_in: Input<G, DeviceId>,
     ) -> (DeviceId, Option<RequestDeviceError>) {
         profiling::scope!("request_device", "Adapter");
 
@@ -897,7 +1048,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(adapter) => adapter,
                 Err(_) => break RequestDeviceError::InvalidAdapter,
             };
-            let device = match adapter.create_device(adapter_id, desc, trace_path) {
+            let device = match adapter.create_device(adapter_id, desc, extra_queues, trace_path) {
                 Ok(device) => device,
                 Err(e) => break e,
             };
@@ -933,11 +1084,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(adapter) => adapter,
                 Err(_) => break RequestDeviceError::InvalidAdapter,
             };
-            let device =
-                match adapter.create_device_from_hal(adapter_id, hal_device, desc, trace_path) {
-                    Ok(device) => device,
-                    Err(e) => break e,
-                };
+            let extra_queues = vec![QueueDescriptor::default(); hal_device.extra_queues.len()];
+            let device = match adapter.create_device_from_hal(
+                adapter_id,
+                hal_device,
+                desc,
+                &extra_queues,
+                trace_path,
+            ) {
+                Ok(device) => device,
+                Err(e) => break e,
+            };
             let id = fid.assign(device, &mut token);
             return (id.0, None);
         };
@@ -983,3 +1140,180 @@ pub fn parse_backends_from_comma_list(string: &str) -> Backends {
 
     backends
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::Instance as _;
+
+    /// An adapter of the no-op backend, exposing `features` and the default limits.
+    fn empty_adapter(features: wgt::Features) -> Adapter<hal::api::Empty> {
+        // The empty backend uses the same type for all of its objects.
+        let raw = unsafe {
+            <hal::api::Empty as hal::Api>::Instance::init(&hal::InstanceDescriptor {
+                name: "test",
+                flags: hal::InstanceFlags::empty(),
+            })
+        }
+        .unwrap();
+        Adapter::new(hal::ExposedAdapter {
+            adapter: raw,
+            info: wgt::AdapterInfo {
+                name: "empty".to_string(),
+                vendor: 0,
+                device: 0,
+                device_type: wgt::DeviceType::Other,
+                backend: Backend::Empty,
+                driver_info: String::new(),
+            },
+            features,
+            capabilities: hal::Capabilities {
+                limits: wgt::Limits::default(),
+                alignments: hal::Alignments {
+                    buffer_copy_offset: wgt::BufferSize::new(4).unwrap(),
+                    buffer_copy_pitch: wgt::BufferSize::new(256).unwrap(),
+                },
+                downlevel: wgt::DownlevelCapabilities::default(),
+            },
+        })
+    }
+
+    fn full_profile<A: hal::Api>(adapter: &Adapter<A>) -> wgt::AdapterProfile {
+        wgt::AdapterProfile {
+            features: adapter.features,
+            limits: adapter.capabilities.limits.clone(),
+            downlevel_caps: adapter.capabilities.downlevel.clone(),
+            texture_format_features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn emulated_profile_narrows_features() {
+        let all = wgt::Features::DEPTH_CLIP_CONTROL | wgt::Features::TEXTURE_COMPRESSION_BC;
+        let adapter = empty_adapter(all);
+        let profile = wgt::AdapterProfile {
+            features: wgt::Features::DEPTH_CLIP_CONTROL,
+            ..full_profile(&adapter)
+        };
+        let emulated = adapter.emulate_profile(&profile).unwrap();
+        assert_eq!(emulated.features, wgt::Features::DEPTH_CLIP_CONTROL);
+        assert_eq!(adapter.features, all);
+
+        // Features left out of a profile can't come back in a later one.
+        match emulated.emulate_profile(&full_profile(&adapter)) {
+            Err(EmulateProfileError::UnsupportedFeatures(missing)) => {
+                assert_eq!(missing, wgt::Features::TEXTURE_COMPRESSION_BC)
+            }
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn emulated_profile_narrows_limits() {
+        let adapter = empty_adapter(wgt::Features::empty());
+        let mut profile = full_profile(&adapter);
+        profile.limits.max_bind_groups = 2;
+        profile.downlevel_caps.flags -= wgt::DownlevelFlags::COMPUTE_SHADERS;
+        profile.downlevel_caps.shader_model = wgt::ShaderModel::Sm4;
+        let emulated = adapter.emulate_profile(&profile).unwrap();
+
+        assert_eq!(emulated.capabilities.limits, profile.limits);
+        assert_eq!(emulated.capabilities.downlevel, profile.downlevel_caps);
+        assert_eq!(adapter.capabilities.limits, wgt::Limits::default());
+        assert_eq!(
+            adapter.capabilities.downlevel,
+            wgt::DownlevelCapabilities::default()
+        );
+    }
+
+    #[test]
+    fn emulated_profiles_narrow_texture_format_features() {
+        let format = wgt::TextureFormat::Rgba8Unorm;
+        let all = wgt::TextureFormatFeatures {
+            allowed_usages: wgt::TextureUsages::all(),
+            flags: wgt::TextureFormatFeatureFlags::all(),
+        };
+        let adapter = empty_adapter(wgt::Features::empty());
+        let profile_with = |allowed_usages, flags| wgt::AdapterProfile {
+            texture_format_features: vec![wgt::TextureFormatProfile {
+                format,
+                features: wgt::TextureFormatFeatures {
+                    allowed_usages,
+                    flags,
+                },
+            }],
+            ..full_profile(&adapter)
+        };
+
+        let first = adapter
+            .emulate_profile(&profile_with(
+                wgt::TextureUsages::TEXTURE_BINDING | wgt::TextureUsages::STORAGE_BINDING,
+                wgt::TextureFormatFeatureFlags::FILTERABLE,
+            ))
+            .unwrap();
+        let second = first
+            .emulate_profile(&profile_with(
+                wgt::TextureUsages::TEXTURE_BINDING | wgt::TextureUsages::RENDER_ATTACHMENT,
+                wgt::TextureFormatFeatureFlags::FILTERABLE
+                    | wgt::TextureFormatFeatureFlags::MULTISAMPLE,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            second.restrict_format_features(format, all),
+            wgt::TextureFormatFeatures {
+                allowed_usages: wgt::TextureUsages::TEXTURE_BINDING,
+                flags: wgt::TextureFormatFeatureFlags::FILTERABLE,
+            }
+        );
+        // Formats the profiles don't mention, and adapters without profiles, are untouched.
+        assert_eq!(
+            second.restrict_format_features(wgt::TextureFormat::R8Unorm, all),
+            all
+        );
+        assert_eq!(adapter.restrict_format_features(format, all), all);
+    }
+
+    #[test]
+    fn unsupported_profile_is_rejected() {
+        let adapter = empty_adapter(wgt::Features::DEPTH_CLIP_CONTROL);
+
+        let mut profile = full_profile(&adapter);
+        profile.features |= wgt::Features::TEXTURE_COMPRESSION_BC;
+        assert!(matches!(
+            adapter.emulate_profile(&profile),
+            Err(EmulateProfileError::UnsupportedFeatures(
+                wgt::Features::TEXTURE_COMPRESSION_BC
+            ))
+        ));
+
+        let mut profile = full_profile(&adapter);
+        profile.limits.max_bind_groups += 1;
+        match adapter.emulate_profile(&profile) {
+            Err(EmulateProfileError::LimitsExceeded(failed)) => {
+                assert_eq!(failed.name, "max_bind_groups")
+            }
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+
+        let mut sm4_adapter = empty_adapter(wgt::Features::empty());
+        sm4_adapter.capabilities.downlevel.shader_model = wgt::ShaderModel::Sm4;
+        sm4_adapter.capabilities.downlevel.flags -= wgt::DownlevelFlags::COMPUTE_SHADERS;
+        let mut profile = full_profile(&sm4_adapter);
+        profile.downlevel_caps.shader_model = wgt::ShaderModel::Sm5;
+        assert!(matches!(
+            sm4_adapter.emulate_profile(&profile),
+            Err(EmulateProfileError::UnsupportedShaderModel(
+                wgt::ShaderModel::Sm5
+            ))
+        ));
+        let mut profile = full_profile(&sm4_adapter);
+        profile.downlevel_caps.flags |= wgt::DownlevelFlags::COMPUTE_SHADERS;
+        assert!(matches!(
+            sm4_adapter.emulate_profile(&profile),
+            Err(EmulateProfileError::UnsupportedDownlevelFlags(
+                wgt::DownlevelFlags::COMPUTE_SHADERS
+            ))
+        ));
+    }
+}
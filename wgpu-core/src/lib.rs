@@ -38,9 +38,13 @@ pub mod hub;
 pub mod id;
 mod init_tracker;
 pub mod instance;
+pub mod offline;
 pub mod pipeline;
+mod pipeline_cache;
 pub mod present;
 pub mod resource;
+#[cfg(feature = "serialized-ir")]
+pub mod serialized_ir;
 mod track;
 mod validation;
 
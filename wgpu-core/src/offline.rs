@@ -0,0 +1,218 @@
+/*! Validation of shaders without a device.
+ *
+ * Runs the checks that shader module and pipeline creation perform on shaders
+ * against a target set of features, limits and downlevel capabilities, so that
+ * shaders can be validated ahead of time without any GPU.
+!*/
+
+use crate::{
+    binding_model::BindEntryMap,
+    device::{naga_capabilities, validate_shader_source, MissingDownlevelFlags},
+    error::PrettyError,
+    pipeline::{CreateShaderModuleError, ShaderModuleDescriptor, ShaderModuleSource},
+    validation::{Interface, StageError},
+    FastHashMap,
+};
+
+use thiserror::Error;
+
+/// The capabilities of the devices to validate shaders for.
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub features: wgt::Features,
+    pub limits: wgt::Limits,
+    pub downlevel: wgt::DownlevelCapabilities,
+}
+
+impl Profile {
+    /// Parse and validate a shader module, as `device_create_shader_module` does.
+    pub fn create_shader_module(
+        &self,
+        desc: &ShaderModuleDescriptor,
+        source: ShaderModuleSource,
+    ) -> Result<ShaderModule, CreateShaderModuleError> {
+        let (module, info) =
+            validate_shader_source(desc, source, naga_capabilities(self.features))?;
+        let interface = Interface::new(&module, &info, self.features, self.limits.clone());
+        Ok(ShaderModule { module, interface })
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum CheckStageError {
+    #[error(transparent)]
+    MissingDownlevelFlags(#[from] MissingDownlevelFlags),
+    #[error("Pipeline layout has {count} bind groups, but the limit is {max}")]
+    TooManyBindGroups { count: usize, max: u32 },
+    #[error(transparent)]
+    Stage(#[from] StageError),
+}
+
+impl PrettyError for CheckStageError {}
+
+/// A shader module that passed validation.
+#[derive(Debug)]
+pub struct ShaderModule {
+    module: naga::Module,
+    interface: Interface,
+}
+
+impl ShaderModule {
+    pub fn module(&self) -> &naga::Module {
+        &self.module
+    }
+
+    /// Check an entry point of the module, as pipeline creation does.
+    ///
+    /// `bind_group_layouts` lists the entries of each bind group layout of an explicit
+    /// pipeline layout. If it's `None`, the layout is derived from the shader instead.
+    /// The inputs of the entry point are assumed to be provided as declared.
+    pub fn check_stage(
+        &self,
+        profile: &Profile,
+        entry_point: &str,
+        stage: naga::ShaderStage,
+        bind_group_layouts: Option<&[Vec<wgt::BindGroupLayoutEntry>]>,
+    ) -> Result<(), CheckStageError> {
+        let stage_bit = match stage {
+            naga::ShaderStage::Vertex => wgt::ShaderStages::VERTEX,
+            naga::ShaderStage::Fragment => wgt::ShaderStages::FRAGMENT,
+            naga::ShaderStage::Compute => {
+                let flags = wgt::DownlevelFlags::COMPUTE_SHADERS;
+                if !profile.downlevel.flags.contains(flags) {
+                    return Err(MissingDownlevelFlags(flags).into());
+                }
+                wgt::ShaderStages::COMPUTE
+            }
+        };
+
+        let mut derived_group_layouts = Vec::new();
+        let provided_layouts = match bind_group_layouts {
+            Some(layouts) => {
+                if layouts.len() > profile.limits.max_bind_groups as usize {
+                    return Err(CheckStageError::TooManyBindGroups {
+                        count: layouts.len(),
+                        max: profile.limits.max_bind_groups,
+                    });
+                }
+                Some(
+                    layouts
+                        .iter()
+                        .map(|entries| {
+                            entries
+                                .iter()
+                                .map(|entry| (entry.binding, *entry))
+                                .collect::<BindEntryMap>()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            }
+            None => {
+                for _ in 0..profile.limits.max_bind_groups {
+                    derived_group_layouts.push(BindEntryMap::default());
+                }
+                None
+            }
+        };
+        let provided_layouts = provided_layouts
+            .as_ref()
+            .map(|layouts| layouts.iter().collect::<Vec<_>>());
+
+        let inputs = self
+            .interface
+            .declared_inputs(entry_point, stage)
+            .ok_or_else(|| StageError::MissingEntryPoint(entry_point.to_string()))?;
+        self.interface.check_stage(
+            provided_layouts.as_deref(),
+            &mut derived_group_layouts,
+            &mut FastHashMap::default(),
+            entry_point,
+            stage_bit,
+            inputs,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn test_profile(flags: wgt::DownlevelFlags) -> Profile {
+    Profile {
+        features: wgt::Features::empty(),
+        limits: wgt::Limits::default(),
+        downlevel: wgt::DownlevelCapabilities {
+            flags,
+            limits: wgt::DownlevelLimits::default(),
+            shader_model: wgt::ShaderModel::Sm5,
+        },
+    }
+}
+
+#[cfg(test)]
+fn compute_module(profile: &Profile) -> ShaderModule {
+    let desc = ShaderModuleDescriptor {
+        label: None,
+        shader_bound_checks: wgt::ShaderBoundChecks::default(),
+        source_hash: None,
+    };
+    let source = "
+        @group(0) @binding(0)
+        var<storage, read_write> data: array<u32>;
+
+        @stage(compute) @workgroup_size(1)
+        fn main() {
+            data[0] = 1u;
+        }
+    ";
+    profile
+        .create_shader_module(&desc, ShaderModuleSource::Wgsl(source.into()))
+        .unwrap()
+}
+
+#[test]
+fn test_check_stage_valid() {
+    let profile = test_profile(wgt::DownlevelFlags::compliant());
+    let module = compute_module(&profile);
+    let stage = naga::ShaderStage::Compute;
+    module.check_stage(&profile, "main", stage, None).unwrap();
+
+    let layout = vec![vec![wgt::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgt::ShaderStages::COMPUTE,
+        ty: wgt::BindingType::Buffer {
+            ty: wgt::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }]];
+    module
+        .check_stage(&profile, "main", stage, Some(&layout))
+        .unwrap();
+}
+
+#[test]
+fn test_check_stage_invalid() {
+    let profile = test_profile(wgt::DownlevelFlags::empty());
+    let module = compute_module(&profile);
+    let stage = naga::ShaderStage::Compute;
+    assert!(matches!(
+        module.check_stage(&profile, "main", stage, None),
+        Err(CheckStageError::MissingDownlevelFlags(_))
+    ));
+
+    let profile = test_profile(wgt::DownlevelFlags::compliant());
+    assert!(matches!(
+        module.check_stage(&profile, "missing", stage, None),
+        Err(CheckStageError::Stage(StageError::MissingEntryPoint(_)))
+    ));
+    // The storage buffer the shader writes to isn't in the layout.
+    assert!(matches!(
+        module.check_stage(&profile, "main", stage, Some(&[Vec::new()])),
+        Err(CheckStageError::Stage(StageError::Binding(..)))
+    ));
+    let too_many = vec![Vec::new(); profile.limits.max_bind_groups as usize + 1];
+    assert!(matches!(
+        module.check_stage(&profile, "main", stage, Some(&too_many)),
+        Err(CheckStageError::TooManyBindGroups { .. })
+    ));
+}
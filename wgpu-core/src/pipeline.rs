@@ -1,12 +1,25 @@
 use crate::{
     binding_model::{CreateBindGroupLayoutError, CreatePipelineLayoutError},
     device::{DeviceError, MissingDownlevelFlags, MissingFeatures, RenderPassContext},
-    hub::Resource,
-    id::{DeviceId, PipelineLayoutId, ShaderModuleId},
-    validation, Label, LifeGuard, Stored,
+    hub::{Global, GlobalIdentityHandlerFactory, Resource},
+    id::{
+        ComputePipelineId, DeviceId, PipelineCacheId, PipelineLayoutId, RenderPipelineId,
+        ShaderModuleId,
+    },
+    validation, FastHashMap, Label, LifeGuard, Stored,
 };
 use arrayvec::ArrayVec;
-use std::{borrow::Cow, error::Error, fmt, num::NonZeroU32};
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt,
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 use thiserror::Error;
 
 /// Information about buffer bindings, which
@@ -22,6 +35,9 @@ pub(crate) struct LateSizedBufferGroup {
 pub enum ShaderModuleSource<'a> {
     Wgsl(Cow<'a, str>),
     Naga(naga::Module),
+    /// Validated IR, as produced by [`crate::serialized_ir::encode`].
+    #[cfg(feature = "serialized-ir")]
+    SerializedIr(Cow<'a, [u8]>),
 }
 
 #[derive(Clone, Debug)]
@@ -31,13 +47,40 @@ pub struct ShaderModuleDescriptor<'a> {
     pub label: Label<'a>,
     #[cfg_attr(feature = "serde", serde(default))]
     pub shader_bound_checks: wgt::ShaderBoundChecks,
+    /// Hash of the code a [`ShaderModuleSource::Naga`] module was parsed from,
+    /// as returned by [`hash_shader_source`]. It identifies the translations
+    /// of the module in pipeline caches, which are not used without it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub source_hash: Option<u64>,
+}
+
+/// Hash shader code, for [`ShaderModuleDescriptor::source_hash`].
+///
+/// The hash is stable across runs.
+pub fn hash_shader_source(code: &[u8]) -> u64 {
+    use std::hash::Hasher as _;
+    let mut hasher = crate::pipeline_cache::KeyHasher::default();
+    hasher.write(code);
+    hasher.finish()
+}
+
+/// Copy of the shader source, kept for modules with overridable constants
+/// so that they can be recompiled at pipeline creation.
+#[derive(Debug)]
+pub(crate) struct SpecializationSource {
+    pub(crate) module: naga::Module,
+    pub(crate) runtime_checks: bool,
 }
 
 #[derive(Debug)]
 pub struct ShaderModule<A: hal::Api> {
-    pub(crate) raw: A::ShaderModule,
+    /// Shared with the pipelines being compiled from this module.
+    pub(crate) raw: Arc<A::ShaderModule>,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) interface: Option<validation::Interface>,
+    pub(crate) specialization: Option<SpecializationSource>,
+    /// Hash of the source, identifying translations of this module in pipeline caches.
+    pub(crate) cache_hash: Option<u64>,
     #[cfg(debug_assertions)]
     pub(crate) label: String,
 }
@@ -124,6 +167,83 @@ pub enum CreateShaderModuleError {
     Validation(#[from] ShaderError<naga::WithSpan<naga::valid::ValidationError>>),
     #[error(transparent)]
     MissingFeatures(#[from] MissingFeatures),
+    #[cfg(feature = "serialized-ir")]
+    #[error(transparent)]
+    SerializedIr(#[from] crate::serialized_ir::SerializedIrError),
+}
+
+impl CreateShaderModuleError {
+    /// Describe the error as compilation messages, located in the shader source
+    /// where possible.
+    pub fn compilation_info(&self) -> wgt::CompilationInfo {
+        let mut messages = Vec::new();
+        match *self {
+            Self::Parsing(ref error) => {
+                let mut labels = error.inner.labels();
+                let text = error.inner.to_string();
+                messages.push(match labels.next() {
+                    Some((range, label)) if !label.is_empty() => compilation_message(
+                        format!("{}: {}", text, label),
+                        Some(range),
+                        &error.source,
+                    ),
+                    Some((range, _)) => compilation_message(text, Some(range), &error.source),
+                    None => compilation_message(text, None, ""),
+                });
+                // The remaining labels point at related parts of the source.
+                for (range, label) in labels {
+                    messages.push(wgt::CompilationMessage {
+                        message_type: wgt::CompilationMessageType::Info,
+                        ..compilation_message(label.to_string(), Some(range), &error.source)
+                    });
+                }
+            }
+            Self::Validation(ref error) => {
+                let mut spans = error
+                    .inner
+                    .spans()
+                    .filter_map(|&(span, ref desc)| Some((span.to_range()?, desc)));
+                let text = error_chain(&error.inner);
+                messages.push(match spans.next() {
+                    Some((range, desc)) => compilation_message(
+                        format!("{}: {}", text, desc),
+                        Some(range),
+                        &error.source,
+                    ),
+                    None => compilation_message(text, None, ""),
+                });
+                // The remaining spans point at related parts of the source.
+                for (range, desc) in spans {
+                    messages.push(wgt::CompilationMessage {
+                        message_type: wgt::CompilationMessageType::Info,
+                        ..compilation_message(desc.clone(), Some(range), &error.source)
+                    });
+                }
+            }
+            _ => messages.push(compilation_message(error_chain(self), None, "")),
+        }
+        wgt::CompilationInfo { messages }
+    }
+}
+
+/// Concatenate the messages of an error and all its sources.
+fn error_chain(error: &dyn Error) -> String {
+    let mut string = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        string += &format!(": {}", error);
+        source = error.source();
+    }
+    string
+}
+
+fn compilation_message(
+    message: String,
+    range: Option<std::ops::Range<usize>>,
+    source: &str,
+) -> wgt::CompilationMessage {
+    // Modules created from naga IR have no source to point into.
+    wgt::CompilationMessage::new(message, wgt::CompilationMessageType::Error, source, range)
 }
 
 /// Describes a programmable pipeline stage.
@@ -136,8 +256,15 @@ pub struct ProgrammableStageDescriptor<'a> {
     /// The name of the entry point in the compiled shader. There must be a function that returns
     /// void with this name in the shader.
     pub entry_point: Cow<'a, str>,
+    /// Values of the pipeline-overridable constants of the shader, keyed by
+    /// the constant name or its numeric ID. Each constant may only be given once.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub constants: Cow<'a, PipelineConstants>,
 }
 
+/// Pipeline-overridable constant values, in the order they were given.
+pub type PipelineConstants = Vec<(String, f64)>;
+
 /// Number of implicit bind groups derived at pipeline creation.
 pub type ImplicitBindGroupCount = u8;
 
@@ -163,6 +290,9 @@ pub struct ComputePipelineDescriptor<'a> {
     pub layout: Option<PipelineLayoutId>,
     /// The compiled compute stage and its entry point.
     pub stage: ProgrammableStageDescriptor<'a>,
+    /// The pipeline cache to use when creating this pipeline.
+    #[cfg_attr(any(feature = "replay", feature = "trace"), serde(default))]
+    pub cache: Option<PipelineCacheId>,
 }
 
 #[derive(Clone, Debug, Error)]
@@ -171,6 +301,8 @@ pub enum CreateComputePipelineError {
     Device(#[from] DeviceError),
     #[error("pipeline layout is invalid")]
     InvalidLayout,
+    #[error("pipeline cache is invalid")]
+    InvalidCache,
     #[error("unable to derive an implicit layout")]
     Implicit(#[from] ImplicitLayoutError),
     #[error("error matching shader requirements against the pipeline")]
@@ -258,6 +390,9 @@ pub struct RenderPipelineDescriptor<'a> {
     /// If the pipeline will be used with a multiview render pass, this indicates how many array
     /// layers the attachments will have.
     pub multiview: Option<NonZeroU32>,
+    /// The pipeline cache to use when creating this pipeline.
+    #[cfg_attr(any(feature = "replay", feature = "trace"), serde(default))]
+    pub cache: Option<PipelineCacheId>,
 }
 
 #[derive(Clone, Debug, Error)]
@@ -299,6 +434,8 @@ pub enum CreateRenderPipelineError {
     Device(#[from] DeviceError),
     #[error("pipeline layout is invalid")]
     InvalidLayout,
+    #[error("pipeline cache is invalid")]
+    InvalidCache,
     #[error("unable to derive an implicit layout")]
     Implicit(#[from] ImplicitLayoutError),
     #[error("color state [{0}] is invalid")]
@@ -376,3 +513,214 @@ impl<A: hal::Api> Resource for RenderPipeline<A> {
         &self.life_guard
     }
 }
+
+/// A pipeline stage that passed validation, owning what its compilation needs.
+#[derive(Debug)]
+pub(crate) struct PendingStage<A: hal::Api> {
+    pub(crate) module: Arc<A::ShaderModule>,
+    /// Copy of `module` specialized for the pipeline constants,
+    /// which has to be destroyed once the pipeline is compiled.
+    pub(crate) specialized_module: Option<A::ShaderModule>,
+    pub(crate) entry_point: String,
+    pub(crate) cache: Option<Arc<hal::StageCache>>,
+}
+
+impl<A: hal::Api> PendingStage<A> {
+    pub(crate) fn to_hal(&self) -> hal::ProgrammableStage<A> {
+        hal::ProgrammableStage {
+            module: self.specialized_module.as_ref().unwrap_or(&self.module),
+            entry_point: &self.entry_point,
+            cache: self.cache.as_deref(),
+        }
+    }
+}
+
+/// A compute pipeline that passed validation, and only remains to be compiled.
+///
+/// It shares the hal objects it is compiled from, and holds references to
+/// the device and the layout, so it can outlive the locks it was created under.
+pub(crate) struct PendingComputePipeline<A: hal::Api> {
+    pub(crate) label: Option<String>,
+    pub(crate) layout: Arc<A::PipelineLayout>,
+    pub(crate) stage: PendingStage<A>,
+    pub(crate) cache: Option<Arc<A::PipelineCache>>,
+    pub(crate) layout_id: Stored<PipelineLayoutId>,
+    pub(crate) device_id: Stored<DeviceId>,
+    pub(crate) late_sized_buffer_groups: ArrayVec<LateSizedBufferGroup, { hal::MAX_BIND_GROUPS }>,
+}
+
+/// A render pipeline that passed validation, and only remains to be compiled.
+///
+/// See [`PendingComputePipeline`].
+pub(crate) struct PendingRenderPipeline<A: hal::Api> {
+    pub(crate) label: Option<String>,
+    pub(crate) layout: Arc<A::PipelineLayout>,
+    pub(crate) vertex_buffers: Vec<VertexBufferLayout<'static>>,
+    pub(crate) vertex_stage: PendingStage<A>,
+    pub(crate) fragment_stage: Option<PendingStage<A>>,
+    pub(crate) primitive: wgt::PrimitiveState,
+    pub(crate) depth_stencil: Option<wgt::DepthStencilState>,
+    pub(crate) multisample: wgt::MultisampleState,
+    pub(crate) color_targets: Vec<wgt::ColorTargetState>,
+    pub(crate) multiview: Option<NonZeroU32>,
+    pub(crate) cache: Option<Arc<A::PipelineCache>>,
+    pub(crate) layout_id: Stored<PipelineLayoutId>,
+    pub(crate) device_id: Stored<DeviceId>,
+    pub(crate) pass_context: RenderPassContext,
+    pub(crate) flags: PipelineFlags,
+    pub(crate) vertex_strides: Vec<(wgt::BufferAddress, wgt::VertexStepMode)>,
+    pub(crate) late_sized_buffer_groups: ArrayVec<LateSizedBufferGroup, { hal::MAX_BIND_GROUPS }>,
+}
+
+/// A pipeline dropped before being compiled, see [`PendingPipeline`].
+pub(crate) enum AbandonedPipeline<A: hal::Api> {
+    Render(RenderPipelineId, PendingRenderPipeline<A>),
+    Compute(ComputePipelineId, PendingComputePipeline<A>),
+}
+
+/// Pipelines of a device dropped before being compiled, which the next
+/// maintenance of the device releases and registers as errors.
+pub(crate) type AbandonedPipelines<A> = Arc<Mutex<Vec<AbandonedPipeline<A>>>>;
+
+/// The work left to create a pipeline, see [`PendingPipeline`].
+pub(crate) trait DeferredPipeline<G: GlobalIdentityHandlerFactory, E>: Send {
+    /// Compile the pipeline, and register it under its ID.
+    fn compile(self: Box<Self>, global: &Global<G>) -> Option<E>;
+    /// Hand the pipeline back to its device without compiling it.
+    fn abandon(self: Box<Self>);
+}
+
+/// A pipeline that passed validation, and only remains to be compiled by the backend.
+///
+/// Returned by `Global::device_create_*_pipeline_deferred`. It keeps the device,
+/// the pipeline layout and the shader modules alive, and can be compiled on any thread.
+/// The pipeline ID must not be used before [`PendingPipeline::compile`] returns.
+///
+/// If it's dropped without being compiled, the ID is registered as an error
+/// the next time the device is maintained, and has to be dropped as usual.
+pub struct PendingPipeline<G: GlobalIdentityHandlerFactory, E> {
+    deferred: Option<Box<dyn DeferredPipeline<G, E>>>,
+}
+
+impl<G: GlobalIdentityHandlerFactory, E> PendingPipeline<G, E> {
+    pub(crate) fn new(deferred: Box<dyn DeferredPipeline<G, E>>) -> Self {
+        Self {
+            deferred: Some(deferred),
+        }
+    }
+
+    /// Compile the pipeline, and register it under its ID.
+    ///
+    /// If the backend fails to compile it, the ID is registered as an error.
+    pub fn compile(mut self, global: &Global<G>) -> Option<E> {
+        self.deferred.take().unwrap().compile(global)
+    }
+}
+
+impl<G: GlobalIdentityHandlerFactory, E> Drop for PendingPipeline<G, E> {
+    fn drop(&mut self) {
+        if let Some(deferred) = self.deferred.take() {
+            deferred.abandon();
+        }
+    }
+}
+
+impl<G: GlobalIdentityHandlerFactory, E> fmt::Debug for PendingPipeline<G, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingPipeline").finish()
+    }
+}
+
+/// Describes a pipeline cache.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "trace", derive(serde::Serialize))]
+#[cfg_attr(feature = "replay", derive(serde::Deserialize))]
+pub struct PipelineCacheDescriptor<'a> {
+    pub label: Label<'a>,
+    /// Data previously returned by `pipeline_cache_get_data`.
+    pub data: Option<Cow<'a, [u8]>>,
+    /// If the data can't be used, start with an empty cache instead of failing.
+    #[cfg_attr(any(feature = "replay", feature = "trace"), serde(default))]
+    pub fallback: bool,
+}
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum PipelineCacheValidationError {
+    #[error("the data is truncated or corrupt")]
+    Corrupt,
+    #[error("the data was produced by a different version of wgpu")]
+    Version,
+    #[error("the data was produced for a different adapter or driver")]
+    Adapter,
+}
+
+#[derive(Clone, Debug, Error)]
+pub enum CreatePipelineCacheError {
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+    #[error("pipeline cache data is invalid: {0}")]
+    Validation(#[from] PipelineCacheValidationError),
+}
+
+/// Identifies the translation of a shader stage in a pipeline cache.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct StageCacheKey {
+    /// Hash of the shader module, including any specialized constants.
+    pub(crate) module: u64,
+    /// Hash of the shader stage, the pipeline layout, and any other
+    /// pipeline state that affects the translation.
+    pub(crate) variant: u64,
+    pub(crate) entry_point: String,
+}
+
+#[derive(Debug)]
+pub struct PipelineCache<A: hal::Api> {
+    /// Shared with the pipelines being compiled with this cache.
+    pub(crate) raw: Arc<A::PipelineCache>,
+    pub(crate) device_id: Stored<DeviceId>,
+    pub(crate) stages: Mutex<FastHashMap<StageCacheKey, Arc<hal::StageCache>>>,
+    /// Number of stages whose translation was found in the cache.
+    pub(crate) hits: AtomicU64,
+    pub(crate) life_guard: LifeGuard,
+}
+
+impl<A: hal::Api> PipelineCache<A> {
+    /// Returns the storage for the translation of a stage, creating it if needed.
+    pub(crate) fn stage(&self, key: StageCacheKey) -> Arc<hal::StageCache> {
+        let stage = Arc::clone(self.stages.lock().entry(key).or_default());
+        if stage.load().is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        stage
+    }
+
+    pub(crate) fn hit_count(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+impl<A: hal::Api> Resource for PipelineCache<A> {
+    const TYPE: &'static str = "PipelineCache";
+
+    fn life_guard(&self) -> &LifeGuard {
+        &self.life_guard
+    }
+}
+
+#[test]
+fn test_compilation_message_utf16() {
+    let source = "// é\nfn 𝄞 ()";
+    let start = source.find('𝄞').unwrap();
+    let message = compilation_message(String::new(), Some(start..start + 4), source);
+    assert_eq!(message.offset, 9);
+    assert_eq!(message.line_num, 2);
+    assert_eq!(message.line_pos, 4);
+    assert_eq!(message.length, 4);
+    assert_eq!(message.utf16_offset, 8);
+    assert_eq!(message.utf16_line_pos, 4);
+    assert_eq!(message.utf16_length, 2);
+
+    // Ranges splitting a character aren't located.
+    let message = compilation_message(String::new(), Some(start..start + 1), source);
+    assert_eq!(message.line_num, 0);
+}
@@ -0,0 +1,243 @@
+/*! Pipeline cache data.
+ *
+ * The data returned by `pipeline_cache_get_data` starts with a header
+ * identifying the version of wgpu, the adapter and the driver it was produced with, so that
+ * stale data is rejected. It is followed by the shader translations kept by
+ * wgpu-core for individual pipeline stages, and by the opaque contents of the
+ * backend cache. All integers are little-endian.
+!*/
+
+use crate::{
+    binding_model::{BindGroupLayout, PipelineLayout},
+    hub::Storage,
+    id::BindGroupLayoutId,
+    pipeline::{PipelineCacheValidationError, StageCacheKey},
+};
+
+use std::hash::{Hash, Hasher};
+
+const MAGIC: [u8; 8] = *b"WGPUPLCH";
+/// Has to be bumped whenever the layout of the data changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// Hasher for the cache keys. Unlike `DefaultHasher`, it is stable across runs.
+pub(crate) type KeyHasher = fxhash::FxHasher64;
+
+/// Hash everything about a pipeline layout that affects shader translation.
+pub(crate) fn hash_layout<A: hal::Api>(
+    layout: &PipelineLayout<A>,
+    bgl_guard: &Storage<BindGroupLayout<A>, BindGroupLayoutId>,
+) -> u64 {
+    let mut hasher = KeyHasher::default();
+    for &bgl_id in layout.bind_group_layout_ids.iter() {
+        let mut entries = bgl_guard[bgl_id].entries.values().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.binding);
+        entries.hash(&mut hasher);
+    }
+    layout.push_constant_ranges.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Identifies the wgpu version, the adapter and the driver the cache data is valid for.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Header {
+    wgpu_version: String,
+    backend: u8,
+    vendor: u64,
+    device: u64,
+    adapter_name: String,
+    driver_info: String,
+}
+
+impl Header {
+    pub(crate) fn new(info: &wgt::AdapterInfo) -> Self {
+        Self {
+            wgpu_version: env!("CARGO_PKG_VERSION").to_string(),
+            backend: info.backend as u8,
+            vendor: info.vendor as u64,
+            device: info.device as u64,
+            adapter_name: info.name.clone(),
+            driver_info: info.driver_info.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Contents {
+    pub(crate) stages: Vec<(StageCacheKey, Vec<u8>)>,
+    pub(crate) backend_data: Option<Vec<u8>>,
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], PipelineCacheValidationError> {
+        if self.0.len() < count {
+            return Err(PipelineCacheValidationError::Corrupt);
+        }
+        let (head, tail) = self.0.split_at(count);
+        self.0 = tail;
+        Ok(head)
+    }
+    fn u32(&mut self) -> Result<u32, PipelineCacheValidationError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+    fn u64(&mut self) -> Result<u64, PipelineCacheValidationError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+    fn bytes(&mut self) -> Result<&'a [u8], PipelineCacheValidationError> {
+        let count = self.u32()? as usize;
+        self.take(count)
+    }
+    fn string(&mut self) -> Result<String, PipelineCacheValidationError> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| PipelineCacheValidationError::Corrupt)
+    }
+}
+
+pub(crate) fn encode(header: &Header, contents: &Contents) -> Vec<u8> {
+    let mut writer = Writer(MAGIC.to_vec());
+    writer.u32(FORMAT_VERSION);
+    writer.bytes(header.wgpu_version.as_bytes());
+    writer.0.push(header.backend);
+    writer.u64(header.vendor);
+    writer.u64(header.device);
+    writer.bytes(header.adapter_name.as_bytes());
+    writer.bytes(header.driver_info.as_bytes());
+
+    writer.u32(contents.stages.len() as u32);
+    for (key, data) in contents.stages.iter() {
+        writer.u64(key.module);
+        writer.u64(key.variant);
+        writer.bytes(key.entry_point.as_bytes());
+        writer.bytes(data);
+    }
+    match contents.backend_data {
+        Some(ref data) => {
+            writer.0.push(1);
+            writer.bytes(data);
+        }
+        None => writer.0.push(0),
+    }
+    writer.0
+}
+
+/// Parse cache data, checking that it was produced for the adapter described by `expected`.
+pub(crate) fn decode(
+    data: &[u8],
+    expected: &Header,
+) -> Result<Contents, PipelineCacheValidationError> {
+    let mut reader = Reader(data);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(PipelineCacheValidationError::Corrupt);
+    }
+    if reader.u32()? != FORMAT_VERSION {
+        return Err(PipelineCacheValidationError::Version);
+    }
+    if reader.string()? != expected.wgpu_version {
+        return Err(PipelineCacheValidationError::Version);
+    }
+    let header = Header {
+        wgpu_version: expected.wgpu_version.clone(),
+        backend: reader.take(1)?[0],
+        vendor: reader.u64()?,
+        device: reader.u64()?,
+        adapter_name: reader.string()?,
+        driver_info: reader.string()?,
+    };
+    if header != *expected {
+        return Err(PipelineCacheValidationError::Adapter);
+    }
+
+    let stage_count = reader.u32()?;
+    let mut stages = Vec::new();
+    for _ in 0..stage_count {
+        let key = StageCacheKey {
+            module: reader.u64()?,
+            variant: reader.u64()?,
+            entry_point: reader.string()?,
+        };
+        stages.push((key, reader.bytes()?.to_vec()));
+    }
+    let backend_data = match reader.take(1)?[0] {
+        0 => None,
+        _ => Some(reader.bytes()?.to_vec()),
+    };
+    if !reader.0.is_empty() {
+        return Err(PipelineCacheValidationError::Corrupt);
+    }
+
+    Ok(Contents {
+        stages,
+        backend_data,
+    })
+}
+
+#[test]
+fn test_round_trip() {
+    let info = wgt::AdapterInfo {
+        name: "Test Adapter".to_string(),
+        vendor: 0x10DE,
+        device: 0x1234,
+        device_type: wgt::DeviceType::DiscreteGpu,
+        backend: wgt::Backend::Vulkan,
+        driver_info: "1.0".to_string(),
+    };
+    let header = Header::new(&info);
+    let contents = Contents {
+        stages: vec![(
+            StageCacheKey {
+                module: 1,
+                variant: 2,
+                entry_point: "main".to_string(),
+            },
+            vec![1, 2, 3],
+        )],
+        backend_data: Some(vec![4, 5]),
+    };
+    let data = encode(&header, &contents);
+
+    let decoded = decode(&data, &header).unwrap();
+    assert_eq!(decoded.stages, contents.stages);
+    assert_eq!(decoded.backend_data, contents.backend_data);
+
+    let other_header = Header::new(&wgt::AdapterInfo {
+        device: 0x4321,
+        ..info.clone()
+    });
+    assert_eq!(
+        decode(&data, &other_header).unwrap_err(),
+        PipelineCacheValidationError::Adapter
+    );
+    let other_driver_header = Header::new(&wgt::AdapterInfo {
+        driver_info: "1.1".to_string(),
+        ..info
+    });
+    assert_eq!(
+        decode(&data, &other_driver_header).unwrap_err(),
+        PipelineCacheValidationError::Adapter
+    );
+    assert_eq!(
+        decode(&data[..data.len() - 1], &header).unwrap_err(),
+        PipelineCacheValidationError::Corrupt
+    );
+}
@@ -181,6 +181,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         clear_views,
                         is_color: true,
                     },
+                    memory: None,
                 };
 
                 let ref_count = texture.life_guard.add_ref();
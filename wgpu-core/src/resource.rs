@@ -11,7 +11,17 @@ use crate::{
 use smallvec::SmallVec;
 use thiserror::Error;
 
-use std::{borrow::Borrow, num::NonZeroU8, ops::Range, ptr::NonNull};
+use std::{
+    borrow::Borrow,
+    fmt,
+    num::NonZeroU8,
+    ops::Range,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 #[repr(C)]
 #[derive(Debug)]
@@ -23,22 +33,41 @@ pub enum BufferMapAsyncStatus {
     ContextLost,
 }
 
+/// Memory used by a resource, counted in a running total of its device
+/// for as long as this is alive.
+#[derive(Debug)]
+pub(crate) struct MemoryUse {
+    total: Arc<AtomicU64>,
+    size: u64,
+}
+
+impl MemoryUse {
+    pub(crate) fn new(total: &Arc<AtomicU64>, size: u64) -> Self {
+        total.fetch_add(size, Ordering::Relaxed);
+        Self {
+            total: Arc::clone(total),
+            size,
+        }
+    }
+}
+
+impl Drop for MemoryUse {
+    fn drop(&mut self) {
+        self.total.fetch_sub(self.size, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum BufferMapState<A: hal::Api> {
     /// Mapped at creation.
     Init {
         ptr: NonNull<u8>,
         stage_buffer: A::Buffer,
+        stage_memory: MemoryUse,
         needs_flush: bool,
     },
-    /// Waiting for GPU to be done before mapping
-    Waiting(BufferPendingMapping),
-    /// Mapped
-    Active {
-        ptr: NonNull<u8>,
-        range: hal::MemoryRange,
-        host: HostMap,
-    },
+    /// Some ranges are mapped, or waiting for GPU to be done before mapping
+    Mapped(BufferMapping),
     /// Not mapped
     Idle,
 }
@@ -46,29 +75,118 @@ pub(crate) enum BufferMapState<A: hal::Api> {
 unsafe impl<A: hal::Api> Send for BufferMapState<A> {}
 unsafe impl<A: hal::Api> Sync for BufferMapState<A> {}
 
-pub type BufferMapCallback = unsafe extern "C" fn(status: BufferMapAsyncStatus, userdata: *mut u8);
+/// The mapped and pending ranges of a buffer.
+///
+/// Ranges are mapped, flushed and unmapped independently. The hal buffer is
+/// unmapped once the last active range is.
+#[derive(Debug, Default)]
+pub(crate) struct BufferMapping {
+    pub is_coherent: bool,
+    /// Ranges waiting for GPU to be done before mapping
+    pub pending: Vec<BufferPendingMapping>,
+    pub active: Vec<BufferActiveMapping>,
+}
+
+impl BufferMapping {
+    /// Check if `range` intersects any of the mapped or pending ranges.
+    pub fn overlaps(&self, range: &Range<wgt::BufferAddress>) -> bool {
+        let intersects =
+            |other: &Range<wgt::BufferAddress>| range.start < other.end && other.start < range.end;
+        self.pending
+            .iter()
+            .any(|pending| intersects(&pending.range))
+            || self.active.iter().any(|active| intersects(&active.range))
+    }
+}
 
-#[repr(C)]
 #[derive(Debug)]
-pub struct BufferMapOperation {
+pub(crate) struct BufferActiveMapping {
+    pub range: hal::MemoryRange,
+    /// Start of `range` in host memory.
+    pub ptr: NonNull<u8>,
     pub host: HostMap,
-    pub callback: BufferMapCallback,
+    /// Writes need to be flushed on unmap.
+    pub needs_flush: bool,
+}
+
+/// A C function pointer with its user data, for use by the C bindings.
+#[repr(C)]
+pub struct BufferMapCallbackC {
+    pub callback: unsafe extern "C" fn(status: BufferMapAsyncStatus, user_data: *mut u8),
     pub user_data: *mut u8,
 }
 
-//TODO: clarify if/why this is needed here
-unsafe impl Send for BufferMapOperation {}
-unsafe impl Sync for BufferMapOperation {}
+unsafe impl Send for BufferMapCallbackC {}
+
+/// Callback invoked once a buffer mapping request is resolved.
+///
+/// It's called without any internal locks held, from whichever thread
+/// polls the device, maps, unmaps or drops the buffer. If the mapping
+/// request is rejected with an error, it's dropped without being called.
+pub struct BufferMapCallback {
+    // We wrap this so creating the enum in the C variant can be unsafe,
+    // allowing our call function to be safe.
+    inner: BufferMapCallbackInner,
+}
+
+enum BufferMapCallbackInner {
+    Rust {
+        callback: Box<dyn FnOnce(BufferMapAsyncStatus) + Send + 'static>,
+    },
+    C {
+        inner: BufferMapCallbackC,
+    },
+}
+
+impl BufferMapCallback {
+    pub fn from_rust(callback: Box<dyn FnOnce(BufferMapAsyncStatus) + Send + 'static>) -> Self {
+        Self {
+            inner: BufferMapCallbackInner::Rust { callback },
+        }
+    }
+
+    /// # Safety
+    ///
+    /// - The callback pointer must be valid to call with the provided `user_data` pointer.
+    /// - Both pointers must point to `'static` data, as the callback may be called at
+    ///   an unspecified time, on any thread.
+    pub unsafe fn from_c(inner: BufferMapCallbackC) -> Self {
+        Self {
+            inner: BufferMapCallbackInner::C { inner },
+        }
+    }
+
+    pub(crate) fn call(self, status: BufferMapAsyncStatus) {
+        match self.inner {
+            BufferMapCallbackInner::Rust { callback } => callback(status),
+            // SAFETY: the contract of `from_c` guarantees this is sound
+            BufferMapCallbackInner::C { inner } => unsafe {
+                (inner.callback)(status, inner.user_data)
+            },
+        }
+    }
 
-impl BufferMapOperation {
     pub(crate) fn call_error(self) {
         log::error!("wgpu_buffer_map_async failed: buffer mapping is pending");
-        unsafe {
-            (self.callback)(BufferMapAsyncStatus::Error, self.user_data);
+        self.call(BufferMapAsyncStatus::Error);
+    }
+}
+
+impl fmt::Debug for BufferMapCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            BufferMapCallbackInner::Rust { .. } => f.write_str("BufferMapCallback::Rust"),
+            BufferMapCallbackInner::C { .. } => f.write_str("BufferMapCallback::C"),
         }
     }
 }
 
+#[derive(Debug)]
+pub struct BufferMapOperation {
+    pub host: HostMap,
+    pub callback: BufferMapCallback,
+}
+
 #[derive(Clone, Debug, Error)]
 pub enum BufferAccessError {
     #[error(transparent)]
@@ -83,6 +201,10 @@ pub enum BufferAccessError {
     MissingBufferUsage(#[from] MissingBufferUsageError),
     #[error("buffer is not mapped")]
     NotMapped,
+    #[error("buffer range {0:?} is not mapped or pending mapping")]
+    RangeNotMapped(Range<wgt::BufferAddress>),
+    #[error("buffer range {0:?} intersects a range that is already mapped or pending mapping")]
+    RangeAlreadyMapped(Range<wgt::BufferAddress>),
     #[error(
         "buffer map range must start aligned to `MAP_ALIGNMENT` and end to `COPY_BUFFER_ALIGNMENT`"
     )]
@@ -122,9 +244,10 @@ pub struct Buffer<A: hal::Api> {
     pub(crate) usage: wgt::BufferUsages,
     pub(crate) size: wgt::BufferAddress,
     pub(crate) initialization_status: BufferInitTracker,
-    pub(crate) sync_mapped_writes: Option<hal::MemoryRange>,
     pub(crate) life_guard: LifeGuard,
     pub(crate) map_state: BufferMapState<A>,
+    /// `None` once the buffer is destroyed.
+    pub(crate) memory: Option<MemoryUse>,
 }
 
 #[derive(Clone, Debug, Error)]
@@ -203,6 +326,8 @@ pub struct Texture<A: hal::Api> {
     pub(crate) full_range: TextureSelector,
     pub(crate) life_guard: LifeGuard,
     pub(crate) clear_mode: TextureClearMode<A>,
+    /// `None` for surface textures, and once the texture is destroyed.
+    pub(crate) memory: Option<MemoryUse>,
 }
 
 impl<A: hal::Api> Texture<A> {
@@ -535,6 +660,7 @@ pub struct QuerySet<A: hal::Api> {
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) life_guard: LifeGuard,
     pub(crate) desc: wgt::QuerySetDescriptor<()>,
+    pub(crate) memory: MemoryUse,
 }
 
 impl<A: hal::Api> Resource for QuerySet<A> {
@@ -0,0 +1,195 @@
+/*! Serialized shader IR.
+ *
+ * Shader modules can be created from naga IR that was parsed and validated
+ * ahead of time, skipping both parsing and validation when the module is
+ * loaded. The data starts with a header identifying the version of wgpu-core
+ * that produced it (the IR has no stable layout across naga versions), the
+ * validation capabilities the module required, and a hash of the payload.
+ * The payload is the `bincode` encoding of the module and its `ModuleInfo`.
+ * All integers are little-endian.
+ *
+ * The hash only guards against corrupted data: the `ModuleInfo` is trusted to
+ * describe the module, so the data must come from [`encode`]. The module is
+ * only validated again when the device lacks some of the capabilities it was
+ * validated with.
+!*/
+
+use crate::{
+    device::{naga_capabilities, validate_shader_source},
+    pipeline::{CreateShaderModuleError, ShaderModuleDescriptor, ShaderModuleSource},
+};
+
+use std::{borrow::Cow, hash::Hasher as _};
+use thiserror::Error;
+
+const MAGIC: [u8; 8] = *b"WGPUNIR\0";
+/// Has to be bumped whenever the layout of the header changes.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Error)]
+pub enum SerializedIrError {
+    #[error("Data is not serialized shader IR")]
+    NotIr,
+    #[error("Serialized IR was produced by wgpu-core {found}, but this is {}", env!("CARGO_PKG_VERSION"))]
+    Version { found: String },
+    #[error("Serialized IR doesn't match its hash")]
+    Hash,
+    #[error("Serialized IR is corrupted: {0}")]
+    Corrupt(String),
+}
+
+/// A naga module together with the results of its validation.
+#[derive(Debug)]
+pub struct ValidatedModule {
+    pub module: naga::Module,
+    pub info: naga::valid::ModuleInfo,
+    /// The capabilities the module was validated with when it was encoded.
+    pub capabilities: naga::valid::Capabilities,
+}
+
+fn hash_payload(payload: &[u8]) -> u64 {
+    let mut hasher = fxhash::FxHasher64::default();
+    hasher.write(payload);
+    hasher.finish()
+}
+
+/// Serialize a module that passed validation with the given capabilities.
+pub fn encode(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    capabilities: naga::valid::Capabilities,
+) -> Vec<u8> {
+    let payload = bincode::serialize(&(module, info)).expect("cannot serialize shader IR");
+    let version = env!("CARGO_PKG_VERSION").as_bytes();
+
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    data.extend_from_slice(&(version.len() as u32).to_le_bytes());
+    data.extend_from_slice(version);
+    data.extend_from_slice(&u32::from(capabilities.bits()).to_le_bytes());
+    data.extend_from_slice(&hash_payload(&payload).to_le_bytes());
+    data.extend_from_slice(&payload);
+    data
+}
+
+/// Parse and validate WGSL source with the capabilities `features` enable, and serialize it.
+pub fn compile(
+    desc: &ShaderModuleDescriptor,
+    source: &str,
+    features: wgt::Features,
+) -> Result<Vec<u8>, CreateShaderModuleError> {
+    let capabilities = naga_capabilities(features);
+    let (module, info) = validate_shader_source(
+        desc,
+        ShaderModuleSource::Wgsl(Cow::Borrowed(source)),
+        capabilities,
+    )?;
+    Ok(encode(&module, &info, capabilities))
+}
+
+fn split(data: &[u8], count: usize) -> Result<(&[u8], &[u8]), SerializedIrError> {
+    if data.len() < count {
+        return Err(SerializedIrError::Corrupt(
+            "unexpected end of data".to_string(),
+        ));
+    }
+    Ok(data.split_at(count))
+}
+
+fn split_u32(data: &[u8]) -> Result<(u32, &[u8]), SerializedIrError> {
+    let (head, tail) = split(data, 4)?;
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(head);
+    Ok((u32::from_le_bytes(bytes), tail))
+}
+
+/// Deserialize a module, checking that it was produced by this version of wgpu-core.
+pub fn decode(data: &[u8]) -> Result<ValidatedModule, SerializedIrError> {
+    if !data.starts_with(&MAGIC) {
+        return Err(SerializedIrError::NotIr);
+    }
+    let (format_version, rest) = split_u32(&data[MAGIC.len()..])?;
+    let (version_len, rest) = split_u32(rest)?;
+    let (version, rest) = split(rest, version_len as usize)?;
+    let version = String::from_utf8_lossy(version);
+    if format_version != FORMAT_VERSION || version != env!("CARGO_PKG_VERSION") {
+        return Err(SerializedIrError::Version {
+            found: version.into_owned(),
+        });
+    }
+
+    let (capability_bits, rest) = split_u32(rest)?;
+    let capabilities = naga::valid::Capabilities::from_bits(capability_bits as _)
+        .ok_or_else(|| SerializedIrError::Corrupt("unknown capabilities".to_string()))?;
+    let (hash, payload) = split(rest, 8)?;
+    let mut hash_bytes = [0; 8];
+    hash_bytes.copy_from_slice(hash);
+    if u64::from_le_bytes(hash_bytes) != hash_payload(payload) {
+        return Err(SerializedIrError::Hash);
+    }
+
+    let (module, info) = bincode::deserialize(payload)
+        .map_err(|error| SerializedIrError::Corrupt(error.to_string()))?;
+    Ok(ValidatedModule {
+        module,
+        info,
+        capabilities,
+    })
+}
+
+#[test]
+fn test_round_trip() {
+    let module = naga::front::wgsl::parse_str(
+        "@stage(compute) @workgroup_size(1)
+        fn main() {}",
+    )
+    .unwrap();
+    let capabilities = naga::valid::Capabilities::empty();
+    let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), capabilities)
+        .validate(&module)
+        .unwrap();
+    let mut data = encode(&module, &info, capabilities);
+
+    let decoded = decode(&data).unwrap();
+    assert_eq!(decoded.module.entry_points.len(), 1);
+    assert_eq!(decoded.capabilities, capabilities);
+
+    *data.last_mut().unwrap() ^= 1;
+    assert!(matches!(decode(&data), Err(SerializedIrError::Hash)));
+    assert!(matches!(decode(b"@stage"), Err(SerializedIrError::NotIr)));
+}
+
+#[test]
+fn test_missing_capabilities_revalidate() {
+    let desc = ShaderModuleDescriptor {
+        label: None,
+        shader_bound_checks: wgt::ShaderBoundChecks::default(),
+        source_hash: None,
+    };
+    let data = compile(
+        &desc,
+        "var<push_constant> value: u32;
+        @stage(compute) @workgroup_size(1)
+        fn main() { let x = value; }",
+        wgt::Features::PUSH_CONSTANTS,
+    )
+    .unwrap();
+
+    let with_capability = validate_shader_source(
+        &desc,
+        ShaderModuleSource::SerializedIr(Cow::Borrowed(&data)),
+        naga_capabilities(wgt::Features::PUSH_CONSTANTS),
+    );
+    assert!(with_capability.is_ok());
+
+    // Without the capability, the module is validated again and rejected.
+    let without_capability = validate_shader_source(
+        &desc,
+        ShaderModuleSource::SerializedIr(Cow::Borrowed(&data)),
+        naga_capabilities(wgt::Features::empty()),
+    );
+    assert!(matches!(
+        without_capability,
+        Err(CreateShaderModuleError::Validation(_))
+    ));
+}
@@ -3,7 +3,7 @@ mod range;
 mod texture;
 
 use crate::{
-    hub,
+    binding_model, hub,
     id::{self, TypedId, Valid},
     resource, Epoch, FastHashMap, Index, RefCount,
 };
@@ -522,6 +522,21 @@ impl<S: ResourceState> ResourceTracker<S> {
     }
 }
 
+impl ResourceTracker<BindGroupState> {
+    /// Use a bind group at the version its contents currently have.
+    pub(crate) fn use_current<'a, A: hal::Api>(
+        &mut self,
+        storage: &'a hub::Storage<binding_model::BindGroup<A>, id::BindGroupId>,
+        id: id::BindGroupId,
+    ) -> Result<&'a binding_model::BindGroup<A>, UseExtendError<u64>> {
+        let version = storage
+            .get(id)
+            .map_err(|_| UseExtendError::InvalidResource)?
+            .version();
+        self.use_extend(storage, id, (), version)
+    }
+}
+
 impl<I: Copy + fmt::Debug + TypedId> ResourceState for PhantomData<I> {
     type Id = I;
     type Selector = ();
@@ -553,6 +568,51 @@ impl<I: Copy + fmt::Debug + TypedId> ResourceState for PhantomData<I> {
     fn optimize(&mut self) {}
 }
 
+/// Tracking state of a bind group: the oldest contents version it was used with.
+///
+/// Regular bind groups are always at version zero. Descriptor tables bump
+/// their version on every update, so a tracker that recorded an older
+/// version than the table currently has holds stale resource usages.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct BindGroupState {
+    version: Option<u64>,
+}
+
+impl ResourceState for BindGroupState {
+    type Id = id::BindGroupId;
+    type Selector = ();
+    type Usage = u64;
+
+    fn query(&self, _selector: Self::Selector) -> Option<Self::Usage> {
+        self.version
+    }
+
+    fn change(
+        &mut self,
+        _id: Valid<Self::Id>,
+        _selector: Self::Selector,
+        usage: Self::Usage,
+        _output: Option<&mut Vec<PendingTransition<Self>>>,
+    ) -> Result<(), PendingTransition<Self>> {
+        self.version = Some(self.version.map_or(usage, |version| version.min(usage)));
+        Ok(())
+    }
+
+    fn merge(
+        &mut self,
+        id: Valid<Self::Id>,
+        other: &Self,
+        output: Option<&mut Vec<PendingTransition<Self>>>,
+    ) -> Result<(), PendingTransition<Self>> {
+        match other.version {
+            Some(version) => self.change(id, (), version, output),
+            None => Ok(()),
+        }
+    }
+
+    fn optimize(&mut self) {}
+}
+
 pub const DUMMY_SELECTOR: () = ();
 
 #[derive(Clone, Debug, Error)]
@@ -584,7 +644,7 @@ pub(crate) struct TrackerSet {
     pub buffers: ResourceTracker<BufferState>,
     pub textures: ResourceTracker<TextureState>,
     pub views: ResourceTracker<PhantomData<id::TextureViewId>>,
-    pub bind_groups: ResourceTracker<PhantomData<id::BindGroupId>>,
+    pub bind_groups: ResourceTracker<BindGroupState>,
     pub samplers: ResourceTracker<PhantomData<id::SamplerId>>,
     pub compute_pipes: ResourceTracker<PhantomData<id::ComputePipelineId>>,
     pub render_pipes: ResourceTracker<PhantomData<id::RenderPipelineId>>,
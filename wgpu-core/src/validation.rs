@@ -1,6 +1,6 @@
-use crate::{binding_model::BindEntryMap, FastHashMap, FastHashSet};
+use crate::{binding_model::BindEntryMap, pipeline::PipelineConstants, FastHashMap, FastHashSet};
 use naga::valid::GlobalUse;
-use std::{collections::hash_map::Entry, fmt};
+use std::{collections::hash_map::Entry, fmt, hash::Hasher};
 use thiserror::Error;
 use wgt::{BindGroupLayoutEntry, BindingType};
 
@@ -101,11 +101,13 @@ enum Varying {
     BuiltIn(naga::BuiltIn),
 }
 
-#[allow(unused)]
 #[derive(Debug)]
 struct SpecializationConstant {
+    handle: naga::Handle<naga::Constant>,
     id: u32,
-    ty: NumericType,
+    name: Option<String>,
+    kind: naga::ScalarKind,
+    width: naga::Bytes,
 }
 
 #[derive(Debug, Default)]
@@ -113,8 +115,6 @@ struct EntryPoint {
     inputs: Vec<Varying>,
     outputs: Vec<Varying>,
     resources: Vec<(naga::Handle<Resource>, GlobalUse)>,
-    #[allow(unused)]
-    spec_constants: Vec<SpecializationConstant>,
     sampling_pairs: FastHashSet<(naga::Handle<Resource>, naga::Handle<Resource>)>,
     workgroup_size: [u32; 3],
 }
@@ -124,6 +124,7 @@ pub struct Interface {
     features: wgt::Features,
     limits: wgt::Limits,
     resources: naga::Arena<Resource>,
+    spec_constants: Vec<SpecializationConstant>,
     entry_points: FastHashMap<(naga::ShaderStage, String), EntryPoint>,
 }
 
@@ -247,6 +248,18 @@ pub enum StageError {
     TooManyVaryings { used: u32, limit: u32 },
     #[error("unable to find entry point '{0}'")]
     MissingEntryPoint(String),
+    #[error("pipeline constant '{0}' doesn't match any overridable constant of the shader")]
+    UnknownPipelineConstant(String),
+    #[error("pipeline constant '{0}' overrides a constant that was already given a value")]
+    DuplicatePipelineConstant(String),
+    #[error("value {value} of pipeline constant '{key}' is not representable as {kind:?}")]
+    InvalidPipelineConstant {
+        key: String,
+        value: f64,
+        kind: naga::ScalarKind,
+    },
+    #[error("failed to specialize the shader module: {0}")]
+    Specialization(String),
     #[error("shader global {0:?} is not available in the layout pipeline layout")]
     Binding(naga::ResourceBinding, #[source] BindingError),
     #[error("unable to filter the texture ({texture:?}) by the sampler ({sampler:?})")]
@@ -265,6 +278,98 @@ pub enum StageError {
     },
 }
 
+fn scalar_value_kind(value: &naga::ScalarValue) -> naga::ScalarKind {
+    match *value {
+        naga::ScalarValue::Sint(_) => naga::ScalarKind::Sint,
+        naga::ScalarValue::Uint(_) => naga::ScalarKind::Uint,
+        naga::ScalarValue::Float(_) => naga::ScalarKind::Float,
+        naga::ScalarValue::Bool(_) => naga::ScalarKind::Bool,
+    }
+}
+
+/// Convert a pipeline constant value to the type of the constant it overrides,
+/// following the WebGPU conversion rules.
+fn map_pipeline_constant(
+    value: f64,
+    kind: naga::ScalarKind,
+    width: naga::Bytes,
+) -> Option<naga::ScalarValue> {
+    use naga::{ScalarKind as Sk, ScalarValue as Sv};
+
+    match kind {
+        Sk::Bool => Some(Sv::Bool(value != 0.0)),
+        Sk::Sint
+            if value.fract() == 0.0 && value >= i32::MIN as f64 && value <= i32::MAX as f64 =>
+        {
+            Some(Sv::Sint(value as i64))
+        }
+        Sk::Uint if value.fract() == 0.0 && value >= 0.0 && value <= u32::MAX as f64 => {
+            Some(Sv::Uint(value as u64))
+        }
+        Sk::Float if width == 4 && value.is_finite() && value.abs() <= f32::MAX as f64 => {
+            Some(Sv::Float(value as f32 as f64))
+        }
+        Sk::Float if width == 8 && value.is_finite() => Some(Sv::Float(value)),
+        _ => None,
+    }
+}
+
+/// Values to specialize a shader module with, resolved from the
+/// pipeline-overridable constants of a stage.
+#[derive(Debug, Default)]
+pub struct StageSpecialization {
+    /// Overridden constants and their new values.
+    pub constants: Vec<(naga::Handle<naga::Constant>, naga::ScalarValue)>,
+}
+
+impl StageSpecialization {
+    /// Returns `true` if the module can be used as is.
+    pub fn is_empty(&self) -> bool {
+        self.constants.is_empty()
+    }
+
+    /// Feed the specialized values to `hasher`.
+    pub fn hash<H: Hasher>(&self, hasher: &mut H) {
+        for &(handle, value) in self.constants.iter() {
+            hasher.write_usize(handle.index());
+            match value {
+                naga::ScalarValue::Sint(value) => hasher.write_i64(value),
+                naga::ScalarValue::Uint(value) => hasher.write_u64(value),
+                naga::ScalarValue::Float(value) => hasher.write_u64(value.to_bits()),
+                naga::ScalarValue::Bool(value) => hasher.write_u8(value as u8),
+            }
+        }
+    }
+}
+
+/// Produce a copy of `module` with the given values substituted into it.
+pub fn specialize_module(
+    module: &naga::Module,
+    specialization: &StageSpecialization,
+) -> naga::Module {
+    let mut module = module.clone();
+    for &(handle, value) in specialization.constants.iter() {
+        let constant = module.constants.get_mut(handle);
+        constant.specialization = None;
+        if let naga::ConstantInner::Scalar {
+            value: ref mut old, ..
+        } = constant.inner
+        {
+            *old = value;
+        }
+    }
+    module
+}
+
+fn map_shader_stage(stage_bit: wgt::ShaderStages) -> naga::ShaderStage {
+    match stage_bit {
+        wgt::ShaderStages::VERTEX => naga::ShaderStage::Vertex,
+        wgt::ShaderStages::FRAGMENT => naga::ShaderStage::Fragment,
+        wgt::ShaderStages::COMPUTE => naga::ShaderStage::Compute,
+        _ => unreachable!(),
+    }
+}
+
 fn map_storage_format_to_naga(format: wgt::TextureFormat) -> Option<naga::StorageFormat> {
     use naga::StorageFormat as Sf;
     use wgt::TextureFormat as Tf;
@@ -904,6 +1009,27 @@ impl Interface {
             resource_mapping.insert(var_handle, handle);
         }
 
+        let spec_constants = module
+            .constants
+            .iter()
+            .filter_map(|(handle, constant)| {
+                let id = constant.specialization?;
+                let (kind, width) = match constant.inner {
+                    naga::ConstantInner::Scalar { width, ref value } => {
+                        (scalar_value_kind(value), width)
+                    }
+                    naga::ConstantInner::Composite { .. } => return None,
+                };
+                Some(SpecializationConstant {
+                    handle,
+                    id,
+                    name: constant.name.clone(),
+                    kind,
+                    width,
+                })
+            })
+            .collect();
+
         let mut entry_points = FastHashMap::default();
         entry_points.reserve(module.entry_points.len());
         for (index, entry_point) in (&module.entry_points).iter().enumerate() {
@@ -945,10 +1071,92 @@ impl Interface {
             features,
             limits,
             resources,
+            spec_constants,
             entry_points,
         }
     }
 
+    /// The inputs an entry point declares, as if the previous stage provided
+    /// exactly those. Returns `None` if there is no such entry point.
+    pub fn declared_inputs(
+        &self,
+        entry_point_name: &str,
+        shader_stage: naga::ShaderStage,
+    ) -> Option<StageIo> {
+        let entry_point = self
+            .entry_points
+            .get(&(shader_stage, entry_point_name.to_string()))?;
+        Some(
+            entry_point
+                .inputs
+                .iter()
+                .filter_map(|input| match *input {
+                    Varying::Local { location, ref iv } => Some((location, iv.clone())),
+                    Varying::BuiltIn(_) => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns `true` if the module has constants that can be overridden at pipeline creation.
+    pub fn is_specializable(&self) -> bool {
+        !self.spec_constants.is_empty()
+    }
+
+    /// Match the pipeline-overridable constant values of a stage against the module,
+    /// returning the values to substitute into it.
+    ///
+    /// Constants are looked up by name first, then by their numeric ID.
+    /// Each constant may only be overridden once.
+    pub fn resolve_pipeline_constants(
+        &self,
+        constants: &PipelineConstants,
+        stage_bit: wgt::ShaderStages,
+        entry_point_name: &str,
+    ) -> Result<StageSpecialization, StageError> {
+        let mut specialization = StageSpecialization::default();
+        if constants.is_empty() {
+            return Ok(specialization);
+        }
+
+        let shader_stage = map_shader_stage(stage_bit);
+        if !self
+            .entry_points
+            .contains_key(&(shader_stage, entry_point_name.to_string()))
+        {
+            return Err(StageError::MissingEntryPoint(entry_point_name.to_string()));
+        }
+
+        for &(ref key, value) in constants.iter() {
+            let spec = self
+                .spec_constants
+                .iter()
+                .find(|sc| sc.name.as_deref() == Some(key.as_str()))
+                .or_else(|| {
+                    let id = key.parse::<u32>().ok()?;
+                    self.spec_constants.iter().find(|sc| sc.id == id)
+                })
+                .ok_or_else(|| StageError::UnknownPipelineConstant(key.clone()))?;
+            let scalar = map_pipeline_constant(value, spec.kind, spec.width).ok_or_else(|| {
+                StageError::InvalidPipelineConstant {
+                    key: key.clone(),
+                    value,
+                    kind: spec.kind,
+                }
+            })?;
+            if specialization
+                .constants
+                .iter()
+                .any(|&(handle, _)| handle == spec.handle)
+            {
+                return Err(StageError::DuplicatePipelineConstant(key.clone()));
+            }
+            specialization.constants.push((spec.handle, scalar));
+        }
+
+        Ok(specialization)
+    }
+
     pub fn check_stage(
         &self,
         given_layouts: Option<&[&BindEntryMap]>,
@@ -960,12 +1168,7 @@ impl Interface {
     ) -> Result<StageIo, StageError> {
         // Since a shader module can have multiple entry points with the same name,
         // we need to look for one with the right execution model.
-        let shader_stage = match stage_bit {
-            wgt::ShaderStages::VERTEX => naga::ShaderStage::Vertex,
-            wgt::ShaderStages::FRAGMENT => naga::ShaderStage::Fragment,
-            wgt::ShaderStages::COMPUTE => naga::ShaderStage::Compute,
-            _ => unreachable!(),
-        };
+        let shader_stage = map_shader_stage(stage_bit);
         let pair = (shader_stage, entry_point_name.to_string());
         let entry_point = self
             .entry_points
@@ -105,9 +105,11 @@ impl<A: hal::Api> Example<A> {
             unsafe { adapter.surface_capabilities(&surface) }.ok_or(hal::InstanceError)?;
         log::info!("Surface caps: {:#?}", surface_caps);
 
-        let hal::OpenDevice { device, mut queue } = unsafe {
+        let hal::OpenDevice {
+            device, mut queue, ..
+        } = unsafe {
             adapter
-                .open(wgt::Features::empty(), &wgt::Limits::default())
+                .open(wgt::Features::empty(), &wgt::Limits::default(), &[])
                 .unwrap()
         };
 
@@ -226,11 +228,13 @@ impl<A: hal::Api> Example<A> {
             vertex_stage: hal::ProgrammableStage {
                 module: &shader,
                 entry_point: "vs_main",
+                cache: None,
             },
             vertex_buffers: &[],
             fragment_stage: Some(hal::ProgrammableStage {
                 module: &shader,
                 entry_point: "fs_main",
+                cache: None,
             }),
             primitive: wgt::PrimitiveState {
                 topology: wgt::PrimitiveTopology::TriangleStrip,
@@ -244,6 +248,7 @@ impl<A: hal::Api> Example<A> {
                 write_mask: wgt::ColorWrites::default(),
             }],
             multiview: None,
+            cache: None,
         };
         let pipeline = unsafe { device.create_render_pipeline(&pipeline_desc).unwrap() };
 
@@ -662,6 +667,8 @@ impl<A: hal::Api> Example<A> {
             }],
             depth_stencil_attachment: None,
             multiview: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
         };
         unsafe {
             ctx.encoder.begin_render_pass(&pass_desc);
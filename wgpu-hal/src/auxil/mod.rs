@@ -32,6 +32,24 @@ pub fn map_naga_stage(stage: naga::ShaderStage) -> wgt::ShaderStages {
     }
 }
 
+/// Packs the translated source of a shader stage together with the name of its
+/// entry point in that source, to be kept in a [`crate::StageCache`].
+pub fn pack_translated_stage(entry_point: &str, source: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(entry_point.len() + 1 + source.len());
+    data.extend_from_slice(entry_point.as_bytes());
+    data.push(0);
+    data.extend_from_slice(source.as_bytes());
+    data
+}
+
+/// Reverses [`pack_translated_stage`], returning the entry point name and the source.
+pub fn unpack_translated_stage(data: &[u8]) -> Option<(String, String)> {
+    let split = data.iter().position(|&byte| byte == 0)?;
+    let entry_point = std::str::from_utf8(&data[..split]).ok()?;
+    let source = std::str::from_utf8(&data[split + 1..]).ok()?;
+    Some((entry_point.to_string(), source.to_string()))
+}
+
 pub fn align_to(value: u32, alignment: u32) -> u32 {
     if alignment.is_power_of_two() {
         (value + alignment - 1) & !(alignment - 1)
@@ -7,6 +7,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
         &self,
         features: wgt::Features,
         limits: &wgt::Limits,
+        extra_queue_labels: &[crate::Label],
     ) -> Result<crate::OpenDevice<super::Api>, crate::DeviceError> {
         todo!()
     }
@@ -120,7 +121,6 @@ impl super::Adapter {
             downlevel |= wgt::DownlevelFlags::FRAGMENT_STORAGE;
             downlevel |= wgt::DownlevelFlags::FRAGMENT_WRITABLE_STORAGE;
             features |= wgt::Features::DEPTH_CLIP_CONTROL;
-            features |= wgt::Features::TIMESTAMP_QUERY;
             features |= wgt::Features::PIPELINE_STATISTICS_QUERY;
         }
 
@@ -241,6 +241,7 @@ impl super::Adapter {
                 _ => unreachable!(),
             },
             backend: wgt::Backend::Dx11,
+            driver_info: String::new(),
         };
 
         //
@@ -9,6 +9,10 @@ impl crate::Device<super::Api> for super::Device {
         todo!()
     }
 
+    unsafe fn destroy_queue(&self, queue: super::Queue) {
+        todo!()
+    }
+
     unsafe fn create_buffer(
         &self,
         desc: &crate::BufferDescriptor,
@@ -120,6 +124,17 @@ impl crate::Device<super::Api> for super::Device {
         todo!()
     }
 
+    unsafe fn update_bind_group(
+        &self,
+        group: &mut super::BindGroup,
+        layout: &super::BindGroupLayout,
+        binding: u32,
+        first_element: u32,
+        textures: &[crate::TextureBinding<super::Api>],
+    ) {
+        todo!()
+    }
+
     unsafe fn destroy_bind_group(&self, group: super::BindGroup) {
         todo!()
     }
@@ -158,6 +173,21 @@ impl crate::Device<super::Api> for super::Device {
         todo!()
     }
 
+    unsafe fn create_pipeline_cache(
+        &self,
+        desc: &crate::PipelineCacheDescriptor,
+    ) -> Result<super::PipelineCache, crate::DeviceError> {
+        todo!()
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, cache: super::PipelineCache) {
+        todo!()
+    }
+
+    unsafe fn pipeline_cache_get_data(&self, cache: &super::PipelineCache) -> Option<Vec<u8>> {
+        todo!()
+    }
+
     unsafe fn create_query_set(
         &self,
         desc: &wgt::QuerySetDescriptor<crate::Label>,
@@ -200,6 +230,10 @@ impl crate::Device<super::Api> for super::Device {
     unsafe fn stop_capture(&self) {
         todo!()
     }
+
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport> {
+        todo!()
+    }
 }
 
 impl crate::Queue<super::Api> for super::Queue {
@@ -211,6 +245,14 @@ impl crate::Queue<super::Api> for super::Queue {
         todo!()
     }
 
+    unsafe fn wait_for_fence(
+        &mut self,
+        fence: &super::Fence,
+        value: crate::FenceValue,
+    ) -> Result<bool, crate::DeviceError> {
+        todo!()
+    }
+
     unsafe fn present(
         &mut self,
         surface: &mut super::Surface,
@@ -36,6 +36,7 @@ impl crate::Api for Api {
     type ShaderModule = ShaderModule;
     type RenderPipeline = RenderPipeline;
     type ComputePipeline = ComputePipeline;
+    type PipelineCache = PipelineCache;
 }
 
 pub struct Instance {
@@ -108,6 +109,8 @@ pub struct PipelineLayout {}
 pub struct ShaderModule {}
 pub struct RenderPipeline {}
 pub struct ComputePipeline {}
+#[derive(Debug)]
+pub struct PipelineCache {}
 
 impl crate::Surface<Api> for Surface {
     unsafe fn configure(
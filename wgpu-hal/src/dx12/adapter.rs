@@ -4,8 +4,9 @@ use crate::{
 };
 use std::{mem, sync::Arc, thread};
 use winapi::{
-    shared::{dxgi, dxgi1_2, dxgi1_5, minwindef, windef, winerror},
+    shared::{dxgi, dxgi1_2, dxgi1_5, minwindef, ntdef, windef, winerror},
     um::{d3d12, d3d12sdklayers, winuser},
+    Interface as _,
 };
 
 impl Drop for super::Adapter {
@@ -81,6 +82,28 @@ impl super::Adapter {
             name.to_string_lossy().into_owned()
         };
 
+        // The user mode driver version, as four 16-bit parts.
+        let driver_info = {
+            let mut umd_version: ntdef::LARGE_INTEGER = unsafe { mem::zeroed() };
+            let hr = unsafe {
+                adapter
+                    .unwrap_adapter2()
+                    .CheckInterfaceSupport(&dxgi::IDXGIDevice::uuidof(), &mut umd_version)
+            };
+            if winerror::SUCCEEDED(hr) {
+                let version = unsafe { *umd_version.QuadPart() } as u64;
+                format!(
+                    "{}.{}.{}.{}",
+                    version >> 48,
+                    (version >> 32) & 0xFFFF,
+                    (version >> 16) & 0xFFFF,
+                    version & 0xFFFF
+                )
+            } else {
+                String::new()
+            }
+        };
+
         let mut features_architecture: d3d12::D3D12_FEATURE_DATA_ARCHITECTURE =
             unsafe { mem::zeroed() };
         assert_eq!(0, unsafe {
@@ -106,6 +129,7 @@ impl super::Adapter {
             } else {
                 wgt::DeviceType::DiscreteGpu
             },
+            driver_info,
         };
 
         let mut options: d3d12::D3D12_FEATURE_DATA_D3D12_OPTIONS = unsafe { mem::zeroed() };
@@ -284,8 +308,9 @@ impl crate::Adapter<super::Api> for super::Adapter {
         &self,
         features: wgt::Features,
         _limits: &wgt::Limits,
+        extra_queue_labels: &[crate::Label],
     ) -> Result<crate::OpenDevice<super::Api>, crate::DeviceError> {
-        let queue = {
+        let create_queue = || {
             profiling::scope!("ID3D12Device::CreateCommandQueue");
             self.device
                 .create_command_queue(
@@ -294,8 +319,21 @@ impl crate::Adapter<super::Api> for super::Adapter {
                     native::CommandQueueFlags::empty(),
                     0,
                 )
-                .into_device_result("Queue creation")?
+                .into_device_result("Queue creation")
         };
+        let queue = create_queue()?;
+        let mut extra_queues = Vec::with_capacity(extra_queue_labels.len());
+        for &label in extra_queue_labels {
+            let raw = create_queue()?;
+            if let Some(label) = label {
+                let cwstr = super::conv::map_label(label);
+                raw.SetName(cwstr.as_ptr());
+            }
+            extra_queues.push(super::Queue {
+                raw,
+                temp_lists: Vec::new(),
+            });
+        }
 
         let device = super::Device::new(
             self.device,
@@ -310,6 +348,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
                 raw: queue,
                 temp_lists: Vec::new(),
             },
+            extra_queues,
         })
     }
 
@@ -59,7 +59,12 @@ impl super::Temp {
 }
 
 impl super::CommandEncoder {
-    unsafe fn begin_pass(&mut self, kind: super::PassKind, label: crate::Label) {
+    unsafe fn begin_pass(
+        &mut self,
+        kind: super::PassKind,
+        label: crate::Label,
+        timestamp_writes: Option<&crate::PassTimestampWrites<super::Api>>,
+    ) {
         let list = self.list.unwrap();
         self.pass.kind = kind;
         if let Some(label) = label {
@@ -67,6 +72,18 @@ impl super::CommandEncoder {
             list.BeginEvent(0, wide_label.as_ptr() as *const _, size);
             self.pass.has_label = true;
         }
+        if let Some(timestamp_writes) = timestamp_writes {
+            if let Some(index) = timestamp_writes.beginning_of_pass_write_index {
+                list.EndQuery(
+                    timestamp_writes.query_set.raw.as_mut_ptr(),
+                    d3d12::D3D12_QUERY_TYPE_TIMESTAMP,
+                    index,
+                );
+            }
+            self.pass.end_of_pass_timer_query = timestamp_writes
+                .end_of_pass_write_index
+                .map(|index| (timestamp_writes.query_set.raw, index));
+        }
         self.pass.dirty_root_elements = 0;
         self.pass.dirty_vertex_buffers = 0;
         list.set_descriptor_heaps(&[self.shared.heap_views.raw, self.shared.heap_samplers.raw]);
@@ -74,6 +91,13 @@ impl super::CommandEncoder {
 
     unsafe fn end_pass(&mut self) {
         let list = self.list.unwrap();
+        if let Some((query_heap, index)) = self.pass.end_of_pass_timer_query.take() {
+            list.EndQuery(
+                query_heap.as_mut_ptr(),
+                d3d12::D3D12_QUERY_TYPE_TIMESTAMP,
+                index,
+            );
+        }
         list.set_descriptor_heaps(&[]);
         if self.pass.has_label {
             list.EndEvent();
@@ -578,7 +602,11 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
     // render
 
     unsafe fn begin_render_pass(&mut self, desc: &crate::RenderPassDescriptor<super::Api>) {
-        self.begin_pass(super::PassKind::Render, desc.label);
+        self.begin_pass(
+            super::PassKind::Render,
+            desc.label,
+            desc.timestamp_writes.as_ref(),
+        );
 
         let mut color_views = [native::CpuDescriptor { ptr: 0 }; crate::MAX_COLOR_TARGETS];
         for (rtv, cat) in color_views.iter_mut().zip(desc.color_attachments.iter()) {
@@ -977,8 +1005,12 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
 
     // compute
 
-    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor) {
-        self.begin_pass(super::PassKind::Compute, desc.label);
+    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor<super::Api>) {
+        self.begin_pass(
+            super::PassKind::Compute,
+            desc.label,
+            desc.timestamp_writes.as_ref(),
+        );
     }
     unsafe fn end_compute_pass(&mut self) {
         self.end_pass();
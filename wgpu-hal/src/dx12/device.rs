@@ -5,7 +5,15 @@ use crate::{
 
 use super::{conv, descriptor, view};
 use parking_lot::Mutex;
-use std::{ffi, mem, num::NonZeroU32, ptr, slice, sync::Arc};
+use std::{
+    ffi, mem,
+    num::NonZeroU32,
+    ptr, slice,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc,
+    },
+};
 use winapi::{
     shared::{dxgiformat, dxgitype, winerror},
     um::{d3d12, d3dcompiler, synchapi, winbase},
@@ -186,14 +194,37 @@ impl super::Device {
 
         let stage_bit = crate::auxil::map_naga_stage(naga_stage);
         let module = &stage.module.naga.module;
-        //TODO: reuse the writer
-        let mut source = String::new();
-        let mut writer = hlsl::Writer::new(&mut source, &layout.naga_options);
-        let reflection_info = {
-            profiling::scope!("naga::back::hlsl::write");
-            writer
-                .write(module, &stage.module.naga.info)
-                .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("HLSL: {:?}", e)))?
+        let ep_index = module
+            .entry_points
+            .iter()
+            .position(|ep| ep.stage == naga_stage && ep.name == stage.entry_point)
+            .ok_or(crate::PipelineError::EntryPoint(naga_stage))?;
+
+        let cached = stage
+            .cache
+            .and_then(|cache| cache.load())
+            .and_then(|data| crate::auxil::unpack_translated_stage(&data));
+        let (ep_name, source) = match cached {
+            Some(translated) => translated,
+            None => {
+                //TODO: reuse the writer
+                let mut source = String::new();
+                let mut writer = hlsl::Writer::new(&mut source, &layout.naga_options);
+                let reflection_info = {
+                    profiling::scope!("naga::back::hlsl::write");
+                    writer.write(module, &stage.module.naga.info).map_err(|e| {
+                        crate::PipelineError::Linkage(stage_bit, format!("HLSL: {:?}", e))
+                    })?
+                };
+                let ep_name = reflection_info.entry_point_names[ep_index]
+                    .as_ref()
+                    .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("{}", e)))?
+                    .clone();
+                if let Some(cache) = stage.cache {
+                    cache.store(crate::auxil::pack_translated_stage(&ep_name, &source));
+                }
+                (ep_name, source)
+            }
         };
 
         let full_stage = format!(
@@ -201,15 +232,7 @@ impl super::Device {
             naga_stage.to_hlsl_str(),
             layout.naga_options.shader_model.to_str()
         );
-        let ep_index = module
-            .entry_points
-            .iter()
-            .position(|ep| ep.stage == naga_stage && ep.name == stage.entry_point)
-            .ok_or(crate::PipelineError::EntryPoint(naga_stage))?;
-        let raw_ep = reflection_info.entry_point_names[ep_index]
-            .as_ref()
-            .map(|name| ffi::CString::new(name.as_str()).unwrap())
-            .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("{}", e)))?;
+        let raw_ep = ffi::CString::new(ep_name).unwrap();
 
         let mut shader_data = native::Blob::null();
         let mut error = native::Blob::null();
@@ -320,6 +343,10 @@ impl crate::Device<super::Api> for super::Device {
         queue.raw.destroy();
     }
 
+    unsafe fn destroy_queue(&self, queue: super::Queue) {
+        queue.raw.destroy();
+    }
+
     unsafe fn create_buffer(
         &self,
         desc: &crate::BufferDescriptor,
@@ -389,7 +416,11 @@ impl crate::Device<super::Api> for super::Device {
             resource.SetName(cwstr.as_ptr());
         }
 
-        Ok(super::Buffer { resource, size })
+        Ok(super::Buffer {
+            resource,
+            size,
+            mapped: AtomicPtr::new(ptr::null_mut()),
+        })
     }
     unsafe fn destroy_buffer(&self, buffer: super::Buffer) {
         buffer.resource.destroy();
@@ -399,18 +430,29 @@ impl crate::Device<super::Api> for super::Device {
         buffer: &super::Buffer,
         range: crate::MemoryRange,
     ) -> Result<crate::BufferMapping, crate::DeviceError> {
-        let mut ptr = ptr::null_mut();
-        let hr = (*buffer.resource).Map(0, ptr::null(), &mut ptr);
-        hr.into_device_result("Map buffer")?;
+        let mut base = buffer.mapped.load(Ordering::Acquire);
+        if base.is_null() {
+            let mut ptr = ptr::null_mut();
+            let hr = (*buffer.resource).Map(0, ptr::null(), &mut ptr);
+            hr.into_device_result("Map buffer")?;
+            base = ptr as *mut u8;
+            buffer.mapped.store(base, Ordering::Release);
+        }
         Ok(crate::BufferMapping {
-            ptr: ptr::NonNull::new(ptr.offset(range.start as isize) as *mut _).unwrap(),
+            ptr: ptr::NonNull::new(base.offset(range.start as isize)).unwrap(),
             //TODO: double-check this. Documentation is a bit misleading -
             // it implies that Map/Unmap is needed to invalidate/flush memory.
             is_coherent: true,
         })
     }
     unsafe fn unmap_buffer(&self, buffer: &super::Buffer) -> Result<(), crate::DeviceError> {
-        (*buffer.resource).Unmap(0, ptr::null());
+        if !buffer
+            .mapped
+            .swap(ptr::null_mut(), Ordering::AcqRel)
+            .is_null()
+        {
+            (*buffer.resource).Unmap(0, ptr::null());
+        }
         Ok(())
     }
     unsafe fn flush_mapped_ranges<I>(&self, _buffer: &super::Buffer, _ranges: I) {}
@@ -1189,6 +1231,16 @@ impl crate::Device<super::Api> for super::Device {
             dynamic_buffers,
         })
     }
+    unsafe fn update_bind_group(
+        &self,
+        _group: &mut super::BindGroup,
+        _layout: &super::BindGroupLayout,
+        _binding: u32,
+        _first_element: u32,
+        _textures: &[crate::TextureBinding<super::Api>],
+    ) {
+        unreachable!()
+    }
     unsafe fn destroy_bind_group(&self, group: super::BindGroup) {
         if let Some(dual) = group.handle_views {
             let _ = self.shared.heap_views.free_slice(dual);
@@ -1438,6 +1490,17 @@ impl crate::Device<super::Api> for super::Device {
         pipeline.raw.destroy();
     }
 
+    unsafe fn create_pipeline_cache(
+        &self,
+        _desc: &crate::PipelineCacheDescriptor,
+    ) -> Result<(), crate::DeviceError> {
+        Ok(())
+    }
+    unsafe fn destroy_pipeline_cache(&self, _cache: ()) {}
+    unsafe fn pipeline_cache_get_data(&self, _cache: &()) -> Option<Vec<u8>> {
+        None
+    }
+
     unsafe fn create_query_set(
         &self,
         desc: &wgt::QuerySetDescriptor<crate::Label>,
@@ -1531,4 +1594,9 @@ impl crate::Device<super::Api> for super::Device {
         self.render_doc
             .end_frame_capture(self.raw.as_mut_ptr() as *mut _, ptr::null_mut())
     }
+
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport> {
+        //TODO: `IDXGIAdapter3::QueryVideoMemoryInfo`
+        Vec::new()
+    }
 }
@@ -45,7 +45,11 @@ use crate::auxil::{self, dxgi::result::HResult as _};
 
 use arrayvec::ArrayVec;
 use parking_lot::Mutex;
-use std::{ffi, mem, num::NonZeroU32, sync::Arc};
+use std::{
+    ffi, mem,
+    num::NonZeroU32,
+    sync::{atomic::AtomicPtr, Arc},
+};
 use winapi::{
     shared::{dxgi, dxgi1_4, dxgitype, windef, winerror},
     um::{d3d12, dcomp, synchapi, winbase, winnt},
@@ -79,6 +83,7 @@ impl crate::Api for Api {
     type ShaderModule = ShaderModule;
     type RenderPipeline = RenderPipeline;
     type ComputePipeline = ComputePipeline;
+    type PipelineCache = ();
 }
 
 // Limited by D3D12's root signature size of 64. Each element takes 1 or 2 entries.
@@ -295,6 +300,7 @@ struct PassState {
     vertex_buffers: [d3d12::D3D12_VERTEX_BUFFER_VIEW; crate::MAX_VERTEX_BUFFERS],
     dirty_vertex_buffers: usize,
     kind: PassKind,
+    end_of_pass_timer_query: Option<(native::QueryHeap, u32)>,
 }
 
 #[test]
@@ -317,6 +323,7 @@ impl PassState {
             vertex_buffers: [unsafe { mem::zeroed() }; crate::MAX_VERTEX_BUFFERS],
             dirty_vertex_buffers: 0,
             kind: PassKind::Transfer,
+            end_of_pass_timer_query: None,
         }
     }
 
@@ -350,6 +357,10 @@ unsafe impl Sync for CommandBuffer {}
 pub struct Buffer {
     resource: native::Resource,
     size: wgt::BufferAddress,
+    /// Start of the resource while it's mapped, null otherwise.
+    ///
+    /// `Map` calls nest, so the resource is only mapped once for all ranges.
+    mapped: AtomicPtr<u8>,
 }
 
 unsafe impl Send for Buffer {}
@@ -754,6 +765,16 @@ impl crate::Queue<Api> for Queue {
         }
         Ok(())
     }
+    unsafe fn wait_for_fence(
+        &mut self,
+        fence: &Fence,
+        value: crate::FenceValue,
+    ) -> Result<bool, crate::DeviceError> {
+        self.raw
+            .Wait(fence.raw.as_mut_ptr(), value)
+            .into_device_result("Wait fence")?;
+        Ok(true)
+    }
     unsafe fn present(
         &mut self,
         surface: &mut Surface,
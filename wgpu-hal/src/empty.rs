@@ -35,6 +35,7 @@ impl crate::Api for Api {
     type ShaderModule = Resource;
     type RenderPipeline = Resource;
     type ComputePipeline = Resource;
+    type PipelineCache = Resource;
 }
 
 impl crate::Instance<Api> for Context {
@@ -78,8 +79,13 @@ impl crate::Adapter<Api> for Context {
         &self,
         features: wgt::Features,
         _limits: &wgt::Limits,
+        _extra_queue_labels: &[crate::Label],
     ) -> DeviceResult<crate::OpenDevice<Api>> {
-        Err(crate::DeviceError::Lost)
+        Ok(crate::OpenDevice {
+            device: Context,
+            queue: Context,
+            extra_queues: Vec::new(),
+        })
     }
     unsafe fn texture_format_capabilities(
         &self,
@@ -100,6 +106,13 @@ impl crate::Queue<Api> for Context {
     ) -> DeviceResult<()> {
         Ok(())
     }
+    unsafe fn wait_for_fence(
+        &mut self,
+        fence: &Resource,
+        value: crate::FenceValue,
+    ) -> DeviceResult<bool> {
+        Ok(false)
+    }
     unsafe fn present(
         &mut self,
         surface: &mut Context,
@@ -115,6 +128,7 @@ impl crate::Queue<Api> for Context {
 
 impl crate::Device<Api> for Context {
     unsafe fn exit(self, queue: Context) {}
+    unsafe fn destroy_queue(&self, queue: Context) {}
     unsafe fn create_buffer(&self, desc: &crate::BufferDescriptor) -> DeviceResult<Resource> {
         Ok(Resource)
     }
@@ -177,6 +191,15 @@ impl crate::Device<Api> for Context {
     ) -> DeviceResult<Resource> {
         Ok(Resource)
     }
+    unsafe fn update_bind_group(
+        &self,
+        group: &mut Resource,
+        layout: &Resource,
+        binding: u32,
+        first_element: u32,
+        textures: &[crate::TextureBinding<Api>],
+    ) {
+    }
     unsafe fn destroy_bind_group(&self, group: Resource) {}
 
     unsafe fn create_shader_module(
@@ -201,6 +224,16 @@ impl crate::Device<Api> for Context {
         Ok(Resource)
     }
     unsafe fn destroy_compute_pipeline(&self, pipeline: Resource) {}
+    unsafe fn create_pipeline_cache(
+        &self,
+        desc: &crate::PipelineCacheDescriptor,
+    ) -> DeviceResult<Resource> {
+        Ok(Resource)
+    }
+    unsafe fn destroy_pipeline_cache(&self, cache: Resource) {}
+    unsafe fn pipeline_cache_get_data(&self, cache: &Resource) -> Option<Vec<u8>> {
+        None
+    }
 
     unsafe fn create_query_set(
         &self,
@@ -229,6 +262,9 @@ impl crate::Device<Api> for Context {
         false
     }
     unsafe fn stop_capture(&self) {}
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport> {
+        Vec::new()
+    }
 }
 
 impl crate::CommandEncoder<Api> for Encoder {
@@ -384,7 +420,7 @@ impl crate::CommandEncoder<Api> for Encoder {
 
     // compute
 
-    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor) {}
+    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor<Api>) {}
     unsafe fn end_compute_pass(&mut self) {}
 
     unsafe fn set_compute_pipeline(&mut self, pipeline: &Resource) {}
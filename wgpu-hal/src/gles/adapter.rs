@@ -91,7 +91,7 @@ impl super::Adapter {
         }
     }
 
-    fn make_info(vendor_orig: String, renderer_orig: String) -> wgt::AdapterInfo {
+    fn make_info(vendor_orig: String, renderer_orig: String, version: String) -> wgt::AdapterInfo {
         let vendor = vendor_orig.to_lowercase();
         let renderer = renderer_orig.to_lowercase();
 
@@ -162,6 +162,7 @@ impl super::Adapter {
             device: 0,
             device_type: inferred_device_type,
             backend: wgt::Backend::Gl,
+            driver_info: version,
         }
     }
 
@@ -497,7 +498,7 @@ impl super::Adapter {
                     max_texture_size,
                 }),
             },
-            info: Self::make_info(vendor, renderer),
+            info: Self::make_info(vendor, renderer, version),
             features,
             capabilities: crate::Capabilities {
                 limits,
@@ -548,6 +549,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
         &self,
         features: wgt::Features,
         _limits: &wgt::Limits,
+        _extra_queue_labels: &[crate::Label],
     ) -> Result<crate::OpenDevice<super::Api>, crate::DeviceError> {
         let gl = &self.shared.context.lock();
         gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
@@ -592,6 +594,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
                 draw_buffer_count: 1,
                 current_index_buffer: None,
             },
+            extra_queues: Vec::new(),
         })
     }
 
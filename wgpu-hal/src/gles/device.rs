@@ -4,7 +4,10 @@ use glow::HasContext;
 use std::{
     convert::TryInto,
     iter, ptr,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc, Mutex,
+    },
 };
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -23,12 +26,39 @@ struct CompilationContext<'a> {
 }
 
 impl CompilationContext<'_> {
-    fn consume_reflection(
-        self,
+    fn consume_reflection(self, translated: &TranslatedStage) {
+        for &(ref name, register, slot) in translated.bindings.iter() {
+            self.name_binding_map.insert(name.clone(), (register, slot));
+        }
+        for &(texture_slot, sampler_slot) in translated.samplers.iter() {
+            self.sampler_map[texture_slot as usize] = Some(sampler_slot);
+        }
+    }
+}
+
+/// GLSL translation of a stage, as kept in a [`crate::StageCache`].
+///
+/// The bindings reflected by naga are kept alongside the source,
+/// since they can't be recovered from it.
+struct TranslatedStage {
+    source: String,
+    /// Names of the resources in `source`, with the slots they are bound to.
+    bindings: Vec<(String, super::BindingRegister, u8)>,
+    /// Texture slots, with the slot of the sampler they are sampled with.
+    samplers: Vec<(u8, u8)>,
+}
+
+impl TranslatedStage {
+    fn new(
+        source: String,
+        layout: &super::PipelineLayout,
         module: &naga::Module,
         ep_info: &naga::valid::FunctionInfo,
         reflection_info: naga::back::glsl::ReflectionInfo,
-    ) {
+    ) -> Self {
+        let mut bindings = Vec::new();
+        let mut samplers = Vec::new();
+
         for (handle, var) in module.global_variables.iter() {
             if ep_info[handle].is_empty() {
                 continue;
@@ -40,7 +70,7 @@ impl CompilationContext<'_> {
             };
 
             let br = var.binding.as_ref().unwrap();
-            let slot = self.layout.get_slot(br);
+            let slot = layout.get_slot(br);
 
             let name = match reflection_info.uniforms.get(&handle) {
                 Some(name) => name.clone(),
@@ -53,7 +83,7 @@ impl CompilationContext<'_> {
                 register,
                 slot
             );
-            self.name_binding_map.insert(name, (register, slot));
+            bindings.push((name, register, slot));
         }
 
         for (name, mapping) in reflection_info.texture_mapping {
@@ -67,19 +97,72 @@ impl CompilationContext<'_> {
             };
 
             let tex_br = var.binding.as_ref().unwrap();
-            let texture_linear_index = self.layout.get_slot(tex_br);
+            let texture_linear_index = layout.get_slot(tex_br);
 
-            self.name_binding_map
-                .insert(name, (register, texture_linear_index));
+            bindings.push((name, register, texture_linear_index));
             if let Some(sampler_handle) = mapping.sampler {
                 let sam_br = module.global_variables[sampler_handle]
                     .binding
                     .as_ref()
                     .unwrap();
-                let sampler_linear_index = self.layout.get_slot(sam_br);
-                self.sampler_map[texture_linear_index as usize] = Some(sampler_linear_index);
+                let sampler_linear_index = layout.get_slot(sam_br);
+                samplers.push((texture_linear_index, sampler_linear_index));
             }
         }
+
+        Self {
+            source,
+            bindings,
+            samplers,
+        }
+    }
+
+    /// Writes the bindings one per line, followed by a null byte and the source.
+    fn pack(&self) -> Vec<u8> {
+        let mut data = String::new();
+        for &(ref name, register, slot) in self.bindings.iter() {
+            data += &format!("b {} {} {}\n", register as u8, slot, name);
+        }
+        for &(texture_slot, sampler_slot) in self.samplers.iter() {
+            data += &format!("s {} {}\n", texture_slot, sampler_slot);
+        }
+        data.push('\0');
+        data += &self.source;
+        data.into_bytes()
+    }
+
+    fn unpack(data: &[u8]) -> Option<Self> {
+        let data = std::str::from_utf8(data).ok()?;
+        let split = data.find('\0')?;
+        let mut translated = Self {
+            source: data[split + 1..].to_string(),
+            bindings: Vec::new(),
+            samplers: Vec::new(),
+        };
+        for line in data[..split].lines() {
+            let mut words = line.split(' ');
+            match words.next()? {
+                "b" => {
+                    let register = match words.next()? {
+                        "0" => super::BindingRegister::UniformBuffers,
+                        "1" => super::BindingRegister::StorageBuffers,
+                        "2" => super::BindingRegister::Textures,
+                        "3" => super::BindingRegister::Images,
+                        _ => return None,
+                    };
+                    let slot = words.next()?.parse().ok()?;
+                    let name = words.next()?.to_string();
+                    translated.bindings.push((name, register, slot));
+                }
+                "s" => {
+                    let texture_slot = words.next()?.parse().ok()?;
+                    let sampler_slot = words.next()?.parse().ok()?;
+                    translated.samplers.push((texture_slot, sampler_slot));
+                }
+                _ => return None,
+            }
+        }
+        Some(translated)
     }
 }
 
@@ -124,12 +207,11 @@ impl super::Device {
         }
     }
 
-    fn create_shader(
-        gl: &glow::Context,
+    fn translate_shader(
         naga_stage: naga::ShaderStage,
         stage: &crate::ProgrammableStage<super::Api>,
-        context: CompilationContext,
-    ) -> Result<glow::Shader, crate::PipelineError> {
+        layout: &super::PipelineLayout,
+    ) -> Result<TranslatedStage, crate::PipelineError> {
         use naga::back::glsl;
         let pipeline_options = glsl::PipelineOptions {
             shader_stage: naga_stage,
@@ -149,7 +231,7 @@ impl super::Device {
             &mut output,
             &shader.module,
             &shader.info,
-            &context.layout.naga_options,
+            &layout.naga_options,
             &pipeline_options,
         )
         .map_err(|e| {
@@ -164,13 +246,46 @@ impl super::Device {
 
         log::debug!("Naga generated shader:\n{}", output);
 
-        context.consume_reflection(
+        Ok(TranslatedStage::new(
+            output,
+            layout,
             &shader.module,
             shader.info.get_entry_point(entry_point_index),
             reflection_info,
-        );
+        ))
+    }
+
+    fn create_shader(
+        gl: &glow::Context,
+        naga_stage: naga::ShaderStage,
+        stage: &crate::ProgrammableStage<super::Api>,
+        context: CompilationContext,
+    ) -> Result<glow::Shader, crate::PipelineError> {
+        let cached = stage
+            .cache
+            .and_then(|cache| cache.load())
+            .and_then(|data| TranslatedStage::unpack(&data));
+        let translated = match cached {
+            Some(translated) => translated,
+            None => {
+                let translated = Self::translate_shader(naga_stage, stage, context.layout)?;
+                if let Some(cache) = stage.cache {
+                    cache.store(translated.pack());
+                }
+                translated
+            }
+        };
+
+        context.consume_reflection(&translated);
 
-        unsafe { Self::compile_shader(gl, &output, naga_stage, stage.module.label.as_deref()) }
+        unsafe {
+            Self::compile_shader(
+                gl,
+                &translated.source,
+                naga_stage,
+                stage.module.label.as_deref(),
+            )
+        }
     }
 
     unsafe fn create_pipeline<'a, I: Iterator<Item = ShaderStage<'a>>>(
@@ -319,6 +434,10 @@ impl crate::Device<super::Api> for super::Device {
         gl.delete_buffer(queue.zero_buffer);
     }
 
+    unsafe fn destroy_queue(&self, _queue: super::Queue) {
+        unreachable!("GL only has a single queue")
+    }
+
     unsafe fn create_buffer(
         &self,
         desc: &crate::BufferDescriptor,
@@ -345,6 +464,7 @@ impl crate::Device<super::Api> for super::Device {
                 size: desc.size,
                 map_flags: 0,
                 data: Some(Arc::new(Mutex::new(vec![0; desc.size as usize]))),
+                map_ptr: None,
             });
         }
 
@@ -424,12 +544,19 @@ impl crate::Device<super::Api> for super::Device {
             None
         };
 
+        let map_ptr = if is_host_visible && data.is_none() {
+            Some(Arc::new(AtomicPtr::new(ptr::null_mut())))
+        } else {
+            None
+        };
+
         Ok(super::Buffer {
             raw,
             target,
             size: desc.size,
             map_flags,
             data,
+            map_ptr,
         })
     }
     unsafe fn destroy_buffer(&self, buffer: super::Buffer) {
@@ -456,16 +583,29 @@ impl crate::Device<super::Api> for super::Device {
                 gl.bind_buffer(buffer.target, Some(raw));
                 let ptr = if let Some(ref map_read_allocation) = buffer.data {
                     let mut guard = map_read_allocation.lock().unwrap();
-                    let slice = guard.as_mut_slice();
-                    self.shared.get_buffer_sub_data(gl, buffer.target, 0, slice);
+                    let slice = &mut guard.as_mut_slice()[range.start as usize..range.end as usize];
+                    self.shared
+                        .get_buffer_sub_data(gl, buffer.target, range.start as i32, slice);
                     slice.as_mut_ptr()
                 } else {
-                    gl.map_buffer_range(
-                        buffer.target,
-                        range.start as i32,
-                        (range.end - range.start) as i32,
-                        buffer.map_flags,
-                    )
+                    // Flushes are relative to the start of the mapping, so the
+                    // whole buffer is mapped to keep buffer offsets valid.
+                    let map_ptr = buffer.map_ptr.as_ref().unwrap();
+                    let mut base = map_ptr.load(Ordering::Relaxed);
+                    if base.is_null() {
+                        base = gl.map_buffer_range(
+                            buffer.target,
+                            0,
+                            buffer.size as i32,
+                            buffer.map_flags,
+                        );
+                        map_ptr.store(base, Ordering::Relaxed);
+                    }
+                    if base.is_null() {
+                        base
+                    } else {
+                        base.offset(range.start as isize)
+                    }
                 };
                 gl.bind_buffer(buffer.target, None);
                 ptr
@@ -477,9 +617,9 @@ impl crate::Device<super::Api> for super::Device {
         })
     }
     unsafe fn unmap_buffer(&self, buffer: &super::Buffer) -> Result<(), crate::DeviceError> {
-        if let Some(raw) = buffer.raw {
-            if buffer.data.is_none() {
-                let gl = &self.shared.context.lock();
+        if let (Some(raw), Some(map_ptr)) = (buffer.raw, buffer.map_ptr.as_ref()) {
+            let gl = &self.shared.context.lock();
+            if !map_ptr.swap(ptr::null_mut(), Ordering::Relaxed).is_null() {
                 gl.bind_buffer(buffer.target, Some(raw));
                 gl.unmap_buffer(buffer.target);
                 gl.bind_buffer(buffer.target, None);
@@ -924,6 +1064,16 @@ impl crate::Device<super::Api> for super::Device {
             contents: contents.into_boxed_slice(),
         })
     }
+    unsafe fn update_bind_group(
+        &self,
+        _group: &mut super::BindGroup,
+        _layout: &super::BindGroupLayout,
+        _binding: u32,
+        _first_element: u32,
+        _textures: &[crate::TextureBinding<super::Api>],
+    ) {
+        unreachable!()
+    }
     unsafe fn destroy_bind_group(&self, _group: super::BindGroup) {}
 
     unsafe fn create_shader_module(
@@ -1030,6 +1180,17 @@ impl crate::Device<super::Api> for super::Device {
         gl.delete_program(pipeline.inner.program);
     }
 
+    unsafe fn create_pipeline_cache(
+        &self,
+        _desc: &crate::PipelineCacheDescriptor,
+    ) -> Result<(), crate::DeviceError> {
+        Ok(())
+    }
+    unsafe fn destroy_pipeline_cache(&self, _cache: ()) {}
+    unsafe fn pipeline_cache_get_data(&self, _cache: &()) -> Option<Vec<u8>> {
+        None
+    }
+
     #[cfg_attr(target_arch = "wasm32", allow(unused))]
     unsafe fn create_query_set(
         &self,
@@ -1136,6 +1297,10 @@ impl crate::Device<super::Api> for super::Device {
         self.render_doc
             .end_frame_capture(ptr::null_mut(), ptr::null_mut())
     }
+
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport> {
+        Vec::new()
+    }
 }
 
 // SAFE: WASM doesn't have threads
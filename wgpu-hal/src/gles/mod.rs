@@ -78,7 +78,10 @@ use arrayvec::ArrayVec;
 
 use glow::HasContext;
 
-use std::{ops::Range, sync::Arc};
+use std::{
+    ops::Range,
+    sync::{atomic::AtomicPtr, Arc},
+};
 
 #[derive(Clone)]
 pub struct Api;
@@ -115,6 +118,7 @@ impl crate::Api for Api {
     type ShaderModule = ShaderModule;
     type RenderPipeline = RenderPipeline;
     type ComputePipeline = ComputePipeline;
+    type PipelineCache = ();
 }
 
 bitflags::bitflags! {
@@ -222,6 +226,11 @@ pub struct Buffer {
     size: wgt::BufferAddress,
     map_flags: u32,
     data: Option<Arc<std::sync::Mutex<Vec<u8>>>>,
+    /// Start of the `glMapBufferRange` mapping, null while unmapped.
+    ///
+    /// A buffer can only be mapped once, so it's mapped entirely and shared
+    /// by all the ranges.
+    map_ptr: Option<Arc<AtomicPtr<u8>>>,
 }
 
 // Safe: WASM doesn't have threads
@@ -326,7 +335,7 @@ impl PipelineLayout {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 enum BindingRegister {
     UniformBuffers,
     StorageBuffers,
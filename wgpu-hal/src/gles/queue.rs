@@ -1193,6 +1193,14 @@ impl crate::Queue<super::Api> for super::Queue {
         Ok(())
     }
 
+    unsafe fn wait_for_fence(
+        &mut self,
+        _fence: &super::Fence,
+        _value: crate::FenceValue,
+    ) -> Result<bool, crate::DeviceError> {
+        Ok(false)
+    }
+
     unsafe fn present(
         &mut self,
         surface: &mut super::Surface,
@@ -92,6 +92,7 @@ use std::{
 };
 
 use bitflags::bitflags;
+use parking_lot::Mutex;
 use thiserror::Error;
 
 pub const MAX_ANISOTROPY: u8 = 16;
@@ -172,6 +173,7 @@ pub trait Api: Clone + Sized {
     type ShaderModule: fmt::Debug + Send + Sync;
     type RenderPipeline: Send + Sync;
     type ComputePipeline: Send + Sync;
+    type PipelineCache: fmt::Debug + Send + Sync;
 }
 
 pub trait Instance<A: Api>: Sized + Send + Sync {
@@ -202,10 +204,15 @@ pub trait Surface<A: Api>: Send + Sync {
 }
 
 pub trait Adapter<A: Api>: Send + Sync {
+    /// Open a device with its main queue, and up to one additional queue
+    /// executing independently of it per label of `extra_queue_labels`.
+    ///
+    /// Backends may provide fewer additional queues than requested, down to none.
     unsafe fn open(
         &self,
         features: wgt::Features,
         limits: &wgt::Limits,
+        extra_queue_labels: &[Label],
     ) -> Result<OpenDevice<A>, DeviceError>;
 
     /// Return the set of supported capabilities for a texture format.
@@ -223,17 +230,25 @@ pub trait Adapter<A: Api>: Send + Sync {
 pub trait Device<A: Api>: Send + Sync {
     /// Exit connection to this logical device.
     unsafe fn exit(self, queue: A::Queue);
+    /// Destroys one of the additional queues of [`OpenDevice::extra_queues`].
+    unsafe fn destroy_queue(&self, queue: A::Queue);
     /// Creates a new buffer.
     ///
     /// The initial usage is `BufferUses::empty()`.
     unsafe fn create_buffer(&self, desc: &BufferDescriptor) -> Result<A::Buffer, DeviceError>;
     unsafe fn destroy_buffer(&self, buffer: A::Buffer);
     //TODO: clarify if zero-sized mapping is allowed
+    /// Makes `range` of the buffer accessible from the host.
+    ///
+    /// The returned pointer is to the start of `range`. The buffer can be
+    /// mapped again for other ranges before it's unmapped, and every pointer
+    /// stays valid until [`Device::unmap_buffer`].
     unsafe fn map_buffer(
         &self,
         buffer: &A::Buffer,
         range: MemoryRange,
     ) -> Result<BufferMapping, DeviceError>;
+    /// Unmaps all the ranges mapped by [`Device::map_buffer`].
     unsafe fn unmap_buffer(&self, buffer: &A::Buffer) -> Result<(), DeviceError>;
     unsafe fn flush_mapped_ranges<I>(&self, buffer: &A::Buffer, ranges: I)
     where
@@ -277,6 +292,22 @@ pub trait Device<A: Api>: Send + Sync {
         &self,
         desc: &BindGroupDescriptor<A>,
     ) -> Result<A::BindGroup, DeviceError>;
+    /// Overwrites elements `first_element..first_element + textures.len()`
+    /// of the texture array at `binding` in an existing group.
+    ///
+    /// The group must not be in use by the GPU, and `layout` must be the
+    /// layout the group was created with.
+    ///
+    /// Only called on devices with `PARTIALLY_BOUND_BINDING_ARRAY` enabled, so
+    /// backends that don't expose that feature never have to implement it.
+    unsafe fn update_bind_group(
+        &self,
+        group: &mut A::BindGroup,
+        layout: &A::BindGroupLayout,
+        binding: u32,
+        first_element: u32,
+        textures: &[TextureBinding<A>],
+    );
     unsafe fn destroy_bind_group(&self, group: A::BindGroup);
 
     unsafe fn create_shader_module(
@@ -295,6 +326,15 @@ pub trait Device<A: Api>: Send + Sync {
         desc: &ComputePipelineDescriptor<A>,
     ) -> Result<A::ComputePipeline, PipelineError>;
     unsafe fn destroy_compute_pipeline(&self, pipeline: A::ComputePipeline);
+    /// Creates a pipeline cache, optionally seeded with data previously returned
+    /// by `pipeline_cache_get_data`. Data the backend can't use is ignored.
+    unsafe fn create_pipeline_cache(
+        &self,
+        desc: &PipelineCacheDescriptor,
+    ) -> Result<A::PipelineCache, DeviceError>;
+    unsafe fn destroy_pipeline_cache(&self, cache: A::PipelineCache);
+    /// Returns the backend-specific contents of the cache, if the backend keeps any.
+    unsafe fn pipeline_cache_get_data(&self, cache: &A::PipelineCache) -> Option<Vec<u8>>;
 
     unsafe fn create_query_set(
         &self,
@@ -313,6 +353,10 @@ pub trait Device<A: Api>: Send + Sync {
 
     unsafe fn start_capture(&self) -> bool;
     unsafe fn stop_capture(&self);
+
+    /// Returns the usage and budget of each memory heap, or nothing
+    /// if the backend can't tell.
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport>;
 }
 
 pub trait Queue<A: Api>: Send + Sync {
@@ -327,6 +371,18 @@ pub trait Queue<A: Api>: Send + Sync {
         command_buffers: &[&A::CommandBuffer],
         signal_fence: Option<(&mut A::Fence, FenceValue)>,
     ) -> Result<(), DeviceError>;
+    /// Makes the next submission to this queue wait on the GPU until `fence`
+    /// reaches `value`.
+    ///
+    /// Returns false without waiting if the backend can't wait for fences on the GPU.
+    ///
+    /// Valid usage:
+    /// - `fence` was created by the device of this queue.
+    unsafe fn wait_for_fence(
+        &mut self,
+        fence: &A::Fence,
+        value: FenceValue,
+    ) -> Result<bool, DeviceError>;
     unsafe fn present(
         &mut self,
         surface: &mut A::Surface,
@@ -506,7 +562,7 @@ pub trait CommandEncoder<A: Api>: Send + Sync {
     // compute passes
 
     // Begins a compute pass, clears all active bindings.
-    unsafe fn begin_compute_pass(&mut self, desc: &ComputePassDescriptor);
+    unsafe fn begin_compute_pass(&mut self, desc: &ComputePassDescriptor<A>);
     unsafe fn end_compute_pass(&mut self);
 
     unsafe fn set_compute_pipeline(&mut self, pipeline: &A::ComputePipeline);
@@ -758,6 +814,8 @@ pub struct AcquiredSurfaceTexture<A: Api> {
 pub struct OpenDevice<A: Api> {
     pub device: A::Device,
     pub queue: A::Queue,
+    /// Additional queues, which can be fewer than requested.
+    pub extra_queues: Vec<A::Queue>,
 }
 
 #[derive(Clone, Debug)]
@@ -928,6 +986,39 @@ pub struct ShaderModuleDescriptor<'a> {
     pub runtime_checks: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct PipelineCacheDescriptor<'a> {
+    pub label: Label<'a>,
+    /// Data previously returned by `Device::pipeline_cache_get_data`.
+    pub data: Option<&'a [u8]>,
+}
+
+/// Backend translation of a single pipeline stage, kept across pipelines.
+///
+/// Backends that translate shaders at pipeline creation look for a previous
+/// translation here before invoking naga, and store new translations back.
+/// The contents are opaque outside of the backend that produced them.
+#[derive(Debug, Default)]
+pub struct StageCache {
+    data: Mutex<Option<Vec<u8>>>,
+}
+
+impl StageCache {
+    pub fn new(data: Option<Vec<u8>>) -> Self {
+        Self {
+            data: Mutex::new(data),
+        }
+    }
+
+    pub fn load(&self) -> Option<Vec<u8>> {
+        self.data.lock().clone()
+    }
+
+    pub fn store(&self, data: Vec<u8>) {
+        *self.data.lock() = Some(data);
+    }
+}
+
 /// Describes a programmable pipeline stage.
 #[derive(Debug)]
 pub struct ProgrammableStage<'a, A: Api> {
@@ -936,6 +1027,8 @@ pub struct ProgrammableStage<'a, A: Api> {
     /// The name of the entry point in the compiled shader. There must be a function that returns
     /// void with this name in the shader.
     pub entry_point: &'a str,
+    /// Storage for the translated shader code of this stage, if any.
+    pub cache: Option<&'a StageCache>,
 }
 
 // Rust gets confused about the impl requirements for `A`
@@ -944,6 +1037,7 @@ impl<A: Api> Clone for ProgrammableStage<'_, A> {
         Self {
             module: self.module,
             entry_point: self.entry_point,
+            cache: self.cache,
         }
     }
 }
@@ -956,6 +1050,8 @@ pub struct ComputePipelineDescriptor<'a, A: Api> {
     pub layout: &'a A::PipelineLayout,
     /// The compiled compute stage and its entry point.
     pub stage: ProgrammableStage<'a, A>,
+    /// The pipeline cache to use when creating this pipeline.
+    pub cache: Option<&'a A::PipelineCache>,
 }
 
 /// Describes how the vertex buffer is interpreted.
@@ -992,6 +1088,8 @@ pub struct RenderPipelineDescriptor<'a, A: Api> {
     /// If the pipeline will be used with a multiview render pass, this indicates how many array
     /// layers the attachments will have.
     pub multiview: Option<NonZeroU32>,
+    /// The pipeline cache to use when creating this pipeline.
+    pub cache: Option<&'a A::PipelineCache>,
 }
 
 /// Specifies how the alpha channel of the textures should be handled during (martin mouv i step)
@@ -1138,6 +1236,30 @@ pub struct DepthStencilAttachment<'a, A: Api> {
     pub clear_value: (f32, u32),
 }
 
+/// Timestamps to write at the boundaries of a render or compute pass.
+///
+/// Only given when `Features::TIMESTAMP_QUERY` is enabled, so backends not
+/// exposing it can ignore them.
+#[derive(Debug)]
+pub struct PassTimestampWrites<'a, A: Api> {
+    pub query_set: &'a A::QuerySet,
+    /// Index of the query written right after the pass begins.
+    pub beginning_of_pass_write_index: Option<u32>,
+    /// Index of the query written right before the pass ends.
+    pub end_of_pass_write_index: Option<u32>,
+}
+
+// Rust gets confused about the impl requirements for `A`
+impl<A: Api> Clone for PassTimestampWrites<'_, A> {
+    fn clone(&self) -> Self {
+        Self {
+            query_set: self.query_set,
+            beginning_of_pass_write_index: self.beginning_of_pass_write_index,
+            end_of_pass_write_index: self.end_of_pass_write_index,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RenderPassDescriptor<'a, A: Api> {
     pub label: Label<'a>,
@@ -1146,11 +1268,15 @@ pub struct RenderPassDescriptor<'a, A: Api> {
     pub color_attachments: &'a [ColorAttachment<'a, A>],
     pub depth_stencil_attachment: Option<DepthStencilAttachment<'a, A>>,
     pub multiview: Option<NonZeroU32>,
+    pub timestamp_writes: Option<PassTimestampWrites<'a, A>>,
+    /// Query set that occlusion queries within the pass write to.
+    pub occlusion_query_set: Option<&'a A::QuerySet>,
 }
 
 #[derive(Clone, Debug)]
-pub struct ComputePassDescriptor<'a> {
+pub struct ComputePassDescriptor<'a, A: Api> {
     pub label: Label<'a>,
+    pub timestamp_writes: Option<PassTimestampWrites<'a, A>>,
 }
 
 /// Stores if any API validation error has occurred in this process
@@ -21,6 +21,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
         &self,
         features: wgt::Features,
         _limits: &wgt::Limits,
+        _extra_queue_labels: &[crate::Label],
     ) -> Result<crate::OpenDevice<super::Api>, crate::DeviceError> {
         let queue = self
             .shared
@@ -35,6 +36,9 @@ impl crate::Adapter<super::Api> for super::Adapter {
             queue: super::Queue {
                 raw: Arc::new(Mutex::new(queue)),
             },
+            // Command buffers are tied to the queue they are created from,
+            // so they can't be submitted to another one.
+            extra_queues: Vec::new(),
         })
     }
 
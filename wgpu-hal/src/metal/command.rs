@@ -342,7 +342,9 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
 
         objc::rc::autoreleasepool(|| {
             let descriptor = mtl::RenderPassDescriptor::new();
-            //TODO: set visibility results buffer
+            if let Some(set) = desc.occlusion_query_set {
+                descriptor.set_visibility_result_buffer(Some(&set.raw_buffer));
+            }
 
             for (i, at) in desc.color_attachments.iter().enumerate() {
                 let at_descriptor = descriptor.color_attachments().object_at(i as u64).unwrap();
@@ -886,7 +888,7 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
 
     // compute
 
-    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor) {
+    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor<super::Api>) {
         self.begin_pass();
 
         let raw = self.raw_cmd_buf.as_ref().unwrap();
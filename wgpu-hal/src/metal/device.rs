@@ -65,13 +65,37 @@ impl super::Device {
         };
 
         let module = &stage.module.naga.module;
-        let (source, info) = naga::back::msl::write_string(
-            module,
-            &stage.module.naga.info,
-            &layout.naga_options,
-            &pipeline_options,
-        )
-        .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("MSL: {:?}", e)))?;
+        let ep_index = module
+            .entry_points
+            .iter()
+            .position(|ep| ep.stage == naga_stage && ep.name == stage.entry_point)
+            .ok_or(crate::PipelineError::EntryPoint(naga_stage))?;
+        let ep = &module.entry_points[ep_index];
+
+        let cached = stage
+            .cache
+            .and_then(|cache| cache.load())
+            .and_then(|data| crate::auxil::unpack_translated_stage(&data));
+        let (name, source) = match cached {
+            Some(translated) => translated,
+            None => {
+                let (source, info) = naga::back::msl::write_string(
+                    module,
+                    &stage.module.naga.info,
+                    &layout.naga_options,
+                    &pipeline_options,
+                )
+                .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("MSL: {:?}", e)))?;
+                let name = info.entry_point_names[ep_index]
+                    .as_ref()
+                    .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("{}", e)))?
+                    .clone();
+                if let Some(cache) = stage.cache {
+                    cache.store(crate::auxil::pack_translated_stage(&name, &source));
+                }
+                (name, source)
+            }
+        };
 
         let options = mtl::CompileOptions::new();
         options.set_language_version(self.shared.private_caps.msl_version);
@@ -90,22 +114,13 @@ impl super::Device {
                 crate::PipelineError::Linkage(stage_bit, format!("Metal: {}", err))
             })?;
 
-        let ep_index = module
-            .entry_points
-            .iter()
-            .position(|ep| ep.stage == naga_stage && ep.name == stage.entry_point)
-            .ok_or(crate::PipelineError::EntryPoint(naga_stage))?;
-        let ep = &module.entry_points[ep_index];
-        let name = info.entry_point_names[ep_index]
-            .as_ref()
-            .map_err(|e| crate::PipelineError::Linkage(stage_bit, format!("{}", e)))?;
         let wg_size = mtl::MTLSize {
             width: ep.workgroup_size[0] as _,
             height: ep.workgroup_size[1] as _,
             depth: ep.workgroup_size[2] as _,
         };
 
-        let function = library.get_function(name, None).map_err(|e| {
+        let function = library.get_function(&name, None).map_err(|e| {
             log::error!("get_function: {:?}", e);
             crate::PipelineError::EntryPoint(naga_stage)
         })?;
@@ -208,6 +223,7 @@ impl super::Device {
 
 impl crate::Device<super::Api> for super::Device {
     unsafe fn exit(self, _queue: super::Queue) {}
+    unsafe fn destroy_queue(&self, _queue: super::Queue) {}
 
     unsafe fn create_buffer(&self, desc: &crate::BufferDescriptor) -> DeviceResult<super::Buffer> {
         let map_read = desc.usage.contains(crate::BufferUses::MAP_READ);
@@ -745,6 +761,17 @@ impl crate::Device<super::Api> for super::Device {
         Ok(bg)
     }
 
+    unsafe fn update_bind_group(
+        &self,
+        _group: &mut super::BindGroup,
+        _layout: &super::BindGroupLayout,
+        _binding: u32,
+        _first_element: u32,
+        _textures: &[crate::TextureBinding<super::Api>],
+    ) {
+        unreachable!()
+    }
+
     unsafe fn destroy_bind_group(&self, _group: super::BindGroup) {}
 
     unsafe fn create_shader_module(
@@ -1006,6 +1033,17 @@ impl crate::Device<super::Api> for super::Device {
     }
     unsafe fn destroy_compute_pipeline(&self, _pipeline: super::ComputePipeline) {}
 
+    unsafe fn create_pipeline_cache(
+        &self,
+        _desc: &crate::PipelineCacheDescriptor,
+    ) -> Result<(), crate::DeviceError> {
+        Ok(())
+    }
+    unsafe fn destroy_pipeline_cache(&self, _cache: ()) {}
+    unsafe fn pipeline_cache_get_data(&self, _cache: &()) -> Option<Vec<u8>> {
+        None
+    }
+
     unsafe fn create_query_set(
         &self,
         desc: &wgt::QuerySetDescriptor<crate::Label>,
@@ -1100,4 +1138,8 @@ impl crate::Device<super::Api> for super::Device {
         }
         shared_capture_manager.stop_capture();
     }
+
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport> {
+        Vec::new()
+    }
 }
@@ -59,6 +59,7 @@ impl crate::Api for Api {
     type ShaderModule = ShaderModule;
     type RenderPipeline = RenderPipeline;
     type ComputePipeline = ComputePipeline;
+    type PipelineCache = ();
 }
 
 pub struct Instance {
@@ -116,6 +117,7 @@ impl crate::Instance<Api> for Instance {
                         device: 0,
                         device_type: shared.private_caps.device_type(),
                         backend: wgt::Backend::Metal,
+                        driver_info: String::new(),
                     },
                     features: shared.private_caps.features(),
                     capabilities: shared.private_caps.capabilities(),
@@ -362,6 +364,13 @@ impl crate::Queue<Api> for Queue {
         });
         Ok(())
     }
+    unsafe fn wait_for_fence(
+        &mut self,
+        _fence: &Fence,
+        _value: crate::FenceValue,
+    ) -> Result<bool, crate::DeviceError> {
+        Ok(false)
+    }
     unsafe fn present(
         &mut self,
         _surface: &mut Surface,
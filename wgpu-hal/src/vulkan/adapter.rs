@@ -658,6 +658,11 @@ impl PhysicalDeviceCapabilities {
             extensions.push(vk::ExtTextureCompressionAstcHdrFn::name())
         }
 
+        // Optional, for reporting memory usage.
+        if self.supports_extension(vk::ExtMemoryBudgetFn::name()) {
+            extensions.push(vk::ExtMemoryBudgetFn::name());
+        }
+
         extensions
     }
 
@@ -937,6 +942,7 @@ impl super::Instance {
                 _ => wgt::DeviceType::Other,
             },
             backend: wgt::Backend::Vulkan,
+            driver_info: phd_capabilities.properties.driver_version.to_string(),
         };
 
         let (available_features, downlevel_flags) = phd_features.to_wgpu(&phd_capabilities);
@@ -1032,6 +1038,8 @@ impl super::Instance {
                     None => false,
                 },
             },
+            memory_budget: self.shared.get_physical_device_properties.is_some()
+                && phd_capabilities.supports_extension(vk::ExtMemoryBudgetFn::name()),
         };
 
         let capabilities = crate::Capabilities {
@@ -1229,6 +1237,7 @@ impl super::Adapter {
             raw: raw_device,
             handle_is_owned,
             instance: Arc::clone(&self.instance),
+            physical_device: self.raw,
             extension_fns: super::DeviceExtensionFunctions {
                 draw_indirect_count: indirect_count_fn,
                 timeline_semaphore: timeline_semaphore_fn,
@@ -1255,6 +1264,7 @@ impl super::Adapter {
             family_index,
             relay_semaphores,
             relay_index: None,
+            timeline_waits: Vec::new(),
         };
 
         let mem_allocator = {
@@ -1304,7 +1314,11 @@ impl super::Adapter {
             render_doc: Default::default(),
         };
 
-        Ok(crate::OpenDevice { device, queue })
+        Ok(crate::OpenDevice {
+            device,
+            queue,
+            extra_queues: Vec::new(),
+        })
     }
 }
 
@@ -1313,6 +1327,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
         &self,
         features: wgt::Features,
         limits: &wgt::Limits,
+        extra_queue_labels: &[crate::Label],
     ) -> Result<crate::OpenDevice<super::Api>, crate::DeviceError> {
         let phd_limits = &self.phd_capabilities.properties.limits;
         let uab_types = super::UpdateAfterBindTypes::from_limits(limits, phd_limits);
@@ -1322,9 +1337,18 @@ impl crate::Adapter<super::Api> for super::Adapter {
             self.physical_device_features(&enabled_extensions, features, uab_types);
 
         let family_index = 0; //TODO
+        let family_queue_count = {
+            profiling::scope!("vkGetPhysicalDeviceQueueFamilyProperties");
+            self.instance
+                .raw
+                .get_physical_device_queue_family_properties(self.raw)[family_index as usize]
+                .queue_count
+        };
+        let queue_count = (1 + extra_queue_labels.len() as u32).min(family_queue_count);
+        let queue_priorities = vec![1.0; queue_count as usize];
         let family_info = vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(family_index)
-            .queue_priorities(&[1.0])
+            .queue_priorities(&queue_priorities)
             .build();
         let family_infos = [family_info];
 
@@ -1347,7 +1371,7 @@ impl crate::Adapter<super::Api> for super::Adapter {
             self.instance.raw.create_device(self.raw, &info, None)?
         };
 
-        self.device_from_raw(
+        let mut open = self.device_from_raw(
             raw_device,
             true,
             &enabled_extensions,
@@ -1355,7 +1379,17 @@ impl crate::Adapter<super::Api> for super::Adapter {
             uab_types,
             family_info.queue_family_index,
             0,
-        )
+        )?;
+        for (queue_index, &label) in (1..queue_count).zip(extra_queue_labels) {
+            let queue = open.queue.sibling(queue_index)?;
+            if let Some(label) = label {
+                open.device
+                    .shared
+                    .set_object_name(vk::ObjectType::QUEUE, queue.raw, label);
+            }
+            open.extra_queues.push(queue);
+        }
+        Ok(open)
     }
 
     unsafe fn texture_format_capabilities(
@@ -44,6 +44,38 @@ impl super::DeviceShared {
     }
 }
 
+impl super::CommandEncoder {
+    unsafe fn write_pass_timestamps(
+        &mut self,
+        timestamp_writes: Option<&crate::PassTimestampWrites<super::Api>>,
+    ) {
+        if let Some(timestamp_writes) = timestamp_writes {
+            if let Some(index) = timestamp_writes.beginning_of_pass_write_index {
+                self.device.raw.cmd_write_timestamp(
+                    self.active,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    timestamp_writes.query_set.raw,
+                    index,
+                );
+            }
+            self.end_of_pass_timer_query = timestamp_writes
+                .end_of_pass_write_index
+                .map(|index| (timestamp_writes.query_set.raw, index));
+        }
+    }
+
+    unsafe fn write_end_of_pass_timestamp(&mut self) {
+        if let Some((query_pool, index)) = self.end_of_pass_timer_query.take() {
+            self.device.raw.cmd_write_timestamp(
+                self.active,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                query_pool,
+                index,
+            );
+        }
+    }
+}
+
 impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
     unsafe fn begin_encoding(&mut self, label: crate::Label) -> Result<(), crate::DeviceError> {
         if self.free.is_empty() {
@@ -66,6 +98,7 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
 
         // Reset this in case the last renderpass was never ended.
         self.rpass_debug_marker_active = false;
+        self.end_of_pass_timer_query = None;
 
         let vk_info = vk::CommandBufferBeginInfo::builder()
             .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
@@ -466,10 +499,12 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
         self.device
             .raw
             .cmd_begin_render_pass(self.active, &vk_info, vk::SubpassContents::INLINE);
+        self.write_pass_timestamps(desc.timestamp_writes.as_ref());
 
         self.bind_point = vk::PipelineBindPoint::GRAPHICS;
     }
     unsafe fn end_render_pass(&mut self) {
+        self.write_end_of_pass_timestamp();
         self.device.raw.cmd_end_render_pass(self.active);
         if self.rpass_debug_marker_active {
             self.end_debug_marker();
@@ -737,14 +772,16 @@ impl crate::CommandEncoder<super::Api> for super::CommandEncoder {
 
     // compute
 
-    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor) {
+    unsafe fn begin_compute_pass(&mut self, desc: &crate::ComputePassDescriptor<super::Api>) {
         self.bind_point = vk::PipelineBindPoint::COMPUTE;
         if let Some(label) = desc.label {
             self.begin_debug_marker(label);
             self.rpass_debug_marker_active = true;
         }
+        self.write_pass_timestamps(desc.timestamp_writes.as_ref());
     }
     unsafe fn end_compute_pass(&mut self) {
+        self.write_end_of_pass_timestamp();
         if self.rpass_debug_marker_active {
             self.end_debug_marker();
             self.rpass_debug_marker_active = false
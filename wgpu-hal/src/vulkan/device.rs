@@ -6,7 +6,15 @@ use inplace_it::inplace_or_alloc_from_iter;
 use parking_lot::Mutex;
 
 use std::{
-    borrow::Cow, collections::hash_map::Entry, ffi::CString, num::NonZeroU32, ptr, sync::Arc,
+    borrow::Cow,
+    collections::hash_map::Entry,
+    ffi::CString,
+    num::NonZeroU32,
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc,
+    },
 };
 
 impl super::DeviceShared {
@@ -636,35 +644,52 @@ impl super::Device {
                 ref naga_shader,
                 runtime_checks,
             } => {
-                let pipeline_options = naga::back::spv::PipelineOptions {
-                    entry_point: stage.entry_point.to_string(),
-                    shader_stage: naga_stage,
-                };
-                let temp_options;
-                let options = if !runtime_checks {
-                    temp_options = naga::back::spv::Options {
-                        bounds_check_policies: naga::proc::BoundsCheckPolicies {
-                            index: naga::proc::BoundsCheckPolicy::Unchecked,
-                            buffer: naga::proc::BoundsCheckPolicy::Unchecked,
-                            image: naga::proc::BoundsCheckPolicy::Unchecked,
-                        },
-                        ..self.naga_options.clone()
-                    };
-                    &temp_options
+                let cached = stage.cache.and_then(|cache| cache.load()).and_then(|data| {
+                    if data.len() % 4 != 0 {
+                        return None;
+                    }
+                    let words = data
+                        .chunks_exact(4)
+                        .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+                        .collect::<Vec<_>>();
+                    Some(words)
+                });
+                if let Some(spv) = cached {
+                    self.create_shader_module_impl(&spv)?
                 } else {
-                    &self.naga_options
-                };
-                let spv = {
-                    profiling::scope!("naga::spv::write_vec");
-                    naga::back::spv::write_vec(
-                        &naga_shader.module,
-                        &naga_shader.info,
-                        options,
-                        Some(&pipeline_options),
-                    )
+                    let pipeline_options = naga::back::spv::PipelineOptions {
+                        entry_point: stage.entry_point.to_string(),
+                        shader_stage: naga_stage,
+                    };
+                    let temp_options;
+                    let options = if !runtime_checks {
+                        temp_options = naga::back::spv::Options {
+                            bounds_check_policies: naga::proc::BoundsCheckPolicies {
+                                index: naga::proc::BoundsCheckPolicy::Unchecked,
+                                buffer: naga::proc::BoundsCheckPolicy::Unchecked,
+                                image: naga::proc::BoundsCheckPolicy::Unchecked,
+                            },
+                            ..self.naga_options.clone()
+                        };
+                        &temp_options
+                    } else {
+                        &self.naga_options
+                    };
+                    let spv = {
+                        profiling::scope!("naga::spv::write_vec");
+                        naga::back::spv::write_vec(
+                            &naga_shader.module,
+                            &naga_shader.info,
+                            options,
+                            Some(&pipeline_options),
+                        )
+                    }
+                    .map_err(|e| crate::PipelineError::Linkage(stage_flags, format!("{}", e)))?;
+                    if let Some(cache) = stage.cache {
+                        cache.store(spv.iter().flat_map(|word| word.to_ne_bytes()).collect());
+                    }
+                    self.create_shader_module_impl(&spv)?
                 }
-                .map_err(|e| crate::PipelineError::Linkage(stage_flags, format!("{}", e)))?;
-                self.create_shader_module_impl(&spv)?
             }
         };
 
@@ -700,6 +725,12 @@ impl crate::Device<super::Api> for super::Device {
         self.shared.free_resources();
     }
 
+    unsafe fn destroy_queue(&self, queue: super::Queue) {
+        for &sem in queue.relay_semaphores.iter() {
+            self.shared.raw.destroy_semaphore(sem, None);
+        }
+    }
+
     unsafe fn create_buffer(
         &self,
         desc: &crate::BufferDescriptor,
@@ -757,6 +788,7 @@ impl crate::Device<super::Api> for super::Device {
         Ok(super::Buffer {
             raw,
             block: Mutex::new(block),
+            mapped: AtomicPtr::new(ptr::null_mut()),
         })
     }
     unsafe fn destroy_buffer(&self, buffer: super::Buffer) {
@@ -771,16 +803,30 @@ impl crate::Device<super::Api> for super::Device {
         buffer: &super::Buffer,
         range: crate::MemoryRange,
     ) -> Result<crate::BufferMapping, crate::DeviceError> {
-        let size = range.end - range.start;
         let mut block = buffer.block.lock();
-        let ptr = block.map(&*self.shared, range.start, size as usize)?;
+        let mut base = buffer.mapped.load(Ordering::Relaxed);
+        if base.is_null() {
+            let size = block.size() as usize;
+            base = block.map(&*self.shared, 0, size)?.as_ptr();
+            buffer.mapped.store(base, Ordering::Relaxed);
+        }
         let is_coherent = block
             .props()
             .contains(gpu_alloc::MemoryPropertyFlags::HOST_COHERENT);
-        Ok(crate::BufferMapping { ptr, is_coherent })
+        Ok(crate::BufferMapping {
+            ptr: ptr::NonNull::new_unchecked(base.offset(range.start as isize)),
+            is_coherent,
+        })
     }
     unsafe fn unmap_buffer(&self, buffer: &super::Buffer) -> Result<(), crate::DeviceError> {
-        buffer.block.lock().unmap(&*self.shared);
+        let mut block = buffer.block.lock();
+        if !buffer
+            .mapped
+            .swap(ptr::null_mut(), Ordering::Relaxed)
+            .is_null()
+        {
+            block.unmap(&*self.shared);
+        }
         Ok(())
     }
 
@@ -1017,6 +1063,7 @@ impl crate::Device<super::Api> for super::Device {
             free: Vec::new(),
             discarded: Vec::new(),
             rpass_debug_marker_active: false,
+            end_of_pass_timer_query: None,
         })
     }
     unsafe fn destroy_command_encoder(&self, cmd_encoder: super::CommandEncoder) {
@@ -1315,6 +1362,36 @@ impl crate::Device<super::Api> for super::Device {
         self.shared.raw.update_descriptor_sets(&writes, &[]);
         Ok(super::BindGroup { set })
     }
+    unsafe fn update_bind_group(
+        &self,
+        group: &mut super::BindGroup,
+        layout: &super::BindGroupLayout,
+        binding: u32,
+        first_element: u32,
+        textures: &[crate::TextureBinding<super::Api>],
+    ) {
+        if textures.is_empty() {
+            return;
+        }
+        let image_infos = textures
+            .iter()
+            .map(|binding| {
+                let layout = conv::derive_image_layout(binding.usage, binding.view.aspects());
+                vk::DescriptorImageInfo::builder()
+                    .image_view(binding.view.raw)
+                    .image_layout(layout)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(*group.set.raw())
+            .dst_binding(binding)
+            .dst_array_element(first_element)
+            .descriptor_type(layout.types[binding as usize].0)
+            .image_info(&image_infos)
+            .build();
+        self.shared.raw.update_descriptor_sets(&[write], &[]);
+    }
     unsafe fn destroy_bind_group(&self, group: super::BindGroup) {
         self.desc_allocator
             .lock()
@@ -1573,7 +1650,11 @@ impl crate::Device<super::Api> for super::Device {
             profiling::scope!("vkCreateGraphicsPipelines");
             self.shared
                 .raw
-                .create_graphics_pipelines(vk::PipelineCache::null(), &vk_infos, None)
+                .create_graphics_pipelines(
+                    desc.cache.copied().unwrap_or_else(vk::PipelineCache::null),
+                    &vk_infos,
+                    None,
+                )
                 .map_err(|(_, e)| crate::DeviceError::from(e))?
         };
 
@@ -1617,7 +1698,11 @@ impl crate::Device<super::Api> for super::Device {
             profiling::scope!("vkCreateComputePipelines");
             self.shared
                 .raw
-                .create_compute_pipelines(vk::PipelineCache::null(), &vk_infos, None)
+                .create_compute_pipelines(
+                    desc.cache.copied().unwrap_or_else(vk::PipelineCache::null),
+                    &vk_infos,
+                    None,
+                )
                 .map_err(|(_, e)| crate::DeviceError::from(e))?
         };
 
@@ -1637,6 +1722,31 @@ impl crate::Device<super::Api> for super::Device {
         self.shared.raw.destroy_pipeline(pipeline.raw, None);
     }
 
+    unsafe fn create_pipeline_cache(
+        &self,
+        desc: &crate::PipelineCacheDescriptor,
+    ) -> Result<vk::PipelineCache, crate::DeviceError> {
+        let mut info = vk::PipelineCacheCreateInfo::builder();
+        if let Some(data) = desc.data {
+            info = info.initial_data(data);
+        }
+        let raw = {
+            profiling::scope!("vkCreatePipelineCache");
+            self.shared.raw.create_pipeline_cache(&info, None)?
+        };
+        if let Some(label) = desc.label {
+            self.shared
+                .set_object_name(vk::ObjectType::PIPELINE_CACHE, raw, label);
+        }
+        Ok(raw)
+    }
+    unsafe fn destroy_pipeline_cache(&self, cache: vk::PipelineCache) {
+        self.shared.raw.destroy_pipeline_cache(cache, None);
+    }
+    unsafe fn pipeline_cache_get_data(&self, cache: &vk::PipelineCache) -> Option<Vec<u8>> {
+        self.shared.raw.get_pipeline_cache_data(*cache).ok()
+    }
+
     unsafe fn create_query_set(
         &self,
         desc: &wgt::QuerySetDescriptor<crate::Label>,
@@ -1797,6 +1907,30 @@ impl crate::Device<super::Api> for super::Device {
                 .end_frame_capture(raw_vk_instance_dispatch_table, ptr::null_mut())
         }
     }
+
+    unsafe fn memory_heaps(&self) -> Vec<wgt::MemoryHeapReport> {
+        let get_properties = match self.shared.instance.get_physical_device_properties {
+            Some(ref get_properties) if self.shared.private_caps.memory_budget => get_properties,
+            _ => return Vec::new(),
+        };
+        let mut budget = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let mut properties = vk::PhysicalDeviceMemoryProperties2::builder()
+            .push_next(&mut budget)
+            .build();
+        get_properties
+            .get_physical_device_memory_properties2(self.shared.physical_device, &mut properties);
+        let memory_properties = properties.memory_properties;
+        memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize]
+            .iter()
+            .enumerate()
+            .map(|(i, heap)| wgt::MemoryHeapReport {
+                size: heap.size,
+                usage: budget.heap_usage[i],
+                budget: budget.heap_budget[i],
+                device_local: heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+            })
+            .collect()
+    }
 }
 
 impl From<gpu_alloc::AllocationError> for crate::DeviceError {
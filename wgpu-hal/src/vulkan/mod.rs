@@ -31,7 +31,12 @@ mod conv;
 mod device;
 mod instance;
 
-use std::{borrow::Borrow, ffi::CStr, num::NonZeroU32, sync::Arc};
+use std::{
+    borrow::Borrow,
+    ffi::CStr,
+    num::NonZeroU32,
+    sync::{atomic::AtomicPtr, Arc},
+};
 
 use arrayvec::ArrayVec;
 use ash::{
@@ -72,6 +77,7 @@ impl crate::Api for Api {
     type ShaderModule = ShaderModule;
     type RenderPipeline = RenderPipeline;
     type ComputePipeline = ComputePipeline;
+    type PipelineCache = vk::PipelineCache;
 }
 
 struct DebugUtils {
@@ -165,6 +171,8 @@ struct PrivateCapabilities {
     non_coherent_map_mask: wgt::BufferAddress,
     robust_buffer_access: bool,
     robust_image_access: bool,
+    /// `VK_EXT_memory_budget` is enabled, and can be queried.
+    memory_budget: bool,
 }
 
 bitflags::bitflags!(
@@ -312,6 +320,7 @@ struct DeviceShared {
     raw: ash::Device,
     handle_is_owned: bool,
     instance: Arc<InstanceShared>,
+    physical_device: vk::PhysicalDevice,
     extension_fns: DeviceExtensionFunctions,
     vendor_id: u32,
     timestamp_period: f32,
@@ -346,12 +355,19 @@ pub struct Queue {
     /// [Intel hangs in `anv_queue_finish`](https://gitlab.freedesktop.org/mesa/mesa/-/issues/5508).
     relay_semaphores: [vk::Semaphore; 2],
     relay_index: Option<usize>,
+    /// Timeline semaphore values the next submission waits for.
+    timeline_waits: Vec<(vk::Semaphore, crate::FenceValue)>,
 }
 
 #[derive(Debug)]
 pub struct Buffer {
     raw: vk::Buffer,
     block: Mutex<gpu_alloc::MemoryBlock<vk::DeviceMemory>>,
+    /// Start of the block while the buffer is mapped, null otherwise.
+    ///
+    /// A block can only be mapped once, so it's mapped entirely and shared
+    /// by all the ranges. Only changed with `block` locked.
+    mapped: AtomicPtr<u8>,
 }
 
 #[derive(Debug)]
@@ -448,6 +464,9 @@ pub struct CommandEncoder {
     /// If this is true, the active renderpass enabled a debug span,
     /// and needs to be disabled on renderpass close.
     rpass_debug_marker_active: bool,
+    /// If set, the query to write a timestamp into when the active
+    /// pass is closed.
+    end_of_pass_timer_query: Option<(vk::QueryPool, u32)>,
 }
 
 pub struct CommandBuffer {
@@ -554,6 +573,34 @@ impl Fence {
     }
 }
 
+impl Queue {
+    /// Creates another queue from the same family, sharing the device.
+    unsafe fn sibling(&self, queue_index: u32) -> Result<Self, crate::DeviceError> {
+        let raw = {
+            profiling::scope!("vkGetDeviceQueue");
+            self.device
+                .raw
+                .get_device_queue(self.family_index, queue_index)
+        };
+        let mut relay_semaphores = [vk::Semaphore::null(); 2];
+        for sem in relay_semaphores.iter_mut() {
+            *sem = self
+                .device
+                .raw
+                .create_semaphore(&vk::SemaphoreCreateInfo::builder(), None)?;
+        }
+        Ok(Self {
+            raw,
+            swapchain_fn: self.swapchain_fn.clone(),
+            device: Arc::clone(&self.device),
+            family_index: self.family_index,
+            relay_semaphores,
+            relay_index: None,
+            timeline_waits: Vec::new(),
+        })
+    }
+}
+
 impl crate::Queue<Api> for Queue {
     unsafe fn submit(
         &mut self,
@@ -568,7 +615,8 @@ impl crate::Queue<Api> for Queue {
         let mut vk_info = vk::SubmitInfo::builder().command_buffers(&vk_cmd_buffers);
 
         let mut fence_raw = vk::Fence::null();
-        let mut vk_timeline_info;
+        let mut vk_timeline_info = vk::TimelineSemaphoreSubmitInfo::builder();
+        let mut uses_timeline = false;
         let mut signal_semaphores = [vk::Semaphore::null(), vk::Semaphore::null()];
         let signal_values;
 
@@ -578,9 +626,8 @@ impl crate::Queue<Api> for Queue {
                 Fence::TimelineSemaphore(raw) => {
                     signal_values = [!0, value];
                     signal_semaphores[1] = raw;
-                    vk_timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
-                        .signal_semaphore_values(&signal_values);
-                    vk_info = vk_info.push_next(&mut vk_timeline_info);
+                    vk_timeline_info = vk_timeline_info.signal_semaphore_values(&signal_values);
+                    uses_timeline = true;
                 }
                 Fence::FencePool {
                     ref mut active,
@@ -599,16 +646,35 @@ impl crate::Queue<Api> for Queue {
             }
         }
 
-        let wait_stage_mask = [vk::PipelineStageFlags::TOP_OF_PIPE];
+        // The relay semaphore, if any, comes first. Its value is ignored.
+        let mut wait_semaphores = Vec::with_capacity(1 + self.timeline_waits.len());
+        let mut wait_values = Vec::with_capacity(1 + self.timeline_waits.len());
         let sem_index = match self.relay_index {
             Some(old_index) => {
-                vk_info = vk_info
-                    .wait_semaphores(&self.relay_semaphores[old_index..old_index + 1])
-                    .wait_dst_stage_mask(&wait_stage_mask);
+                wait_semaphores.push(self.relay_semaphores[old_index]);
+                wait_values.push(0);
                 (old_index + 1) % self.relay_semaphores.len()
             }
             None => 0,
         };
+        let has_timeline_waits = !self.timeline_waits.is_empty();
+        for (raw, value) in self.timeline_waits.drain(..) {
+            wait_semaphores.push(raw);
+            wait_values.push(value);
+        }
+        let wait_stage_mask = vec![vk::PipelineStageFlags::TOP_OF_PIPE; wait_semaphores.len()];
+        if !wait_semaphores.is_empty() {
+            vk_info = vk_info
+                .wait_semaphores(&wait_semaphores)
+                .wait_dst_stage_mask(&wait_stage_mask);
+            if has_timeline_waits {
+                vk_timeline_info = vk_timeline_info.wait_semaphore_values(&wait_values);
+                uses_timeline = true;
+            }
+        }
+        if uses_timeline {
+            vk_info = vk_info.push_next(&mut vk_timeline_info);
+        }
         self.relay_index = Some(sem_index);
         signal_semaphores[0] = self.relay_semaphores[sem_index];
 
@@ -626,6 +692,20 @@ impl crate::Queue<Api> for Queue {
         Ok(())
     }
 
+    unsafe fn wait_for_fence(
+        &mut self,
+        fence: &Fence,
+        value: crate::FenceValue,
+    ) -> Result<bool, crate::DeviceError> {
+        match *fence {
+            Fence::TimelineSemaphore(raw) => {
+                self.timeline_waits.push((raw, value));
+                Ok(true)
+            }
+            Fence::FencePool { .. } => Ok(false),
+        }
+    }
+
     unsafe fn present(
         &mut self,
         surface: &mut Surface,
@@ -0,0 +1,222 @@
+use crate::report::{AdapterReport, GpuReport};
+
+/// List the ways `actual` falls short of `reference`.
+///
+/// An empty list means `actual` can do everything `reference` can.
+pub fn shortfalls(reference: &AdapterReport, actual: &AdapterReport) -> Vec<String> {
+    let mut missing = Vec::new();
+
+    let features = reference.features - actual.features;
+    if !features.is_empty() {
+        missing.push(format!("Missing features: {:?}", features));
+    }
+
+    reference
+        .limits
+        .check_limits_with_fail_fn(&actual.limits, false, |name, wanted, got| {
+            missing.push(format!(
+                "Limit {}: {} (reference has {})",
+                name, got, wanted
+            ));
+        });
+
+    let flags = reference.downlevel_caps.flags - actual.downlevel_caps.flags;
+    if !flags.is_empty() {
+        missing.push(format!("Missing downlevel flags: {:?}", flags));
+    }
+    if actual.downlevel_caps.shader_model < reference.downlevel_caps.shader_model {
+        missing.push(format!(
+            "Shader model: {:?} (reference has {:?})",
+            actual.downlevel_caps.shader_model, reference.downlevel_caps.shader_model
+        ));
+    }
+
+    for format_report in reference.texture_format_features.iter() {
+        let wanted = &format_report.features;
+        let got = actual
+            .texture_format_features(format_report.format)
+            .copied()
+            .unwrap_or(wgt::TextureFormatFeatures {
+                allowed_usages: wgt::TextureUsages::empty(),
+                flags: wgt::TextureFormatFeatureFlags::empty(),
+            });
+        let usages = wanted.allowed_usages - got.allowed_usages;
+        let flags = wanted.flags - got.flags;
+        if !usages.is_empty() || !flags.is_empty() {
+            missing.push(format!(
+                "Format {:?}: missing usages {:?}, missing flags {:?}",
+                format_report.format, usages, flags
+            ));
+        }
+    }
+
+    missing
+}
+
+/// Compare every adapter of `actual` against every adapter of `reference`, printing
+/// what falls short.
+///
+/// Returns true if every reference adapter is matched by at least one actual adapter.
+pub fn compare(reference: &GpuReport, actual: &GpuReport) -> bool {
+    let mut all_matched = true;
+    for reference_adapter in reference.devices.iter() {
+        let reference_info = &reference_adapter.info;
+        println!(
+            "Reference {} on {:?}:",
+            reference_info.name, reference_info.backend
+        );
+        let mut matched = false;
+        for actual_adapter in actual.devices.iter() {
+            let info = &actual_adapter.info;
+            let missing = shortfalls(reference_adapter, actual_adapter);
+            if missing.is_empty() {
+                println!("\t{} on {:?}: OK", info.name, info.backend);
+                matched = true;
+            } else {
+                println!("\t{} on {:?}: falls short", info.name, info.backend);
+                for line in missing {
+                    println!("\t\t{}", line);
+                }
+            }
+        }
+        if !matched {
+            println!("\tNo adapter matches the reference");
+            all_matched = false;
+        }
+    }
+    all_matched
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::report::TextureFormatReport;
+
+    fn adapter(features: wgt::Features, limits: wgt::Limits) -> AdapterReport {
+        AdapterReport {
+            info: wgt::AdapterInfo {
+                name: "test".to_string(),
+                vendor: 0,
+                device: 0,
+                device_type: wgt::DeviceType::Other,
+                backend: wgt::Backend::Empty,
+                driver_info: String::new(),
+            },
+            features,
+            limits,
+            downlevel_caps: wgt::DownlevelCapabilities::default(),
+            texture_format_features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matching_adapter() {
+        let reference = adapter(wgt::Features::DEPTH_CLIP_CONTROL, wgt::Limits::default());
+        let actual = adapter(
+            wgt::Features::DEPTH_CLIP_CONTROL | wgt::Features::TEXTURE_COMPRESSION_BC,
+            wgt::Limits {
+                max_bind_groups: 8,
+                ..wgt::Limits::default()
+            },
+        );
+        assert_eq!(shortfalls(&reference, &actual), Vec::<String>::new());
+    }
+
+    #[test]
+    fn missing_features() {
+        let reference = adapter(
+            wgt::Features::DEPTH_CLIP_CONTROL | wgt::Features::TEXTURE_COMPRESSION_BC,
+            wgt::Limits::default(),
+        );
+        let actual = adapter(wgt::Features::DEPTH_CLIP_CONTROL, wgt::Limits::default());
+        assert_eq!(
+            shortfalls(&reference, &actual),
+            [format!(
+                "Missing features: {:?}",
+                wgt::Features::TEXTURE_COMPRESSION_BC
+            )]
+        );
+    }
+
+    #[test]
+    fn lower_limits() {
+        let reference = adapter(wgt::Features::empty(), wgt::Limits::default());
+        let actual = adapter(
+            wgt::Features::empty(),
+            wgt::Limits {
+                max_bind_groups: 2,
+                // Alignments fall short by being larger.
+                min_uniform_buffer_offset_alignment: 512,
+                ..wgt::Limits::default()
+            },
+        );
+        assert_eq!(
+            shortfalls(&reference, &actual),
+            [
+                "Limit max_bind_groups: 2 (reference has 4)",
+                "Limit min_uniform_buffer_offset_alignment: 512 (reference has 256)",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_downlevel_capabilities() {
+        let reference = adapter(wgt::Features::empty(), wgt::Limits::default());
+        let mut actual = adapter(wgt::Features::empty(), wgt::Limits::default());
+        actual.downlevel_caps.flags -= wgt::DownlevelFlags::COMPUTE_SHADERS;
+        actual.downlevel_caps.shader_model = wgt::ShaderModel::Sm4;
+        assert_eq!(
+            shortfalls(&reference, &actual),
+            [
+                format!(
+                    "Missing downlevel flags: {:?}",
+                    wgt::DownlevelFlags::COMPUTE_SHADERS
+                ),
+                "Shader model: Sm4 (reference has Sm5)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_texture_format_features() {
+        let features = wgt::TextureFormatFeatures {
+            allowed_usages: wgt::TextureUsages::TEXTURE_BINDING
+                | wgt::TextureUsages::STORAGE_BINDING,
+            flags: wgt::TextureFormatFeatureFlags::FILTERABLE,
+        };
+        let mut reference = adapter(wgt::Features::empty(), wgt::Limits::default());
+        reference.texture_format_features = vec![
+            TextureFormatReport {
+                format: wgt::TextureFormat::Rgba8Unorm,
+                features,
+            },
+            TextureFormatReport {
+                format: wgt::TextureFormat::R32Float,
+                features,
+            },
+        ];
+        let mut actual = adapter(wgt::Features::empty(), wgt::Limits::default());
+        // `R32Float` isn't reported at all, so it has no features.
+        actual.texture_format_features = vec![TextureFormatReport {
+            format: wgt::TextureFormat::Rgba8Unorm,
+            features: wgt::TextureFormatFeatures {
+                allowed_usages: wgt::TextureUsages::TEXTURE_BINDING,
+                ..features
+            },
+        }];
+        assert_eq!(
+            shortfalls(&reference, &actual),
+            [
+                format!(
+                    "Format Rgba8Unorm: missing usages {:?}, missing flags {:?}",
+                    wgt::TextureUsages::STORAGE_BINDING,
+                    wgt::TextureFormatFeatureFlags::empty()
+                ),
+                format!(
+                    "Format R32Float: missing usages {:?}, missing flags {:?}",
+                    features.allowed_usages, features.flags
+                ),
+            ]
+        );
+    }
+}
@@ -1,10 +1,97 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod compare;
+#[cfg(not(target_arch = "wasm32"))]
+mod report;
+#[cfg(not(target_arch = "wasm32"))]
+mod runner;
+
 #[cfg(not(target_arch = "wasm32"))]
 mod inner {
-    use std::{
-        mem::size_of,
-        process::{exit, Command},
-        time::Instant,
-    };
+    use std::{fs, mem::size_of, path::Path, process::exit};
+
+    use crate::report::{GpuReport, ReportFormat};
+    use wgpu::{TextureFormatFeatureFlags as Tfff, TextureUsages as Tu};
+
+    /// Columns of the texture format matrix, with the usage or flag each one checks.
+    const FORMAT_COLUMNS: [(&str, Tu, Tfff); 10] = [
+        ("Src", Tu::COPY_SRC, Tfff::empty()),
+        ("Dst", Tu::COPY_DST, Tfff::empty()),
+        ("Tex", Tu::TEXTURE_BINDING, Tfff::empty()),
+        ("Sto", Tu::STORAGE_BINDING, Tfff::empty()),
+        ("Rnd", Tu::RENDER_ATTACHMENT, Tfff::empty()),
+        ("Flt", Tu::empty(), Tfff::FILTERABLE),
+        ("Msa", Tu::empty(), Tfff::MULTISAMPLE),
+        ("Res", Tu::empty(), Tfff::MULTISAMPLE_RESOLVE),
+        ("RW", Tu::empty(), Tfff::STORAGE_READ_WRITE),
+        ("Atm", Tu::empty(), Tfff::STORAGE_ATOMICS),
+    ];
+
+    /// The matrix row of `format`, listing its allowed usages, feature flags,
+    /// block dimensions and whether it is sRGB.
+    fn texture_format_row(
+        format: wgpu::TextureFormat,
+        features: wgpu::TextureFormatFeatures,
+    ) -> String {
+        let info = format.describe();
+        let name = format!("{:?}", format);
+        let mut row = format!("{:<42}", name);
+        for &(_, usage, flag) in FORMAT_COLUMNS.iter() {
+            let supported =
+                features.allowed_usages.contains(usage) && features.flags.contains(flag);
+            row += &format!(" {:<3}", if supported { "X" } else { "-" });
+        }
+        let (block_width, block_height) = info.block_dimensions;
+        let block = format!("{}x{}", block_width, block_height);
+        row += &format!(" {:<6} {}", block, if info.srgb { "X" } else { "-" });
+        row
+    }
+
+    /// Prints a row per texture format.
+    fn print_texture_format_features(adapter: &wgpu::Adapter) {
+        println!("\tTexture Format Features:");
+        print!("\t\t{:<42}", "Format");
+        for &(name, _, _) in FORMAT_COLUMNS.iter() {
+            print!(" {:<3}", name);
+        }
+        println!(" Block  sRGB");
+        for format in crate::report::texture_formats() {
+            let features = adapter.get_texture_format_features(format);
+            println!("\t\t{}", texture_format_row(format, features));
+        }
+    }
+
+    /// Lines describing the formats a surface supports.
+    fn surface_format_lines(formats: &[wgpu::TextureFormat]) -> Vec<String> {
+        if formats.is_empty() {
+            return vec!["Surface is incompatible with the adapter".to_string()];
+        }
+        formats
+            .iter()
+            .map(|format| format!("{:?}", format))
+            .collect()
+    }
+
+    fn print_surface_formats(surface: &wgpu::Surface, adapter: &wgpu::Adapter) {
+        println!("\tSurface Formats:");
+        for line in surface_format_lines(&surface.get_supported_formats(adapter)) {
+            println!("\t\t{}", line);
+        }
+    }
+
+    /// Windowing systems are always there, except on Linux and BSDs without a display server.
+    fn window_available() -> bool {
+        if cfg!(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )) {
+            std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some()
+        } else {
+            true
+        }
+    }
 
     // Lets keep these on one line
     #[rustfmt::skip]
@@ -106,70 +193,147 @@ mod inner {
         }
     }
 
+    fn load_report(path: &str) -> GpuReport {
+        match GpuReport::from_file(Path::new(path)) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Failed to load report {}: {}", path, e);
+                exit(1);
+            }
+        }
+    }
+
+    /// Writes a report of all adapters to `output`, or to stdout if there is none.
+    fn write_report(format: ReportFormat, output: Option<&String>) {
+        let report = GpuReport::generate();
+        let contents = report
+            .to_string(format)
+            .expect("Failed to serialize the report");
+        match output {
+            Some(path) => fs::write(path, contents).expect("Failed to write the report"),
+            None => println!("{}", contents),
+        }
+    }
+
+    /// Compares a reference report against another one, or against the live adapters.
+    fn compare_reports(args: &[String]) {
+        let reference = match args.first() {
+            Some(path) => load_report(path),
+            None => {
+                eprintln!("Usage: wgpu-info compare <reference> [<report>]");
+                exit(2);
+            }
+        };
+        let actual = match args.get(1) {
+            Some(path) => load_report(path),
+            None => GpuReport::generate(),
+        };
+        if !crate::compare::compare(&reference, &actual) {
+            exit(1);
+        }
+    }
+
     pub fn main() {
         env_logger::init();
         let args: Vec<_> = std::env::args().skip(1).collect();
 
+        match args.first().map(String::as_str) {
+            Some("--json") => return write_report(ReportFormat::Json, args.get(1)),
+            Some("--ron") => return write_report(ReportFormat::Ron, args.get(1)),
+            Some("compare") => return compare_reports(&args[1..]),
+            _ => {}
+        }
+
         let instance = wgpu::Instance::new(wgpu::Backends::all());
+
+        // The surface has to exist before enumerating adapters, as GL adapters
+        // are only compatible with surfaces created before them.
+        let window = if args.is_empty() && window_available() {
+            let event_loop = winit::event_loop::EventLoop::new();
+            winit::window::WindowBuilder::new()
+                .with_title("wgpu-info")
+                .with_visible(false)
+                .build(&event_loop)
+                .ok()
+                .map(|window| (event_loop, window))
+        } else {
+            None
+        };
+        let surface = window
+            .as_ref()
+            .map(|(_, window)| unsafe { instance.create_surface(window) });
+
         let adapters: Vec<_> = instance.enumerate_adapters(wgpu::Backends::all()).collect();
-        let adapter_count = adapters.len();
 
         if args.is_empty() {
             for (idx, adapter) in adapters.into_iter().enumerate() {
-                print_info_from_adapter(&adapter, idx)
+                print_info_from_adapter(&adapter, idx);
+                print_texture_format_features(&adapter);
+                if let Some(ref surface) = surface {
+                    print_surface_formats(surface, &adapter);
+                }
             }
         } else {
-            let all_start = Instant::now();
-
-            for (idx, adapter) in adapters.into_iter().enumerate() {
-                let adapter_start_time = Instant::now();
-                let idx = idx + 1;
-                let info = adapter.get_info();
-                println!(
-                    "=========== TESTING {} on {:?} ({} of {}) ===========",
-                    info.name, info.backend, idx, adapter_count
-                );
-                let exit_status = Command::new(&args[0])
-                    .args(&args[1..])
-                    .env("WGPU_ADAPTER_NAME", &info.name)
-                    .env(
-                        "WGPU_BACKEND",
-                        match info.backend {
-                            wgpu::Backend::Empty => unreachable!(),
-                            wgpu::Backend::Vulkan => "vulkan",
-                            wgpu::Backend::Metal => "metal",
-                            wgpu::Backend::Dx12 => "dx12",
-                            wgpu::Backend::Dx11 => "dx11",
-                            wgpu::Backend::Gl => "gl",
-                            wgpu::Backend::BrowserWebGpu => "webgpu",
-                        },
-                    )
-                    .spawn()
-                    .unwrap()
-                    .wait()
-                    .unwrap();
-
-                let adapter_time = adapter_start_time.elapsed().as_secs_f32();
-
-                if exit_status.success() {
-                    println!(
-                        "=========== PASSED! {} on {:?} ({} of {}) in {:.3}s ===========",
-                        info.name, info.backend, idx, adapter_count, adapter_time
-                    );
-                } else {
-                    println!(
-                        "=========== FAILED! {} on {:?} ({} of {}) in {:.3}s ===========",
-                        info.name, info.backend, idx, adapter_count, adapter_time
-                    );
-                    exit(1);
-                }
+            let infos = adapters.iter().map(|adapter| adapter.get_info()).collect();
+            if !crate::runner::run(&args, infos) {
+                exit(1);
             }
+        }
+    }
 
-            let all_time = all_start.elapsed().as_secs_f32();
+    #[cfg(test)]
+    mod test {
+        use super::*;
 
-            println!(
-                "=========== {} adapters PASSED in {:.3}s ===========",
-                adapter_count, all_time
+        #[test]
+        fn supported_format_row() {
+            let features = wgpu::TextureFormatFeatures {
+                allowed_usages: Tu::COPY_SRC | Tu::TEXTURE_BINDING | Tu::RENDER_ATTACHMENT,
+                flags: Tfff::FILTERABLE | Tfff::MULTISAMPLE,
+            };
+            assert_eq!(
+                texture_format_row(wgpu::TextureFormat::Rgba8UnormSrgb, features),
+                format!(
+                    "{:<42} X   -   X   -   X   X   X   -   -   -   1x1    X",
+                    "Rgba8UnormSrgb"
+                )
+            );
+            assert_eq!(
+                texture_format_row(wgpu::TextureFormat::Bc1RgbaUnorm, features),
+                format!(
+                    "{:<42} X   -   X   -   X   X   X   -   -   -   4x4    -",
+                    "Bc1RgbaUnorm"
+                )
+            );
+        }
+
+        #[test]
+        fn unsupported_format_row() {
+            let features = wgpu::TextureFormatFeatures {
+                allowed_usages: Tu::empty(),
+                flags: Tfff::empty(),
+            };
+            assert_eq!(
+                texture_format_row(wgpu::TextureFormat::R32Float, features),
+                format!(
+                    "{:<42} -   -   -   -   -   -   -   -   -   -   1x1    -",
+                    "R32Float"
+                )
+            );
+        }
+
+        #[test]
+        fn surface_formats() {
+            assert_eq!(
+                surface_format_lines(&[
+                    wgpu::TextureFormat::Bgra8UnormSrgb,
+                    wgpu::TextureFormat::Rgba16Float
+                ]),
+                ["Bgra8UnormSrgb", "Rgba16Float"]
+            );
+            assert_eq!(
+                surface_format_lines(&[]),
+                ["Surface is incompatible with the adapter"]
             );
         }
     }
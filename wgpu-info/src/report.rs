@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path};
+
+/// Every texture format wgpu knows about, including all the ASTC variations.
+pub fn texture_formats() -> Vec<wgt::TextureFormat> {
+    use wgt::{AstcBlock, AstcChannel, TextureFormat as Tf};
+
+    let mut formats = vec![
+        Tf::R8Unorm,
+        Tf::R8Snorm,
+        Tf::R8Uint,
+        Tf::R8Sint,
+        Tf::R16Uint,
+        Tf::R16Sint,
+        Tf::R16Unorm,
+        Tf::R16Snorm,
+        Tf::R16Float,
+        Tf::Rg8Unorm,
+        Tf::Rg8Snorm,
+        Tf::Rg8Uint,
+        Tf::Rg8Sint,
+        Tf::R32Uint,
+        Tf::R32Sint,
+        Tf::R32Float,
+        Tf::Rg16Uint,
+        Tf::Rg16Sint,
+        Tf::Rg16Unorm,
+        Tf::Rg16Snorm,
+        Tf::Rg16Float,
+        Tf::Rgba8Unorm,
+        Tf::Rgba8UnormSrgb,
+        Tf::Rgba8Snorm,
+        Tf::Rgba8Uint,
+        Tf::Rgba8Sint,
+        Tf::Bgra8Unorm,
+        Tf::Bgra8UnormSrgb,
+        Tf::Rgb10a2Unorm,
+        Tf::Rg11b10Float,
+        Tf::Rg32Uint,
+        Tf::Rg32Sint,
+        Tf::Rg32Float,
+        Tf::Rgba16Uint,
+        Tf::Rgba16Sint,
+        Tf::Rgba16Unorm,
+        Tf::Rgba16Snorm,
+        Tf::Rgba16Float,
+        Tf::Rgba32Uint,
+        Tf::Rgba32Sint,
+        Tf::Rgba32Float,
+        Tf::Depth32Float,
+        Tf::Depth24Plus,
+        Tf::Depth24PlusStencil8,
+        Tf::Rgb9e5Ufloat,
+        Tf::Bc1RgbaUnorm,
+        Tf::Bc1RgbaUnormSrgb,
+        Tf::Bc2RgbaUnorm,
+        Tf::Bc2RgbaUnormSrgb,
+        Tf::Bc3RgbaUnorm,
+        Tf::Bc3RgbaUnormSrgb,
+        Tf::Bc4RUnorm,
+        Tf::Bc4RSnorm,
+        Tf::Bc5RgUnorm,
+        Tf::Bc5RgSnorm,
+        Tf::Bc6hRgbUfloat,
+        Tf::Bc6hRgbSfloat,
+        Tf::Bc7RgbaUnorm,
+        Tf::Bc7RgbaUnormSrgb,
+        Tf::Etc2Rgb8Unorm,
+        Tf::Etc2Rgb8UnormSrgb,
+        Tf::Etc2Rgb8A1Unorm,
+        Tf::Etc2Rgb8A1UnormSrgb,
+        Tf::Etc2Rgba8Unorm,
+        Tf::Etc2Rgba8UnormSrgb,
+        Tf::EacR11Unorm,
+        Tf::EacR11Snorm,
+        Tf::EacRg11Unorm,
+        Tf::EacRg11Snorm,
+    ];
+    for &block in &[
+        AstcBlock::B4x4,
+        AstcBlock::B5x4,
+        AstcBlock::B5x5,
+        AstcBlock::B6x5,
+        AstcBlock::B6x6,
+        AstcBlock::B8x5,
+        AstcBlock::B8x6,
+        AstcBlock::B8x8,
+        AstcBlock::B10x5,
+        AstcBlock::B10x6,
+        AstcBlock::B10x8,
+        AstcBlock::B10x10,
+        AstcBlock::B12x10,
+        AstcBlock::B12x12,
+    ] {
+        for &channel in &[AstcChannel::Unorm, AstcChannel::UnormSrgb, AstcChannel::Hdr] {
+            formats.push(Tf::Astc { block, channel });
+        }
+    }
+    formats
+}
+
+/// Serialization format of a report file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Ron,
+}
+
+impl ReportFormat {
+    /// Pick the format from the file extension, defaulting to JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ron") => Self::Ron,
+            _ => Self::Json,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TextureFormatReport {
+    pub format: wgt::TextureFormat,
+    pub features: wgt::TextureFormatFeatures,
+}
+
+/// Capabilities of a single adapter.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdapterReport {
+    pub info: wgt::AdapterInfo,
+    pub features: wgt::Features,
+    pub limits: wgt::Limits,
+    pub downlevel_caps: wgt::DownlevelCapabilities,
+    pub texture_format_features: Vec<TextureFormatReport>,
+}
+
+impl AdapterReport {
+    pub fn from_adapter(adapter: &wgpu::Adapter) -> Self {
+        Self {
+            info: adapter.get_info(),
+            features: adapter.features(),
+            limits: adapter.limits(),
+            downlevel_caps: adapter.get_downlevel_capabilities(),
+            texture_format_features: texture_formats()
+                .into_iter()
+                .map(|format| TextureFormatReport {
+                    format,
+                    features: adapter.get_texture_format_features(format),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn texture_format_features(
+        &self,
+        format: wgt::TextureFormat,
+    ) -> Option<&wgt::TextureFormatFeatures> {
+        self.texture_format_features
+            .iter()
+            .find(|report| report.format == format)
+            .map(|report| &report.features)
+    }
+}
+
+/// Capabilities of all the adapters of a system.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GpuReport {
+    pub devices: Vec<AdapterReport>,
+}
+
+impl GpuReport {
+    pub fn generate() -> Self {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let devices = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .map(|adapter| AdapterReport::from_adapter(&adapter))
+            .collect();
+        Self { devices }
+    }
+
+    pub fn to_string(&self, format: ReportFormat) -> Result<String, Box<dyn Error>> {
+        Ok(match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())?,
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        Ok(match ReportFormat::from_path(path) {
+            ReportFormat::Json => serde_json::from_str(&contents)?,
+            ReportFormat::Ron => ron::de::from_str(&contents)?,
+        })
+    }
+}
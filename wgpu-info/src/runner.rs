@@ -0,0 +1,604 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::Read,
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "Usage: wgpu-info [--parallel] [--backend <list>] [--adapter <name>] \
+    [--device-type <list>] [--timeout <seconds>] [--junit <file>] [--] <command> [<args>...]";
+
+/// How the test command is run, parsed from the arguments before the command.
+#[derive(Debug)]
+pub struct Options {
+    /// Run all adapters at once, capturing their output.
+    parallel: bool,
+    backends: wgpu::Backends,
+    /// Case-insensitive part of the adapter name.
+    name: Option<String>,
+    device_types: Option<Vec<wgpu::DeviceType>>,
+    timeout: Option<Duration>,
+    junit: Option<PathBuf>,
+    command: Vec<String>,
+}
+
+fn parse_device_type(name: &str) -> Result<wgpu::DeviceType, String> {
+    Ok(match name {
+        "other" => wgpu::DeviceType::Other,
+        "integrated-gpu" => wgpu::DeviceType::IntegratedGpu,
+        "discrete-gpu" => wgpu::DeviceType::DiscreteGpu,
+        "virtual-gpu" => wgpu::DeviceType::VirtualGpu,
+        "cpu" => wgpu::DeviceType::Cpu,
+        _ => return Err(format!("Unknown device type {:?}", name)),
+    })
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self {
+            parallel: false,
+            backends: wgpu::Backends::all(),
+            name: None,
+            device_types: None,
+            timeout: None,
+            junit: None,
+            command: Vec::new(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--parallel" => options.parallel = true,
+                "--backend" => {
+                    options.backends =
+                        wgpu::util::parse_backends_from_comma_list(&value()?.to_lowercase())
+                }
+                "--adapter" => options.name = Some(value()?.to_lowercase()),
+                "--device-type" => {
+                    options.device_types = Some(
+                        value()?
+                            .split(',')
+                            .map(|name| parse_device_type(&name.to_lowercase()))
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+                "--timeout" => {
+                    let value = value()?;
+                    let seconds = match value.parse::<f64>() {
+                        // Also rejects NaN, and values `Duration` can't hold.
+                        Ok(seconds) if seconds >= 0.0 && seconds < u64::MAX as f64 => seconds,
+                        _ => return Err(format!("Invalid timeout {:?}", value)),
+                    };
+                    options.timeout = Some(Duration::from_secs_f64(seconds));
+                }
+                "--junit" => options.junit = Some(PathBuf::from(value()?)),
+                "--" => {
+                    options.command = args.by_ref().cloned().collect();
+                    break;
+                }
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+                _ => {
+                    options.command = std::iter::once(arg).chain(args.by_ref()).cloned().collect();
+                    break;
+                }
+            }
+        }
+
+        if options.command.is_empty() {
+            return Err("Missing command to run".to_string());
+        }
+        Ok(options)
+    }
+
+    fn matches(&self, info: &wgpu::AdapterInfo) -> bool {
+        self.backends.contains(wgpu::Backends::from(info.backend))
+            && self
+                .name
+                .as_ref()
+                .map_or(true, |name| info.name.to_lowercase().contains(name))
+            && self
+                .device_types
+                .as_ref()
+                .map_or(true, |types| types.contains(&info.device_type))
+    }
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Passed,
+    Failed(ExitStatus),
+    TimedOut,
+    SpawnFailed(String),
+}
+
+impl Outcome {
+    fn is_success(&self) -> bool {
+        matches!(*self, Self::Passed)
+    }
+
+    fn description(&self) -> String {
+        match *self {
+            Self::Passed => "PASSED".to_string(),
+            Self::Failed(status) => match status.code() {
+                Some(code) => format!("FAILED (exit code {})", code),
+                None => "FAILED (killed by a signal)".to_string(),
+            },
+            Self::TimedOut => "TIMED OUT".to_string(),
+            Self::SpawnFailed(ref error) => format!("FAILED TO START ({})", error),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Run {
+    info: wgpu::AdapterInfo,
+    outcome: Outcome,
+    time: Duration,
+    /// Output of the command, when it was captured.
+    output: Option<String>,
+}
+
+/// The `WGPU_BACKEND` value selecting `backend`.
+fn backend_env_name(backend: wgpu::Backend) -> Result<&'static str, String> {
+    Ok(match backend {
+        wgpu::Backend::Vulkan => "vulkan",
+        wgpu::Backend::Metal => "metal",
+        wgpu::Backend::Dx12 => "dx12",
+        wgpu::Backend::Dx11 => "dx11",
+        wgpu::Backend::Gl => "gl",
+        wgpu::Backend::BrowserWebGpu => "webgpu",
+        wgpu::Backend::Empty => {
+            return Err(format!("{:?} backend can't be selected", backend));
+        }
+    })
+}
+
+/// Output of a pipe, read on its own thread.
+struct PipeReader {
+    data: Arc<Mutex<Vec<u8>>>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl PipeReader {
+    fn new(mut source: impl Read + Send + 'static) -> Self {
+        let data = Arc::new(Mutex::new(Vec::new()));
+        let thread_data = Arc::clone(&data);
+        let handle = thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            loop {
+                match source.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(count) => thread_data
+                        .lock()
+                        .unwrap()
+                        .extend_from_slice(&buffer[..count]),
+                }
+            }
+        });
+        Self { data, handle }
+    }
+
+    /// Everything read so far, waiting for the end of the pipe if `wait` is set.
+    ///
+    /// Processes started by the command can keep the pipe open after it's
+    /// killed, so the end isn't waited for after a timeout.
+    fn output(self, wait: bool) -> String {
+        if wait {
+            let _ = self.handle.join();
+        }
+        let data = self.data.lock().unwrap();
+        String::from_utf8_lossy(&data).into_owned()
+    }
+}
+
+/// Run the command against a single adapter, killing it once `timeout` expires.
+fn run_adapter(options: &Options, info: wgpu::AdapterInfo, capture: bool) -> Run {
+    let start = Instant::now();
+    let backend_name = match backend_env_name(info.backend) {
+        Ok(name) => name,
+        Err(e) => {
+            return Run {
+                info,
+                outcome: Outcome::SpawnFailed(e),
+                time: start.elapsed(),
+                output: None,
+            }
+        }
+    };
+    let stdio = || {
+        if capture {
+            Stdio::piped()
+        } else {
+            Stdio::inherit()
+        }
+    };
+    let child = Command::new(&options.command[0])
+        .args(&options.command[1..])
+        .env("WGPU_ADAPTER_NAME", &info.name)
+        .env("WGPU_BACKEND", backend_name)
+        .stdout(stdio())
+        .stderr(stdio())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            return Run {
+                info,
+                outcome: Outcome::SpawnFailed(e.to_string()),
+                time: start.elapsed(),
+                output: None,
+            }
+        }
+    };
+
+    // Read both pipes while waiting, so the child never blocks on a full pipe.
+    let stdout = child.stdout.take().map(PipeReader::new);
+    let stderr = child.stderr.take().map(PipeReader::new);
+
+    let outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break Outcome::Passed,
+            Ok(Some(status)) => break Outcome::Failed(status),
+            Ok(None) => {}
+            Err(e) => break Outcome::SpawnFailed(e.to_string()),
+        }
+        if options
+            .timeout
+            .map_or(false, |timeout| start.elapsed() > timeout)
+        {
+            let _ = child.kill();
+            let _ = child.wait();
+            break Outcome::TimedOut;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let time = start.elapsed();
+
+    let output = if capture {
+        let mut output = String::new();
+        // Only an exited command is known to have closed its end of the pipes.
+        let wait = matches!(outcome, Outcome::Passed | Outcome::Failed(_));
+        for reader in stdout.into_iter().chain(stderr) {
+            output.push_str(&reader.output(wait));
+        }
+        Some(output)
+    } else {
+        None
+    };
+
+    Run {
+        info,
+        outcome,
+        time,
+        output,
+    }
+}
+
+fn print_banner(run: &Run, idx: usize, count: usize) {
+    println!(
+        "=========== {} {} on {:?} ({} of {}) in {:.3}s ===========",
+        run.outcome.description(),
+        run.info.name,
+        run.info.backend,
+        idx + 1,
+        count,
+        run.time.as_secs_f32()
+    );
+}
+
+fn print_summary(runs: &[Run]) {
+    let name_width = runs
+        .iter()
+        .map(|run| run.info.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Adapter".len());
+    println!(
+        "{:<width$}  {:<8}  {:>9}  Result",
+        "Adapter",
+        "Backend",
+        "Time",
+        width = name_width
+    );
+    for run in runs {
+        println!(
+            "{:<width$}  {:<8}  {:>8.3}s  {}",
+            run.info.name,
+            format!("{:?}", run.info.backend),
+            run.time.as_secs_f32(),
+            run.outcome.description(),
+            width = name_width
+        );
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace aren't allowed in XML.
+            c if c.is_control() && !c.is_whitespace() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format the runs as a JUnit report, with one test case per adapter.
+fn junit_report(options: &Options, runs: &[Run], total_time: Duration) -> String {
+    let failures = runs.iter().filter(|run| !run.outcome.is_success()).count();
+    let suite_name = xml_escape(&options.command.join(" "));
+
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        xml,
+        r#"<testsuites name="wgpu-info" tests="{}" failures="{}" time="{:.3}">"#,
+        runs.len(),
+        failures,
+        total_time.as_secs_f32()
+    )
+    .unwrap();
+    writeln!(
+        xml,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+        suite_name,
+        runs.len(),
+        failures,
+        total_time.as_secs_f32()
+    )
+    .unwrap();
+    for run in runs {
+        writeln!(
+            xml,
+            r#"    <testcase classname="{:?}" name="{}" time="{:.3}">"#,
+            run.info.backend,
+            xml_escape(&run.info.name),
+            run.time.as_secs_f32()
+        )
+        .unwrap();
+        if !run.outcome.is_success() {
+            writeln!(
+                xml,
+                r#"      <failure message="{}"/>"#,
+                xml_escape(&run.outcome.description())
+            )
+            .unwrap();
+        }
+        if let Some(ref output) = run.output {
+            writeln!(xml, "      <system-out>{}</system-out>", xml_escape(output)).unwrap();
+        }
+        writeln!(xml, "    </testcase>").unwrap();
+    }
+    writeln!(xml, "  </testsuite>").unwrap();
+    writeln!(xml, "</testsuites>").unwrap();
+    xml
+}
+
+/// Run the command given in `args` once per matching adapter.
+///
+/// Returns true if every run passed.
+pub fn run(args: &[String], adapters: Vec<wgpu::AdapterInfo>) -> bool {
+    let options = match Options::parse(args) {
+        Ok(options) => Arc::new(options),
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let adapters: Vec<_> = adapters
+        .into_iter()
+        .filter(|info| options.matches(info))
+        .collect();
+    let count = adapters.len();
+    if count == 0 {
+        println!("=========== No adapters match the filters ===========");
+        return false;
+    }
+
+    let all_start = Instant::now();
+    let runs: Vec<_> = if options.parallel {
+        let handles: Vec<_> = adapters
+            .into_iter()
+            .map(|info| {
+                let options = Arc::clone(&options);
+                thread::spawn(move || run_adapter(&options, info, true))
+            })
+            .collect();
+        let runs: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        for (idx, run) in runs.iter().enumerate() {
+            println!(
+                "=========== OUTPUT {} on {:?} ({} of {}) ===========",
+                run.info.name,
+                run.info.backend,
+                idx + 1,
+                count
+            );
+            print!("{}", run.output.as_deref().unwrap_or_default());
+            print_banner(run, idx, count);
+        }
+        runs
+    } else {
+        adapters
+            .into_iter()
+            .enumerate()
+            .map(|(idx, info)| {
+                println!(
+                    "=========== TESTING {} on {:?} ({} of {}) ===========",
+                    info.name,
+                    info.backend,
+                    idx + 1,
+                    count
+                );
+                let run = run_adapter(&options, info, false);
+                print_banner(&run, idx, count);
+                run
+            })
+            .collect()
+    };
+    let all_time = all_start.elapsed();
+
+    print_summary(&runs);
+    let passed = runs.iter().filter(|run| run.outcome.is_success()).count();
+    println!(
+        "=========== {} of {} adapters PASSED in {:.3}s ===========",
+        passed,
+        count,
+        all_time.as_secs_f32()
+    );
+
+    if let Some(ref path) = options.junit {
+        if let Err(e) = fs::write(path, junit_report(&options, &runs, all_time)) {
+            eprintln!(
+                "Failed to write the JUnit report to {}: {}",
+                path.display(),
+                e
+            );
+            return false;
+        }
+    }
+
+    passed == count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+        Options::parse(&args)
+    }
+
+    fn run(name: &str, outcome: Outcome, output: Option<&str>) -> Run {
+        Run {
+            info: wgpu::AdapterInfo {
+                name: name.to_string(),
+                vendor: 0,
+                device: 0,
+                device_type: wgpu::DeviceType::DiscreteGpu,
+                backend: wgpu::Backend::Vulkan,
+                driver_info: String::new(),
+            },
+            outcome,
+            time: Duration::from_millis(1500),
+            output: output.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = parse(&[
+            "--parallel",
+            "--backend",
+            "Vulkan,GL",
+            "--adapter",
+            "RTX",
+            "--device-type",
+            "discrete-gpu,cpu",
+            "--timeout",
+            "2.5",
+            "--junit",
+            "report.xml",
+            "--",
+            "cargo",
+            "test",
+            "--release",
+        ])
+        .unwrap();
+        assert!(options.parallel);
+        assert_eq!(
+            options.backends,
+            wgpu::Backends::VULKAN | wgpu::Backends::GL
+        );
+        assert_eq!(options.name.as_deref(), Some("rtx"));
+        assert_eq!(
+            options.device_types,
+            Some(vec![wgpu::DeviceType::DiscreteGpu, wgpu::DeviceType::Cpu])
+        );
+        assert_eq!(options.timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(options.junit, Some(PathBuf::from("report.xml")));
+        assert_eq!(options.command, ["cargo", "test", "--release"]);
+    }
+
+    #[test]
+    fn parse_command_without_separator() {
+        let options = parse(&["cargo", "test", "--release"]).unwrap();
+        assert!(!options.parallel);
+        assert_eq!(options.backends, wgpu::Backends::all());
+        assert_eq!(options.timeout, None);
+        assert_eq!(options.command, ["cargo", "test", "--release"]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--parallel", "--"]).is_err());
+        assert!(parse(&["--unknown", "cargo"]).is_err());
+        assert!(parse(&["--adapter"]).is_err());
+        assert!(parse(&["--device-type", "gpu", "cargo"]).is_err());
+        for timeout in ["abc", "-1", "NaN", "inf", "1e30"] {
+            assert_eq!(
+                parse(&["--timeout", timeout, "cargo"]).unwrap_err(),
+                format!("Invalid timeout {:?}", timeout)
+            );
+        }
+    }
+
+    #[test]
+    fn backend_names() {
+        assert_eq!(backend_env_name(wgpu::Backend::Vulkan), Ok("vulkan"));
+        assert!(backend_env_name(wgpu::Backend::Empty).is_err());
+    }
+
+    #[test]
+    fn escape_xml() {
+        assert_eq!(
+            xml_escape(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;"
+        );
+        assert_eq!(xml_escape("line\n\tend\u{1b}[0m\u{0}"), "line\n\tend[0m");
+    }
+
+    #[test]
+    fn junit() {
+        let options = parse(&["cargo", "test", "a&b"]).unwrap();
+        let runs = [
+            run("GPU <1>", Outcome::Passed, Some("ok")),
+            run("GPU 2", Outcome::TimedOut, None),
+        ];
+        let report = junit_report(&options, &runs, Duration::from_secs(3));
+        assert_eq!(
+            report,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="wgpu-info" tests="2" failures="1" time="3.000">
+  <testsuite name="cargo test a&amp;b" tests="2" failures="1" time="3.000">
+    <testcase classname="Vulkan" name="GPU &lt;1&gt;" time="1.500">
+      <system-out>ok</system-out>
+    </testcase>
+    <testcase classname="Vulkan" name="GPU 2" time="1.500">
+      <failure message="TIMED OUT"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
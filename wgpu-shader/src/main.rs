@@ -0,0 +1,263 @@
+use std::{borrow::Cow, env, error::Error, fs, mem::size_of, path::Path, process::exit};
+
+const USAGE: &str = "\
+Usage:
+    wgpu-shader compile [--features <list>] <input.wgsl> <output.ir>
+    wgpu-shader validate [options] <shader>...
+
+Commands:
+    compile    Parse and validate a WGSL shader with the given features enabled, and
+               write it as serialized IR to be loaded with `ShaderSource::SerializedIr`.
+    validate   Check shaders and all their entry points the way shader module and
+               pipeline creation do, for the given target. Shaders can be WGSL (.wgsl),
+               SPIR-V (.spv) or GLSL (.vert, .frag, .comp).
+
+Options (`compile` only takes `--features`):
+    --profile <name>     Target to validate for: `webgpu` (default), `downlevel` or `webgl2`.
+    --features <list>    Comma-separated features to enable, e.g. `PUSH_CONSTANTS,SHADER_FLOAT64`.
+    --limits <file>      RON file with the `Limits` to use instead of the profile ones.
+    --layout <file>      RON file with the pipeline layout to check entry points against, as a
+                         list of bind group layouts, each a list of `BindGroupLayoutEntry`.
+                         Without it, the layout is derived from each shader.";
+
+fn compile(args: &[&str]) -> Result<(), String> {
+    let mut features = wgt::Features::empty();
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--features" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("Missing value of {}", arg))?;
+                features = parse_features(value)?;
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => paths.push(arg),
+        }
+    }
+    let (input, output) = match paths[..] {
+        [input, output] => (input, output),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let source =
+        fs::read_to_string(input).map_err(|e| format!("Cannot read '{}': {}", input, e))?;
+    let desc = wgc::pipeline::ShaderModuleDescriptor {
+        label: Some(Cow::Borrowed(input)),
+        shader_bound_checks: wgt::ShaderBoundChecks::default(),
+        source_hash: None,
+    };
+    let data =
+        wgc::serialized_ir::compile(&desc, &source, features).map_err(|error| error.to_string())?;
+    fs::write(output, data).map_err(|e| format!("Cannot write '{}': {}", output, e))
+}
+
+fn parse_profile(name: &str) -> Result<wgc::offline::Profile, String> {
+    let (limits, flags, shader_model) = match name {
+        "webgpu" => (
+            wgt::Limits::default(),
+            wgt::DownlevelFlags::compliant(),
+            wgt::ShaderModel::Sm5,
+        ),
+        "downlevel" => (
+            wgt::Limits::downlevel_defaults(),
+            wgt::DownlevelFlags::COMPUTE_SHADERS,
+            wgt::ShaderModel::Sm5,
+        ),
+        "webgl2" => (
+            wgt::Limits::downlevel_webgl2_defaults(),
+            wgt::DownlevelFlags::empty(),
+            wgt::ShaderModel::Sm4,
+        ),
+        _ => return Err(format!("Unknown profile '{}'", name)),
+    };
+    Ok(wgc::offline::Profile {
+        features: wgt::Features::empty(),
+        limits,
+        downlevel: wgt::DownlevelCapabilities {
+            flags,
+            limits: wgt::DownlevelLimits::default(),
+            shader_model,
+        },
+    })
+}
+
+fn parse_features(list: &str) -> Result<wgt::Features, String> {
+    let mut features = wgt::Features::empty();
+    for name in list.split(',').filter(|name| !name.is_empty()) {
+        // Features are named the same as their `Debug` representation.
+        let feature = (0..size_of::<wgt::Features>() * 8)
+            .filter_map(|i| wgt::Features::from_bits(1 << i))
+            .find(|bit| format!("{:?}", bit) == name)
+            .ok_or_else(|| format!("Unknown feature '{}'", name))?;
+        features |= feature;
+    }
+    Ok(features)
+}
+
+fn read_ron<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, String> {
+    let string = fs::read_to_string(path).map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+    ron::de::from_str(&string).map_err(|e| format!("Cannot parse '{}': {}", path, e))
+}
+
+fn load_shader(path: &str) -> Result<wgc::pipeline::ShaderModuleSource<'static>, String> {
+    let read_string =
+        || fs::read_to_string(path).map_err(|e| format!("Cannot read '{}': {}", path, e));
+    let glsl = |stage| -> Result<_, String> {
+        let options = naga::front::glsl::Options {
+            stage,
+            defines: Default::default(),
+        };
+        naga::front::glsl::Parser::default()
+            .parse(&options, &read_string()?)
+            .map_err(|errors| {
+                let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                format!("Cannot parse '{}':\n{}", path, messages.join("\n"))
+            })
+    };
+
+    let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+    let module = match extension {
+        Some("wgsl") => {
+            return Ok(wgc::pipeline::ShaderModuleSource::Wgsl(Cow::Owned(
+                read_string()?,
+            )))
+        }
+        Some("spv") => {
+            let data = fs::read(path).map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+            let options = naga::front::spv::Options {
+                adjust_coordinate_space: false,
+                strict_capabilities: true,
+                block_ctx_dump_prefix: None,
+            };
+            naga::front::spv::parse_u8_slice(&data, &options)
+                .map_err(|e| format!("Cannot parse '{}': {}", path, e))?
+        }
+        Some("vert") => glsl(naga::ShaderStage::Vertex)?,
+        Some("frag") => glsl(naga::ShaderStage::Fragment)?,
+        Some("comp") => glsl(naga::ShaderStage::Compute)?,
+        _ => return Err(format!("Unknown shader type of '{}'", path)),
+    };
+    Ok(wgc::pipeline::ShaderModuleSource::Naga(module))
+}
+
+/// Format an error and its sources the way wgpu reports validation errors.
+fn format_error(global: &Global, error: &(dyn Error + 'static)) -> String {
+    let mut string = String::new();
+    let mut previous = None;
+    let mut source = Some(error);
+    while let Some(error) = source {
+        // Transparent layers display the same message as the error they wrap.
+        let message = error.to_string();
+        if previous.as_ref() != Some(&message) {
+            wgc::error::format_pretty_any(&mut string, global, error);
+        }
+        previous = Some(message);
+        source = error.source();
+    }
+    string
+}
+
+type Global = wgc::hub::Global<wgc::hub::IdentityManagerFactory>;
+
+fn validate(args: &[&str]) -> Result<(), String> {
+    let mut profile = parse_profile("webgpu")?;
+    let mut features = wgt::Features::empty();
+    let mut limits = None;
+    let mut layout = None::<Vec<Vec<wgt::BindGroupLayoutEntry>>>;
+    let mut shaders = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .copied()
+                .ok_or_else(|| format!("Missing value of {}", arg))
+        };
+        match arg {
+            "--profile" => profile = parse_profile(value()?)?,
+            "--features" => features = parse_features(value()?)?,
+            "--limits" => limits = Some(read_ron(value()?)?),
+            "--layout" => layout = Some(read_ron(value()?)?),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => shaders.push(arg),
+        }
+    }
+    if shaders.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    profile.features = features;
+    if let Some(limits) = limits {
+        profile.limits = limits;
+    }
+
+    // The error formatter looks up labels of resources, but there are none here.
+    let global = Global::new(
+        "wgpu-shader",
+        wgc::hub::IdentityManagerFactory,
+        wgt::Backends::empty(),
+    );
+    let mut failures = 0;
+    for &path in shaders.iter() {
+        let desc = wgc::pipeline::ShaderModuleDescriptor {
+            label: Some(Cow::Borrowed(path)),
+            shader_bound_checks: wgt::ShaderBoundChecks::default(),
+            source_hash: None,
+        };
+        let module = match profile.create_shader_module(&desc, load_shader(path)?) {
+            Ok(module) => module,
+            Err(error) => {
+                println!("{}: invalid shader module", path);
+                print!("{}", format_error(&global, &error));
+                failures += 1;
+                continue;
+            }
+        };
+        for entry_point in module.module().entry_points.iter() {
+            let result = module.check_stage(
+                &profile,
+                &entry_point.name,
+                entry_point.stage,
+                layout.as_deref(),
+            );
+            match result {
+                Ok(()) => println!(
+                    "{}: {:?} entry point '{}' is valid",
+                    path, entry_point.stage, entry_point.name
+                ),
+                Err(error) => {
+                    println!(
+                        "{}: {:?} entry point '{}' is invalid",
+                        path, entry_point.stage, entry_point.name
+                    );
+                    print!("{}", format_error(&global, &error));
+                    failures += 1;
+                }
+            }
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        _ => Err(format!("{} validation errors", failures)),
+    }
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let result = match args[..] {
+        ["compile", ref rest @ ..] => compile(rest),
+        ["validate", ref rest @ ..] => validate(rest),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        exit(1);
+    }
+}
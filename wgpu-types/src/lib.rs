@@ -10,7 +10,7 @@
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::{num::NonZeroU32, ops::Range};
+use std::{num::NonZeroU32, ops::Range, time::Duration};
 
 /// Integral type used for buffer offsets.
 pub type BufferAddress = u64;
@@ -898,6 +898,8 @@ impl Limits {
 /// Represents the sets of additional limits on an adapter,
 /// which take place when running on downlevel backends.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
 pub struct DownlevelLimits {}
 
 #[allow(unknown_lints)] // derivable_impls is nightly only currently
@@ -910,6 +912,8 @@ impl Default for DownlevelLimits {
 
 /// Lists various ways the underlying platform does not conform to the WebGPU standard.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
 pub struct DownlevelCapabilities {
     /// Combined boolean flags.
     pub flags: DownlevelFlags,
@@ -1017,6 +1021,8 @@ impl DownlevelFlags {
 /// Collections of shader features a device supports if they support less than WebGPU normally allows.
 // TODO: Fill out the differences between shader models more completely
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
 pub enum ShaderModel {
     /// Extremely limited shaders, including a total instruction limit.
     Sm2,
@@ -1061,6 +1067,41 @@ pub struct AdapterInfo {
     pub device_type: DeviceType,
     /// Backend used for device
     pub backend: Backend,
+    /// Version of the driver, in a format specific to the backend and vendor.
+    ///
+    /// Empty if the backend doesn't report it.
+    #[cfg_attr(feature = "replay", serde(default))]
+    pub driver_info: String,
+}
+
+/// Capabilities an adapter can be restricted to, in order to emulate a less capable one.
+///
+/// The adapter entries of a `wgpu-info` report deserialize as profiles.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
+pub struct AdapterProfile {
+    /// Features exposed by the adapter.
+    pub features: Features,
+    /// Limits exposed by the adapter.
+    pub limits: Limits,
+    /// Downlevel capabilities exposed by the adapter.
+    pub downlevel_caps: DownlevelCapabilities,
+    /// Features of individual texture formats.
+    ///
+    /// Formats that aren't listed keep the features of the adapter.
+    pub texture_format_features: Vec<TextureFormatProfile>,
+}
+
+/// Features of a texture format in an [`AdapterProfile`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
+pub struct TextureFormatProfile {
+    /// The texture format.
+    pub format: TextureFormat,
+    /// Features supported for the format.
+    pub features: TextureFormatFeatures,
 }
 
 /// Describes a [`Device`](../wgpu/struct.Device.html).
@@ -1090,6 +1131,131 @@ impl<L> DeviceDescriptor<L> {
     }
 }
 
+/// Describes an additional [`Queue`](../wgpu/struct.Queue.html) of a device.
+#[repr(C)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
+pub struct QueueDescriptor<L> {
+    /// Debug label for the queue.
+    pub label: L,
+}
+
+impl<L> QueueDescriptor<L> {
+    ///
+    pub fn map_label<K>(&self, fun: impl FnOnce(&L) -> K) -> QueueDescriptor<K> {
+        QueueDescriptor {
+            label: fun(&self.label),
+        }
+    }
+}
+
+/// Passed to `Device::poll` to control how and if it should block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Maintain<T> {
+    /// Block until the given submission and all the ones before it are done.
+    WaitForSubmissionIndex(T),
+    /// Block until the given submission and all the ones before it are done,
+    /// or until the timeout elapses.
+    WaitForSubmissionIndexTimeout(T, Duration),
+    /// Block until all submitted work is done.
+    Wait,
+    /// Don't block.
+    Poll,
+}
+
+impl<T> Maintain<T> {
+    /// Returns true if this blocks until some work is done.
+    pub fn is_wait(&self) -> bool {
+        match *self {
+            Self::WaitForSubmissionIndex(..)
+            | Self::WaitForSubmissionIndexTimeout(..)
+            | Self::Wait => true,
+            Self::Poll => false,
+        }
+    }
+
+    /// Map the submission index, if any.
+    pub fn map_index<U>(self, fun: impl FnOnce(T) -> U) -> Maintain<U> {
+        match self {
+            Self::WaitForSubmissionIndex(index) => Maintain::WaitForSubmissionIndex(fun(index)),
+            Self::WaitForSubmissionIndexTimeout(index, timeout) => {
+                Maintain::WaitForSubmissionIndexTimeout(fun(index), timeout)
+            }
+            Self::Wait => Maintain::Wait,
+            Self::Poll => Maintain::Poll,
+        }
+    }
+}
+
+/// Why a device was lost, passed to the device lost callback.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceLostReason {
+    /// The device was lost for a reason outside of the user's control,
+    /// like a driver crash or a GPU reset.
+    Unknown = 0,
+    /// The device was destroyed with `Device::destroy`.
+    Destroyed = 1,
+}
+
+/// Result of `Device::poll`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaintainResult {
+    /// The work waited for is done, if any.
+    Complete,
+    /// The timeout elapsed before the work waited for was done.
+    TimedOut,
+}
+
+/// Usage and budget of a memory heap, as reported by the backend.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
+pub struct MemoryHeapReport {
+    /// Size of the heap, in bytes.
+    pub size: u64,
+    /// Bytes allocated from the heap by this process.
+    pub usage: u64,
+    /// Bytes this process can allocate from the heap before running into
+    /// memory pressure. Can change over time, and be larger than `size`.
+    pub budget: u64,
+    /// True if the heap is local to the device, rather than in system memory.
+    pub device_local: bool,
+}
+
+/// GPU memory allocated by a device, returned by `Device::memory_report`.
+///
+/// The sizes by category are computed from the descriptors of the live resources,
+/// so they don't include padding and alignment added by the backend.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
+pub struct MemoryReport {
+    /// Bytes in buffers.
+    pub buffers: u64,
+    /// Bytes in textures, including all their mip levels and samples.
+    pub textures: u64,
+    /// Bytes in staging buffers of `Queue::write_*` calls, until the GPU is
+    /// done with them, and of buffers mapped at creation.
+    pub staging: u64,
+    /// Bytes in query sets.
+    pub query_sets: u64,
+    /// Usage and budget of each memory heap of the device.
+    ///
+    /// Only reported on Vulkan, with the `VK_EXT_memory_budget` extension.
+    /// Empty on other backends.
+    pub heaps: Vec<MemoryHeapReport>,
+}
+
+impl MemoryReport {
+    /// Total bytes in all the categories.
+    pub fn total(&self) -> u64 {
+        self.buffers + self.textures + self.staging + self.query_sets
+    }
+}
+
 bitflags::bitflags! {
     /// Describes the shader stages that a binding will be visible from.
     ///
@@ -1538,6 +1704,8 @@ bitflags_serde_shim::impl_serde_for_bitflags!(TextureFormatFeatureFlags);
 ///
 /// Features are defined by WebGPU specification unless `Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES` is enabled.
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "trace", derive(Serialize))]
+#[cfg_attr(feature = "replay", derive(Deserialize))]
 pub struct TextureFormatFeatures {
     /// Valid bits for `TextureDescriptor::Usage` provided for format creation.
     pub allowed_usages: TextureUsages,
@@ -3122,6 +3290,23 @@ impl<L> TextureDescriptor<L> {
             TextureDimension::D3 => 1,
         }
     }
+
+    /// Returns the size in bytes of all the mip levels and samples, as counted
+    /// in [`MemoryReport::textures`].
+    pub fn memory_size(&self) -> u64 {
+        let info = self.format.describe();
+        let (block_width, block_height) = info.block_dimensions;
+        let blocks = (0..self.mip_level_count)
+            .filter_map(|level| self.mip_level_size(level))
+            .map(|size| {
+                let size = size.physical_size(self.format);
+                (size.width / block_width as u32) as u64
+                    * (size.height / block_height as u32) as u64
+                    * size.depth_or_array_layers as u64
+            })
+            .sum::<u64>();
+        blocks * info.block_size as u64 * self.sample_count as u64
+    }
 }
 
 /// Kind of data the texture holds.
@@ -3847,3 +4032,111 @@ impl Default for ShaderBoundChecks {
         Self::new()
     }
 }
+
+/// Severity of a [`CompilationMessage`].
+///
+/// Corresponds to [WebGPU `GPUCompilationMessageType`](
+/// https://gpuweb.github.io/gpuweb/#enumdef-gpucompilationmessagetype).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "trace", derive(serde::Serialize))]
+#[cfg_attr(feature = "replay", derive(serde::Deserialize))]
+pub enum CompilationMessageType {
+    /// The shader module can't be used.
+    Error,
+    /// The shader module is usable, but something in it is likely a mistake.
+    Warning,
+    /// Informational message that doesn't indicate a problem.
+    Info,
+}
+
+/// A message produced while compiling a shader module.
+///
+/// Locations refer to the source the module was created from, and are counted
+/// both in bytes and in UTF-16 code units like in WebGPU. They are all zero if
+/// the message isn't associated with a specific part of the source.
+///
+/// Corresponds to [WebGPU `GPUCompilationMessage`](
+/// https://gpuweb.github.io/gpuweb/#gpucompilationmessage).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "trace", derive(serde::Serialize))]
+#[cfg_attr(feature = "replay", derive(serde::Deserialize))]
+pub struct CompilationMessage {
+    /// Human-readable description of the message.
+    pub message: String,
+    /// Severity of the message.
+    pub message_type: CompilationMessageType,
+    /// Byte offset of the start of the relevant part of the source.
+    pub offset: u64,
+    /// One-based line number of `offset`.
+    pub line_num: u64,
+    /// One-based position of `offset` within its line, in bytes.
+    pub line_pos: u64,
+    /// Length in bytes of the relevant part of the source.
+    pub length: u64,
+    /// `offset`, in UTF-16 code units.
+    pub utf16_offset: u64,
+    /// `line_pos`, in UTF-16 code units.
+    pub utf16_line_pos: u64,
+    /// `length`, in UTF-16 code units.
+    pub utf16_length: u64,
+}
+
+impl CompilationMessage {
+    /// Creates a message about the bytes of `source` in `range`.
+    ///
+    /// The message isn't located if there's no `range`, or if it doesn't fall
+    /// on character boundaries of `source`.
+    pub fn new(
+        message: String,
+        message_type: CompilationMessageType,
+        source: &str,
+        range: Option<std::ops::Range<usize>>,
+    ) -> Self {
+        let located =
+            range.and_then(|range| Some((source.get(..range.start)?, source.get(range)?)));
+        let mut this = Self {
+            message,
+            message_type,
+            offset: 0,
+            line_num: 0,
+            line_pos: 0,
+            length: 0,
+            utf16_offset: 0,
+            utf16_line_pos: 0,
+            utf16_length: 0,
+        };
+        if let Some((before, text)) = located {
+            let line = &before[before.rfind('\n').map_or(0, |index| index + 1)..];
+            this.offset = before.len() as u64;
+            this.line_num = before.matches('\n').count() as u64 + 1;
+            this.line_pos = line.len() as u64 + 1;
+            this.length = text.len() as u64;
+            this.utf16_offset = before.encode_utf16().count() as u64;
+            this.utf16_line_pos = line.encode_utf16().count() as u64 + 1;
+            this.utf16_length = text.encode_utf16().count() as u64;
+        }
+        this
+    }
+}
+
+/// The messages produced while compiling a shader module.
+///
+/// Corresponds to [WebGPU `GPUCompilationInfo`](
+/// https://gpuweb.github.io/gpuweb/#gpucompilationinfo).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "trace", derive(serde::Serialize))]
+#[cfg_attr(feature = "replay", derive(serde::Deserialize))]
+pub struct CompilationInfo {
+    /// All the messages, in the order they were produced.
+    pub messages: Vec<CompilationMessage>,
+}
+
+impl CompilationInfo {
+    /// Returns `true` if any of the messages is an error.
+    pub fn has_errors(&self) -> bool {
+        self.messages
+            .iter()
+            .any(|message| message.message_type == CompilationMessageType::Error)
+    }
+}
@@ -110,6 +110,7 @@ impl framework::Example for Example {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &[],
+                constants: &[],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
@@ -119,6 +120,7 @@ impl framework::Example for Example {
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::default(),
                 }],
+                constants: &[],
             }),
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleStrip,
@@ -128,6 +130,7 @@ impl framework::Example for Example {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
+            cache: None,
         });
 
         let texture = {
@@ -333,6 +336,8 @@ impl framework::Example for Example {
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             rpass.set_pipeline(&self.pipeline);
             rpass.set_bind_group(0, &self.global_group, &[]);
@@ -101,11 +101,13 @@ impl framework::Example for Example {
                     module: &shader_triangle_and_lines,
                     entry_point: "vs_main",
                     buffers: &[],
+                    constants: &[],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader_triangle_and_lines,
                     entry_point: "fs_main_red",
                     targets: &[RENDER_TARGET_FORMAT.into()],
+                    constants: &[],
                 }),
                 primitive: wgpu::PrimitiveState {
                     conservative: true,
@@ -114,6 +116,7 @@ impl framework::Example for Example {
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
+                cache: None,
             });
 
         let pipeline_triangle_regular =
@@ -124,16 +127,19 @@ impl framework::Example for Example {
                     module: &shader_triangle_and_lines,
                     entry_point: "vs_main",
                     buffers: &[],
+                    constants: &[],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader_triangle_and_lines,
                     entry_point: "fs_main_blue",
                     targets: &[RENDER_TARGET_FORMAT.into()],
+                    constants: &[],
                 }),
                 primitive: wgpu::PrimitiveState::default(),
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
+                cache: None,
             });
 
         let pipeline_lines = if device
@@ -148,11 +154,13 @@ impl framework::Example for Example {
                         module: &shader_triangle_and_lines,
                         entry_point: "vs_main",
                         buffers: &[],
+                        constants: &[],
                     },
                     fragment: Some(wgpu::FragmentState {
                         module: &shader_triangle_and_lines,
                         entry_point: "fs_main_white",
                         targets: &[config.format.into()],
+                        constants: &[],
                     }),
                     primitive: wgpu::PrimitiveState {
                         polygon_mode: wgpu::PolygonMode::Line,
@@ -162,6 +170,7 @@ impl framework::Example for Example {
                     depth_stencil: None,
                     multisample: wgpu::MultisampleState::default(),
                     multiview: None,
+                    cache: None,
                 }),
             )
         } else {
@@ -209,16 +218,19 @@ impl framework::Example for Example {
                         module: &shader,
                         entry_point: "vs_main",
                         buffers: &[],
+                        constants: &[],
                     },
                     fragment: Some(wgpu::FragmentState {
                         module: &shader,
                         entry_point: "fs_main",
                         targets: &[config.format.into()],
+                        constants: &[],
                     }),
                     primitive: wgpu::PrimitiveState::default(),
                     depth_stencil: None,
                     multisample: wgpu::MultisampleState::default(),
                     multiview: None,
+                    cache: None,
                 }),
                 bind_group_layout,
             )
@@ -276,6 +288,8 @@ impl framework::Example for Example {
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
 
             rpass.set_pipeline(&self.pipeline_triangle_conservative);
@@ -295,6 +309,8 @@ impl framework::Example for Example {
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
 
             rpass.set_pipeline(&self.pipeline_upscale);
@@ -273,11 +273,13 @@ impl framework::Example for Example {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &vertex_buffers,
+                constants: &[],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
                 targets: &[config.format.into()],
+                constants: &[],
             }),
             primitive: wgpu::PrimitiveState {
                 cull_mode: Some(wgpu::Face::Back),
@@ -286,6 +288,7 @@ impl framework::Example for Example {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
+            cache: None,
         });
 
         let pipeline_wire = if device.features().contains(wgt::Features::POLYGON_MODE_LINE) {
@@ -296,6 +299,7 @@ impl framework::Example for Example {
                     module: &shader,
                     entry_point: "vs_main",
                     buffers: &vertex_buffers,
+                    constants: &[],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
@@ -312,6 +316,7 @@ impl framework::Example for Example {
                         }),
                         write_mask: wgpu::ColorWrites::ALL,
                     }],
+                    constants: &[],
                 }),
                 primitive: wgpu::PrimitiveState {
                     front_face: wgpu::FrontFace::Ccw,
@@ -322,6 +327,7 @@ impl framework::Example for Example {
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
+                cache: None,
             });
             Some(pipeline_wire)
         } else {
@@ -398,6 +404,8 @@ impl framework::Example for Example {
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             rpass.push_debug_group("Prepare data for draw.");
             rpass.set_pipeline(&self.pipeline);
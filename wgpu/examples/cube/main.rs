@@ -287,11 +287,13 @@ impl framework::Example for Example {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &vertex_buffers,
+                constants: &[],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
                 targets: &[config.format.into()],
+                constants: &[],
             }),
             primitive: wgpu::PrimitiveState {
                 cull_mode: Some(wgpu::Face::Back),
@@ -300,6 +302,7 @@ impl framework::Example for Example {
             depth_stencil: None,
             multisample: wgpu::MultisampleState::default(),
             multiview: None,
+            cache: None,
         });
 
         let pipeline_wire = if device.features().contains(wgt::Features::POLYGON_MODE_LINE) {
@@ -310,6 +313,7 @@ impl framework::Example for Example {
                     module: &shader,
                     entry_point: "vs_main",
                     buffers: &vertex_buffers,
+                    constants: &[],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
@@ -326,6 +330,7 @@ impl framework::Example for Example {
                         }),
                         write_mask: wgpu::ColorWrites::ALL,
                     }],
+                    constants: &[],
                 }),
                 primitive: wgpu::PrimitiveState {
                     front_face: wgpu::FrontFace::Ccw,
@@ -336,6 +341,7 @@ impl framework::Example for Example {
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
+                cache: None,
             });
             Some(pipeline_wire)
         } else {
@@ -396,6 +402,8 @@ impl framework::Example for Example {
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             rpass.push_debug_group("Prepare data for draw.");
             rpass.set_pipeline(&self.pipeline);
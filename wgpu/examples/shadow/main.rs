@@ -535,6 +535,7 @@ impl framework::Example for Example {
                     module: &shader,
                     entry_point: "vs_bake",
                     buffers: &[vb_desc.clone()],
+                    constants: &[],
                 },
                 fragment: None,
                 primitive: wgpu::PrimitiveState {
@@ -559,6 +560,7 @@ impl framework::Example for Example {
                 }),
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
+                cache: None,
             });
 
             Pass {
@@ -667,6 +669,7 @@ impl framework::Example for Example {
                     module: &shader,
                     entry_point: "vs_main",
                     buffers: &[vb_desc],
+                    constants: &[],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
@@ -676,6 +679,7 @@ impl framework::Example for Example {
                         "fs_main_without_storage"
                     },
                     targets: &[sc_desc.format.into()],
+                    constants: &[],
                 }),
                 primitive: wgpu::PrimitiveState {
                     front_face: wgpu::FrontFace::Ccw,
@@ -691,6 +695,7 @@ impl framework::Example for Example {
                 }),
                 multisample: wgpu::MultisampleState::default(),
                 multiview: None,
+                cache: None,
             });
 
             Pass {
@@ -811,6 +816,8 @@ impl framework::Example for Example {
                         }),
                         stencil_ops: None,
                     }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
                 });
                 pass.set_pipeline(&self.shadow_pass.pipeline);
                 pass.set_bind_group(0, &self.shadow_pass.bind_group, &[]);
@@ -853,6 +860,8 @@ impl framework::Example for Example {
                     }),
                     stencil_ops: None,
                 }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             pass.set_pipeline(&self.forward_pass.pipeline);
             pass.set_bind_group(0, &self.forward_pass.bind_group, &[]);
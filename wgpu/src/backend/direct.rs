@@ -1,8 +1,9 @@
 use crate::{
     backend::native_gpu_future, AdapterInfo, BindGroupDescriptor, BindGroupLayoutDescriptor,
     BindingResource, BufferBinding, CommandEncoderDescriptor, ComputePassDescriptor,
-    ComputePipelineDescriptor, DownlevelCapabilities, Features, Label, Limits, LoadOp, MapMode,
-    Operations, PipelineLayoutDescriptor, RenderBundleEncoderDescriptor, RenderPipelineDescriptor,
+    ComputePipelineDescriptor, DescriptorTableDescriptor, DeviceLostReason, DownlevelCapabilities,
+    Features, Label, Limits, LoadOp, MapMode, Operations, PipelineCacheDescriptor,
+    PipelineLayoutDescriptor, RenderBundleEncoderDescriptor, RenderPipelineDescriptor,
     SamplerDescriptor, ShaderModuleDescriptor, ShaderModuleDescriptorSpirV, ShaderSource,
     SurfaceStatus, TextureDescriptor, TextureFormat, TextureViewDescriptor,
 };
@@ -11,23 +12,30 @@ use arrayvec::ArrayVec;
 use parking_lot::Mutex;
 use smallvec::SmallVec;
 use std::{
-    borrow::Cow::Borrowed,
+    borrow::Cow::{Borrowed, Owned},
     error::Error,
     fmt,
     future::{ready, Ready},
     marker::PhantomData,
     ops::Range,
     slice,
-    sync::Arc,
+    sync::{mpsc, Arc},
 };
 
 const LABEL: &str = "label";
 
-pub struct Context(wgc::hub::Global<wgc::hub::IdentityManagerFactory>);
+/// Upper bound on the number of threads compiling pipelines in the background.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_PIPELINE_WORKERS: usize = 4;
+
+type Global = wgc::hub::Global<wgc::hub::IdentityManagerFactory>;
+
+pub struct Context(Arc<Global>, PipelineWorkers);
 
 impl Drop for Context {
     fn drop(&mut self) {
-        //nothing
+        // The compilations in flight use the global, which has to be dropped here.
+        self.1.join();
     }
 }
 
@@ -40,14 +48,17 @@ impl fmt::Debug for Context {
 impl Context {
     #[cfg(any(not(target_arch = "wasm32"), feature = "emscripten"))]
     pub unsafe fn from_hal_instance<A: wgc::hub::HalApi>(hal_instance: A::Instance) -> Self {
-        Self(wgc::hub::Global::from_hal_instance::<A>(
-            "wgpu",
-            wgc::hub::IdentityManagerFactory,
-            hal_instance,
-        ))
+        Self(
+            Arc::new(wgc::hub::Global::from_hal_instance::<A>(
+                "wgpu",
+                wgc::hub::IdentityManagerFactory,
+                hal_instance,
+            )),
+            PipelineWorkers::default(),
+        )
     }
 
-    pub(crate) fn global(&self) -> &wgc::hub::Global<wgc::hub::IdentityManagerFactory> {
+    pub(crate) fn global(&self) -> &Global {
         &self.0
     }
 
@@ -74,7 +85,7 @@ impl Context {
         hal_device: hal::OpenDevice<A>,
         desc: &crate::DeviceDescriptor,
         trace_dir: Option<&std::path::Path>,
-    ) -> Result<(Device, wgc::id::QueueId), crate::RequestDeviceError> {
+    ) -> Result<(Device, Queue), crate::RequestDeviceError> {
         let global = &self.0;
         let (device_id, error) = global.create_device_from_hal(
             *adapter,
@@ -91,7 +102,7 @@ impl Context {
             error_sink: Arc::new(Mutex::new(ErrorSinkRaw::new())),
             features: desc.features,
         };
-        Ok((device, device_id))
+        Ok((device, Queue::main(device_id)))
     }
 
     #[cfg(any(not(target_arch = "wasm32"), feature = "emscripten"))]
@@ -188,30 +199,8 @@ impl Context {
         label: Label,
         string: &'static str,
     ) {
-        let error = wgc::error::ContextError {
-            string,
-            cause: Box::new(cause),
-            label: label.unwrap_or_default().to_string(),
-            label_key,
-        };
-        let mut sink = sink_mutex.lock();
-        let mut source_opt: Option<&(dyn Error + 'static)> = Some(&error);
-        while let Some(source) = source_opt {
-            if let Some(wgc::device::DeviceError::OutOfMemory) =
-                source.downcast_ref::<wgc::device::DeviceError>()
-            {
-                return sink.handle_error(crate::Error::OutOfMemory {
-                    source: Box::new(error),
-                });
-            }
-            source_opt = source.source();
-        }
-
-        // Otherwise, it is a validation error
-        sink.handle_error(crate::Error::Validation {
-            description: self.format_error(&error),
-            source: Box::new(error),
-        });
+        let error = make_error(self.global(), cause, label_key, label, string);
+        sink_mutex.lock().handle_error(error);
     }
 
     fn handle_error_nolabel(
@@ -230,24 +219,118 @@ impl Context {
     ) -> ! {
         panic!("Error in {}: {}", string, cause);
     }
+}
 
-    fn format_error(&self, err: &(impl Error + 'static)) -> String {
-        let global = self.global();
-        let mut err_descs = vec![];
+fn make_error(
+    global: &Global,
+    cause: impl Error + Send + Sync + 'static,
+    label_key: &'static str,
+    label: Label,
+    string: &'static str,
+) -> crate::Error {
+    let error = wgc::error::ContextError {
+        string,
+        cause: Box::new(cause),
+        label: label.unwrap_or_default().to_string(),
+        label_key,
+    };
+    let mut source_opt: Option<&(dyn Error + 'static)> = Some(&error);
+    while let Some(source) = source_opt {
+        if let Some(wgc::device::DeviceError::OutOfMemory) =
+            source.downcast_ref::<wgc::device::DeviceError>()
+        {
+            return crate::Error::OutOfMemory {
+                source: Box::new(error),
+            };
+        }
+        source_opt = source.source();
+    }
 
-        let mut err_str = String::new();
-        wgc::error::format_pretty_any(&mut err_str, global, err);
-        err_descs.push(err_str);
+    // Otherwise, it is a validation error
+    crate::Error::Validation {
+        description: format_error(global, &error),
+        source: Box::new(error),
+    }
+}
 
-        let mut source_opt = err.source();
-        while let Some(source) = source_opt {
-            let mut source_str = String::new();
-            wgc::error::format_pretty_any(&mut source_str, global, source);
-            err_descs.push(source_str);
-            source_opt = source.source();
-        }
+fn format_error(global: &Global, err: &(impl Error + 'static)) -> String {
+    let mut err_descs = vec![];
 
-        format!("Validation Error\n\nCaused by:\n{}", err_descs.join(""))
+    let mut err_str = String::new();
+    wgc::error::format_pretty_any(&mut err_str, global, err);
+    err_descs.push(err_str);
+
+    let mut source_opt = err.source();
+    while let Some(source) = source_opt {
+        let mut source_str = String::new();
+        wgc::error::format_pretty_any(&mut source_str, global, source);
+        err_descs.push(source_str);
+        source_opt = source.source();
+    }
+
+    format!("Validation Error\n\nCaused by:\n{}", err_descs.join(""))
+}
+
+type PipelineJob = Box<dyn FnOnce() + Send>;
+
+/// Threads compiling pipelines requested through `Device::create_*_pipeline_async`.
+///
+/// Pipelines are validated on the calling thread, and only compiled by the workers.
+/// The threads are spawned on the first request, and joined when the context is dropped.
+#[derive(Default)]
+struct PipelineWorkers {
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    sender: Mutex<Option<mpsc::Sender<PipelineJob>>>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    threads: Mutex<Vec<std::thread::JoinHandle<()>>>,
+}
+
+impl PipelineWorkers {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn(&self, job: PipelineJob) {
+        let mut sender = self.sender.lock();
+        let sender = sender.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<PipelineJob>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            let count = std::thread::available_parallelism()
+                .map_or(1, |count| count.get())
+                .min(MAX_PIPELINE_WORKERS);
+            let mut threads = self.threads.lock();
+            for index in 0..count {
+                let receiver = Arc::clone(&receiver);
+                let thread = std::thread::Builder::new()
+                    .name(format!("wgpu pipeline worker {}", index))
+                    .spawn(move || loop {
+                        let job = receiver.lock().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("Unable to spawn a pipeline worker thread");
+                threads.push(thread);
+            }
+            sender
+        });
+        // The workers only stop once the sender is dropped, so this can't fail.
+        sender.send(job).unwrap();
+    }
+
+    /// There are no threads to offload to, so the pipeline is compiled right away.
+    #[cfg(target_arch = "wasm32")]
+    fn spawn(&self, job: PipelineJob) {
+        job()
+    }
+
+    /// Wait for the queued compilations to finish, and stop the threads.
+    fn join(&self) {
+        // The workers exit once the queue is empty and the sender is gone.
+        drop(self.sender.lock().take());
+        for thread in self.threads.lock().drain(..) {
+            if thread.join().is_err() {
+                log::error!("A pipeline worker thread panicked");
+            }
+        }
     }
 }
 
@@ -528,6 +611,14 @@ mod pass_impl {
             wgpu_render_pass_end_pipeline_statistics_query(self)
         }
 
+        fn begin_occlusion_query(&mut self, query_index: u32) {
+            wgpu_render_pass_begin_occlusion_query(self, query_index)
+        }
+
+        fn end_occlusion_query(&mut self) {
+            wgpu_render_pass_end_occlusion_query(self)
+        }
+
         fn execute_bundles<'a, I: Iterator<Item = &'a wgc::id::RenderBundleId>>(
             &mut self,
             render_bundles: I,
@@ -663,6 +754,26 @@ mod pass_impl {
             unimplemented!()
         }
     }
+
+    impl crate::RenderBundleEncoderInner<Context> for wgc::command::RenderBundleEncoder {
+        fn set_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+            wgpu_render_bundle_set_scissor_rect(self, x, y, width, height)
+        }
+        fn set_viewport(
+            &mut self,
+            x: f32,
+            y: f32,
+            width: f32,
+            height: f32,
+            min_depth: f32,
+            max_depth: f32,
+        ) {
+            wgpu_render_bundle_set_viewport(self, x, y, width, height, min_depth, max_depth)
+        }
+        fn set_stencil_reference(&mut self, reference: u32) {
+            wgpu_render_bundle_set_stencil_reference(self, reference)
+        }
+    }
 }
 
 fn map_buffer_copy_view(view: crate::ImageCopyBuffer) -> wgc::command::ImageCopyBuffer {
@@ -720,6 +831,23 @@ fn map_pass_channel<V: Copy + Default>(
     }
 }
 
+fn map_pass_timestamp_writes(
+    timestamp_writes: &crate::PassTimestampWrites,
+) -> wgc::command::PassTimestampWrites {
+    wgc::command::PassTimestampWrites {
+        query_set: timestamp_writes.query_set.id,
+        beginning_of_pass_write_index: timestamp_writes.beginning_of_pass_write_index,
+        end_of_pass_write_index: timestamp_writes.end_of_pass_write_index,
+    }
+}
+
+fn map_pipeline_constants(constants: &[(&str, f64)]) -> wgc::pipeline::PipelineConstants {
+    constants
+        .iter()
+        .map(|&(key, value)| (key.to_string(), value))
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct Surface {
     id: wgc::id::SurfaceId,
@@ -735,6 +863,29 @@ pub struct Device {
     features: Features,
 }
 
+#[derive(Debug)]
+pub struct Queue {
+    id: wgc::id::QueueId,
+    /// Index of the queue within its device, `0` being the main queue.
+    index: u32,
+}
+
+#[derive(Debug)]
+pub struct Timeline {
+    /// The device the timeline was created from, which selects its backend.
+    device_id: wgc::id::DeviceId,
+    raw: Arc<wgc::device::timeline::Timeline>,
+}
+
+impl Queue {
+    fn main(device_id: wgc::id::DeviceId) -> Self {
+        Self {
+            id: device_id,
+            index: 0,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Buffer {
     id: wgc::id::BufferId,
@@ -747,6 +898,12 @@ pub struct Texture {
     error_sink: ErrorSink,
 }
 
+#[derive(Debug)]
+pub struct DescriptorTable {
+    id: wgc::id::BindGroupId,
+    error_sink: ErrorSink,
+}
+
 #[derive(Debug)]
 pub(crate) struct CommandEncoder {
     id: wgc::id::CommandEncoderId,
@@ -757,7 +914,7 @@ pub(crate) struct CommandEncoder {
 impl crate::Context for Context {
     type AdapterId = wgc::id::AdapterId;
     type DeviceId = Device;
-    type QueueId = wgc::id::QueueId;
+    type QueueId = Queue;
     type ShaderModuleId = wgc::id::ShaderModuleId;
     type BindGroupLayoutId = wgc::id::BindGroupLayoutId;
     type BindGroupId = wgc::id::BindGroupId;
@@ -769,6 +926,7 @@ impl crate::Context for Context {
     type PipelineLayoutId = wgc::id::PipelineLayoutId;
     type RenderPipelineId = wgc::id::RenderPipelineId;
     type ComputePipelineId = wgc::id::ComputePipelineId;
+    type PipelineCacheId = wgc::id::PipelineCacheId;
     type CommandEncoderId = CommandEncoder;
     type ComputePassId = wgc::command::ComputePass;
     type RenderPassId = wgc::command::RenderPass;
@@ -776,6 +934,9 @@ impl crate::Context for Context {
     type RenderBundleEncoderId = wgc::command::RenderBundleEncoder;
     type RenderBundleId = wgc::id::RenderBundleId;
     type SurfaceId = Surface;
+    type TimelineId = Timeline;
+    type DescriptorTableId = DescriptorTable;
+    type SubmissionIndex = wgc::device::queue::WrappedSubmissionIndex;
 
     type SurfaceOutputDetail = SurfaceOutputDetail;
 
@@ -786,13 +947,21 @@ impl crate::Context for Context {
     type MapAsyncFuture = native_gpu_future::GpuFuture<Result<(), crate::BufferAsyncError>>;
     type OnSubmittedWorkDoneFuture = native_gpu_future::GpuFuture<()>;
     type PopErrorScopeFuture = Ready<Option<crate::Error>>;
+    type CreateRenderPipelineFuture =
+        native_gpu_future::GpuFuture<Result<Self::RenderPipelineId, crate::Error>>;
+    type CreateComputePipelineFuture =
+        native_gpu_future::GpuFuture<Result<Self::ComputePipelineId, crate::Error>>;
+    type CompilationInfoFuture = Ready<wgt::CompilationInfo>;
 
     fn init(backends: wgt::Backends) -> Self {
-        Self(wgc::hub::Global::new(
-            "wgpu",
-            wgc::hub::IdentityManagerFactory,
-            backends,
-        ))
+        Self(
+            Arc::new(wgc::hub::Global::new(
+                "wgpu",
+                wgc::hub::IdentityManagerFactory,
+                backends,
+            )),
+            PipelineWorkers::default(),
+        )
     }
 
     fn instance_create_surface(
@@ -850,7 +1019,45 @@ impl crate::Context for Context {
             error_sink: Arc::new(Mutex::new(ErrorSinkRaw::new())),
             features: desc.features,
         };
-        ready(Ok((device, device_id)))
+        ready(Ok((device, Queue::main(device_id))))
+    }
+
+    fn adapter_request_device_with_queues(
+        &self,
+        adapter: &Self::AdapterId,
+        desc: &crate::DeviceDescriptor,
+        queue_descs: &[crate::QueueDescriptor],
+        trace_dir: Option<&std::path::Path>,
+    ) -> Result<(Self::DeviceId, Self::QueueId, Vec<Self::QueueId>), crate::RequestDeviceError>
+    {
+        let global = &self.0;
+        let queue_descs = queue_descs
+            .iter()
+            .map(|desc| desc.map_label(|l| l.map(Borrowed)))
+            .collect::<Vec<_>>();
+        let (device_id, error) = wgc::gfx_select!(*adapter => global.adapter_request_device_with_queues(
+            *adapter,
+            &desc.map_label(|l| l.map(Borrowed)),
+            &queue_descs,
+            trace_dir,
+            PhantomData
+        ));
+        if let Some(err) = error {
+            log::error!("Error in Adapter::request_device_with_queues: {}", err);
+            return Err(crate::RequestDeviceError);
+        }
+        let device = Device {
+            id: device_id,
+            error_sink: Arc::new(Mutex::new(ErrorSinkRaw::new())),
+            features: desc.features,
+        };
+        let extra_queues = (1..=queue_descs.len() as u32)
+            .map(|index| Queue {
+                id: device_id,
+                index,
+            })
+            .collect();
+        Ok((device, Queue::main(device_id), extra_queues))
     }
 
     fn adapter_is_surface_supported(
@@ -911,6 +1118,19 @@ impl crate::Context for Context {
         }
     }
 
+    fn adapter_emulate_profile(
+        &self,
+        adapter: &Self::AdapterId,
+        profile: &wgt::AdapterProfile,
+    ) -> Self::AdapterId {
+        let global = &self.0;
+        match wgc::gfx_select!(*adapter => global.adapter_emulate_profile(*adapter, profile, PhantomData))
+        {
+            Ok(id) => id,
+            Err(err) => self.handle_error_fatal(err, "Adapter::emulate_profile"),
+        }
+    }
+
     fn surface_get_preferred_format(
         &self,
         surface: &Self::SurfaceId,
@@ -925,6 +1145,22 @@ impl crate::Context for Context {
         }
     }
 
+    fn surface_get_supported_formats(
+        &self,
+        surface: &Self::SurfaceId,
+        adapter: &Self::AdapterId,
+    ) -> Vec<TextureFormat> {
+        let global = &self.0;
+        match wgc::gfx_select!(adapter => global.surface_get_supported_formats(surface.id, *adapter))
+        {
+            Ok(formats) => formats,
+            Err(wgc::instance::GetSurfacePreferredFormatError::UnsupportedQueueFamily) => {
+                Vec::new()
+            }
+            Err(err) => self.handle_error_fatal(err, "Surface::get_supported_formats"),
+        }
+    }
+
     fn surface_configure(
         &self,
         surface: &Self::SurfaceId,
@@ -1022,13 +1258,14 @@ impl crate::Context for Context {
         shader_bound_checks: wgt::ShaderBoundChecks,
     ) -> Self::ShaderModuleId {
         let global = &self.0;
-        let descriptor = wgc::pipeline::ShaderModuleDescriptor {
-            label: desc.label.map(Borrowed),
-            shader_bound_checks,
-        };
-        let source = match desc.source {
+        // Modules parsed here are identified in pipeline caches by the hash of their code.
+        let (source, source_hash) = match desc.source {
             #[cfg(feature = "spirv")]
             ShaderSource::SpirV(ref spv) => {
+                let bytes = spv
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .collect::<Vec<_>>();
                 // Parse the given shader code and store its representation.
                 let options = naga::front::spv::Options {
                     adjust_coordinate_space: false, // we require NDC_Y_UP feature
@@ -1037,7 +1274,10 @@ impl crate::Context for Context {
                 };
                 let parser = naga::front::spv::Parser::new(spv.iter().cloned(), &options);
                 let module = parser.parse().unwrap();
-                wgc::pipeline::ShaderModuleSource::Naga(module)
+                (
+                    wgc::pipeline::ShaderModuleSource::Naga(module),
+                    Some(wgc::pipeline::hash_shader_source(&bytes)),
+                )
             }
             #[cfg(feature = "glsl")]
             ShaderSource::Glsl {
@@ -1045,6 +1285,14 @@ impl crate::Context for Context {
                 stage,
                 ref defines,
             } => {
+                let mut sorted_defines = defines.iter().collect::<Vec<_>>();
+                sorted_defines.sort();
+                let mut code = format!("{:?}\n", stage);
+                for (name, value) in sorted_defines {
+                    code.push_str(&format!("#define {} {}\n", name, value));
+                }
+                code.push_str(shader);
+
                 // Parse the given shader code and store its representation.
                 let options = naga::front::glsl::Options {
                     stage,
@@ -1052,10 +1300,25 @@ impl crate::Context for Context {
                 };
                 let mut parser = naga::front::glsl::Parser::default();
                 let module = parser.parse(&options, shader).unwrap();
-
-                wgc::pipeline::ShaderModuleSource::Naga(module)
+                (
+                    wgc::pipeline::ShaderModuleSource::Naga(module),
+                    Some(wgc::pipeline::hash_shader_source(code.as_bytes())),
+                )
             }
-            ShaderSource::Wgsl(ref code) => wgc::pipeline::ShaderModuleSource::Wgsl(Borrowed(code)),
+            ShaderSource::Wgsl(ref code) => (
+                wgc::pipeline::ShaderModuleSource::Wgsl(Borrowed(code)),
+                None,
+            ),
+            #[cfg(feature = "serialized-ir")]
+            ShaderSource::SerializedIr(ref data) => (
+                wgc::pipeline::ShaderModuleSource::SerializedIr(Borrowed(data)),
+                None,
+            ),
+        };
+        let descriptor = wgc::pipeline::ShaderModuleDescriptor {
+            label: desc.label.map(Borrowed),
+            shader_bound_checks,
+            source_hash,
         };
         let (id, error) = wgc::gfx_select!(
             device.id => global.device_create_shader_module(device.id, &descriptor, source, PhantomData)
@@ -1083,6 +1346,7 @@ impl crate::Context for Context {
             // Doesn't matter the value since spirv shaders aren't mutated to include
             // runtime checks
             shader_bound_checks: wgt::ShaderBoundChecks::unchecked(),
+            source_hash: None,
         };
         let (id, error) = wgc::gfx_select!(
             device.id => global.device_create_shader_module_spirv(device.id, &descriptor, Borrowed(&desc.source), PhantomData)
@@ -1225,6 +1489,38 @@ impl crate::Context for Context {
         id
     }
 
+    fn device_create_descriptor_table(
+        &self,
+        device: &Self::DeviceId,
+        desc: &DescriptorTableDescriptor,
+    ) -> (Self::BindGroupId, Self::DescriptorTableId) {
+        let descriptor = wgc::binding_model::DescriptorTableDescriptor {
+            label: desc.label.map(Borrowed),
+            layout: desc.layout.id,
+        };
+
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_descriptor_table(
+            device.id,
+            &descriptor,
+            PhantomData
+        ));
+        if let Some(cause) = error {
+            self.handle_error(
+                &device.error_sink,
+                cause,
+                LABEL,
+                desc.label,
+                "Device::create_descriptor_table",
+            );
+        }
+        let table = DescriptorTable {
+            id,
+            error_sink: Arc::clone(&device.error_sink),
+        };
+        (id, table)
+    }
+
     fn device_create_pipeline_layout(
         &self,
         device: &Self::DeviceId,
@@ -1300,6 +1596,7 @@ impl crate::Context for Context {
                 stage: pipe::ProgrammableStageDescriptor {
                     module: desc.vertex.module.id,
                     entry_point: Borrowed(desc.vertex.entry_point),
+                    constants: Owned(map_pipeline_constants(desc.vertex.constants)),
                 },
                 buffers: Borrowed(&vertex_buffers),
             },
@@ -1310,10 +1607,12 @@ impl crate::Context for Context {
                 stage: pipe::ProgrammableStageDescriptor {
                     module: frag.module.id,
                     entry_point: Borrowed(frag.entry_point),
+                    constants: Owned(map_pipeline_constants(frag.constants)),
                 },
                 targets: Borrowed(frag.targets),
             }),
             multiview: desc.multiview,
+            cache: desc.cache.map(|c| c.id),
         };
 
         let global = &self.0;
@@ -1359,7 +1658,9 @@ impl crate::Context for Context {
             stage: pipe::ProgrammableStageDescriptor {
                 module: desc.module.id,
                 entry_point: Borrowed(desc.entry_point),
+                constants: Owned(map_pipeline_constants(desc.constants)),
             },
+            cache: desc.cache.map(|c| c.id),
         };
 
         let global = &self.0;
@@ -1389,6 +1690,174 @@ impl crate::Context for Context {
         id
     }
 
+    fn device_create_render_pipeline_async(
+        &self,
+        device: &Self::DeviceId,
+        desc: &RenderPipelineDescriptor,
+    ) -> Self::CreateRenderPipelineFuture {
+        use wgc::pipeline as pipe;
+
+        let vertex_buffers: ArrayVec<_, { wgc::MAX_VERTEX_BUFFERS }> = desc
+            .vertex
+            .buffers
+            .iter()
+            .map(|vbuf| pipe::VertexBufferLayout {
+                array_stride: vbuf.array_stride,
+                step_mode: vbuf.step_mode,
+                attributes: Borrowed(vbuf.attributes),
+            })
+            .collect();
+
+        let implicit_pipeline_ids = match desc.layout {
+            Some(_) => None,
+            None => Some(wgc::device::ImplicitPipelineIds {
+                root_id: PhantomData,
+                group_ids: &[PhantomData; wgc::MAX_BIND_GROUPS],
+            }),
+        };
+        let descriptor = pipe::RenderPipelineDescriptor {
+            label: desc.label.map(Borrowed),
+            layout: desc.layout.map(|l| l.id),
+            vertex: pipe::VertexState {
+                stage: pipe::ProgrammableStageDescriptor {
+                    module: desc.vertex.module.id,
+                    entry_point: Borrowed(desc.vertex.entry_point),
+                    constants: Owned(map_pipeline_constants(desc.vertex.constants)),
+                },
+                buffers: Borrowed(&vertex_buffers),
+            },
+            primitive: desc.primitive,
+            depth_stencil: desc.depth_stencil.clone(),
+            multisample: desc.multisample,
+            fragment: desc.fragment.as_ref().map(|frag| pipe::FragmentState {
+                stage: pipe::ProgrammableStageDescriptor {
+                    module: frag.module.id,
+                    entry_point: Borrowed(frag.entry_point),
+                    constants: Owned(map_pipeline_constants(frag.constants)),
+                },
+                targets: Borrowed(frag.targets),
+            }),
+            multiview: desc.multiview,
+            cache: desc.cache.map(|c| c.id),
+        };
+
+        // Validation happens right away, only the backend compilation is deferred.
+        let global = &self.0;
+        let (id, pending) = wgc::gfx_select!(device.id => global.device_create_render_pipeline_deferred(
+            device.id,
+            &descriptor,
+            PhantomData,
+            implicit_pipeline_ids
+        ));
+
+        let (future, completion) = native_gpu_future::new_gpu_future();
+        let label = desc.label.map(str::to_string);
+        let finish = move |global: &Global, error: Option<pipe::CreateRenderPipelineError>| {
+            let result = match error {
+                None => Ok(id),
+                Some(cause) => {
+                    if let pipe::CreateRenderPipelineError::Internal { stage, ref error } = cause {
+                        log::warn!("Shader translation error for stage {:?}: {}", stage, error);
+                        log::warn!("Please report it to https://github.com/gfx-rs/naga");
+                    }
+                    wgc::gfx_select!(id => global.render_pipeline_drop(id));
+                    Err(make_error(
+                        global,
+                        cause,
+                        LABEL,
+                        label.as_deref(),
+                        "Device::create_render_pipeline_async",
+                    ))
+                }
+            };
+            completion.complete(result);
+        };
+        match pending {
+            Ok(pending) => {
+                let global = Arc::clone(&self.0);
+                self.1.spawn(Box::new(move || {
+                    let error = pending.compile(&global);
+                    finish(&global, error);
+                }));
+            }
+            Err(cause) => finish(global, Some(cause)),
+        }
+        future
+    }
+
+    fn device_create_compute_pipeline_async(
+        &self,
+        device: &Self::DeviceId,
+        desc: &ComputePipelineDescriptor,
+    ) -> Self::CreateComputePipelineFuture {
+        use wgc::pipeline as pipe;
+
+        let implicit_pipeline_ids = match desc.layout {
+            Some(_) => None,
+            None => Some(wgc::device::ImplicitPipelineIds {
+                root_id: PhantomData,
+                group_ids: &[PhantomData; wgc::MAX_BIND_GROUPS],
+            }),
+        };
+        let descriptor = pipe::ComputePipelineDescriptor {
+            label: desc.label.map(Borrowed),
+            layout: desc.layout.map(|l| l.id),
+            stage: pipe::ProgrammableStageDescriptor {
+                module: desc.module.id,
+                entry_point: Borrowed(desc.entry_point),
+                constants: Owned(map_pipeline_constants(desc.constants)),
+            },
+            cache: desc.cache.map(|c| c.id),
+        };
+
+        // Validation happens right away, only the backend compilation is deferred.
+        let global = &self.0;
+        let (id, pending) = wgc::gfx_select!(device.id => global.device_create_compute_pipeline_deferred(
+            device.id,
+            &descriptor,
+            PhantomData,
+            implicit_pipeline_ids
+        ));
+
+        let (future, completion) = native_gpu_future::new_gpu_future();
+        let label = desc.label.map(str::to_string);
+        let finish = move |global: &Global, error: Option<pipe::CreateComputePipelineError>| {
+            let result = match error {
+                None => Ok(id),
+                Some(cause) => {
+                    if let pipe::CreateComputePipelineError::Internal(ref error) = cause {
+                        log::warn!(
+                            "Shader translation error for stage {:?}: {}",
+                            wgt::ShaderStages::COMPUTE,
+                            error
+                        );
+                        log::warn!("Please report it to https://github.com/gfx-rs/naga");
+                    }
+                    wgc::gfx_select!(id => global.compute_pipeline_drop(id));
+                    Err(make_error(
+                        global,
+                        cause,
+                        LABEL,
+                        label.as_deref(),
+                        "Device::create_compute_pipeline_async",
+                    ))
+                }
+            };
+            completion.complete(result);
+        };
+        match pending {
+            Ok(pending) => {
+                let global = Arc::clone(&self.0);
+                self.1.spawn(Box::new(move || {
+                    let error = pending.compile(&global);
+                    finish(&global, error);
+                }));
+            }
+            Err(cause) => finish(global, Some(cause)),
+        }
+        future
+    }
+
     fn device_create_buffer(
         &self,
         device: &Self::DeviceId,
@@ -1498,6 +1967,50 @@ impl crate::Context for Context {
         id
     }
 
+    fn device_create_timeline(
+        &self,
+        device: &Self::DeviceId,
+        initial_value: u64,
+    ) -> Self::TimelineId {
+        let global = &self.0;
+        match wgc::gfx_select!(device.id => global.device_create_timeline(device.id, initial_value))
+        {
+            Ok(raw) => Timeline {
+                device_id: device.id,
+                raw: Arc::new(raw),
+            },
+            Err(cause) => self.handle_error_fatal(cause, "Device::create_timeline"),
+        }
+    }
+
+    fn device_create_pipeline_cache(
+        &self,
+        device: &Self::DeviceId,
+        desc: &PipelineCacheDescriptor,
+    ) -> Self::PipelineCacheId {
+        let descriptor = pipe::PipelineCacheDescriptor {
+            label: desc.label.map(Borrowed),
+            data: desc.data.map(Borrowed),
+            fallback: desc.fallback,
+        };
+        let global = &self.0;
+        let (id, error) = wgc::gfx_select!(device.id => global.device_create_pipeline_cache(
+            device.id,
+            &descriptor,
+            PhantomData
+        ));
+        if let Some(cause) = error {
+            self.handle_error(
+                &device.error_sink,
+                cause,
+                LABEL,
+                desc.label,
+                "Device::create_pipeline_cache",
+            );
+        }
+        id
+    }
+
     fn device_create_command_encoder(
         &self,
         device: &Self::DeviceId,
@@ -1549,8 +2062,9 @@ impl crate::Context for Context {
 
         #[cfg(any(not(target_arch = "wasm32"), feature = "emscripten"))]
         {
-            match wgc::gfx_select!(device.id => global.device_poll(device.id, true)) {
-                Ok(()) => (),
+            match wgc::gfx_select!(device.id => global.device_poll(device.id, wgt::Maintain::Wait))
+            {
+                Ok(_) => (),
                 Err(err) => self.handle_error_fatal(err, "Device::drop"),
             }
         }
@@ -1558,16 +2072,25 @@ impl crate::Context for Context {
         wgc::gfx_select!(device.id => global.device_drop(device.id));
     }
 
-    fn device_poll(&self, device: &Self::DeviceId, maintain: crate::Maintain) {
+    fn device_destroy(&self, device: &Self::DeviceId) {
+        let global = &self.0;
+        match wgc::gfx_select!(device.id => global.device_destroy(device.id)) {
+            Ok(()) => (),
+            Err(err) => self.handle_error_fatal(err, "Device::destroy"),
+        }
+    }
+
+    fn device_poll(
+        &self,
+        device: &Self::DeviceId,
+        maintain: crate::Maintain,
+    ) -> wgt::MaintainResult {
         let global = &self.0;
         match wgc::gfx_select!(device.id => global.device_poll(
             device.id,
-            match maintain {
-                crate::Maintain::Poll => false,
-                crate::Maintain::Wait => true,
-            }
+            maintain.map_index(|index| index.0)
         )) {
-            Ok(()) => (),
+            Ok(result) => result,
             Err(err) => self.handle_error_fatal(err, "Device::poll"),
         }
     }
@@ -1581,6 +2104,42 @@ impl crate::Context for Context {
         error_sink.uncaptured_handler = Box::new(handler);
     }
 
+    fn device_set_device_lost_callback(
+        &self,
+        device: &Self::DeviceId,
+        callback: Box<dyn FnOnce(DeviceLostReason, String) + Send + 'static>,
+    ) {
+        let global = &self.0;
+        let closure = wgc::device::DeviceLostClosure::from_rust(callback);
+        if let Err(err) = wgc::gfx_select!(
+            device.id => global.device_set_device_lost_closure(device.id, closure)
+        ) {
+            self.handle_error_fatal(err, "Device::set_device_lost_callback");
+        }
+    }
+
+    fn device_memory_report(&self, device: &Self::DeviceId) -> wgt::MemoryReport {
+        let global = &self.0;
+        match wgc::gfx_select!(device.id => global.device_memory_report(device.id)) {
+            Ok(report) => report,
+            Err(err) => self.handle_error_fatal(err, "Device::memory_report"),
+        }
+    }
+
+    fn device_set_memory_threshold_callback(
+        &self,
+        device: &Self::DeviceId,
+        threshold: u64,
+        callback: Box<dyn FnMut(wgt::MemoryReport) + Send + 'static>,
+    ) {
+        let global = &self.0;
+        if let Err(err) = wgc::gfx_select!(
+            device.id => global.device_set_memory_threshold_callback(device.id, threshold, callback)
+        ) {
+            self.handle_error_fatal(err, "Device::set_memory_threshold_callback");
+        }
+    }
+
     fn device_push_error_scope(&self, device: &Self::DeviceId, filter: crate::ErrorFilter) {
         let mut error_sink = device.error_sink.lock();
         error_sink.scopes.push(ErrorScope {
@@ -1601,27 +2160,21 @@ impl crate::Context for Context {
         mode: MapMode,
         range: Range<wgt::BufferAddress>,
     ) -> Self::MapAsyncFuture {
-        let (future, completion) = native_gpu_future::new_gpu_future();
-
-        extern "C" fn buffer_map_future_wrapper(
-            status: wgc::resource::BufferMapAsyncStatus,
-            user_data: *mut u8,
-        ) {
-            let completion =
-                unsafe { native_gpu_future::GpuFutureCompletion::from_raw(user_data as _) };
-            completion.complete(match status {
-                wgc::resource::BufferMapAsyncStatus::Success => Ok(()),
-                _ => Err(crate::BufferAsyncError),
-            })
-        }
+        // The callback is dropped without being called if the request is rejected.
+        let (future, completion) =
+            native_gpu_future::new_gpu_future_or(Err(crate::BufferAsyncError));
 
         let operation = wgc::resource::BufferMapOperation {
             host: match mode {
                 MapMode::Read => wgc::device::HostMap::Read,
                 MapMode::Write => wgc::device::HostMap::Write,
             },
-            callback: buffer_map_future_wrapper,
-            user_data: completion.into_raw() as _,
+            callback: wgc::resource::BufferMapCallback::from_rust(Box::new(move |status| {
+                completion.complete(match status {
+                    wgc::resource::BufferMapAsyncStatus::Success => Ok(()),
+                    _ => Err(crate::BufferAsyncError),
+                })
+            })),
         };
 
         let global = &self.0;
@@ -1662,6 +2215,52 @@ impl crate::Context for Context {
         }
     }
 
+    fn buffer_unmap_range(&self, buffer: &Self::BufferId, range: Range<wgt::BufferAddress>) {
+        let global = &self.0;
+        match wgc::gfx_select!(buffer.id => global.buffer_unmap_range(buffer.id, range)) {
+            Ok(()) => (),
+            Err(cause) => {
+                self.handle_error_nolabel(&buffer.error_sink, cause, "BufferSlice::unmap")
+            }
+        }
+    }
+
+    fn descriptor_table_update_slots(
+        &self,
+        table: &Self::DescriptorTableId,
+        first_slot: u32,
+        views: &[&crate::TextureView],
+    ) {
+        let views = views.iter().map(|view| view.id).collect::<Vec<_>>();
+        let global = &self.0;
+        match wgc::gfx_select!(
+            table.id => global.descriptor_table_update_slots(table.id, first_slot, &views)
+        ) {
+            Ok(()) => (),
+            Err(cause) => {
+                self.handle_error_nolabel(&table.error_sink, cause, "DescriptorTable::update_slots")
+            }
+        }
+    }
+
+    fn descriptor_table_clear_slots(&self, table: &Self::DescriptorTableId, slots: Range<u32>) {
+        let global = &self.0;
+        match wgc::gfx_select!(table.id => global.descriptor_table_clear_slots(table.id, slots)) {
+            Ok(()) => (),
+            Err(cause) => {
+                self.handle_error_nolabel(&table.error_sink, cause, "DescriptorTable::clear_slots")
+            }
+        }
+    }
+
+    fn shader_get_compilation_info(
+        &self,
+        shader: &Self::ShaderModuleId,
+    ) -> Self::CompilationInfoFuture {
+        let global = &self.0;
+        ready(wgc::gfx_select!(*shader => global.shader_module_get_compilation_info(*shader)))
+    }
+
     fn texture_create_view(
         &self,
         texture: &Self::TextureId,
@@ -1780,6 +2379,18 @@ impl crate::Context for Context {
         let global = &self.0;
         wgc::gfx_select!(*pipeline => global.render_pipeline_drop(*pipeline))
     }
+    fn pipeline_cache_drop(&self, cache: &Self::PipelineCacheId) {
+        let global = &self.0;
+        wgc::gfx_select!(*cache => global.pipeline_cache_drop(*cache))
+    }
+    fn pipeline_cache_get_data(&self, cache: &Self::PipelineCacheId) -> Option<Vec<u8>> {
+        let global = &self.0;
+        wgc::gfx_select!(*cache => global.pipeline_cache_get_data(*cache))
+    }
+    fn pipeline_cache_hit_count(&self, cache: &Self::PipelineCacheId) -> u64 {
+        let global = &self.0;
+        wgc::gfx_select!(*cache => global.pipeline_cache_hit_count(*cache))
+    }
 
     fn compute_pipeline_get_bind_group_layout(
         &self,
@@ -1949,10 +2560,15 @@ impl crate::Context for Context {
         encoder: &Self::CommandEncoderId,
         desc: &ComputePassDescriptor,
     ) -> Self::ComputePassId {
+        let timestamp_writes = desc
+            .timestamp_writes
+            .as_ref()
+            .map(map_pass_timestamp_writes);
         wgc::command::ComputePass::new(
             encoder.id,
             &wgc::command::ComputePassDescriptor {
                 label: desc.label.map(Borrowed),
+                timestamp_writes: timestamp_writes.as_ref(),
             },
         )
     }
@@ -2000,12 +2616,19 @@ impl crate::Context for Context {
             }
         });
 
+        let timestamp_writes = desc
+            .timestamp_writes
+            .as_ref()
+            .map(map_pass_timestamp_writes);
+
         wgc::command::RenderPass::new(
             encoder.id,
             &wgc::command::RenderPassDescriptor {
                 label: desc.label.map(Borrowed),
                 color_attachments: Borrowed(&colors),
                 depth_stencil_attachment: depth_stencil.as_ref(),
+                timestamp_writes: timestamp_writes.as_ref(),
+                occlusion_query_set: desc.occlusion_query_set.map(|query_set| query_set.id),
             },
         )
     }
@@ -2136,9 +2759,12 @@ impl crate::Context for Context {
     ) {
         let global = &self.0;
         match wgc::gfx_select!(
-            *queue => global.queue_write_buffer(*queue, buffer.id, offset, data)
+            queue.id => global.queue_write_buffer(queue.id, buffer.id, offset, data)
         ) {
             Ok(()) => (),
+            // Writing to the queue of a lost device does nothing, the loss is
+            // reported through the device lost callback.
+            Err(wgc::device::queue::QueueWriteError::Queue(wgc::device::DeviceError::Lost)) => (),
             Err(err) => self.handle_error_fatal(err, "Queue::write_buffer"),
         }
     }
@@ -2152,14 +2778,15 @@ impl crate::Context for Context {
         size: wgt::Extent3d,
     ) {
         let global = &self.0;
-        match wgc::gfx_select!(*queue => global.queue_write_texture(
-            *queue,
+        match wgc::gfx_select!(queue.id => global.queue_write_texture(
+            queue.id,
             &map_texture_copy_view(texture),
             data,
             &data_layout,
             &size
         )) {
             Ok(()) => (),
+            Err(wgc::device::queue::QueueWriteError::Queue(wgc::device::DeviceError::Lost)) => (),
             Err(err) => self.handle_error_fatal(err, "Queue::write_texture"),
         }
     }
@@ -2168,20 +2795,32 @@ impl crate::Context for Context {
         &self,
         queue: &Self::QueueId,
         command_buffers: I,
-    ) {
+    ) -> Self::SubmissionIndex {
         let temp_command_buffers = command_buffers.collect::<SmallVec<[_; 4]>>();
 
         let global = &self.0;
-        match wgc::gfx_select!(*queue => global.queue_submit(*queue, &temp_command_buffers)) {
-            Ok(()) => (),
+        match wgc::gfx_select!(queue.id => global.queue_submit_indexed(
+            queue.id,
+            queue.index,
+            &temp_command_buffers
+        )) {
+            Ok(index) => index,
+            // Nothing gets submitted to a lost device, and there is nothing
+            // to wait for on it either.
+            Err(wgc::device::queue::QueueSubmitError::Queue(wgc::device::DeviceError::Lost)) => {
+                wgc::device::queue::WrappedSubmissionIndex {
+                    queue_id: queue.id,
+                    index: 0,
+                }
+            }
             Err(err) => self.handle_error_fatal(err, "Queue::submit"),
         }
     }
 
     fn queue_get_timestamp_period(&self, queue: &Self::QueueId) -> f32 {
         let global = &self.0;
-        let res = wgc::gfx_select!(queue => global.queue_get_timestamp_period(
-            *queue
+        let res = wgc::gfx_select!(queue.id => global.queue_get_timestamp_period(
+            queue.id
         ));
         match res {
             Ok(v) => v,
@@ -2191,6 +2830,15 @@ impl crate::Context for Context {
         }
     }
 
+    fn queue_is_independent(&self, queue: &Self::QueueId) -> bool {
+        let global = &self.0;
+        let res = wgc::gfx_select!(queue.id => global.queue_is_independent(queue.id, queue.index));
+        match res {
+            Ok(independent) => independent,
+            Err(cause) => self.handle_error_fatal(cause, "Queue::is_independent"),
+        }
+    }
+
     fn queue_on_submitted_work_done(
         &self,
         queue: &Self::QueueId,
@@ -2209,7 +2857,11 @@ impl crate::Context for Context {
         };
 
         let global = &self.0;
-        let res = wgc::gfx_select!(queue => global.queue_on_submitted_work_done(*queue, closure));
+        let res = wgc::gfx_select!(queue.id => global.queue_on_submitted_work_done_indexed(
+            queue.id,
+            queue.index,
+            closure
+        ));
         if let Err(cause) = res {
             self.handle_error_fatal(cause, "Queue::on_submitted_work_done");
         }
@@ -2221,6 +2873,60 @@ impl crate::Context for Context {
         wgc::gfx_select!(device.id => global.device_start_capture(device.id));
     }
 
+    fn queue_signal_timeline(
+        &self,
+        queue: &Self::QueueId,
+        timeline: &Self::TimelineId,
+        value: u64,
+    ) {
+        let global = &self.0;
+        if let Err(cause) = wgc::gfx_select!(queue.id => global.queue_signal_timeline(
+            queue.id,
+            queue.index,
+            &timeline.raw,
+            value
+        )) {
+            self.handle_error_fatal(cause, "Queue::signal_timeline");
+        }
+    }
+
+    fn queue_wait_timeline(&self, queue: &Self::QueueId, timeline: &Self::TimelineId, value: u64) {
+        let global = &self.0;
+        if let Err(cause) = wgc::gfx_select!(queue.id => global.queue_wait_timeline(
+            queue.id,
+            queue.index,
+            Arc::clone(&timeline.raw),
+            value
+        )) {
+            self.handle_error_fatal(cause, "Queue::wait_timeline");
+        }
+    }
+
+    fn timeline_get_value(&self, timeline: &Self::TimelineId) -> u64 {
+        let global = &self.0;
+        match wgc::gfx_select!(timeline.device_id => global.timeline_value(&timeline.raw)) {
+            Ok(value) => value,
+            Err(cause) => self.handle_error_fatal(cause, "Timeline::value"),
+        }
+    }
+
+    fn timeline_wait(
+        &self,
+        timeline: &Self::TimelineId,
+        value: u64,
+        timeout: Option<std::time::Duration>,
+    ) -> bool {
+        let global = &self.0;
+        match wgc::gfx_select!(timeline.device_id => global.timeline_wait(
+            &timeline.raw,
+            value,
+            timeout
+        )) {
+            Ok(reached) => reached,
+            Err(cause) => self.handle_error_fatal(cause, "Timeline::wait"),
+        }
+    }
+
     fn device_stop_capture(&self, device: &Self::DeviceId) {
         let global = &self.0;
         wgc::gfx_select!(device.id => global.device_stop_capture(device.id));
@@ -22,6 +22,8 @@ pub enum OpaqueData {}
 /// A completion handle to set the result on a GpuFuture
 pub struct GpuFutureCompletion<T> {
     data: Arc<GpuFutureData<T>>,
+    /// Result the future gets if the handle is dropped without completing it.
+    abandoned: Option<T>,
 }
 
 impl<T> Future for GpuFuture<T> {
@@ -41,7 +43,12 @@ impl<T> Future for GpuFuture<T> {
 }
 
 impl<T> GpuFutureCompletion<T> {
-    pub fn complete(self, value: T) {
+    pub fn complete(mut self, value: T) {
+        self.abandoned = None;
+        self.set(value);
+    }
+
+    fn set(&self, value: T) {
         let mut waker_or_result = self.data.lock();
 
         match waker_or_result.replace(WakerOrResult::Result(value)) {
@@ -55,13 +62,24 @@ impl<T> GpuFutureCompletion<T> {
         };
     }
 
-    pub(crate) fn into_raw(self) -> *mut OpaqueData {
-        Arc::into_raw(self.data) as _
+    /// The raw handle doesn't complete the future if it's never turned back.
+    pub(crate) fn into_raw(mut self) -> *mut OpaqueData {
+        self.abandoned = None;
+        Arc::into_raw(Arc::clone(&self.data)) as _
     }
 
     pub(crate) unsafe fn from_raw(this: *mut OpaqueData) -> Self {
         Self {
             data: Arc::from_raw(this as _),
+            abandoned: None,
+        }
+    }
+}
+
+impl<T> Drop for GpuFutureCompletion<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.abandoned.take() {
+            self.set(value);
         }
     }
 }
@@ -72,6 +90,17 @@ pub(crate) fn new_gpu_future<T>() -> (GpuFuture<T>, GpuFutureCompletion<T>) {
         GpuFuture {
             data: Arc::clone(&data),
         },
-        GpuFutureCompletion { data },
+        GpuFutureCompletion {
+            data,
+            abandoned: None,
+        },
     )
 }
+
+/// Like [`new_gpu_future`], but the future resolves to `abandoned` if the
+/// completion handle is dropped without being completed, instead of never.
+pub(crate) fn new_gpu_future_or<T>(abandoned: T) -> (GpuFuture<T>, GpuFutureCompletion<T>) {
+    let (future, mut completion) = new_gpu_future();
+    completion.abandoned = Some(abandoned);
+    (future, completion)
+}
@@ -1,10 +1,12 @@
 #![allow(clippy::type_complexity)]
 
 use std::{
+    cell::{Cell, RefCell},
     fmt,
     future::Future,
-    ops::Range,
+    ops::{Deref, Range},
     pin::Pin,
+    rc::Rc,
     task::{self, Poll},
 };
 use wasm_bindgen::{prelude::*, JsCast};
@@ -22,6 +24,179 @@ pub(crate) struct Sendable<T>(T);
 unsafe impl<T> Send for Sendable<T> {}
 unsafe impl<T> Sync for Sendable<T> {}
 
+/// A shader module, with the WGSL source the browser compiled.
+#[derive(Debug)]
+pub(crate) struct ShaderModule {
+    module: web_sys::GpuShaderModule,
+    /// Turns the UTF-16 locations of compilation messages into byte offsets.
+    source: String,
+}
+
+type DeviceLostCallback = Box<dyn FnOnce(crate::DeviceLostReason, String) + Send + 'static>;
+
+#[derive(Default)]
+struct DeviceLostState {
+    callback: Option<DeviceLostCallback>,
+    /// The reason and message of the loss, once the device is lost.
+    lost: Option<(crate::DeviceLostReason, String)>,
+}
+
+/// Memory used by a resource, counted in a running total of its device
+/// for as long as this is alive.
+struct MemoryUse {
+    total: Rc<Cell<u64>>,
+    size: u64,
+}
+
+impl MemoryUse {
+    fn new(total: &Rc<Cell<u64>>, size: u64) -> Self {
+        total.set(total.get() + size);
+        Self {
+            total: Rc::clone(total),
+            size,
+        }
+    }
+}
+
+impl Drop for MemoryUse {
+    fn drop(&mut self) {
+        self.total.set(self.total.get() - self.size);
+    }
+}
+
+/// Running totals of the memory of the buffers and textures of a device,
+/// computed from their descriptors since the browser doesn't expose it.
+#[derive(Default)]
+struct MemoryTotals {
+    buffers: Rc<Cell<u64>>,
+    textures: Rc<Cell<u64>>,
+}
+
+struct MemoryThreshold {
+    threshold: u64,
+    callback: Box<dyn FnMut(crate::MemoryReport) + Send + 'static>,
+    /// Whether the memory used was above the threshold when last checked.
+    above: bool,
+}
+
+/// A buffer or texture, with the memory it's counted for.
+pub(crate) struct Tracked<T> {
+    raw: T,
+    /// Released once destroyed.
+    memory: RefCell<Option<MemoryUse>>,
+}
+
+impl<T> Tracked<T> {
+    fn new(raw: T, memory: Option<MemoryUse>) -> Self {
+        Self {
+            raw,
+            memory: RefCell::new(memory),
+        }
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.raw.fmt(f)
+    }
+}
+
+type Buffer = Tracked<web_sys::GpuBuffer>;
+type Texture = Tracked<web_sys::GpuTexture>;
+
+/// A device, with the state kept alongside the browser's.
+pub(crate) struct Device {
+    raw: web_sys::GpuDevice,
+    /// Shared with the single handler of `raw.lost`, so that setting a
+    /// callback replaces the previous one.
+    lost: Rc<RefCell<DeviceLostState>>,
+    memory_totals: MemoryTotals,
+    memory_threshold: RefCell<Option<MemoryThreshold>>,
+}
+
+impl Device {
+    fn new(raw: web_sys::GpuDevice) -> Self {
+        let lost = Rc::new(RefCell::new(DeviceLostState::default()));
+        let lost_clone = Rc::clone(&lost);
+        let closure = Closure::once(move |info: JsValue| {
+            let info = info.unchecked_into::<web_sys::GpuDeviceLostInfo>();
+            let reason = js_sys::Reflect::get(&info, &JsValue::from_str("reason"))
+                .ok()
+                .and_then(|reason| reason.as_string());
+            let reason = match reason.as_deref() {
+                Some("destroyed") => crate::DeviceLostReason::Destroyed,
+                _ => crate::DeviceLostReason::Unknown,
+            };
+            let mut state = lost_clone.borrow_mut();
+            state.lost = Some((reason, info.message()));
+            if let Some(callback) = state.callback.take() {
+                drop(state);
+                callback(reason, info.message());
+            }
+        });
+        let _ = raw.lost().then(&closure);
+        // TODO: This will leak the memory associated with the handler if the device is never lost.
+        closure.forget();
+        Self {
+            raw,
+            lost,
+            memory_totals: MemoryTotals::default(),
+            memory_threshold: RefCell::new(None),
+        }
+    }
+
+    fn memory_report(&self) -> crate::MemoryReport {
+        crate::MemoryReport {
+            buffers: self.memory_totals.buffers.get(),
+            textures: self.memory_totals.textures.get(),
+            ..Default::default()
+        }
+    }
+
+    /// Invoke the memory threshold callback if the memory went above the
+    /// threshold since last checked.
+    fn check_memory_threshold(&self) {
+        // Taken out while invoked, since the callback may create resources.
+        let mut memory_threshold = match self.memory_threshold.borrow_mut().take() {
+            Some(memory_threshold) => memory_threshold,
+            None => return,
+        };
+        let report = self.memory_report();
+        let above = report.total() >= memory_threshold.threshold;
+        if above && !memory_threshold.above {
+            (memory_threshold.callback)(report);
+        }
+        memory_threshold.above = above;
+        let mut slot = self.memory_threshold.borrow_mut();
+        // Unless the callback was replaced meanwhile.
+        if slot.is_none() {
+            *slot = Some(memory_threshold);
+        }
+    }
+}
+
+impl Deref for Device {
+    type Target = web_sys::GpuDevice;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+impl fmt::Debug for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Device").field("raw", &self.raw).finish()
+    }
+}
+
 pub(crate) struct Context(web_sys::Gpu);
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
@@ -46,6 +221,18 @@ impl crate::Error {
             panic!("Unexpected error");
         }
     }
+
+    /// Pipeline creation promises reject with an exception rather than a `GPUError`.
+    fn from_js_rejection(js_error: JsValue) -> Self {
+        let description = js_sys::Reflect::get(&js_error, &JsValue::from_str("message"))
+            .ok()
+            .and_then(|message| message.as_string())
+            .unwrap_or_else(|| format!("{:?}", js_error));
+        crate::Error::Validation {
+            source: Box::<dyn std::error::Error + Send + Sync>::from("<WebGPU Error>"),
+            description,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -133,7 +320,7 @@ impl crate::ComputePassInner<Context> for ComputePass {
     }
     fn dispatch_indirect(
         &mut self,
-        indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        indirect_buffer: &Sendable<Buffer>,
         indirect_offset: wgt::BufferAddress,
     ) {
         self.0
@@ -174,7 +361,7 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn set_index_buffer(
         &mut self,
-        buffer: &Sendable<web_sys::GpuBuffer>,
+        buffer: &Sendable<Buffer>,
         index_format: wgt::IndexFormat,
         offset: wgt::BufferAddress,
         size: Option<wgt::BufferSize>,
@@ -200,7 +387,7 @@ impl crate::RenderInner<Context> for RenderPass {
     fn set_vertex_buffer(
         &mut self,
         slot: u32,
-        buffer: &Sendable<web_sys::GpuBuffer>,
+        buffer: &Sendable<Buffer>,
         offset: wgt::BufferAddress,
         size: Option<wgt::BufferSize>,
     ) {
@@ -243,7 +430,7 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn draw_indirect(
         &mut self,
-        indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        indirect_buffer: &Sendable<Buffer>,
         indirect_offset: wgt::BufferAddress,
     ) {
         self.0
@@ -251,7 +438,7 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn draw_indexed_indirect(
         &mut self,
-        indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        indirect_buffer: &Sendable<Buffer>,
         indirect_offset: wgt::BufferAddress,
     ) {
         self.0
@@ -259,7 +446,7 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn multi_draw_indirect(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
         _count: u32,
     ) {
@@ -267,7 +454,7 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn multi_draw_indexed_indirect(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
         _count: u32,
     ) {
@@ -275,9 +462,9 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn multi_draw_indirect_count(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
-        _count_buffer: &Sendable<web_sys::GpuBuffer>,
+        _count_buffer: &Sendable<Buffer>,
         _count_buffer_offset: wgt::BufferAddress,
         _max_count: u32,
     ) {
@@ -287,9 +474,9 @@ impl crate::RenderInner<Context> for RenderPass {
     }
     fn multi_draw_indexed_indirect_count(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
-        _count_buffer: &Sendable<web_sys::GpuBuffer>,
+        _count_buffer: &Sendable<Buffer>,
         _count_buffer_offset: wgt::BufferAddress,
         _max_count: u32,
     ) {
@@ -318,7 +505,7 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn set_index_buffer(
         &mut self,
-        buffer: &Sendable<web_sys::GpuBuffer>,
+        buffer: &Sendable<Buffer>,
         index_format: wgt::IndexFormat,
         offset: wgt::BufferAddress,
         size: Option<wgt::BufferSize>,
@@ -344,7 +531,7 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     fn set_vertex_buffer(
         &mut self,
         slot: u32,
-        buffer: &Sendable<web_sys::GpuBuffer>,
+        buffer: &Sendable<Buffer>,
         offset: wgt::BufferAddress,
         size: Option<wgt::BufferSize>,
     ) {
@@ -387,7 +574,7 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn draw_indirect(
         &mut self,
-        indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        indirect_buffer: &Sendable<Buffer>,
         indirect_offset: wgt::BufferAddress,
     ) {
         self.0
@@ -395,7 +582,7 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn draw_indexed_indirect(
         &mut self,
-        indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        indirect_buffer: &Sendable<Buffer>,
         indirect_offset: wgt::BufferAddress,
     ) {
         self.0
@@ -403,7 +590,7 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn multi_draw_indirect(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
         _count: u32,
     ) {
@@ -411,7 +598,7 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn multi_draw_indexed_indirect(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
         _count: u32,
     ) {
@@ -419,9 +606,9 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn multi_draw_indirect_count(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
-        _count_buffer: &Sendable<web_sys::GpuBuffer>,
+        _count_buffer: &Sendable<Buffer>,
         _count_buffer_offset: wgt::BufferAddress,
         _max_count: u32,
     ) {
@@ -431,9 +618,9 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
     fn multi_draw_indexed_indirect_count(
         &mut self,
-        _indirect_buffer: &Sendable<web_sys::GpuBuffer>,
+        _indirect_buffer: &Sendable<Buffer>,
         _indirect_offset: wgt::BufferAddress,
-        _count_buffer: &Sendable<web_sys::GpuBuffer>,
+        _count_buffer: &Sendable<Buffer>,
         _count_buffer_offset: wgt::BufferAddress,
         _max_count: u32,
     ) {
@@ -441,6 +628,28 @@ impl crate::RenderInner<Context> for RenderBundleEncoder {
     }
 }
 
+impl crate::RenderBundleEncoderInner<Context> for RenderBundleEncoder {
+    fn set_scissor_rect(&mut self, _x: u32, _y: u32, _width: u32, _height: u32) {
+        panic!("Render bundles can't use `set_scissor_rect` on the web")
+    }
+
+    fn set_viewport(
+        &mut self,
+        _x: f32,
+        _y: f32,
+        _width: f32,
+        _height: f32,
+        _min_depth: f32,
+        _max_depth: f32,
+    ) {
+        panic!("Render bundles can't use `set_viewport` on the web")
+    }
+
+    fn set_stencil_reference(&mut self, _reference: u32) {
+        panic!("Render bundles can't use `set_stencil_reference` on the web")
+    }
+}
+
 impl crate::RenderPassInner<Context> for RenderPass {
     fn set_blend_constant(&mut self, color: wgt::Color) {
         self.0
@@ -501,6 +710,14 @@ impl crate::RenderPassInner<Context> for RenderPass {
     fn end_pipeline_statistics_query(&mut self) {
         // Not available in gecko yet
     }
+
+    fn begin_occlusion_query(&mut self, _query_index: u32) {
+        // Not available in gecko yet
+    }
+
+    fn end_occlusion_query(&mut self) {
+        // Not available in gecko yet
+    }
 }
 
 fn map_texture_format(texture_format: wgt::TextureFormat) -> web_sys::GpuTextureFormat {
@@ -810,6 +1027,37 @@ fn map_vertex_step_mode(mode: wgt::VertexStepMode) -> web_sys::GpuVertexStepMode
     }
 }
 
+/// The WebGPU stage dictionaries in `web_sys` don't expose `constants` yet,
+/// so the record is set directly on the JS object.
+fn map_pipeline_constants(stage: &js_sys::Object, constants: &[(&str, f64)]) {
+    if constants.is_empty() {
+        return;
+    }
+    let record = js_sys::Object::new();
+    for &(key, value) in constants.iter() {
+        let js_key = JsValue::from_str(key);
+        // A record can't hold the same key twice, which native validation rejects.
+        if js_sys::Reflect::has(&record, &js_key).unwrap() {
+            panic!("Pipeline constant `{}` is given more than once", key);
+        }
+        js_sys::Reflect::set(&record, &js_key, &JsValue::from_f64(value)).unwrap();
+    }
+    js_sys::Reflect::set(stage, &JsValue::from_str("constants"), &record).unwrap();
+}
+
+/// The browser only accepts WGSL, so serialized IR is written back out as text.
+/// The data can't be trusted, so the module is validated before the writer sees it.
+#[cfg(feature = "serialized-ir")]
+fn serialized_ir_to_wgsl(data: &[u8]) -> Result<String, String> {
+    let validated = wgc::serialized_ir::decode(data).map_err(|e| e.to_string())?;
+    naga::back::wgsl::write_string(
+        &validated.module,
+        &validated.info,
+        naga::back::wgsl::WriterFlags::empty(),
+    )
+    .map_err(|e| e.to_string())
+}
+
 fn map_extent_3d(extent: wgt::Extent3d) -> web_sys::GpuExtent3dDict {
     let mut mapped = web_sys::GpuExtent3dDict::new(extent.width);
     mapped.height(extent.height);
@@ -909,6 +1157,104 @@ fn map_map_mode(mode: crate::MapMode) -> u32 {
     }
 }
 
+fn map_render_pipeline_descriptor(
+    desc: &crate::RenderPipelineDescriptor,
+) -> web_sys::GpuRenderPipelineDescriptor {
+    let mut mapped_vertex_state =
+        web_sys::GpuVertexState::new(desc.vertex.entry_point, &desc.vertex.module.id.0.module);
+    map_pipeline_constants(&mapped_vertex_state, desc.vertex.constants);
+
+    let buffers = desc
+        .vertex
+        .buffers
+        .iter()
+        .map(|vbuf| {
+            let mapped_attributes = vbuf
+                .attributes
+                .iter()
+                .map(|attr| {
+                    web_sys::GpuVertexAttribute::new(
+                        map_vertex_format(attr.format),
+                        attr.offset as f64,
+                        attr.shader_location,
+                    )
+                })
+                .collect::<js_sys::Array>();
+
+            let mut mapped_vbuf =
+                web_sys::GpuVertexBufferLayout::new(vbuf.array_stride as f64, &mapped_attributes);
+            mapped_vbuf.step_mode(map_vertex_step_mode(vbuf.step_mode));
+            mapped_vbuf
+        })
+        .collect::<js_sys::Array>();
+
+    mapped_vertex_state.buffers(&buffers);
+
+    let mut mapped_desc = web_sys::GpuRenderPipelineDescriptor::new(&mapped_vertex_state);
+
+    if let Some(label) = desc.label {
+        mapped_desc.label(label);
+    }
+
+    if let Some(layout) = desc.layout {
+        mapped_desc.layout(&layout.id.0);
+    }
+
+    if let Some(ref depth_stencil) = desc.depth_stencil {
+        mapped_desc.depth_stencil(&map_depth_stencil_state(depth_stencil));
+    }
+
+    if let Some(ref frag) = desc.fragment {
+        let targets = frag
+            .targets
+            .iter()
+            .map(|target| {
+                let mapped_format = map_texture_format(target.format);
+                let mut mapped_color_state = web_sys::GpuColorTargetState::new(mapped_format);
+                if let Some(ref bs) = target.blend {
+                    let alpha = map_blend_component(&bs.alpha);
+                    let color = map_blend_component(&bs.color);
+                    let mapped_blend_state = web_sys::GpuBlendState::new(&alpha, &color);
+                    mapped_color_state.blend(&mapped_blend_state);
+                }
+                mapped_color_state.write_mask(target.write_mask.bits());
+                mapped_color_state
+            })
+            .collect::<js_sys::Array>();
+        let mapped_fragment_desc =
+            web_sys::GpuFragmentState::new(frag.entry_point, &frag.module.id.0.module, &targets);
+        map_pipeline_constants(&mapped_fragment_desc, frag.constants);
+        mapped_desc.fragment(&mapped_fragment_desc);
+    }
+
+    let mut mapped_multisample = web_sys::GpuMultisampleState::new();
+    mapped_multisample.count(desc.multisample.count);
+    mapped_multisample.mask(desc.multisample.mask as u32);
+    mapped_multisample.alpha_to_coverage_enabled(desc.multisample.alpha_to_coverage_enabled);
+    mapped_desc.multisample(&mapped_multisample);
+
+    let mapped_primitive = map_primitive_state(&desc.primitive);
+    mapped_desc.primitive(&mapped_primitive);
+
+    mapped_desc
+}
+
+fn map_compute_pipeline_descriptor(
+    desc: &crate::ComputePipelineDescriptor,
+) -> web_sys::GpuComputePipelineDescriptor {
+    let mapped_compute_stage =
+        web_sys::GpuProgrammableStage::new(desc.entry_point, &desc.module.id.0.module);
+    map_pipeline_constants(&mapped_compute_stage, desc.constants);
+    let mut mapped_desc = web_sys::GpuComputePipelineDescriptor::new(&mapped_compute_stage);
+    if let Some(layout) = desc.layout {
+        mapped_desc.layout(&layout.id.0);
+    }
+    if let Some(label) = desc.label {
+        mapped_desc.label(label);
+    }
+    mapped_desc
+}
+
 type JsFutureResult = Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue>;
 
 fn future_request_adapter(result: JsFutureResult) -> Option<Sendable<web_sys::GpuAdapter>> {
@@ -920,13 +1266,12 @@ fn future_request_adapter(result: JsFutureResult) -> Option<Sendable<web_sys::Gp
 
 fn future_request_device(
     result: JsFutureResult,
-) -> Result<(Sendable<web_sys::GpuDevice>, Sendable<web_sys::GpuQueue>), crate::RequestDeviceError>
-{
+) -> Result<(Sendable<Device>, Sendable<web_sys::GpuQueue>), crate::RequestDeviceError> {
     result
         .map(|js_value| {
             let device_id = web_sys::GpuDevice::from(js_value);
             let queue_id = device_id.queue();
-            (Sendable(device_id), Sendable(queue_id))
+            (Sendable(Device::new(device_id)), Sendable(queue_id))
         })
         .map_err(|_| crate::RequestDeviceError)
 }
@@ -935,6 +1280,69 @@ fn future_map_async(result: JsFutureResult) -> Result<(), crate::BufferAsyncErro
     result.map(|_| ()).map_err(|_| crate::BufferAsyncError)
 }
 
+fn future_create_render_pipeline(
+    result: JsFutureResult,
+) -> Result<Sendable<web_sys::GpuRenderPipeline>, crate::Error> {
+    result
+        .map(|js_value| Sendable(js_value.unchecked_into()))
+        .map_err(crate::Error::from_js_rejection)
+}
+
+fn future_create_compute_pipeline(
+    result: JsFutureResult,
+) -> Result<Sendable<web_sys::GpuComputePipeline>, crate::Error> {
+    result
+        .map(|js_value| Sendable(js_value.unchecked_into()))
+        .map_err(crate::Error::from_js_rejection)
+}
+
+/// Byte offset of the given number of UTF-16 code units into `source`.
+fn utf16_to_byte_offset(source: &str, utf16_offset: usize) -> Option<usize> {
+    let mut units = 0;
+    let end = std::iter::once((source.len(), '\0'));
+    for (index, c) in source.char_indices().chain(end) {
+        if units == utf16_offset {
+            return Some(index);
+        }
+        units += c.len_utf16();
+    }
+    None
+}
+
+fn future_compilation_info(result: JsFutureResult, source: &str) -> wgt::CompilationInfo {
+    let info = match result {
+        Ok(js_value) => web_sys::GpuCompilationInfo::from(js_value),
+        Err(_) => return wgt::CompilationInfo::default(),
+    };
+    let messages = info
+        .messages()
+        .iter()
+        .map(|js_value| {
+            let message = web_sys::GpuCompilationMessage::from(js_value);
+            // Messages not associated with a part of the source are on line 0.
+            let range = Some(message.offset() as usize)
+                .filter(|_| message.line_num() > 0.0)
+                .and_then(|offset| {
+                    let end = offset + message.length() as usize;
+                    Some(utf16_to_byte_offset(source, offset)?..utf16_to_byte_offset(source, end)?)
+                });
+            wgt::CompilationMessage::new(
+                message.message(),
+                match message.type_() {
+                    web_sys::GpuCompilationMessageType::Error => wgt::CompilationMessageType::Error,
+                    web_sys::GpuCompilationMessageType::Warning => {
+                        wgt::CompilationMessageType::Warning
+                    }
+                    _ => wgt::CompilationMessageType::Info,
+                },
+                source,
+                range,
+            )
+        })
+        .collect();
+    wgt::CompilationInfo { messages }
+}
+
 fn future_pop_error_scope(result: JsFutureResult) -> Option<crate::Error> {
     match result {
         Ok(js_value) if js_value.is_object() => {
@@ -971,19 +1379,21 @@ impl Context {
 
 impl crate::Context for Context {
     type AdapterId = Sendable<web_sys::GpuAdapter>;
-    type DeviceId = Sendable<web_sys::GpuDevice>;
+    type DeviceId = Sendable<Device>;
     type QueueId = Sendable<web_sys::GpuQueue>;
-    type ShaderModuleId = Sendable<web_sys::GpuShaderModule>;
+    type ShaderModuleId = Sendable<ShaderModule>;
     type BindGroupLayoutId = Sendable<web_sys::GpuBindGroupLayout>;
     type BindGroupId = Sendable<web_sys::GpuBindGroup>;
     type TextureViewId = Sendable<web_sys::GpuTextureView>;
     type SamplerId = Sendable<web_sys::GpuSampler>;
-    type BufferId = Sendable<web_sys::GpuBuffer>;
-    type TextureId = Sendable<web_sys::GpuTexture>;
+    type BufferId = Sendable<Buffer>;
+    type TextureId = Sendable<Texture>;
     type QuerySetId = (); //TODO!
     type PipelineLayoutId = Sendable<web_sys::GpuPipelineLayout>;
     type RenderPipelineId = Sendable<web_sys::GpuRenderPipeline>;
     type ComputePipelineId = Sendable<web_sys::GpuComputePipeline>;
+    // WebGPU has no pipeline caches, the browser caches pipelines on its own.
+    type PipelineCacheId = ();
     type CommandEncoderId = web_sys::GpuCommandEncoder;
     type ComputePassId = ComputePass;
     type RenderPassId = RenderPass;
@@ -991,6 +1401,9 @@ impl crate::Context for Context {
     type RenderBundleEncoderId = RenderBundleEncoder;
     type RenderBundleId = Sendable<web_sys::GpuRenderBundle>;
     type SurfaceId = Sendable<web_sys::GpuCanvasContext>;
+    type TimelineId = ();
+    type DescriptorTableId = ();
+    type SubmissionIndex = ();
 
     type SurfaceOutputDetail = SurfaceOutputDetail;
 
@@ -1010,6 +1423,18 @@ impl crate::Context for Context {
         MakeSendFuture<wasm_bindgen_futures::JsFuture, fn(JsFutureResult) -> ()>;
     type PopErrorScopeFuture =
         MakeSendFuture<wasm_bindgen_futures::JsFuture, fn(JsFutureResult) -> Option<crate::Error>>;
+    type CreateRenderPipelineFuture = MakeSendFuture<
+        wasm_bindgen_futures::JsFuture,
+        fn(JsFutureResult) -> Result<Self::RenderPipelineId, crate::Error>,
+    >;
+    type CreateComputePipelineFuture = MakeSendFuture<
+        wasm_bindgen_futures::JsFuture,
+        fn(JsFutureResult) -> Result<Self::ComputePipelineId, crate::Error>,
+    >;
+    type CompilationInfoFuture = MakeSendFuture<
+        wasm_bindgen_futures::JsFuture,
+        Box<dyn Fn(JsFutureResult) -> wgt::CompilationInfo>,
+    >;
 
     fn init(_backends: wgt::Backends) -> Self {
         Context(web_sys::window().unwrap().navigator().gpu())
@@ -1066,6 +1491,18 @@ impl crate::Context for Context {
         )
     }
 
+    fn adapter_request_device_with_queues(
+        &self,
+        _adapter: &Self::AdapterId,
+        _desc: &crate::DeviceDescriptor,
+        _queue_descs: &[crate::QueueDescriptor],
+        _trace_dir: Option<&std::path::Path>,
+    ) -> Result<(Self::DeviceId, Self::QueueId, Vec<Self::QueueId>), crate::RequestDeviceError>
+    {
+        // Devices are only created asynchronously on the web.
+        Err(crate::RequestDeviceError)
+    }
+
     fn instance_poll_all_devices(&self, _force_wait: bool) {
         // Devices are automatically polled.
     }
@@ -1173,6 +1610,7 @@ impl crate::Context for Context {
             device: 0,
             device_type: wgt::DeviceType::Other,
             backend: wgt::Backend::BrowserWebGpu,
+            driver_info: String::new(),
         }
     }
 
@@ -1184,6 +1622,14 @@ impl crate::Context for Context {
         format.describe().guaranteed_format_features
     }
 
+    fn adapter_emulate_profile(
+        &self,
+        _adapter: &Self::AdapterId,
+        _profile: &wgt::AdapterProfile,
+    ) -> Self::AdapterId {
+        panic!("Adapter profiles can't be emulated on the web")
+    }
+
     fn surface_get_preferred_format(
         &self,
         surface: &Self::SurfaceId,
@@ -1193,6 +1639,19 @@ impl crate::Context for Context {
         Some(format)
     }
 
+    fn surface_get_supported_formats(
+        &self,
+        _surface: &Self::SurfaceId,
+        _adapter: &Self::AdapterId,
+    ) -> Vec<wgt::TextureFormat> {
+        // Formats every canvas context accepts.
+        vec![
+            wgt::TextureFormat::Bgra8Unorm,
+            wgt::TextureFormat::Rgba8Unorm,
+            wgt::TextureFormat::Rgba16Float,
+        ]
+    }
+
     fn surface_configure(
         &self,
         surface: &Self::SurfaceId,
@@ -1214,7 +1673,11 @@ impl crate::Context for Context {
         Self::SurfaceOutputDetail,
     ) {
         (
-            Some(Sendable(surface.0.get_current_texture())),
+            // Surface textures belong to the canvas, not to the device.
+            Some(Sendable(Texture::new(
+                surface.0.get_current_texture(),
+                None,
+            ))),
             wgt::SurfaceStatus::Good,
             (),
         )
@@ -1253,7 +1716,7 @@ impl crate::Context for Context {
         desc: &crate::ShaderModuleDescriptor,
         _shader_bound_checks: wgt::ShaderBoundChecks,
     ) -> Self::ShaderModuleId {
-        let mut descriptor = match desc.source {
+        let source = match desc.source {
             #[cfg(feature = "spirv")]
             crate::ShaderSource::SpirV(ref spv) => {
                 use naga::{back, front, valid};
@@ -1273,9 +1736,7 @@ impl crate::Context for Context {
                 let spv_module_info = validator.validate(&spv_module).unwrap();
 
                 let writer_flags = naga::back::wgsl::WriterFlags::empty();
-                let wgsl_text =
-                    back::wgsl::write_string(&spv_module, &spv_module_info, writer_flags).unwrap();
-                web_sys::GpuShaderModuleDescriptor::new(wgsl_text.as_str())
+                back::wgsl::write_string(&spv_module, &spv_module_info, writer_flags).unwrap()
             }
             #[cfg(feature = "glsl")]
             crate::ShaderSource::Glsl {
@@ -1300,17 +1761,21 @@ impl crate::Context for Context {
                 let glsl_module_info = validator.validate(&glsl_module).unwrap();
 
                 let writer_flags = naga::back::wgsl::WriterFlags::empty();
-                let wgsl_text =
-                    back::wgsl::write_string(&glsl_module, &glsl_module_info, writer_flags)
-                        .unwrap();
-                web_sys::GpuShaderModuleDescriptor::new(wgsl_text.as_str())
+                back::wgsl::write_string(&glsl_module, &glsl_module_info, writer_flags).unwrap()
             }
-            crate::ShaderSource::Wgsl(ref code) => web_sys::GpuShaderModuleDescriptor::new(code),
+            crate::ShaderSource::Wgsl(ref code) => code.to_string(),
+            #[cfg(feature = "serialized-ir")]
+            crate::ShaderSource::SerializedIr(ref data) => serialized_ir_to_wgsl(data)
+                .unwrap_or_else(|error| panic!("Invalid serialized shader IR: {}", error)),
         };
+        let mut descriptor = web_sys::GpuShaderModuleDescriptor::new(&source);
         if let Some(label) = desc.label {
             descriptor.label(label);
         }
-        Sendable(device.0.create_shader_module(&descriptor))
+        Sendable(ShaderModule {
+            module: device.0.create_shader_module(&descriptor),
+            source,
+        })
     }
 
     fn device_create_bind_group_layout(
@@ -1469,6 +1934,14 @@ impl crate::Context for Context {
         Sendable(device.0.create_bind_group(&mapped_desc))
     }
 
+    fn device_create_descriptor_table(
+        &self,
+        _device: &Self::DeviceId,
+        _desc: &crate::DescriptorTableDescriptor,
+    ) -> (Self::BindGroupId, Self::DescriptorTableId) {
+        panic!("PARTIALLY_BOUND_BINDING_ARRAY feature must be enabled to create descriptor tables")
+    }
+
     fn device_create_pipeline_layout(
         &self,
         device: &Self::DeviceId,
@@ -1491,82 +1964,7 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &crate::RenderPipelineDescriptor,
     ) -> Self::RenderPipelineId {
-        let mut mapped_vertex_state =
-            web_sys::GpuVertexState::new(desc.vertex.entry_point, &desc.vertex.module.id.0);
-
-        let buffers = desc
-            .vertex
-            .buffers
-            .iter()
-            .map(|vbuf| {
-                let mapped_attributes = vbuf
-                    .attributes
-                    .iter()
-                    .map(|attr| {
-                        web_sys::GpuVertexAttribute::new(
-                            map_vertex_format(attr.format),
-                            attr.offset as f64,
-                            attr.shader_location,
-                        )
-                    })
-                    .collect::<js_sys::Array>();
-
-                let mut mapped_vbuf = web_sys::GpuVertexBufferLayout::new(
-                    vbuf.array_stride as f64,
-                    &mapped_attributes,
-                );
-                mapped_vbuf.step_mode(map_vertex_step_mode(vbuf.step_mode));
-                mapped_vbuf
-            })
-            .collect::<js_sys::Array>();
-
-        mapped_vertex_state.buffers(&buffers);
-
-        let mut mapped_desc = web_sys::GpuRenderPipelineDescriptor::new(&mapped_vertex_state);
-
-        if let Some(label) = desc.label {
-            mapped_desc.label(label);
-        }
-
-        if let Some(layout) = desc.layout {
-            mapped_desc.layout(&layout.id.0);
-        }
-
-        if let Some(ref depth_stencil) = desc.depth_stencil {
-            mapped_desc.depth_stencil(&map_depth_stencil_state(depth_stencil));
-        }
-
-        if let Some(ref frag) = desc.fragment {
-            let targets = frag
-                .targets
-                .iter()
-                .map(|target| {
-                    let mapped_format = map_texture_format(target.format);
-                    let mut mapped_color_state = web_sys::GpuColorTargetState::new(mapped_format);
-                    if let Some(ref bs) = target.blend {
-                        let alpha = map_blend_component(&bs.alpha);
-                        let color = map_blend_component(&bs.color);
-                        let mapped_blend_state = web_sys::GpuBlendState::new(&alpha, &color);
-                        mapped_color_state.blend(&mapped_blend_state);
-                    }
-                    mapped_color_state.write_mask(target.write_mask.bits());
-                    mapped_color_state
-                })
-                .collect::<js_sys::Array>();
-            let mapped_fragment_desc =
-                web_sys::GpuFragmentState::new(frag.entry_point, &frag.module.id.0, &targets);
-            mapped_desc.fragment(&mapped_fragment_desc);
-        }
-
-        let mut mapped_multisample = web_sys::GpuMultisampleState::new();
-        mapped_multisample.count(desc.multisample.count);
-        mapped_multisample.mask(desc.multisample.mask as u32);
-        mapped_multisample.alpha_to_coverage_enabled(desc.multisample.alpha_to_coverage_enabled);
-        mapped_desc.multisample(&mapped_multisample);
-
-        let mapped_primitive = map_primitive_state(&desc.primitive);
-        mapped_desc.primitive(&mapped_primitive);
-
+        let mapped_desc = map_render_pipeline_descriptor(desc);
         Sendable(device.0.create_render_pipeline(&mapped_desc))
     }
 
@@ -1575,18 +1973,36 @@ impl crate::Context for Context {
         device: &Self::DeviceId,
         desc: &crate::ComputePipelineDescriptor,
     ) -> Self::ComputePipelineId {
-        let mapped_compute_stage =
-            web_sys::GpuProgrammableStage::new(desc.entry_point, &desc.module.id.0);
-        let mut mapped_desc = web_sys::GpuComputePipelineDescriptor::new(&mapped_compute_stage);
-        if let Some(layout) = desc.layout {
-            mapped_desc.layout(&layout.id.0);
-        }
-        if let Some(label) = desc.label {
-            mapped_desc.label(label);
-        }
+        let mapped_desc = map_compute_pipeline_descriptor(desc);
         Sendable(device.0.create_compute_pipeline(&mapped_desc))
     }
 
+    fn device_create_render_pipeline_async(
+        &self,
+        device: &Self::DeviceId,
+        desc: &crate::RenderPipelineDescriptor,
+    ) -> Self::CreateRenderPipelineFuture {
+        let mapped_desc = map_render_pipeline_descriptor(desc);
+        let pipeline_promise = device.0.create_render_pipeline_async(&mapped_desc);
+        MakeSendFuture::new(
+            wasm_bindgen_futures::JsFuture::from(pipeline_promise),
+            future_create_render_pipeline,
+        )
+    }
+
+    fn device_create_compute_pipeline_async(
+        &self,
+        device: &Self::DeviceId,
+        desc: &crate::ComputePipelineDescriptor,
+    ) -> Self::CreateComputePipelineFuture {
+        let mapped_desc = map_compute_pipeline_descriptor(desc);
+        let pipeline_promise = device.0.create_compute_pipeline_async(&mapped_desc);
+        MakeSendFuture::new(
+            wasm_bindgen_futures::JsFuture::from(pipeline_promise),
+            future_create_compute_pipeline,
+        )
+    }
+
     fn device_create_buffer(
         &self,
         device: &Self::DeviceId,
@@ -1598,7 +2014,13 @@ impl crate::Context for Context {
         if let Some(label) = desc.label {
             mapped_desc.label(label);
         }
-        Sendable(device.0.create_buffer(&mapped_desc))
+        let memory = MemoryUse::new(&device.0.memory_totals.buffers, desc.size);
+        let buffer = Sendable(Buffer::new(
+            device.0.create_buffer(&mapped_desc),
+            Some(memory),
+        ));
+        device.0.check_memory_threshold();
+        buffer
     }
 
     fn device_create_texture(
@@ -1617,7 +2039,13 @@ impl crate::Context for Context {
         mapped_desc.dimension(map_texture_dimension(desc.dimension));
         mapped_desc.mip_level_count(desc.mip_level_count);
         mapped_desc.sample_count(desc.sample_count);
-        Sendable(device.0.create_texture(&mapped_desc))
+        let memory = MemoryUse::new(&device.0.memory_totals.textures, desc.memory_size());
+        let texture = Sendable(Texture::new(
+            device.0.create_texture(&mapped_desc),
+            Some(memory),
+        ));
+        device.0.check_memory_threshold();
+        texture
     }
 
     fn device_create_sampler(
@@ -1652,6 +2080,21 @@ impl crate::Context for Context {
     ) -> Self::QuerySetId {
     }
 
+    fn device_create_timeline(
+        &self,
+        _device: &Self::DeviceId,
+        _initial_value: u64,
+    ) -> Self::TimelineId {
+        panic!("Timelines are not supported on the web")
+    }
+
+    fn device_create_pipeline_cache(
+        &self,
+        _device: &Self::DeviceId,
+        _desc: &crate::PipelineCacheDescriptor,
+    ) -> Self::PipelineCacheId {
+    }
+
     fn device_create_command_encoder(
         &self,
         device: &Self::DeviceId,
@@ -1691,8 +2134,17 @@ impl crate::Context for Context {
         // Device is dropped automatically
     }
 
-    fn device_poll(&self, _device: &Self::DeviceId, _maintain: crate::Maintain) {
+    fn device_destroy(&self, device: &Self::DeviceId) {
+        device.0.destroy();
+    }
+
+    fn device_poll(
+        &self,
+        _device: &Self::DeviceId,
+        _maintain: crate::Maintain,
+    ) -> crate::MaintainResult {
         // Device is polled automatically
+        crate::MaintainResult::Complete
     }
 
     fn device_on_uncaptured_error(
@@ -1711,6 +2163,39 @@ impl crate::Context for Context {
         f.forget();
     }
 
+    fn device_set_device_lost_callback(
+        &self,
+        device: &Self::DeviceId,
+        callback: DeviceLostCallback,
+    ) {
+        let mut state = device.0.lost.borrow_mut();
+        match state.lost {
+            Some((reason, ref message)) => {
+                let message = message.clone();
+                drop(state);
+                callback(reason, message);
+            }
+            None => state.callback = Some(callback),
+        }
+    }
+
+    fn device_memory_report(&self, device: &Self::DeviceId) -> crate::MemoryReport {
+        device.0.memory_report()
+    }
+
+    fn device_set_memory_threshold_callback(
+        &self,
+        device: &Self::DeviceId,
+        threshold: u64,
+        callback: Box<dyn FnMut(crate::MemoryReport) + Send + 'static>,
+    ) {
+        *device.0.memory_threshold.borrow_mut() = Some(MemoryThreshold {
+            threshold,
+            callback,
+            above: false,
+        });
+    }
+
     fn device_push_error_scope(&self, device: &Self::DeviceId, filter: crate::ErrorFilter) {
         device.0.push_error_scope(match filter {
             crate::ErrorFilter::OutOfMemory => web_sys::GpuErrorFilter::OutOfMemory,
@@ -1765,6 +2250,36 @@ impl crate::Context for Context {
         buffer.0.unmap();
     }
 
+    fn buffer_unmap_range(&self, buffer: &Self::BufferId, _range: Range<wgt::BufferAddress>) {
+        // `MapContext` rejects mapping a second range, so this is the whole mapping.
+        buffer.0.unmap();
+    }
+
+    fn descriptor_table_update_slots(
+        &self,
+        _table: &Self::DescriptorTableId,
+        _first_slot: u32,
+        _views: &[&crate::TextureView],
+    ) {
+        unreachable!("Descriptor tables can't be created on the web")
+    }
+
+    fn descriptor_table_clear_slots(&self, _table: &Self::DescriptorTableId, _slots: Range<u32>) {
+        unreachable!("Descriptor tables can't be created on the web")
+    }
+
+    fn shader_get_compilation_info(
+        &self,
+        shader: &Self::ShaderModuleId,
+    ) -> Self::CompilationInfoFuture {
+        let info_promise = shader.0.module.compilation_info();
+        let source = shader.0.source.clone();
+        MakeSendFuture::new(
+            wasm_bindgen_futures::JsFuture::from(info_promise),
+            Box::new(move |result| future_compilation_info(result, &source)),
+        )
+    }
+
     fn texture_create_view(
         &self,
         texture: &Self::TextureId,
@@ -1802,6 +2317,7 @@ impl crate::Context for Context {
 
     fn buffer_destroy(&self, buffer: &Self::BufferId) {
         buffer.0.destroy();
+        buffer.0.memory.borrow_mut().take();
     }
 
     fn buffer_drop(&self, _buffer: &Self::BufferId) {
@@ -1814,6 +2330,7 @@ impl crate::Context for Context {
 
     fn texture_destroy(&self, texture: &Self::TextureId) {
         texture.0.destroy();
+        texture.0.memory.borrow_mut().take();
     }
 
     fn texture_view_drop(&self, _texture_view: &Self::TextureViewId) {
@@ -1864,6 +2381,16 @@ impl crate::Context for Context {
         // Dropped automatically
     }
 
+    fn pipeline_cache_drop(&self, _cache: &Self::PipelineCacheId) {}
+
+    fn pipeline_cache_get_data(&self, _cache: &Self::PipelineCacheId) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn pipeline_cache_hit_count(&self, _cache: &Self::PipelineCacheId) -> u64 {
+        0
+    }
+
     fn compute_pipeline_get_bind_group_layout(
         &self,
         pipeline: &Self::ComputePipelineId,
@@ -2196,7 +2723,7 @@ impl crate::Context for Context {
         &self,
         queue: &Self::QueueId,
         command_buffers: I,
-    ) {
+    ) -> Self::SubmissionIndex {
         let temp_command_buffers = command_buffers.map(|i| i.0).collect::<js_sys::Array>();
 
         queue.0.submit(&temp_command_buffers);
@@ -2206,6 +2733,11 @@ impl crate::Context for Context {
         1.0 //TODO
     }
 
+    fn queue_is_independent(&self, _queue: &Self::QueueId) -> bool {
+        // Devices have a single queue on the web.
+        true
+    }
+
     fn queue_on_submitted_work_done(
         &self,
         _queue: &Self::QueueId,
@@ -2214,6 +2746,37 @@ impl crate::Context for Context {
     }
 
     fn device_start_capture(&self, _device: &Self::DeviceId) {}
+    fn queue_signal_timeline(
+        &self,
+        _queue: &Self::QueueId,
+        _timeline: &Self::TimelineId,
+        _value: u64,
+    ) {
+        panic!("Timelines are not supported on the web")
+    }
+
+    fn queue_wait_timeline(
+        &self,
+        _queue: &Self::QueueId,
+        _timeline: &Self::TimelineId,
+        _value: u64,
+    ) {
+        panic!("Timelines are not supported on the web")
+    }
+
+    fn timeline_get_value(&self, _timeline: &Self::TimelineId) -> u64 {
+        panic!("Timelines are not supported on the web")
+    }
+
+    fn timeline_wait(
+        &self,
+        _timeline: &Self::TimelineId,
+        _value: u64,
+        _timeout: Option<std::time::Duration>,
+    ) -> bool {
+        panic!("Timelines are not supported on the web")
+    }
+
     fn device_stop_capture(&self, _device: &Self::DeviceId) {}
 }
 
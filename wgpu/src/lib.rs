@@ -21,26 +21,29 @@ use std::{
     ops::{Bound, Range, RangeBounds},
     sync::Arc,
     thread,
+    time::Duration,
 };
 
 use parking_lot::Mutex;
 
 pub use wgt::{
-    AdapterInfo, AddressMode, AstcBlock, AstcChannel, Backend, Backends, BindGroupLayoutEntry,
-    BindingType, BlendComponent, BlendFactor, BlendOperation, BlendState, BufferAddress,
-    BufferBindingType, BufferSize, BufferUsages, Color, ColorTargetState, ColorWrites,
-    CommandBufferDescriptor, CompareFunction, DepthBiasState, DepthStencilState, DeviceType,
+    AdapterInfo, AdapterProfile, AddressMode, AstcBlock, AstcChannel, Backend, Backends,
+    BindGroupLayoutEntry, BindingType, BlendComponent, BlendFactor, BlendOperation, BlendState,
+    BufferAddress, BufferBindingType, BufferSize, BufferUsages, Color, ColorTargetState,
+    ColorWrites, CommandBufferDescriptor, CompareFunction, CompilationInfo, CompilationMessage,
+    CompilationMessageType, DepthBiasState, DepthStencilState, DeviceLostReason, DeviceType,
     DownlevelCapabilities, DownlevelFlags, DynamicOffset, Extent3d, Face, Features, FilterMode,
-    FrontFace, ImageDataLayout, ImageSubresourceRange, IndexFormat, Limits, MultisampleState,
-    Origin3d, PipelineStatisticsTypes, PolygonMode, PowerPreference, PresentMode, PrimitiveState,
-    PrimitiveTopology, PushConstantRange, QueryType, RenderBundleDepthStencil, SamplerBindingType,
-    SamplerBorderColor, ShaderLocation, ShaderModel, ShaderStages, StencilFaceState,
-    StencilOperation, StencilState, StorageTextureAccess, SurfaceConfiguration, SurfaceStatus,
-    TextureAspect, TextureDimension, TextureFormat, TextureFormatFeatureFlags,
-    TextureFormatFeatures, TextureSampleType, TextureUsages, TextureViewDimension, VertexAttribute,
-    VertexFormat, VertexStepMode, COPY_BUFFER_ALIGNMENT, COPY_BYTES_PER_ROW_ALIGNMENT,
-    MAP_ALIGNMENT, PUSH_CONSTANT_ALIGNMENT, QUERY_RESOLVE_BUFFER_ALIGNMENT, QUERY_SET_MAX_QUERIES,
-    QUERY_SIZE, VERTEX_STRIDE_ALIGNMENT,
+    FrontFace, ImageDataLayout, ImageSubresourceRange, IndexFormat, Limits, MaintainResult,
+    MemoryHeapReport, MemoryReport, MultisampleState, Origin3d, PipelineStatisticsTypes,
+    PolygonMode, PowerPreference, PresentMode, PrimitiveState, PrimitiveTopology,
+    PushConstantRange, QueryType, RenderBundleDepthStencil, SamplerBindingType, SamplerBorderColor,
+    ShaderLocation, ShaderModel, ShaderStages, StencilFaceState, StencilOperation, StencilState,
+    StorageTextureAccess, SurfaceConfiguration, SurfaceStatus, TextureAspect, TextureDimension,
+    TextureFormat, TextureFormatFeatureFlags, TextureFormatFeatures, TextureFormatProfile,
+    TextureSampleType, TextureUsages, TextureViewDimension, VertexAttribute, VertexFormat,
+    VertexStepMode, COPY_BUFFER_ALIGNMENT, COPY_BYTES_PER_ROW_ALIGNMENT, MAP_ALIGNMENT,
+    PUSH_CONSTANT_ALIGNMENT, QUERY_RESOLVE_BUFFER_ALIGNMENT, QUERY_SET_MAX_QUERIES, QUERY_SIZE,
+    VERTEX_STRIDE_ALIGNMENT,
 };
 
 use backend::{BufferMappedRange, Context as C};
@@ -157,12 +160,28 @@ trait RenderPassInner<Ctx: Context>: RenderInner<Ctx> {
     fn write_timestamp(&mut self, query_set: &Ctx::QuerySetId, query_index: u32);
     fn begin_pipeline_statistics_query(&mut self, query_set: &Ctx::QuerySetId, query_index: u32);
     fn end_pipeline_statistics_query(&mut self);
+    fn begin_occlusion_query(&mut self, query_index: u32);
+    fn end_occlusion_query(&mut self);
     fn execute_bundles<'a, I: Iterator<Item = &'a Ctx::RenderBundleId>>(
         &mut self,
         render_bundles: I,
     );
 }
 
+trait RenderBundleEncoderInner<Ctx: Context>: RenderInner<Ctx> {
+    fn set_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32);
+    fn set_viewport(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        min_depth: f32,
+        max_depth: f32,
+    );
+    fn set_stencil_reference(&mut self, reference: u32);
+}
+
 trait Context: Debug + Send + Sized + Sync {
     type AdapterId: Debug + Send + Sync + 'static;
     type DeviceId: Debug + Send + Sync + 'static;
@@ -178,13 +197,17 @@ trait Context: Debug + Send + Sized + Sync {
     type PipelineLayoutId: Debug + Send + Sync + 'static;
     type RenderPipelineId: Debug + Send + Sync + 'static;
     type ComputePipelineId: Debug + Send + Sync + 'static;
+    type PipelineCacheId: Debug + Send + Sync + 'static;
     type CommandEncoderId: Debug;
     type ComputePassId: Debug + ComputePassInner<Self>;
     type RenderPassId: Debug + RenderPassInner<Self>;
     type CommandBufferId: Debug + Send + Sync;
-    type RenderBundleEncoderId: Debug + RenderInner<Self>;
+    type RenderBundleEncoderId: Debug + RenderBundleEncoderInner<Self>;
     type RenderBundleId: Debug + Send + Sync + 'static;
     type SurfaceId: Debug + Send + Sync + 'static;
+    type TimelineId: Debug + Send + Sync + 'static;
+    type DescriptorTableId: Debug + Send + Sync + 'static;
+    type SubmissionIndex: Debug + Copy + Clone + PartialEq + Eq + Send + Sync + 'static;
 
     type SurfaceOutputDetail: Send;
 
@@ -194,6 +217,9 @@ trait Context: Debug + Send + Sized + Sync {
     type MapAsyncFuture: Future<Output = Result<(), BufferAsyncError>> + Send;
     type OnSubmittedWorkDoneFuture: Future<Output = ()> + Send;
     type PopErrorScopeFuture: Future<Output = Option<Error>> + Send;
+    type CreateRenderPipelineFuture: Future<Output = Result<Self::RenderPipelineId, Error>> + Send;
+    type CreateComputePipelineFuture: Future<Output = Result<Self::ComputePipelineId, Error>> + Send;
+    type CompilationInfoFuture: Future<Output = CompilationInfo> + Send;
 
     fn init(backends: Backends) -> Self;
     fn instance_create_surface(
@@ -210,6 +236,13 @@ trait Context: Debug + Send + Sized + Sync {
         desc: &DeviceDescriptor,
         trace_dir: Option<&std::path::Path>,
     ) -> Self::RequestDeviceFuture;
+    fn adapter_request_device_with_queues(
+        &self,
+        adapter: &Self::AdapterId,
+        desc: &DeviceDescriptor,
+        queue_descs: &[QueueDescriptor],
+        trace_dir: Option<&std::path::Path>,
+    ) -> Result<(Self::DeviceId, Self::QueueId, Vec<Self::QueueId>), RequestDeviceError>;
     fn instance_poll_all_devices(&self, force_wait: bool);
     fn adapter_is_surface_supported(
         &self,
@@ -225,12 +258,22 @@ trait Context: Debug + Send + Sized + Sync {
         adapter: &Self::AdapterId,
         format: TextureFormat,
     ) -> TextureFormatFeatures;
+    fn adapter_emulate_profile(
+        &self,
+        adapter: &Self::AdapterId,
+        profile: &AdapterProfile,
+    ) -> Self::AdapterId;
 
     fn surface_get_preferred_format(
         &self,
         surface: &Self::SurfaceId,
         adapter: &Self::AdapterId,
     ) -> Option<TextureFormat>;
+    fn surface_get_supported_formats(
+        &self,
+        surface: &Self::SurfaceId,
+        adapter: &Self::AdapterId,
+    ) -> Vec<TextureFormat>;
     fn surface_configure(
         &self,
         surface: &Self::SurfaceId,
@@ -276,6 +319,11 @@ trait Context: Debug + Send + Sized + Sync {
         device: &Self::DeviceId,
         desc: &BindGroupDescriptor,
     ) -> Self::BindGroupId;
+    fn device_create_descriptor_table(
+        &self,
+        device: &Self::DeviceId,
+        desc: &DescriptorTableDescriptor,
+    ) -> (Self::BindGroupId, Self::DescriptorTableId);
     fn device_create_pipeline_layout(
         &self,
         device: &Self::DeviceId,
@@ -291,6 +339,16 @@ trait Context: Debug + Send + Sized + Sync {
         device: &Self::DeviceId,
         desc: &ComputePipelineDescriptor,
     ) -> Self::ComputePipelineId;
+    fn device_create_render_pipeline_async(
+        &self,
+        device: &Self::DeviceId,
+        desc: &RenderPipelineDescriptor,
+    ) -> Self::CreateRenderPipelineFuture;
+    fn device_create_compute_pipeline_async(
+        &self,
+        device: &Self::DeviceId,
+        desc: &ComputePipelineDescriptor,
+    ) -> Self::CreateComputePipelineFuture;
     fn device_create_buffer(
         &self,
         device: &Self::DeviceId,
@@ -311,6 +369,16 @@ trait Context: Debug + Send + Sized + Sync {
         device: &Self::DeviceId,
         desc: &QuerySetDescriptor,
     ) -> Self::QuerySetId;
+    fn device_create_timeline(
+        &self,
+        device: &Self::DeviceId,
+        initial_value: u64,
+    ) -> Self::TimelineId;
+    fn device_create_pipeline_cache(
+        &self,
+        device: &Self::DeviceId,
+        desc: &PipelineCacheDescriptor,
+    ) -> Self::PipelineCacheId;
     fn device_create_command_encoder(
         &self,
         device: &Self::DeviceId,
@@ -322,12 +390,25 @@ trait Context: Debug + Send + Sized + Sync {
         desc: &RenderBundleEncoderDescriptor,
     ) -> Self::RenderBundleEncoderId;
     fn device_drop(&self, device: &Self::DeviceId);
-    fn device_poll(&self, device: &Self::DeviceId, maintain: Maintain);
+    fn device_destroy(&self, device: &Self::DeviceId);
+    fn device_poll(&self, device: &Self::DeviceId, maintain: Maintain) -> MaintainResult;
     fn device_on_uncaptured_error(
         &self,
         device: &Self::DeviceId,
         handler: impl UncapturedErrorHandler,
     );
+    fn device_set_device_lost_callback(
+        &self,
+        device: &Self::DeviceId,
+        callback: Box<dyn FnOnce(DeviceLostReason, String) + Send + 'static>,
+    );
+    fn device_memory_report(&self, device: &Self::DeviceId) -> MemoryReport;
+    fn device_set_memory_threshold_callback(
+        &self,
+        device: &Self::DeviceId,
+        threshold: u64,
+        callback: Box<dyn FnMut(MemoryReport) + Send + 'static>,
+    );
     fn device_push_error_scope(&self, device: &Self::DeviceId, filter: ErrorFilter);
     fn device_pop_error_scope(&self, device: &Self::DeviceId) -> Self::PopErrorScopeFuture;
 
@@ -343,6 +424,18 @@ trait Context: Debug + Send + Sized + Sync {
         sub_range: Range<BufferAddress>,
     ) -> BufferMappedRange;
     fn buffer_unmap(&self, buffer: &Self::BufferId);
+    fn buffer_unmap_range(&self, buffer: &Self::BufferId, range: Range<BufferAddress>);
+    fn descriptor_table_update_slots(
+        &self,
+        table: &Self::DescriptorTableId,
+        first_slot: u32,
+        views: &[&TextureView],
+    );
+    fn descriptor_table_clear_slots(&self, table: &Self::DescriptorTableId, slots: Range<u32>);
+    fn shader_get_compilation_info(
+        &self,
+        shader: &Self::ShaderModuleId,
+    ) -> Self::CompilationInfoFuture;
     fn texture_create_view(
         &self,
         texture: &Self::TextureId,
@@ -367,6 +460,9 @@ trait Context: Debug + Send + Sized + Sync {
     fn render_bundle_drop(&self, render_bundle: &Self::RenderBundleId);
     fn compute_pipeline_drop(&self, pipeline: &Self::ComputePipelineId);
     fn render_pipeline_drop(&self, pipeline: &Self::RenderPipelineId);
+    fn pipeline_cache_drop(&self, cache: &Self::PipelineCacheId);
+    fn pipeline_cache_get_data(&self, cache: &Self::PipelineCacheId) -> Option<Vec<u8>>;
+    fn pipeline_cache_hit_count(&self, cache: &Self::PipelineCacheId) -> u64;
 
     fn compute_pipeline_get_bind_group_layout(
         &self,
@@ -490,14 +586,25 @@ trait Context: Debug + Send + Sized + Sync {
         &self,
         queue: &Self::QueueId,
         command_buffers: I,
-    );
+    ) -> Self::SubmissionIndex;
     fn queue_get_timestamp_period(&self, queue: &Self::QueueId) -> f32;
+    fn queue_is_independent(&self, queue: &Self::QueueId) -> bool;
     fn queue_on_submitted_work_done(
         &self,
         queue: &Self::QueueId,
     ) -> Self::OnSubmittedWorkDoneFuture;
 
     fn device_start_capture(&self, device: &Self::DeviceId);
+    fn queue_signal_timeline(&self, queue: &Self::QueueId, timeline: &Self::TimelineId, value: u64);
+    fn queue_wait_timeline(&self, queue: &Self::QueueId, timeline: &Self::TimelineId, value: u64);
+    fn timeline_get_value(&self, timeline: &Self::TimelineId) -> u64;
+    fn timeline_wait(
+        &self,
+        timeline: &Self::TimelineId,
+        value: u64,
+        timeout: Option<Duration>,
+    ) -> bool;
+
     fn device_stop_capture(&self, device: &Self::DeviceId);
 }
 
@@ -544,22 +651,22 @@ pub struct Device {
     id: <C as Context>::DeviceId,
 }
 
-/// Passed to [`Device::poll`] to control if it should block or not. This has no effect on
-/// the web.
+/// Identifier of a call to [`Queue::submit`].
+///
+/// Can be passed to [`Device::poll`] to block until that submission is done.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum Maintain {
-    /// Block
-    Wait,
-    /// Don't block
-    Poll,
-}
+pub struct SubmissionIndex(<C as Context>::SubmissionIndex);
+
+/// Passed to [`Device::poll`] to control how and if it should block. This has no effect on
+/// the web.
+pub type Maintain = wgt::Maintain<SubmissionIndex>;
 
 /// The main purpose of this struct is to resolve mapped ranges (convert sizes
 /// to end points), and to ensure that the sub-ranges don't intersect.
 #[derive(Debug)]
 struct MapContext {
     total_size: BufferAddress,
-    initial_range: Range<BufferAddress>,
+    mapped_ranges: Vec<Range<BufferAddress>>,
     sub_ranges: Vec<Range<BufferAddress>>,
 }
 
@@ -567,13 +674,13 @@ impl MapContext {
     fn new(total_size: BufferAddress) -> Self {
         Self {
             total_size,
-            initial_range: 0..0,
+            mapped_ranges: Vec::new(),
             sub_ranges: Vec::new(),
         }
     }
 
     fn reset(&mut self) {
-        self.initial_range = 0..0;
+        self.mapped_ranges.clear();
 
         assert!(
             self.sub_ranges.is_empty(),
@@ -581,12 +688,58 @@ impl MapContext {
         );
     }
 
-    fn add(&mut self, offset: BufferAddress, size: Option<BufferSize>) -> BufferAddress {
+    fn map(&mut self, range: Range<BufferAddress>) {
+        // WebGPU maps a single range of a buffer, and unmaps it as a whole.
+        #[cfg(all(target_arch = "wasm32", not(feature = "webgl")))]
+        if let Some(mapped) = self.mapped_ranges.first() {
+            panic!(
+                "Map range {:?} requested while {:?} is mapped, only one range can be mapped at a time on the web",
+                range, mapped
+            );
+        }
+        for mapped in self.mapped_ranges.iter() {
+            assert!(
+                range.end <= mapped.start || range.start >= mapped.end,
+                "Map range {:?} intersects already mapped range {:?}",
+                range,
+                mapped
+            );
+        }
+        self.mapped_ranges.push(range);
+    }
+
+    fn unmap(&mut self, range: Range<BufferAddress>) {
+        let index = self
+            .mapped_ranges
+            .iter()
+            .position(|r| *r == range)
+            .expect("unable to unmap a range that wasn't mapped as a whole");
+        assert!(
+            self.sub_ranges
+                .iter()
+                .all(|sub| sub.end <= range.start || sub.start >= range.end),
+            "You cannot unmap a range that still has accessible mapped views"
+        );
+        self.mapped_ranges.swap_remove(index);
+    }
+
+    /// Resolve the end of a sub-range, which has to lie within a mapped range.
+    fn resolve(&self, offset: BufferAddress, size: Option<BufferSize>) -> BufferAddress {
+        let mapped = self
+            .mapped_ranges
+            .iter()
+            .find(|r| r.start <= offset && offset < r.end)
+            .expect("Range is not mapped");
         let end = match size {
             Some(s) => offset + s.get(),
-            None => self.initial_range.end,
+            None => mapped.end,
         };
-        assert!(self.initial_range.start <= offset && end <= self.initial_range.end);
+        assert!(end <= mapped.end);
+        end
+    }
+
+    fn add(&mut self, offset: BufferAddress, size: Option<BufferSize>) -> BufferAddress {
+        let end = self.resolve(offset, size);
         for sub in self.sub_ranges.iter() {
             assert!(
                 end <= sub.start || offset >= sub.end,
@@ -599,10 +752,7 @@ impl MapContext {
     }
 
     fn remove(&mut self, offset: BufferAddress, size: Option<BufferSize>) {
-        let end = match size {
-            Some(s) => offset + s.get(),
-            None => self.initial_range.end,
-        };
+        let end = self.resolve(offset, size);
 
         let index = self
             .sub_ranges
@@ -734,6 +884,77 @@ impl Drop for BindGroup {
     }
 }
 
+/// Handle to a descriptor table.
+///
+/// A `DescriptorTable` is a [`BindGroup`] with a single array of textures, whose slots
+/// start out empty and can be overwritten with [`DescriptorTable::update_slots`] and
+/// emptied with [`DescriptorTable::clear_slots`].
+/// It can be created with [`Device::create_descriptor_table`], and dereferences to a
+/// [`BindGroup`] so it can be bound like one.
+///
+/// Requires [`Features::TEXTURE_BINDING_ARRAY`] and
+/// [`Features::PARTIALLY_BOUND_BINDING_ARRAY`].
+#[derive(Debug)]
+pub struct DescriptorTable {
+    bind_group: BindGroup,
+    id: <C as Context>::DescriptorTableId,
+}
+
+impl DescriptorTable {
+    /// Bind `views` to the slots in `slots`.
+    ///
+    /// The table must not be in use by a submission that hasn't completed yet.
+    /// Command buffers and render bundles recorded with the table before the
+    /// update can no longer be submitted.
+    ///
+    /// # Panics
+    ///
+    /// - If `slots` ends before it starts.
+    /// - If the length of `slots` and `views` differ.
+    pub fn update_slots(&self, slots: Range<u32>, views: &[&TextureView]) {
+        let count = slots
+            .end
+            .checked_sub(slots.start)
+            .unwrap_or_else(|| panic!("Slot range {:?} ends before it starts", slots));
+        assert_eq!(
+            count as usize,
+            views.len(),
+            "Slot range length must match the number of views"
+        );
+        Context::descriptor_table_update_slots(
+            &*self.bind_group.context,
+            &self.id,
+            slots.start,
+            views,
+        );
+    }
+
+    /// Clear the slots in `slots`, releasing the views bound to them.
+    ///
+    /// Shaders must not access cleared slots. The same restrictions as for
+    /// [`DescriptorTable::update_slots`] apply.
+    ///
+    /// # Panics
+    ///
+    /// - If `slots` ends before it starts.
+    pub fn clear_slots(&self, slots: Range<u32>) {
+        assert!(
+            slots.start <= slots.end,
+            "Slot range {:?} ends before it starts",
+            slots
+        );
+        Context::descriptor_table_clear_slots(&*self.bind_group.context, &self.id, slots);
+    }
+}
+
+impl std::ops::Deref for DescriptorTable {
+    type Target = BindGroup;
+
+    fn deref(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
+
 /// Handle to a compiled shader module.
 ///
 /// A `ShaderModule` represents a compiled shader module on the GPU. It can be created by passing
@@ -753,6 +974,14 @@ impl Drop for ShaderModule {
     }
 }
 
+impl ShaderModule {
+    /// Get the messages produced while compiling this shader module, such as the
+    /// parsing or validation errors if the module is invalid.
+    pub fn get_compilation_info(&self) -> impl Future<Output = CompilationInfo> + Send {
+        self.context.shader_get_compilation_info(&self.id)
+    }
+}
+
 /// Source of a shader module.
 ///
 /// The source will be parsed and validated.
@@ -780,6 +1009,19 @@ pub enum ShaderSource<'a> {
     },
     /// WGSL module as a string slice.
     Wgsl(Cow<'a, str>),
+    /// Naga IR that was parsed and validated ahead of time, encoded by
+    /// `wgpu_core::serialized_ir::encode`.
+    ///
+    /// The data is only valid for the version of wgpu that produced it. Loading it skips
+    /// parsing, and validation unless the device lacks features the module was validated
+    /// with. The data is trusted to have been produced by `encode`: its hash only guards
+    /// against corruption.
+    ///
+    /// # Panics
+    /// - On the web, if the data is invalid.
+    #[cfg(feature = "serialized-ir")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serialized-ir")))]
+    SerializedIr(Cow<'a, [u8]>),
 }
 
 /// Descriptor for a shader module.
@@ -972,6 +1214,7 @@ impl Drop for RenderBundle {
 }
 
 /// Handle to a query set.
+#[derive(Debug)]
 pub struct QuerySet {
     context: Arc<C>,
     id: <C as Context>::QuerySetId,
@@ -985,6 +1228,79 @@ impl Drop for QuerySet {
     }
 }
 
+/// Handle to a pipeline cache.
+///
+/// A `PipelineCache` stores the results of compiling pipelines, so that pipelines created
+/// with the same shaders and state can skip the expensive compilation step. Its contents
+/// can be retrieved with [`PipelineCache::get_data`] and used to create a new cache in a
+/// later run of the application.
+///
+/// It can be created with [`Device::create_pipeline_cache`].
+///
+/// Caches have no effect on the empty backend, or on the web:
+/// pipelines are compiled in full every time.
+#[derive(Debug)]
+pub struct PipelineCache {
+    context: Arc<C>,
+    id: <C as Context>::PipelineCacheId,
+}
+
+impl PipelineCache {
+    /// Returns the data of this cache, to be passed in [`PipelineCacheDescriptor::data`].
+    ///
+    /// The data is only valid for the same version of wgpu running on the same adapter and driver.
+    /// Returns `None` if the cache is invalid or the backend doesn't support retrieving it.
+    pub fn get_data(&self) -> Option<Vec<u8>> {
+        self.context.pipeline_cache_get_data(&self.id)
+    }
+
+    /// Returns the number of pipeline stages created with this cache, whose
+    /// translation was found in it.
+    pub fn hit_count(&self) -> u64 {
+        self.context.pipeline_cache_hit_count(&self.id)
+    }
+}
+
+impl Drop for PipelineCache {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.context.pipeline_cache_drop(&self.id);
+        }
+    }
+}
+
+/// Handle to a timeline, a counter shared between queues and the host.
+///
+/// Queues signal increasing values of a timeline once their submitted work is done,
+/// and wait for values before their next submission. The host can read the current
+/// value and wait for a value with [`Timeline::wait`]. This makes it possible to
+/// order work between queues, including queues of different devices created from
+/// the same adapter.
+///
+/// Reaching a value also reaches all the smaller ones.
+///
+/// It can be created with [`Device::create_timeline`].
+#[derive(Debug)]
+pub struct Timeline {
+    context: Arc<C>,
+    id: <C as Context>::TimelineId,
+}
+
+impl Timeline {
+    /// Returns the largest value reached so far.
+    pub fn value(&self) -> u64 {
+        Context::timeline_get_value(&*self.context, &self.id)
+    }
+
+    /// Blocks until the timeline reaches `value`, or until `timeout` elapses.
+    ///
+    /// If `value` isn't signaled yet, this waits for another thread to signal it.
+    /// Returns false on timeout.
+    pub fn wait(&self, value: u64, timeout: Option<Duration>) -> bool {
+        Context::timeline_wait(&*self.context, &self.id, value, timeout)
+    }
+}
+
 /// Handle to a command queue on a device.
 ///
 /// A `Queue` executes recorded [`CommandBuffer`] objects and provides convenience methods
@@ -1112,6 +1428,20 @@ pub struct RenderPassDepthStencilAttachment<'a> {
     pub stencil_ops: Option<Operations<u32>>,
 }
 
+/// Describes the timestamps written at the boundaries of a [`RenderPass`] or [`ComputePass`].
+///
+/// The query set must be of type [`QueryType::Timestamp`], which requires
+/// [`Features::TIMESTAMP_QUERY`] to be enabled.
+#[derive(Clone, Debug)]
+pub struct PassTimestampWrites<'a> {
+    /// The query set to write the timestamps into.
+    pub query_set: &'a QuerySet,
+    /// The index of the query written when the pass begins, if any.
+    pub beginning_of_pass_write_index: Option<u32>,
+    /// The index of the query written when the pass ends, if any.
+    pub end_of_pass_write_index: Option<u32>,
+}
+
 // The underlying types are also exported so that documentation shows up for them
 
 /// Object label.
@@ -1121,6 +1451,8 @@ pub use wgt::RequestAdapterOptions as RequestAdapterOptionsBase;
 pub type RequestAdapterOptions<'a> = RequestAdapterOptionsBase<&'a Surface>;
 /// Describes a [`Device`].
 pub type DeviceDescriptor<'a> = wgt::DeviceDescriptor<Label<'a>>;
+/// Describes an additional [`Queue`], see [`Adapter::request_device_with_queues`].
+pub type QueueDescriptor<'a> = wgt::QueueDescriptor<Label<'a>>;
 /// Describes a [`Buffer`].
 pub type BufferDescriptor<'a> = wgt::BufferDescriptor<Label<'a>>;
 /// Describes a [`CommandEncoder`].
@@ -1245,6 +1577,16 @@ pub struct BindGroupDescriptor<'a> {
     pub entries: &'a [BindGroupEntry<'a>],
 }
 
+/// Describes a [`DescriptorTable`].
+#[derive(Clone, Debug)]
+pub struct DescriptorTableDescriptor<'a> {
+    /// Debug label of the table. This will show up in graphics debuggers for easy identification.
+    pub label: Label<'a>,
+    /// The [`BindGroupLayout`] of the table. It must have a single entry with a `count`,
+    /// binding textures or storage textures. The table has `count` slots.
+    pub layout: &'a BindGroupLayout,
+}
+
 /// Describes the attachments of a render pass.
 ///
 /// Note: separate lifetimes are needed because the texture views
@@ -1257,6 +1599,12 @@ pub struct RenderPassDescriptor<'a, 'b> {
     pub color_attachments: &'b [RenderPassColorAttachment<'a>],
     /// The depth and stencil attachment of the render pass, if any.
     pub depth_stencil_attachment: Option<RenderPassDepthStencilAttachment<'a>>,
+    /// Timestamps to write at the beginning and the end of the render pass, if any.
+    pub timestamp_writes: Option<PassTimestampWrites<'a>>,
+    /// The query set occlusion queries of the render pass are written to, if any.
+    ///
+    /// Must be of type [`QueryType::Occlusion`].
+    pub occlusion_query_set: Option<&'a QuerySet>,
 }
 
 /// Describes how the vertex buffer is interpreted.
@@ -1278,6 +1626,10 @@ pub struct VertexState<'a> {
     /// The name of the entry point in the compiled shader. There must be a function that returns
     /// void with this name in the shader.
     pub entry_point: &'a str,
+    /// Values of the pipeline-overridable constants of the shader, keyed by the constant
+    /// name or its numeric ID. Constants that aren't listed keep their default value,
+    /// and giving a constant more than once is a validation error.
+    pub constants: &'a [(&'a str, f64)],
     /// The format of any vertex buffers used with this pipeline.
     pub buffers: &'a [VertexBufferLayout<'a>],
 }
@@ -1290,6 +1642,10 @@ pub struct FragmentState<'a> {
     /// The name of the entry point in the compiled shader. There must be a function that returns
     /// void with this name in the shader.
     pub entry_point: &'a str,
+    /// Values of the pipeline-overridable constants of the shader, keyed by the constant
+    /// name or its numeric ID. Constants that aren't listed keep their default value,
+    /// and giving a constant more than once is a validation error.
+    pub constants: &'a [(&'a str, f64)],
     /// The color state of the render targets.
     pub targets: &'a [ColorTargetState],
 }
@@ -1314,6 +1670,8 @@ pub struct RenderPipelineDescriptor<'a> {
     /// If the pipeline will be used with a multiview render pass, this indicates how many array
     /// layers the attachments will have.
     pub multiview: Option<NonZeroU32>,
+    /// The pipeline cache to use when creating this pipeline, if any.
+    pub cache: Option<&'a PipelineCache>,
 }
 
 /// Describes the attachments of a compute pass.
@@ -1321,6 +1679,8 @@ pub struct RenderPipelineDescriptor<'a> {
 pub struct ComputePassDescriptor<'a> {
     /// Debug label of the compute pass. This will show up in graphics debuggers for easy identification.
     pub label: Label<'a>,
+    /// Timestamps to write at the beginning and the end of the compute pass, if any.
+    pub timestamp_writes: Option<PassTimestampWrites<'a>>,
 }
 
 /// Describes a compute pipeline.
@@ -1335,6 +1695,24 @@ pub struct ComputePipelineDescriptor<'a> {
     /// The name of the entry point in the compiled shader. There must be a function that returns
     /// void with this name in the shader.
     pub entry_point: &'a str,
+    /// Values of the pipeline-overridable constants of the shader, keyed by the constant
+    /// name or its numeric ID. Constants that aren't listed keep their default value,
+    /// and giving a constant more than once is a validation error.
+    pub constants: &'a [(&'a str, f64)],
+    /// The pipeline cache to use when creating this pipeline, if any.
+    pub cache: Option<&'a PipelineCache>,
+}
+
+/// Describes a [`PipelineCache`].
+#[derive(Clone, Debug, Default)]
+pub struct PipelineCacheDescriptor<'a> {
+    /// Debug label of the pipeline cache. This will show up in graphics debuggers for easy identification.
+    pub label: Label<'a>,
+    /// Data previously returned by [`PipelineCache::get_data`], if any.
+    pub data: Option<&'a [u8]>,
+    /// If the data was produced by a different version of wgpu or for a different adapter,
+    /// or is corrupted, create an empty cache instead of raising a validation error.
+    pub fallback: bool,
 }
 
 pub use wgt::ImageCopyBuffer as ImageCopyBufferBase;
@@ -1614,6 +1992,64 @@ impl Adapter {
         }
     }
 
+    /// Requests a logical device with additional queues besides the main one.
+    ///
+    /// Returns the [`Device`], its main [`Queue`], and one more [`Queue`] per entry of
+    /// `queue_descs`. Work submitted to different queues may execute concurrently,
+    /// so that for example compute or transfers don't wait behind rendering.
+    ///
+    /// When the adapter has fewer hardware queues than requested, several of
+    /// the returned queues share one, and their submissions execute in order.
+    ///
+    /// A buffer or texture used by a submission on one queue can't be used on
+    /// another queue until that submission is done. Surface textures can only be
+    /// used on the main queue. Writes through [`Queue::write_buffer`] and
+    /// [`Queue::write_texture`] are executed with the next submission to any of the queues.
+    ///
+    /// Not supported on the web, where it always fails with [`RequestDeviceError`];
+    /// use [`Adapter::request_device`] for a device with a single queue there.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`Adapter::request_device`].
+    pub fn request_device_with_queues(
+        &self,
+        desc: &DeviceDescriptor,
+        queue_descs: &[QueueDescriptor],
+        trace_path: Option<&std::path::Path>,
+    ) -> impl Future<Output = Result<(Device, Queue, Vec<Queue>), RequestDeviceError>> + Send {
+        let context = Arc::clone(&self.context);
+        let result = Context::adapter_request_device_with_queues(
+            &*self.context,
+            &self.id,
+            desc,
+            queue_descs,
+            trace_path,
+        );
+        async move {
+            result.map(|(device_id, queue_id, extra_queue_ids)| {
+                let extra_queues = extra_queue_ids
+                    .into_iter()
+                    .map(|id| Queue {
+                        context: Arc::clone(&context),
+                        id,
+                    })
+                    .collect();
+                (
+                    Device {
+                        context: Arc::clone(&context),
+                        id: device_id,
+                    },
+                    Queue {
+                        context,
+                        id: queue_id,
+                    },
+                    extra_queues,
+                )
+            })
+        }
+    }
+
     /// Create a wgpu [`Device`] and [`Queue`] from a wgpu-hal `OpenDevice`
     ///
     /// # Safety
@@ -1682,14 +2118,35 @@ impl Adapter {
     pub fn get_texture_format_features(&self, format: TextureFormat) -> TextureFormatFeatures {
         Context::adapter_get_texture_format_features(&*self.context, &self.id, format)
     }
+
+    /// Returns an adapter restricted to the features, limits, downlevel capabilities
+    /// and texture format features of `profile`, to emulate a less capable adapter.
+    ///
+    /// Everything querying the returned adapter reports the profile, and devices
+    /// requested from it are validated against it. This adapter, and the devices
+    /// requested from it, aren't affected.
+    ///
+    /// # Panics
+    ///
+    /// - The profile exceeds the capabilities of the adapter.
+    /// - On the web, where profiles can't be emulated.
+    pub fn emulate_profile(&self, profile: &AdapterProfile) -> Adapter {
+        Adapter {
+            context: Arc::clone(&self.context),
+            id: Context::adapter_emulate_profile(&*self.context, &self.id, profile),
+        }
+    }
 }
 
 impl Device {
     /// Check for resource cleanups and mapping callbacks.
     ///
+    /// Returns [`MaintainResult::TimedOut`] if waiting with
+    /// [`Maintain::WaitForSubmissionIndexTimeout`] gave up before the submission was done.
+    ///
     /// no-op on the web, device is automatically polled.
-    pub fn poll(&self, maintain: Maintain) {
-        Context::device_poll(&*self.context, &self.id, maintain);
+    pub fn poll(&self, maintain: Maintain) -> MaintainResult {
+        Context::device_poll(&*self.context, &self.id, maintain)
     }
 
     /// List all features that may be used with this device.
@@ -1795,6 +2252,19 @@ impl Device {
         }
     }
 
+    /// Creates a new [`DescriptorTable`] with all of its slots empty.
+    pub fn create_descriptor_table(&self, desc: &DescriptorTableDescriptor) -> DescriptorTable {
+        let (bind_group_id, id) =
+            Context::device_create_descriptor_table(&*self.context, &self.id, desc);
+        DescriptorTable {
+            bind_group: BindGroup {
+                context: Arc::clone(&self.context),
+                id: bind_group_id,
+            },
+            id,
+        }
+    }
+
     /// Creates a [`BindGroupLayout`].
     pub fn create_bind_group_layout(&self, desc: &BindGroupLayoutDescriptor) -> BindGroupLayout {
         BindGroupLayout {
@@ -1827,11 +2297,40 @@ impl Device {
         }
     }
 
+    /// Creates a [`RenderPipeline`] without blocking the calling thread.
+    ///
+    /// Shader compilation happens in the background and the returned future resolves once the
+    /// pipeline is ready to be used. Unlike [`Device::create_render_pipeline`], errors are
+    /// reported through the future rather than the device's error handler.
+    pub fn create_render_pipeline_async(
+        &self,
+        desc: &RenderPipelineDescriptor,
+    ) -> impl Future<Output = Result<RenderPipeline, Error>> + Send {
+        let context = Arc::clone(&self.context);
+        let pipeline = Context::device_create_render_pipeline_async(&*self.context, &self.id, desc);
+        async move { pipeline.await.map(|id| RenderPipeline { context, id }) }
+    }
+
+    /// Creates a [`ComputePipeline`] without blocking the calling thread.
+    ///
+    /// Shader compilation happens in the background and the returned future resolves once the
+    /// pipeline is ready to be used. Unlike [`Device::create_compute_pipeline`], errors are
+    /// reported through the future rather than the device's error handler.
+    pub fn create_compute_pipeline_async(
+        &self,
+        desc: &ComputePipelineDescriptor,
+    ) -> impl Future<Output = Result<ComputePipeline, Error>> + Send {
+        let context = Arc::clone(&self.context);
+        let pipeline =
+            Context::device_create_compute_pipeline_async(&*self.context, &self.id, desc);
+        async move { pipeline.await.map(|id| ComputePipeline { context, id }) }
+    }
+
     /// Creates a [`Buffer`].
     pub fn create_buffer(&self, desc: &BufferDescriptor) -> Buffer {
         let mut map_context = MapContext::new(desc.size);
         if desc.mapped_at_creation {
-            map_context.initial_range = 0..desc.size;
+            map_context.map(0..desc.size);
         }
         Buffer {
             context: Arc::clone(&self.context),
@@ -1892,11 +2391,81 @@ impl Device {
         }
     }
 
+    /// Creates a new [`Timeline`] starting at `initial_value`.
+    ///
+    /// The timeline can be used by the queues of any device created from the same adapter.
+    pub fn create_timeline(&self, initial_value: u64) -> Timeline {
+        Timeline {
+            context: Arc::clone(&self.context),
+            id: Context::device_create_timeline(&*self.context, &self.id, initial_value),
+        }
+    }
+
+    /// Creates a new [`PipelineCache`], optionally filled with data from a previous run.
+    pub fn create_pipeline_cache(&self, desc: &PipelineCacheDescriptor) -> PipelineCache {
+        PipelineCache {
+            context: Arc::clone(&self.context),
+            id: Context::device_create_pipeline_cache(&*self.context, &self.id, desc),
+        }
+    }
+
     /// Set a callback for errors that are not handled in error scopes.
     pub fn on_uncaptured_error(&self, handler: impl UncapturedErrorHandler) {
         self.context.device_on_uncaptured_error(&self.id, handler);
     }
 
+    /// Destroy the device right away, instead of once it and its resources are dropped.
+    ///
+    /// Waits for the GPU to be idle and frees all the objects created with the device,
+    /// which become invalid. Pending [`BufferSlice::map_async`] calls fail, while
+    /// mapped buffers keep their memory until they're unmapped. The device is then lost
+    /// with [`DeviceLostReason::Destroyed`], see [`Device::set_device_lost_callback`].
+    pub fn destroy(&self) {
+        Context::device_destroy(&*self.context, &self.id)
+    }
+
+    /// Set a callback invoked once the device is lost, with the reason and a message.
+    ///
+    /// Replaces the previous callback. If the device is already lost, the callback is
+    /// invoked right away. Once lost, the device can't be used anymore: resources created
+    /// with it are invalid, and queue operations do nothing.
+    ///
+    /// On native, a loss noticed while creating an object invokes the callback on the
+    /// next [`Device::poll`].
+    pub fn set_device_lost_callback(
+        &self,
+        callback: impl FnOnce(DeviceLostReason, String) + Send + 'static,
+    ) {
+        self.context
+            .device_set_device_lost_callback(&self.id, Box::new(callback));
+    }
+
+    /// Report the GPU memory allocated by the device, by category and by heap.
+    ///
+    /// Heaps are only reported on Vulkan, see [`MemoryReport::heaps`]. On the web,
+    /// only buffers and textures are counted, from their descriptors.
+    pub fn memory_report(&self) -> MemoryReport {
+        Context::device_memory_report(&*self.context, &self.id)
+    }
+
+    /// Set a callback invoked with the memory report of the device whenever its
+    /// [`MemoryReport::total`] goes from below `threshold` bytes to above.
+    ///
+    /// Replaces the previous callback. Memory is checked when buffers, textures and
+    /// query sets are created, when the device is polled, and after submissions.
+    pub fn set_memory_threshold_callback(
+        &self,
+        threshold: u64,
+        callback: impl FnMut(MemoryReport) + Send + 'static,
+    ) {
+        Context::device_set_memory_threshold_callback(
+            &*self.context,
+            &self.id,
+            threshold,
+            Box::new(callback),
+        )
+    }
+
     /// Push an error scope.
     pub fn push_error_scope(&self, filter: ErrorFilter) {
         self.context.device_push_error_scope(&self.id, filter);
@@ -2141,6 +2710,10 @@ impl<'a> BufferSlice<'a> {
 
     /// Map the buffer. Buffer is ready to map once the future is resolved.
     ///
+    /// Several disjoint slices of the same buffer can be mapped at the same time,
+    /// and unmapped independently with [`BufferSlice::unmap`]. On the web only one
+    /// range can be mapped at a time, and mapping another one panics.
+    ///
     /// For the future to complete, `device.poll(...)` must be called elsewhere in the runtime, possibly integrated
     /// into an event loop, run on a separate thread, or continually polled in the same task runtime that this
     /// future will be run on.
@@ -2152,17 +2725,11 @@ impl<'a> BufferSlice<'a> {
         mode: MapMode,
     ) -> impl Future<Output = Result<(), BufferAsyncError>> + Send {
         let mut mc = self.buffer.map_context.lock();
-        assert_eq!(
-            mc.initial_range,
-            0..0,
-            "Buffer {:?} is already mapped",
-            self.buffer.id
-        );
         let end = match self.size {
             Some(s) => self.offset + s.get(),
             None => mc.total_size,
         };
-        mc.initial_range = self.offset..end;
+        mc.map(self.offset..end);
 
         Context::buffer_map_async(
             &*self.buffer.context,
@@ -2199,6 +2766,20 @@ impl<'a> BufferSlice<'a> {
             readable: self.buffer.usage.contains(BufferUsages::MAP_READ),
         }
     }
+
+    /// Flushes any pending write operations and unmaps this slice from host memory.
+    ///
+    /// The slice has to match one passed to [`BufferSlice::map_async`] exactly.
+    /// Other mapped slices of the buffer stay mapped.
+    pub fn unmap(&self) {
+        let mut mc = self.buffer.map_context.lock();
+        let end = match self.size {
+            Some(s) => self.offset + s.get(),
+            None => mc.total_size,
+        };
+        mc.unmap(self.offset..end);
+        Context::buffer_unmap_range(&*self.buffer.context, &self.buffer.id, self.offset..end);
+    }
 }
 
 impl Drop for Buffer {
@@ -2776,6 +3357,10 @@ impl<'a> RenderPass<'a> {
     /// For example, with an offset of 4 and an array of `[u32; 3]`, that will write to the range
     /// of 4..16.
     ///
+    /// Push constant values persist across [`RenderPass::set_pipeline`] calls, and may be set
+    /// before any pipeline. Every range of the current pipeline layout must have been written
+    /// by the time of a draw call.
+    ///
     /// For each byte in the range of push constant data written, the union of the stages of all push constant
     /// ranges that covers that byte must be exactly `stages`. There's no good way of explaining this simply,
     /// so here are some examples:
@@ -2831,6 +3416,25 @@ impl<'a> RenderPass<'a> {
     }
 }
 
+/// [`RenderPassDescriptor::occlusion_query_set`] must be set in order to call these functions.
+impl<'a> RenderPass<'a> {
+    /// Start an occlusion query on this render pass. It can be ended with
+    /// `end_occlusion_query`. Occlusion queries may not be nested, and each query index
+    /// may only be used once per render pass.
+    ///
+    /// Once resolved with [`CommandEncoder::resolve_query_set`], the query is non-zero
+    /// if any sample passed the depth and stencil tests while it was active.
+    pub fn begin_occlusion_query(&mut self, query_index: u32) {
+        self.id.begin_occlusion_query(query_index);
+    }
+
+    /// End the occlusion query on this render pass. It can be started with
+    /// `begin_occlusion_query`. Occlusion queries may not be nested.
+    pub fn end_occlusion_query(&mut self) {
+        self.id.end_occlusion_query();
+    }
+}
+
 impl<'a> Drop for RenderPass<'a> {
     fn drop(&mut self) {
         if !thread::panicking() {
@@ -2906,6 +3510,10 @@ impl<'a> ComputePass<'a> {
     /// Data size must be a multiple of 4 and must be aligned to the 4s, so we take an array of u32.
     /// For example, with an offset of 4 and an array of `[u32; 3]`, that will write to the range
     /// of 4..16.
+    ///
+    /// Push constant values persist across [`ComputePass::set_pipeline`] calls, and may be set
+    /// before any pipeline. Every range of the current pipeline layout must have been written
+    /// by the time of a dispatch call.
     pub fn set_push_constants(&mut self, offset: u32, data: &[u8]) {
         self.id.set_push_constants(offset, data);
     }
@@ -3053,6 +3661,42 @@ impl<'a> RenderBundleEncoder<'a> {
         self.id
             .draw_indexed_indirect(&indirect_buffer.id, indirect_offset);
     }
+
+    /// Sets the scissor region.
+    ///
+    /// Subsequent draw calls of the bundle will discard any fragments that fall outside this region.
+    /// The scissor region of the render pass is restored once the bundle has been executed.
+    ///
+    /// # Panics
+    ///
+    /// - On the web, where render bundles have no dynamic state.
+    pub fn set_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        self.id.set_scissor_rect(x, y, width, height);
+    }
+
+    /// Sets the viewport region.
+    ///
+    /// Subsequent draw calls of the bundle will draw any fragments in this region.
+    /// The viewport of the render pass is restored once the bundle has been executed.
+    ///
+    /// # Panics
+    ///
+    /// - On the web, where render bundles have no dynamic state.
+    pub fn set_viewport(&mut self, x: f32, y: f32, w: f32, h: f32, min_depth: f32, max_depth: f32) {
+        self.id.set_viewport(x, y, w, h, min_depth, max_depth);
+    }
+
+    /// Sets the stencil reference.
+    ///
+    /// Subsequent stencil tests of the bundle will test against this value.
+    /// The stencil reference of the render pass is restored once the bundle has been executed.
+    ///
+    /// # Panics
+    ///
+    /// - On the web, where render bundles have no dynamic state.
+    pub fn set_stencil_reference(&mut self, reference: u32) {
+        self.id.set_stencil_reference(reference);
+    }
 }
 
 /// [`Features::PUSH_CONSTANTS`] must be enabled on the device in order to call these functions.
@@ -3120,14 +3764,19 @@ impl Queue {
     }
 
     /// Submits a series of finished command buffers for execution.
-    pub fn submit<I: IntoIterator<Item = CommandBuffer>>(&self, command_buffers: I) {
-        Context::queue_submit(
+    ///
+    /// Returns the index of the submission, which [`Device::poll`] can wait for.
+    pub fn submit<I: IntoIterator<Item = CommandBuffer>>(
+        &self,
+        command_buffers: I,
+    ) -> SubmissionIndex {
+        SubmissionIndex(Context::queue_submit(
             &*self.context,
             &self.id,
             command_buffers
                 .into_iter()
                 .map(|mut comb| comb.id.take().unwrap()),
-        );
+        ))
     }
 
     /// Gets the amount of nanoseconds each tick of a timestamp query represents.
@@ -3137,11 +3786,46 @@ impl Queue {
         Context::queue_get_timestamp_period(&*self.context, &self.id)
     }
 
+    /// Returns whether this queue has a hardware queue of its own.
+    ///
+    /// Queues requested with [`Adapter::request_device_with_queues`] beyond what the
+    /// adapter provides share a hardware queue with another queue of the device, and
+    /// their submissions execute in order with the ones of that queue.
+    pub fn is_independent(&self) -> bool {
+        Context::queue_is_independent(&*self.context, &self.id)
+    }
+
     /// Returns a future that resolves once all the work submitted by this point
     /// is done processing on GPU.
     pub fn on_submitted_work_done(&self) -> impl Future<Output = ()> + Send {
         Context::queue_on_submitted_work_done(&*self.context, &self.id)
     }
+
+    /// Signals `value` on `timeline` once all the work submitted to this queue
+    /// by this point is done.
+    ///
+    /// # Panics
+    ///
+    /// - `value` is not greater than the last value signaled on `timeline`.
+    /// - `timeline` was created from a device of another adapter.
+    pub fn signal_timeline(&self, timeline: &Timeline, value: u64) {
+        Context::queue_signal_timeline(&*self.context, &self.id, &timeline.id, value)
+    }
+
+    /// Makes the next submission to this queue wait until `timeline` reaches `value`.
+    ///
+    /// When `value` is signaled by a queue of the same device, the wait happens on
+    /// the GPU on Vulkan with timeline semaphores and on DX12. Otherwise the thread
+    /// calling [`Queue::submit`] blocks until the GPU work signaling `value` is done,
+    /// however long it takes.
+    ///
+    /// # Panics
+    ///
+    /// - `timeline` was created from a device of another adapter.
+    /// - On the next submission, if `value` was not signaled on `timeline` by then.
+    pub fn wait_timeline(&self, timeline: &Timeline, value: u64) {
+        Context::queue_wait_timeline(&*self.context, &self.id, &timeline.id, value)
+    }
 }
 
 impl SurfaceTexture {
@@ -3174,6 +3858,13 @@ impl Surface {
         Context::surface_get_preferred_format(&*self.context, &self.id, &adapter.id)
     }
 
+    /// Returns the texture formats the [`Surface`] can be configured with on this adapter.
+    ///
+    /// Returns an empty list if the surface is incompatible with the adapter.
+    pub fn get_supported_formats(&self, adapter: &Adapter) -> Vec<TextureFormat> {
+        Context::surface_get_supported_formats(&*self.context, &self.id, &adapter.id)
+    }
+
     /// Initializes [`Surface`] for presentation.
     ///
     /// # Panics
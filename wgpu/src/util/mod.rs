@@ -5,6 +5,9 @@ mod device;
 mod encoder;
 mod indirect;
 mod init;
+#[cfg(not(target_arch = "wasm32"))]
+mod poller;
+mod profiler;
 
 use std::future::Future;
 use std::{
@@ -18,6 +21,11 @@ pub use device::{BufferInitDescriptor, DeviceExt};
 pub use encoder::RenderEncoder;
 pub use indirect::*;
 pub use init::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use poller::{DevicePoller, Driven};
+pub use profiler::{
+    write_chrome_trace, GpuProfiler, GpuProfilerError, GpuTimerScopeResult, ProfilerCommandRecorder,
+};
 
 /// Treat the given byte slice as a SPIR-V module.
 ///
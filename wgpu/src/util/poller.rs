@@ -0,0 +1,178 @@
+use crate::{Device, Maintain};
+use parking_lot::{Condvar, Mutex};
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{self, Poll},
+    thread,
+    time::Duration,
+};
+
+/// Bounds of the delay between polls while driven futures wait on work that
+/// hasn't been submitted yet, as polling then returns right away.
+const MIN_BACKOFF: Duration = Duration::from_millis(1);
+const MAX_BACKOFF: Duration = Duration::from_millis(16);
+
+#[derive(Debug, Default)]
+struct PollerState {
+    /// Number of driven futures that haven't completed yet.
+    pending: usize,
+    /// Bumped whenever a future starts or stops being driven.
+    changes: u64,
+    shutdown: bool,
+}
+
+#[derive(Debug, Default)]
+struct PollerShared {
+    state: Mutex<PollerState>,
+    condvar: Condvar,
+}
+
+impl PollerShared {
+    fn run(&self, device: &Device) {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let changes = {
+                let mut state = self.state.lock();
+                while state.pending == 0 && !state.shutdown {
+                    self.condvar.wait(&mut state);
+                }
+                if state.shutdown {
+                    return;
+                }
+                state.changes
+            };
+            // Blocks while there is work in flight, and wakes the futures
+            // waiting on it once it's done.
+            device.poll(Maintain::Wait);
+
+            // Nothing may be in flight while the futures haven't been polled
+            // since they were woken, or if they wait on work that isn't submitted
+            // yet. Sleep until a future completes or a new one is driven, backing
+            // off as long as nothing happens.
+            let mut state = self.state.lock();
+            if state.changes == changes && state.pending != 0 && !state.shutdown {
+                if self.condvar.wait_for(&mut state, backoff).timed_out() {
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                } else {
+                    backoff = MIN_BACKOFF;
+                }
+            } else {
+                backoff = MIN_BACKOFF;
+            }
+        }
+    }
+
+    fn change(&self, f: impl FnOnce(&mut PollerState)) {
+        let mut state = self.state.lock();
+        f(&mut state);
+        state.changes += 1;
+        self.condvar.notify_one();
+    }
+
+    fn release(&self) {
+        self.change(|state| state.pending -= 1);
+    }
+}
+
+/// Drives [`Device::poll`] on a background thread, so that futures waiting on the
+/// device make progress without the application polling it.
+///
+/// Futures like the one returned by [`BufferSlice::map_async`](crate::BufferSlice::map_async)
+/// only resolve when the device is polled. Wrapping them with [`DevicePoller::drive`] makes
+/// them usable from any async executor: the poller thread blocks on
+/// [`Maintain::Wait`] while any driven future is pending, waking them as the GPU
+/// finishes work, and sleeps otherwise.
+///
+/// Wrap futures once the work they wait on has been submitted. The poller can't
+/// block on work that isn't submitted yet, so it checks back periodically until
+/// they resolve.
+///
+/// The thread is stopped when the poller is dropped.
+///
+/// ```no_run
+/// # async fn example(device: std::sync::Arc<wgpu::Device>, buffer: wgpu::Buffer) {
+/// let poller = wgpu::util::DevicePoller::new(device);
+/// let slice = buffer.slice(..);
+/// poller.drive(slice.map_async(wgpu::MapMode::Read)).await.unwrap();
+/// let data = slice.get_mapped_range();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DevicePoller {
+    shared: Arc<PollerShared>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl DevicePoller {
+    /// Spawns a thread polling `device`.
+    pub fn new(device: Arc<Device>) -> Self {
+        let shared = Arc::new(PollerShared::default());
+        let thread_shared = Arc::clone(&shared);
+        let thread = thread::Builder::new()
+            .name("wgpu device poller".to_string())
+            .spawn(move || thread_shared.run(&device))
+            .expect("Failed to spawn the device poller thread");
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Keeps the device polled until `future` resolves or is dropped.
+    pub fn drive<F: Future>(&self, future: F) -> Driven<F> {
+        self.shared.change(|state| state.pending += 1);
+        Driven {
+            future: Box::pin(future),
+            shared: Arc::clone(&self.shared),
+            done: false,
+        }
+    }
+}
+
+impl Drop for DevicePoller {
+    fn drop(&mut self) {
+        self.shared.change(|state| state.shutdown = true);
+        if let Some(thread) = self.thread.take() {
+            // A panic on the poller thread has already been reported.
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A future driven by a [`DevicePoller`], returned by [`DevicePoller::drive`].
+pub struct Driven<F> {
+    future: Pin<Box<F>>,
+    shared: Arc<PollerShared>,
+    done: bool,
+}
+
+impl<F: Future> Future for Driven<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let poll = this.future.as_mut().poll(cx);
+        if poll.is_ready() && !this.done {
+            this.done = true;
+            this.shared.release();
+        }
+        poll
+    }
+}
+
+impl<F> Drop for Driven<F> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.shared.release();
+        }
+    }
+}
+
+impl<F> fmt::Debug for Driven<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Driven").field("done", &self.done).finish()
+    }
+}
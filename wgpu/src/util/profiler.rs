@@ -0,0 +1,504 @@
+use crate::{
+    Buffer, BufferAddress, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder,
+    ComputePass, Device, Features, MapMode, QuerySet, QuerySetDescriptor, QueryType, Queue,
+    RenderPass, QUERY_SIZE,
+};
+use std::{
+    error, fmt,
+    future::Future,
+    io,
+    ops::Range,
+    pin::Pin,
+    ptr,
+    task::{self, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+/// Number of timestamp queries allocated per query set.
+const QUERY_POOL_CAPACITY: u32 = 64;
+
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), BufferAsyncError>> + Send>>;
+
+/// Anything timestamps can be written into: command encoders and passes.
+pub trait ProfilerCommandRecorder {
+    /// Writes a timestamp into the given query set at the given index.
+    fn write_timestamp(&mut self, query_set: &QuerySet, query_index: u32);
+}
+
+impl ProfilerCommandRecorder for CommandEncoder {
+    fn write_timestamp(&mut self, query_set: &QuerySet, query_index: u32) {
+        CommandEncoder::write_timestamp(self, query_set, query_index)
+    }
+}
+
+impl<'a> ProfilerCommandRecorder for RenderPass<'a> {
+    fn write_timestamp(&mut self, query_set: &QuerySet, query_index: u32) {
+        RenderPass::write_timestamp(self, query_set, query_index)
+    }
+}
+
+impl<'a> ProfilerCommandRecorder for ComputePass<'a> {
+    fn write_timestamp(&mut self, query_set: &QuerySet, query_index: u32) {
+        ComputePass::write_timestamp(self, query_set, query_index)
+    }
+}
+
+/// Timing results of a single profiler scope, in seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuTimerScopeResult {
+    /// Label the scope was opened with.
+    pub label: String,
+    /// Start and end of the scope, in seconds.
+    ///
+    /// Only differences between values are meaningful, the origin is arbitrary.
+    pub time: Range<f64>,
+    /// Scopes that were opened while this one was open.
+    pub nested_scopes: Vec<GpuTimerScopeResult>,
+}
+
+/// Error returned by [`GpuProfiler`] when it is misused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GpuProfilerError {
+    /// [`GpuProfiler::end_scope`] was called without a matching [`GpuProfiler::begin_scope`].
+    NoOpenScope,
+    /// The frame was ended while some scopes were still open.
+    UnclosedScopes(usize),
+    /// The frame was ended before [`GpuProfiler::resolve_queries`] was called.
+    UnresolvedQueries,
+    /// Too many frames are waiting for their results, the frame was dropped.
+    PendingFrameQueueFull,
+}
+
+impl fmt::Display for GpuProfilerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NoOpenScope => write!(f, "No profiler scope is open"),
+            Self::UnclosedScopes(count) => {
+                write!(
+                    f,
+                    "{} profiler scope(s) were left open at the end of the frame",
+                    count
+                )
+            }
+            Self::UnresolvedQueries => {
+                write!(
+                    f,
+                    "Profiler queries were not resolved before the end of the frame"
+                )
+            }
+            Self::PendingFrameQueueFull => write!(
+                f,
+                "Too many profiler frames are pending, call `process_finished_frame` more often"
+            ),
+        }
+    }
+}
+
+impl error::Error for GpuProfilerError {}
+
+struct QueryPool {
+    query_set: QuerySet,
+    buffer: Buffer,
+    num_used: u32,
+    resolved: bool,
+}
+
+impl QueryPool {
+    fn new(device: &Device) -> Self {
+        QueryPool {
+            query_set: device.create_query_set(&QuerySetDescriptor {
+                label: Some("profiler queries"),
+                ty: QueryType::Timestamp,
+                count: QUERY_POOL_CAPACITY,
+            }),
+            buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("profiler resolve"),
+                size: (QUERY_POOL_CAPACITY * QUERY_SIZE) as BufferAddress,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            num_used: 0,
+            resolved: false,
+        }
+    }
+
+    fn used_size(&self) -> BufferAddress {
+        (self.num_used * QUERY_SIZE) as BufferAddress
+    }
+}
+
+/// Location of the start query of a scope. The end query directly follows it.
+#[derive(Clone, Copy)]
+struct QueryLocation {
+    pool: usize,
+    index: u32,
+}
+
+struct OpenScope {
+    label: String,
+    query: Option<QueryLocation>,
+    nested_scopes: Vec<ClosedScope>,
+}
+
+struct ClosedScope {
+    label: String,
+    query: QueryLocation,
+    nested_scopes: Vec<ClosedScope>,
+}
+
+struct PendingFrame {
+    pools: Vec<QueryPool>,
+    scopes: Vec<ClosedScope>,
+    map_futures: Vec<Option<MapFuture>>,
+    failed: bool,
+}
+
+/// Profiler measuring GPU time spent within nested, named scopes.
+///
+/// Scopes are opened and closed on command encoders or passes with [`GpuProfiler::begin_scope`]
+/// and [`GpuProfiler::end_scope`]. Query sets and the buffers they are resolved into are
+/// allocated on demand and recycled once the results have been read back.
+///
+/// Using the profiler generally goes as follows, once per frame:
+/// - Record scopes with `begin_scope` and `end_scope`.
+/// - Call `resolve_queries` on the last encoder of the frame.
+/// - Submit all command encoders used with the profiler.
+/// - Call `end_frame`.
+/// - Call `process_finished_frame` to retrieve the results of a previous frame, if available.
+///
+/// As with any buffer mapping, `device.poll(...)` needs to be called for results to become available.
+///
+/// If [`Features::TIMESTAMP_QUERY`] is not enabled on the device, scopes are still tracked
+/// but no timestamps are written and no results are ever produced.
+pub struct GpuProfiler {
+    enabled: bool,
+    timestamp_period: f32,
+    max_pending_frames: usize,
+    /// Pools that queries of the current frame are allocated from.
+    active_pools: Vec<QueryPool>,
+    /// Pools that are back from the GPU and ready to be used.
+    free_pools: Vec<QueryPool>,
+    open_scopes: Vec<OpenScope>,
+    closed_scopes: Vec<ClosedScope>,
+    pending_frames: Vec<PendingFrame>,
+}
+
+impl GpuProfiler {
+    /// Create a new profiler.
+    ///
+    /// `max_pending_frames` is the number of frames whose results may be awaited at the same time.
+    /// Results are typically available 2-3 frames later.
+    pub fn new(device: &Device, queue: &Queue, max_pending_frames: usize) -> Self {
+        assert!(
+            max_pending_frames > 0,
+            "max_pending_frames must be at least 1"
+        );
+        GpuProfiler {
+            enabled: device.features().contains(Features::TIMESTAMP_QUERY),
+            timestamp_period: queue.get_timestamp_period(),
+            max_pending_frames,
+            active_pools: Vec::new(),
+            free_pools: Vec::new(),
+            open_scopes: Vec::new(),
+            closed_scopes: Vec::new(),
+            pending_frames: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the profiler writes timestamps, i.e. [`Features::TIMESTAMP_QUERY`] is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Open a new scope, nested into the currently open one if any.
+    pub fn begin_scope<R: ProfilerCommandRecorder>(
+        &mut self,
+        label: &str,
+        recorder: &mut R,
+        device: &Device,
+    ) {
+        let query = if self.enabled {
+            let query = self.allocate_query_pair(device);
+            recorder.write_timestamp(&self.active_pools[query.pool].query_set, query.index);
+            Some(query)
+        } else {
+            None
+        };
+        self.open_scopes.push(OpenScope {
+            label: label.to_string(),
+            query,
+            nested_scopes: Vec::new(),
+        });
+    }
+
+    /// Close the most recently opened scope.
+    ///
+    /// The recorder doesn't have to be the one the scope was opened on, but it has to be
+    /// executed after it.
+    pub fn end_scope<R: ProfilerCommandRecorder>(
+        &mut self,
+        recorder: &mut R,
+    ) -> Result<(), GpuProfilerError> {
+        let scope = self
+            .open_scopes
+            .pop()
+            .ok_or(GpuProfilerError::NoOpenScope)?;
+        let query = match scope.query {
+            Some(query) => query,
+            None => return Ok(()),
+        };
+        recorder.write_timestamp(&self.active_pools[query.pool].query_set, query.index + 1);
+
+        let closed = ClosedScope {
+            label: scope.label,
+            query,
+            nested_scopes: scope.nested_scopes,
+        };
+        match self.open_scopes.last_mut() {
+            Some(parent) => parent.nested_scopes.push(closed),
+            None => self.closed_scopes.push(closed),
+        }
+        Ok(())
+    }
+
+    /// Resolve all queries written so far into their readback buffers.
+    ///
+    /// Must be called at least once per frame after the last scope was closed,
+    /// on an encoder that is submitted after all other encoders used with the profiler.
+    pub fn resolve_queries(&mut self, encoder: &mut CommandEncoder) {
+        for pool in self.active_pools.iter_mut().filter(|pool| !pool.resolved) {
+            if pool.num_used != 0 {
+                encoder.resolve_query_set(&pool.query_set, 0..pool.num_used, &pool.buffer, 0);
+            }
+            pool.resolved = true;
+        }
+    }
+
+    /// Mark the end of a frame, starting the readback of its results.
+    ///
+    /// Must be called after the command buffers of the frame have been submitted.
+    pub fn end_frame(&mut self) -> Result<(), GpuProfilerError> {
+        if !self.open_scopes.is_empty() {
+            return Err(GpuProfilerError::UnclosedScopes(self.open_scopes.len()));
+        }
+        if self.active_pools.iter().any(|pool| !pool.resolved) {
+            return Err(GpuProfilerError::UnresolvedQueries);
+        }
+
+        let pools = std::mem::take(&mut self.active_pools);
+        let scopes = std::mem::take(&mut self.closed_scopes);
+        if pools.is_empty() {
+            return Ok(());
+        }
+        if self.pending_frames.len() >= self.max_pending_frames {
+            // The pools are dropped along with the frame, recycling them isn't possible
+            // since they may still be in use by the GPU.
+            return Err(GpuProfilerError::PendingFrameQueueFull);
+        }
+
+        let map_futures = pools
+            .iter()
+            .map(|pool| {
+                let future: MapFuture = Box::pin(
+                    pool.buffer
+                        .slice(..pool.used_size())
+                        .map_async(MapMode::Read),
+                );
+                Some(future)
+            })
+            .collect();
+        self.pending_frames.push(PendingFrame {
+            pools,
+            scopes,
+            map_futures,
+            failed: false,
+        });
+        Ok(())
+    }
+
+    /// Retrieve the results of the oldest pending frame if they are available.
+    ///
+    /// Returns `None` if no frame is ready yet, or if the profiler is disabled.
+    pub fn process_finished_frame(&mut self) -> Option<Vec<GpuTimerScopeResult>> {
+        let frame = self.pending_frames.first_mut()?;
+
+        let waker = noop_waker();
+        let mut cx = task::Context::from_waker(&waker);
+        for slot in frame.map_futures.iter_mut() {
+            if let Some(future) = slot {
+                if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+                    frame.failed |= result.is_err();
+                    *slot = None;
+                }
+            }
+        }
+        if frame.map_futures.iter().any(Option::is_some) {
+            return None;
+        }
+
+        let frame = self.pending_frames.remove(0);
+        if frame.failed {
+            log::warn!("Failed to map profiler results, dropping the frame");
+            return None;
+        }
+
+        let timestamps = frame
+            .pools
+            .iter()
+            .map(|pool| {
+                let view = pool.buffer.slice(..pool.used_size()).get_mapped_range();
+                view.chunks_exact(QUERY_SIZE as usize)
+                    .map(|bytes| {
+                        let mut raw = [0u8; QUERY_SIZE as usize];
+                        raw.copy_from_slice(bytes);
+                        u64::from_ne_bytes(raw)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let results = self.build_results(frame.scopes, &timestamps);
+
+        for mut pool in frame.pools {
+            pool.buffer.unmap();
+            pool.num_used = 0;
+            pool.resolved = false;
+            self.free_pools.push(pool);
+        }
+        Some(results)
+    }
+
+    fn allocate_query_pair(&mut self, device: &Device) -> QueryLocation {
+        let has_room = match self.active_pools.last() {
+            Some(pool) => !pool.resolved && pool.num_used + 2 <= QUERY_POOL_CAPACITY,
+            None => false,
+        };
+        if !has_room {
+            let pool = self
+                .free_pools
+                .pop()
+                .unwrap_or_else(|| QueryPool::new(device));
+            self.active_pools.push(pool);
+        }
+
+        let pool_index = self.active_pools.len() - 1;
+        let pool = &mut self.active_pools[pool_index];
+        let index = pool.num_used;
+        pool.num_used += 2;
+        QueryLocation {
+            pool: pool_index,
+            index,
+        }
+    }
+
+    fn build_results(
+        &self,
+        scopes: Vec<ClosedScope>,
+        timestamps: &[Vec<u64>],
+    ) -> Vec<GpuTimerScopeResult> {
+        let to_seconds = self.timestamp_period as f64 / 1_000_000_000.0;
+        scopes
+            .into_iter()
+            .map(|scope| {
+                let pool = &timestamps[scope.query.pool];
+                let index = scope.query.index as usize;
+                GpuTimerScopeResult {
+                    label: scope.label,
+                    time: pool[index] as f64 * to_seconds..pool[index + 1] as f64 * to_seconds,
+                    nested_scopes: self.build_results(scope.nested_scopes, timestamps),
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for GpuProfiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpuProfiler")
+            .field("enabled", &self.enabled)
+            .field("active_pools", &self.active_pools.len())
+            .field("free_pools", &self.free_pools.len())
+            .field("open_scopes", &self.open_scopes.len())
+            .field("pending_frames", &self.pending_frames.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    // This is safe because the vtable functions don't access the data pointer.
+    unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+}
+
+/// Write profiler results in the Chrome trace event format.
+///
+/// The output can be loaded into `chrome://tracing` or any compatible viewer.
+pub fn write_chrome_trace<W: io::Write>(
+    mut target: W,
+    results: &[GpuTimerScopeResult],
+) -> io::Result<()> {
+    fn write_events<W: io::Write>(
+        target: &mut W,
+        results: &[GpuTimerScopeResult],
+        first: &mut bool,
+    ) -> io::Result<()> {
+        for result in results {
+            if !*first {
+                write!(target, ",")?;
+            }
+            *first = false;
+            write!(
+                target,
+                r#"{{"pid":1,"tid":1,"ph":"X","ts":{},"dur":{},"name":""#,
+                result.time.start * 1_000_000.0,
+                (result.time.end - result.time.start) * 1_000_000.0,
+            )?;
+            for c in result.label.chars() {
+                match c {
+                    '"' => write!(target, "\\\"")?,
+                    '\\' => write!(target, "\\\\")?,
+                    c if (c as u32) < 0x20 => write!(target, "\\u{:04x}", c as u32)?,
+                    c => write!(target, "{}", c)?,
+                }
+            }
+            write!(target, "\"}}")?;
+            write_events(target, &result.nested_scopes, first)?;
+        }
+        Ok(())
+    }
+
+    write!(target, r#"{{"traceEvents":["#)?;
+    write_events(&mut target, results, &mut true)?;
+    write!(target, "]}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chrome_trace() {
+        let results = vec![GpuTimerScopeResult {
+            label: "frame".to_string(),
+            time: 1.0..2.0,
+            nested_scopes: vec![GpuTimerScopeResult {
+                label: "\"shadow\"\n".to_string(),
+                time: 1.0..1.5,
+                nested_scopes: Vec::new(),
+            }],
+        }];
+        let mut output = Vec::new();
+        write_chrome_trace(&mut output, &results).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"traceEvents":["#,
+                r#"{"pid":1,"tid":1,"ph":"X","ts":1000000,"dur":1000000,"name":"frame"},"#,
+                r#"{"pid":1,"tid":1,"ph":"X","ts":1000000,"dur":500000,"name":"\"shadow\"\u000a"}"#,
+                "]}",
+            )
+        );
+    }
+}
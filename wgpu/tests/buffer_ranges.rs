@@ -0,0 +1,82 @@
+use crate::common::{initialize_test, TestParameters, TestingContext};
+
+const SIZE: wgpu::BufferAddress = 512;
+const HALF: wgpu::BufferAddress = SIZE / 2;
+
+/// Copy `buffer` to a readable buffer, and read back each of its halves
+/// through its own mapping.
+fn read_halves(ctx: &TestingContext, buffer: &wgpu::Buffer) -> (Vec<u8>, Vec<u8>) {
+    let readback = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: SIZE,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    encoder.copy_buffer_to_buffer(buffer, 0, &readback, 0, SIZE);
+    ctx.queue.submit(Some(encoder.finish()));
+
+    let first = readback.slice(..HALF);
+    let second = readback.slice(HALF..);
+    let first_mapped = first.map_async(wgpu::MapMode::Read);
+    let second_mapped = second.map_async(wgpu::MapMode::Read);
+    ctx.device.poll(wgpu::Maintain::Wait);
+    pollster::block_on(first_mapped).unwrap();
+    pollster::block_on(second_mapped).unwrap();
+
+    // Unmapping one range leaves the other one readable.
+    let second_data = second.get_mapped_range().to_vec();
+    second.unmap();
+    let first_data = first.get_mapped_range().to_vec();
+    first.unmap();
+    (first_data, second_data)
+}
+
+#[test]
+fn map_and_unmap_disjoint_ranges() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: SIZE,
+            usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let first = buffer.slice(..HALF);
+        let second = buffer.slice(HALF..);
+        let first_mapped = first.map_async(wgpu::MapMode::Write);
+        let second_mapped = second.map_async(wgpu::MapMode::Write);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(first_mapped).unwrap();
+        pollster::block_on(second_mapped).unwrap();
+
+        first.get_mapped_range_mut().fill(1);
+        first.unmap();
+
+        // The second range stays accessible after the first one is unmapped.
+        second.get_mapped_range_mut().fill(2);
+        second.unmap();
+
+        // Both writes have to be flushed on unmap for the GPU to see them.
+        let (first_data, second_data) = read_halves(&ctx, &buffer);
+        assert_eq!(first_data, [1; HALF as usize]);
+        assert_eq!(second_data, [2; HALF as usize]);
+
+        // An unmapped range can be mapped again on its own.
+        let remapped = first.map_async(wgpu::MapMode::Write);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(remapped).unwrap();
+        {
+            let mut view = first.get_mapped_range_mut();
+            assert_eq!(view.len(), HALF as usize);
+            view.fill(3);
+        }
+        buffer.unmap();
+
+        let (first_data, second_data) = read_halves(&ctx, &buffer);
+        assert_eq!(first_data, [3; HALF as usize]);
+        assert_eq!(second_data, [2; HALF as usize]);
+    })
+}
@@ -0,0 +1,401 @@
+use std::{
+    num::NonZeroU32,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use wgpu::util::DeviceExt;
+
+use crate::common::{initialize_test, TestParameters, TestingContext};
+
+const SLOTS: u32 = 4;
+
+fn table_features() -> wgpu::Features {
+    wgpu::Features::TEXTURE_BINDING_ARRAY | wgpu::Features::PARTIALLY_BOUND_BINDING_ARRAY
+}
+
+fn table_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: None,
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: NonZeroU32::new(SLOTS),
+        }],
+    })
+}
+
+/// Create a 1x1 texture of each of `colors`.
+fn texel_views(ctx: &TestingContext, colors: &[[u8; 4]]) -> Vec<wgpu::TextureView> {
+    colors
+        .iter()
+        .map(|color| {
+            ctx.device
+                .create_texture_with_data(
+                    &ctx.queue,
+                    &wgpu::TextureDescriptor {
+                        label: None,
+                        size: wgpu::Extent3d {
+                            width: 1,
+                            height: 1,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    },
+                    color,
+                )
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        })
+        .collect()
+}
+
+/// Record a compute pass binding `table`, dispatching `workgroups` of `pipeline` if given.
+fn encode_with_table(
+    ctx: &TestingContext,
+    table: &wgpu::DescriptorTable,
+    pipeline: Option<(&wgpu::ComputePipeline, &wgpu::BindGroup, u32)>,
+) -> wgpu::CommandBuffer {
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        pass.set_bind_group(0, table, &[]);
+        if let Some((pipeline, output_group, workgroups)) = pipeline {
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(1, output_group, &[]);
+            pass.dispatch(workgroups, 1, 1);
+        }
+    }
+    encoder.finish()
+}
+
+/// Runs `read.comp`, which copies the texel of each slot into a buffer.
+struct TexelReader {
+    pipeline: wgpu::ComputePipeline,
+    output: wgpu::Buffer,
+    output_group: wgpu::BindGroup,
+    readback: wgpu::Buffer,
+}
+
+impl TexelReader {
+    const SIZE: wgpu::BufferAddress = SLOTS as wgpu::BufferAddress * 16;
+
+    fn new(ctx: &TestingContext, table_layout: &wgpu::BindGroupLayout) -> Self {
+        // Binding arrays can't be expressed in WGSL yet.
+        let module = unsafe {
+            ctx.device
+                .create_shader_module_spirv(&wgpu::ShaderModuleDescriptorSpirV {
+                    label: Some("read.comp"),
+                    source: wgpu::util::make_spirv_raw(include_bytes!("read.comp.spv")),
+                })
+        };
+        let output_layout = ctx
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let pipeline_layout = ctx
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[table_layout, &output_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = ctx
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: "main",
+            });
+
+        let output = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: Self::SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let output_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &output_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: output.as_entire_binding(),
+            }],
+        });
+        let readback = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: Self::SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            pipeline,
+            output,
+            output_group,
+            readback,
+        }
+    }
+
+    fn encode(
+        &self,
+        ctx: &TestingContext,
+        table: &wgpu::DescriptorTable,
+        workgroups: u32,
+    ) -> wgpu::CommandBuffer {
+        encode_with_table(
+            ctx,
+            table,
+            Some((&self.pipeline, &self.output_group, workgroups)),
+        )
+    }
+
+    fn read(&self, ctx: &TestingContext, table: &wgpu::DescriptorTable) -> Vec<[f32; 4]> {
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&self.output, 0, &self.readback, 0, Self::SIZE);
+        ctx.queue
+            .submit(vec![self.encode(ctx, table, 1), encoder.finish()]);
+
+        let slice = self.readback.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping).unwrap();
+        let texels = bytemuck::cast_slice(&*slice.get_mapped_range()).to_vec();
+        self.readback.unmap();
+        texels
+    }
+}
+
+#[test]
+fn update_slots_between_submissions() {
+    initialize_test(
+        TestParameters::default().features(table_features()),
+        |ctx| {
+            let layout = table_layout(&ctx.device);
+            let table = ctx
+                .device
+                .create_descriptor_table(&wgpu::DescriptorTableDescriptor {
+                    label: Some("table"),
+                    layout: &layout,
+                });
+            let views = texel_views(&ctx, &[[0; 4]; 3]);
+
+            table.update_slots(0..2, &[&views[0], &views[1]]);
+            ctx.queue
+                .submit(Some(encode_with_table(&ctx, &table, None)));
+            ctx.device.poll(wgpu::Maintain::Wait);
+
+            // Once the submission is done, slots can be overwritten, including
+            // the ones referencing views that were dropped in the meantime.
+            let mut views = views.into_iter();
+            drop(views.next());
+            let second = views.next().unwrap();
+            let third = views.next().unwrap();
+            table.update_slots(0..1, &[&third]);
+            table.update_slots(SLOTS - 1..SLOTS, &[&second]);
+            ctx.queue
+                .submit(Some(encode_with_table(&ctx, &table, None)));
+            ctx.device.poll(wgpu::Maintain::Wait);
+        },
+    )
+}
+
+#[test]
+fn read_slots_from_shader() {
+    initialize_test(
+        TestParameters::default()
+            .features(table_features() | wgpu::Features::SPIRV_SHADER_PASSTHROUGH),
+        |ctx| {
+            let layout = table_layout(&ctx.device);
+            let table = ctx
+                .device
+                .create_descriptor_table(&wgpu::DescriptorTableDescriptor {
+                    label: Some("table"),
+                    layout: &layout,
+                });
+            let views = texel_views(
+                &ctx,
+                &[
+                    [255, 0, 0, 255],
+                    [0, 255, 0, 255],
+                    [0, 0, 255, 255],
+                    [255, 255, 255, 0],
+                ],
+            );
+            let reader = TexelReader::new(&ctx, &layout);
+
+            table.update_slots(0..SLOTS, &views.iter().collect::<Vec<_>>());
+            assert_eq!(
+                reader.read(&ctx, &table),
+                [
+                    [1.0, 0.0, 0.0, 1.0],
+                    [0.0, 1.0, 0.0, 1.0],
+                    [0.0, 0.0, 1.0, 1.0],
+                    [1.0, 1.0, 1.0, 0.0],
+                ]
+            );
+
+            table.update_slots(1..3, &[&views[3], &views[0]]);
+            assert_eq!(
+                reader.read(&ctx, &table),
+                [
+                    [1.0, 0.0, 0.0, 1.0],
+                    [1.0, 1.0, 1.0, 0.0],
+                    [1.0, 0.0, 0.0, 1.0],
+                    [1.0, 1.0, 1.0, 0.0],
+                ]
+            );
+        },
+    )
+}
+
+#[test]
+fn update_slots_errors() {
+    initialize_test(
+        TestParameters::default()
+            .features(table_features() | wgpu::Features::SPIRV_SHADER_PASSTHROUGH),
+        |ctx| {
+            let layout = table_layout(&ctx.device);
+            let table = ctx
+                .device
+                .create_descriptor_table(&wgpu::DescriptorTableDescriptor {
+                    label: Some("table"),
+                    layout: &layout,
+                });
+            let views = texel_views(&ctx, &[[0; 4]; SLOTS as usize]);
+            let all_views = views.iter().collect::<Vec<_>>();
+            let reader = TexelReader::new(&ctx, &layout);
+            table.update_slots(0..SLOTS, &all_views);
+
+            let expect_error = |update: &dyn Fn(), message: &str| {
+                ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+                update();
+                match pollster::block_on(ctx.device.pop_error_scope()) {
+                    Some(wgpu::Error::Validation { description, .. }) => assert!(
+                        description.contains(message),
+                        "expected '{}' in:\n{}",
+                        message,
+                        description
+                    ),
+                    error => panic!("expected a validation error, got {:?}", error),
+                }
+            };
+
+            expect_error(
+                &|| table.update_slots(SLOTS - 1..SLOTS + 1, &all_views[..2]),
+                "out of bounds",
+            );
+
+            // Enough work that the submission is still running when the
+            // update is attempted right after it.
+            ctx.queue.submit(Some(reader.encode(&ctx, &table, 65535)));
+            expect_error(
+                &|| table.update_slots(0..1, &all_views[..1]),
+                "in use by a submission",
+            );
+            ctx.device.poll(wgpu::Maintain::Wait);
+
+            // Command buffers recorded before an update see the old slots,
+            // so they can't be submitted anymore.
+            let outdated = encode_with_table(&ctx, &table, None);
+            table.update_slots(0..1, &all_views[1..2]);
+            let panic = catch_unwind(AssertUnwindSafe(|| ctx.queue.submit(Some(outdated))))
+                .expect_err("submitting an outdated command buffer should fail");
+            let message = panic.downcast_ref::<String>().unwrap();
+            assert!(message.contains("was updated after"), "{}", message);
+        },
+    )
+}
+
+#[test]
+fn clear_slots() {
+    initialize_test(
+        TestParameters::default().features(table_features()),
+        |ctx| {
+            let layout = table_layout(&ctx.device);
+            let table = ctx
+                .device
+                .create_descriptor_table(&wgpu::DescriptorTableDescriptor {
+                    label: Some("table"),
+                    layout: &layout,
+                });
+            let views = texel_views(&ctx, &[[0; 4]; 2]);
+            table.update_slots(0..SLOTS, &[&views[0], &views[0], &views[1], &views[1]]);
+
+            // Views stay bound as long as one of their slots holds them.
+            ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            table.clear_slots(0..1);
+            table.clear_slots(2..2);
+            ctx.queue
+                .submit(Some(encode_with_table(&ctx, &table, None)));
+            ctx.device.poll(wgpu::Maintain::Wait);
+            table.clear_slots(1..SLOTS);
+            drop(views);
+            ctx.queue
+                .submit(Some(encode_with_table(&ctx, &table, None)));
+            ctx.device.poll(wgpu::Maintain::Wait);
+            assert!(pollster::block_on(ctx.device.pop_error_scope()).is_none());
+
+            // Cleared slots can be written again.
+            let views = texel_views(&ctx, &[[0; 4]]);
+            table.update_slots(1..2, &[&views[0]]);
+            ctx.queue
+                .submit(Some(encode_with_table(&ctx, &table, None)));
+            ctx.device.poll(wgpu::Maintain::Wait);
+
+            ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            table.clear_slots(SLOTS - 1..SLOTS + 1);
+            let error = pollster::block_on(ctx.device.pop_error_scope()).unwrap();
+            assert!(error.to_string().contains("out of bounds"), "{}", error);
+
+            assert!(catch_unwind(AssertUnwindSafe(|| table.clear_slots(2..1))).is_err());
+        },
+    )
+}
+
+#[test]
+fn create_table_without_features() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let layout = ctx
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[],
+            });
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = ctx
+            .device
+            .create_descriptor_table(&wgpu::DescriptorTableDescriptor {
+                label: None,
+                layout: &layout,
+            });
+        match pollster::block_on(ctx.device.pop_error_scope()) {
+            Some(wgpu::Error::Validation { description, .. }) => {
+                assert!(description.contains("not enabled"), "{}", description)
+            }
+            error => panic!("expected a validation error, got {:?}", error),
+        }
+    })
+}
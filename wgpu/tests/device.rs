@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
 use crate::common::{initialize_test, TestParameters};
 
 #[test]
@@ -6,3 +10,262 @@ fn device_initialization() {
         // intentionally empty
     })
 }
+
+#[test]
+fn device_destroy() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let reasons_clone = Arc::clone(&reasons);
+        ctx.device
+            .set_device_lost_callback(move |reason, _message| reasons_clone.lock().push(reason));
+
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mapping = buffer.slice(..).map_async(wgpu::MapMode::Read);
+
+        ctx.device.destroy();
+        assert!(pollster::block_on(mapping).is_err());
+        assert_eq!(*reasons.lock(), [wgpu::DeviceLostReason::Destroyed]);
+
+        // Callbacks set on a lost device are called right away.
+        let reasons_clone = Arc::clone(&reasons);
+        ctx.device
+            .set_device_lost_callback(move |reason, _message| reasons_clone.lock().push(reason));
+        assert_eq!(reasons.lock().len(), 2);
+
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_some());
+
+        // Queue operations on a destroyed device do nothing.
+        ctx.queue.submit(None);
+    })
+}
+
+#[test]
+fn device_lost() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let calls_clone = Arc::clone(&calls);
+        ctx.device.set_device_lost_callback(move |reason, message| {
+            calls_clone.lock().push((reason, message))
+        });
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        ctx.device.destroy();
+        ctx.device.destroy();
+        ctx.device.poll(wgpu::Maintain::Poll);
+        {
+            let calls = calls.lock();
+            assert_eq!(calls.len(), 1);
+            assert_eq!(calls[0].0, wgpu::DeviceLostReason::Destroyed);
+            assert!(!calls[0].1.is_empty());
+        }
+
+        // Creating objects reports the loss.
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let error = pollster::block_on(ctx.device.pop_error_scope()).unwrap();
+        assert!(error.to_string().contains("lost"), "{}", error);
+
+        // Queue writes do nothing.
+        ctx.queue.write_buffer(&buffer, 0, &[1; 16]);
+        ctx.queue.submit(None);
+        assert_eq!(calls.lock().len(), 1);
+    })
+}
+
+#[test]
+fn device_destroy_keeps_mapped_buffers() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let created_mapped = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        let mapped = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_WRITE,
+            mapped_at_creation: false,
+        });
+        let mapping = mapped.slice(..).map_async(wgpu::MapMode::Write);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping).unwrap();
+
+        let mut created_view = created_mapped.slice(..).get_mapped_range_mut();
+        let mut view = mapped.slice(..).get_mapped_range_mut();
+        ctx.device.destroy();
+
+        // The mapped memory stays accessible until it's unmapped.
+        created_view.fill(1);
+        view.fill(2);
+        assert_eq!(&created_view[..], &[1; 16]);
+        assert_eq!(&view[..], &[2; 16]);
+        drop(created_view);
+        drop(view);
+        created_mapped.unmap();
+        mapped.unmap();
+        ctx.device.poll(wgpu::Maintain::Poll);
+    })
+}
+
+#[test]
+fn device_destroy_frees_other_objects() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let before = ctx.device.memory_report();
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = ctx
+            .device
+            .create_sampler(&wgpu::SamplerDescriptor::default());
+        let layout = ctx
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        let query_set = ctx.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: None,
+            ty: wgpu::QueryType::Occlusion,
+            count: 4,
+        });
+        let encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        let report = ctx.device.memory_report();
+        assert!(report.query_sets > before.query_sets);
+
+        ctx.device.destroy();
+        let after = ctx.device.memory_report();
+        assert_eq!(after.query_sets, before.query_sets);
+        assert_eq!(after.textures, before.textures);
+
+        // Dropping the freed objects afterwards is fine, in any order.
+        drop(texture);
+        drop(bind_group);
+        drop(layout);
+        drop(sampler);
+        drop(view);
+        drop(query_set);
+        drop(encoder);
+        ctx.device.poll(wgpu::Maintain::Poll);
+    })
+}
+
+#[test]
+fn device_memory_report() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let before = ctx.device.memory_report();
+
+        let _buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 1024,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+        let report = ctx.device.memory_report();
+        assert_eq!(report.buffers, before.buffers + 1024);
+        assert_eq!(report.textures, before.textures + 64 * 64 * 4);
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = Arc::clone(&reports);
+        ctx.device
+            .set_memory_threshold_callback(report.total() + 1, move |report| {
+                reports_clone.lock().push(report)
+            });
+        let other_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 1024,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Checked on creation already, and only reported once while above.
+        assert_eq!(reports.lock().len(), 1);
+        ctx.device.poll(wgpu::Maintain::Poll);
+        ctx.device.poll(wgpu::Maintain::Poll);
+        assert_eq!(reports.lock().len(), 1);
+
+        // Destroyed and dropped resources don't count anymore.
+        other_buffer.destroy();
+        drop(texture);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        let after = ctx.device.memory_report();
+        assert_eq!(after.buffers, report.buffers);
+        assert_eq!(after.textures, before.textures);
+    })
+}
@@ -0,0 +1,153 @@
+use crate::common::{initialize_test, TestParameters};
+
+const SHADER_A: &str = "
+    @group(0) @binding(0)
+    var<storage, read_write> values: array<u32>;
+
+    @stage(compute) @workgroup_size(1)
+    fn main() {
+        values[0] = 1u;
+    }
+";
+
+const SHADER_B: &str = "
+    @group(0) @binding(0)
+    var<storage, read_write> values: array<u32>;
+
+    @stage(compute) @workgroup_size(1)
+    fn main() {
+        values[0] = values[0] + 1u;
+    }
+";
+
+#[test]
+fn derived_layouts_are_compatible() {
+    initialize_test(
+        TestParameters::default().downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+        |ctx| {
+            let create_pipeline = |source: &str| {
+                let module = ctx
+                    .device
+                    .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                        label: None,
+                        source: wgpu::ShaderSource::Wgsl(source.into()),
+                    });
+                ctx.device
+                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: None,
+                        layout: None,
+                        module: &module,
+                        entry_point: "main",
+                        constants: &[],
+                        cache: None,
+                    })
+            };
+            let pipeline_a = create_pipeline(SHADER_A);
+            let pipeline_b = create_pipeline(SHADER_B);
+
+            let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 4,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+            let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &pipeline_a.get_bind_group_layout(0),
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+
+            // The bind group of one pipeline's derived layout can be used with the other.
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.set_pipeline(&pipeline_a);
+                pass.dispatch(1, 1, 1);
+                pass.set_pipeline(&pipeline_b);
+                pass.dispatch(1, 1, 1);
+            }
+            ctx.queue.submit(Some(encoder.finish()));
+        },
+    )
+}
+
+#[test]
+fn explicit_layouts_are_compatible() {
+    initialize_test(
+        TestParameters::default().downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+        |ctx| {
+            let create_layout = || {
+                ctx.device
+                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: None,
+                        entries: &[wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        }],
+                    })
+            };
+            let bind_group_layout = create_layout();
+            let pipeline_layout =
+                ctx.device
+                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: None,
+                        bind_group_layouts: &[&create_layout()],
+                        push_constant_ranges: &[],
+                    });
+            let module = ctx
+                .device
+                .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                    label: None,
+                    source: wgpu::ShaderSource::Wgsl(SHADER_A.into()),
+                });
+            let pipeline = ctx
+                .device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: None,
+                    layout: Some(&pipeline_layout),
+                    module: &module,
+                    entry_point: "main",
+                    constants: &[],
+                    cache: None,
+                });
+
+            let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 4,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+            let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch(1, 1, 1);
+            }
+            ctx.queue.submit(Some(encoder.finish()));
+        },
+    )
+}
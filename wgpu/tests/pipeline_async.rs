@@ -0,0 +1,99 @@
+use crate::common::{initialize_test, TestParameters};
+
+const SHADER: &str = "
+    @stage(compute) @workgroup_size(1)
+    fn main() {}
+";
+
+#[test]
+fn compute_pipeline_async() {
+    initialize_test(
+        TestParameters::default().downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+        |ctx| {
+            let module = ctx
+                .device
+                .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                    label: None,
+                    source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+                });
+
+            let pipeline =
+                ctx.device
+                    .create_compute_pipeline_async(&wgpu::ComputePipelineDescriptor {
+                        label: None,
+                        layout: None,
+                        module: &module,
+                        entry_point: "main",
+                        constants: &[],
+                        cache: None,
+                    });
+            assert!(pollster::block_on(pipeline).is_ok());
+
+            // Errors are reported through the future, not the device error handler.
+            let pipeline =
+                ctx.device
+                    .create_compute_pipeline_async(&wgpu::ComputePipelineDescriptor {
+                        label: None,
+                        layout: None,
+                        module: &module,
+                        entry_point: "missing",
+                        constants: &[],
+                        cache: None,
+                    });
+            assert!(matches!(
+                pollster::block_on(pipeline),
+                Err(wgpu::Error::Validation { .. })
+            ));
+        },
+    )
+}
+
+#[test]
+fn compute_pipeline_async_outlives_resources() {
+    initialize_test(
+        TestParameters::default().downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+        |ctx| {
+            let module = ctx
+                .device
+                .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                    label: None,
+                    source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+                });
+            let layout = ctx
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: None,
+                    bind_group_layouts: &[],
+                    push_constant_ranges: &[],
+                });
+
+            let pipeline =
+                ctx.device
+                    .create_compute_pipeline_async(&wgpu::ComputePipelineDescriptor {
+                        label: None,
+                        layout: Some(&layout),
+                        module: &module,
+                        entry_point: "main",
+                        constants: &[],
+                        cache: None,
+                    });
+            // The compilation keeps what it needs alive.
+            drop(module);
+            drop(layout);
+            ctx.device.poll(wgpu::Maintain::Poll);
+
+            let pipeline = pollster::block_on(pipeline).unwrap();
+
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                pass.set_pipeline(&pipeline);
+                pass.dispatch(1, 1, 1);
+            }
+            ctx.queue.submit(Some(encoder.finish()));
+            ctx.device.poll(wgpu::Maintain::Wait);
+        },
+    )
+}
@@ -0,0 +1,70 @@
+use crate::common::{initialize_test, TestParameters};
+
+const SHADER: &str = "
+    @stage(compute) @workgroup_size(1)
+    fn main() {}
+";
+
+#[test]
+fn pipeline_cache_round_trip() {
+    initialize_test(
+        TestParameters::default().downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+        |ctx| {
+            let module = ctx
+                .device
+                .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                    label: None,
+                    source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+                });
+            let create_pipeline = |cache| {
+                ctx.device
+                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: None,
+                        layout: None,
+                        module: &module,
+                        entry_point: "main",
+                        constants: &[],
+                        cache: Some(cache),
+                    })
+            };
+            // Only these backends keep the translations of the stages.
+            let expected_hits = match ctx.adapter_info.backend {
+                wgpu::Backend::Vulkan
+                | wgpu::Backend::Metal
+                | wgpu::Backend::Dx12
+                | wgpu::Backend::Gl => 1,
+                _ => 0,
+            };
+
+            let cache = ctx
+                .device
+                .create_pipeline_cache(&wgpu::PipelineCacheDescriptor::default());
+            let _pipeline = create_pipeline(&cache);
+            assert_eq!(cache.hit_count(), 0);
+            let data = cache.get_data().unwrap();
+
+            // A new cache created from the data already has the translation.
+            let cache = ctx
+                .device
+                .create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                    label: None,
+                    data: Some(&data),
+                    fallback: false,
+                });
+            let _pipeline = create_pipeline(&cache);
+            assert_eq!(cache.hit_count(), expected_hits);
+            assert!(cache.get_data().is_some());
+
+            // Garbage data is ignored when falling back is allowed.
+            let cache = ctx
+                .device
+                .create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                    label: None,
+                    data: Some(b"not a pipeline cache"),
+                    fallback: true,
+                });
+            let _pipeline = create_pipeline(&cache);
+            assert_eq!(cache.hit_count(), 0);
+        },
+    )
+}
@@ -0,0 +1,147 @@
+use crate::common::{initialize_test, TestParameters, TestingContext};
+
+const SHADER: &str = "
+    @group(0) @binding(0)
+    var<storage, read_write> output: array<u32, 4>;
+
+    @stage(compute) @workgroup_size(1)
+    fn main(@builtin(local_invocation_index) index: u32) {
+        output[index] = index + 1u;
+    }
+";
+
+fn constants_parameters() -> TestParameters {
+    TestParameters::default()
+        .downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        .limits(wgpu::Limits::downlevel_defaults())
+}
+
+/// Create a compute pipeline from `source`, returning the validation error if any.
+fn create_pipeline(
+    ctx: &TestingContext,
+    source: wgpu::ShaderSource,
+    constants: &[(&str, f64)],
+) -> (wgpu::ComputePipeline, Option<wgpu::Error>) {
+    let module = ctx
+        .device
+        .create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: None,
+            source,
+        });
+    ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = ctx
+        .device
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            constants,
+            cache: None,
+        });
+    let error = pollster::block_on(ctx.device.pop_error_scope());
+    (pipeline, error)
+}
+
+/// Dispatch a single workgroup of `pipeline` and return its output.
+#[cfg(feature = "glsl")]
+fn run(ctx: &TestingContext, pipeline: &wgpu::ComputePipeline) -> Vec<u32> {
+    let output = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+    let readback = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: output.as_entire_binding(),
+        }],
+    });
+
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch(1, 1, 1);
+    }
+    encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, 16);
+    ctx.queue.submit(Some(encoder.finish()));
+
+    let slice = readback.slice(..);
+    let mapping = slice.map_async(wgpu::MapMode::Read);
+    ctx.device.poll(wgpu::Maintain::Wait);
+    pollster::block_on(mapping).unwrap();
+    let mapped = slice.get_mapped_range();
+    bytemuck::cast_slice(&*mapped).to_vec()
+}
+
+#[test]
+fn invalid_pipeline_constants() {
+    initialize_test(constants_parameters(), |ctx| {
+        let invalid_constants: &[&[(&str, f64)]] = &[
+            // Unknown keys.
+            &[("missing", 1.0)],
+            &[("workgroup_size.x", 4.0)],
+        ];
+        for &constants in invalid_constants {
+            let (_, error) =
+                create_pipeline(&ctx, wgpu::ShaderSource::Wgsl(SHADER.into()), constants);
+            assert!(error.is_some(), "{:?} should be rejected", constants);
+        }
+    })
+}
+
+#[cfg(feature = "glsl")]
+#[test]
+fn scalar_constant_override() {
+    const GLSL_SHADER: &str = "
+        #version 450
+        layout(local_size_x = 1) in;
+        layout(constant_id = 0) const uint VALUE = 1u;
+        layout(set = 0, binding = 0) buffer Output {
+            uint values[4];
+        };
+        void main() {
+            values[0] = VALUE;
+        }
+    ";
+    let glsl = || wgpu::ShaderSource::Glsl {
+        shader: GLSL_SHADER.into(),
+        stage: naga::ShaderStage::Compute,
+        defines: Default::default(),
+    };
+
+    initialize_test(constants_parameters(), |ctx| {
+        // Defaults are kept when not overridden.
+        let (pipeline, error) = create_pipeline(&ctx, glsl(), &[]);
+        assert!(error.is_none());
+        assert_eq!(run(&ctx, &pipeline)[0], 1);
+
+        // Constants are looked up by ID.
+        let (pipeline, error) = create_pipeline(&ctx, glsl(), &[("0", 7.0)]);
+        assert!(error.is_none());
+        assert_eq!(run(&ctx, &pipeline)[0], 7);
+
+        // Values must be representable as the type of the constant.
+        let (_, error) = create_pipeline(&ctx, glsl(), &[("0", -1.0)]);
+        assert!(error.is_some());
+
+        // Constants can only be given once, whether by name or by ID.
+        let (_, error) = create_pipeline(&ctx, glsl(), &[("0", 2.0), ("0", 3.0)]);
+        assert!(error.is_some());
+        let (_, error) = create_pipeline(&ctx, glsl(), &[("VALUE", 2.0), ("0", 3.0)]);
+        assert!(error.is_some());
+    })
+}
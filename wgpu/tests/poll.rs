@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use crate::common::{initialize_test, TestParameters};
+
+#[test]
+fn wait_for_submission_index() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.clear_buffer(&buffer, 0, None);
+        let first = ctx.queue.submit(Some(encoder.finish()));
+        let second = ctx.queue.submit(None);
+        assert_ne!(first, second);
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        let result = ctx
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndexTimeout(
+                first,
+                Duration::from_secs(5),
+            ));
+        assert_eq!(result, wgpu::MaintainResult::Complete);
+        let result = ctx
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(second));
+        assert_eq!(result, wgpu::MaintainResult::Complete);
+        pollster::block_on(mapping).unwrap();
+        assert_eq!(&*slice.get_mapped_range(), &[0; 16]);
+    })
+}
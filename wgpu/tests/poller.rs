@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use crate::common::{initialize_test, TestParameters};
+
+#[test]
+fn map_async_completes_without_polling() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let device = Arc::new(ctx.device);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        ctx.queue.write_buffer(&buffer, 0, &[7; 16]);
+        ctx.queue.submit(None);
+
+        let poller = wgpu::util::DevicePoller::new(Arc::clone(&device));
+        let slice = buffer.slice(..);
+        pollster::block_on(poller.drive(slice.map_async(wgpu::MapMode::Read))).unwrap();
+        assert_eq!(&*slice.get_mapped_range(), &[7; 16]);
+        drop(poller);
+    })
+}
+
+#[test]
+fn rejected_map_async_resolves() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let slice = buffer.slice(..);
+
+        // A request overlapping a pending one fails right away.
+        let first = slice.map_async(wgpu::MapMode::Read);
+        assert!(pollster::block_on(slice.map_async(wgpu::MapMode::Read)).is_err());
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(first).unwrap();
+
+        // Mapping a range that is already mapped is a validation error, and
+        // the future of the rejected request resolves without any polling.
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let second = slice.map_async(wgpu::MapMode::Read);
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_some());
+        assert!(pollster::block_on(second).is_err());
+    })
+}
@@ -0,0 +1,67 @@
+use crate::common::{initialize_test, TestParameters};
+
+#[test]
+fn emulated_profile_restricts_new_devices() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let adapter = ctx.adapter;
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        let adapter_format_features = adapter.get_texture_format_features(format);
+        let mut format_features = adapter_format_features;
+        format_features.allowed_usages -= wgpu::TextureUsages::STORAGE_BINDING;
+
+        let mut downlevel_caps = adapter.get_downlevel_capabilities();
+        downlevel_caps.flags -= wgpu::DownlevelFlags::INDEPENDENT_BLEND;
+
+        let profile = wgpu::AdapterProfile {
+            features: wgpu::Features::empty(),
+            limits: adapter.limits(),
+            downlevel_caps: downlevel_caps.clone(),
+            texture_format_features: vec![wgpu::TextureFormatProfile {
+                format,
+                features: format_features,
+            }],
+        };
+        let features = adapter.features();
+        let emulated = adapter.emulate_profile(&profile);
+
+        assert_eq!(emulated.features(), wgpu::Features::empty());
+        assert_eq!(emulated.get_downlevel_capabilities(), downlevel_caps);
+        assert!(!emulated
+            .get_texture_format_features(format)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::STORAGE_BINDING));
+
+        // The adapter the profile is emulated on is left untouched.
+        assert_eq!(adapter.features(), features);
+        assert_eq!(
+            adapter.get_texture_format_features(format),
+            adapter_format_features
+        );
+
+        let (device, _queue) = pollster::block_on(emulated.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: profile.limits.clone(),
+            },
+            None,
+        ))
+        .unwrap();
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::STORAGE_BINDING,
+        });
+        assert!(pollster::block_on(device.pop_error_scope()).is_some());
+    })
+}
@@ -0,0 +1,110 @@
+use wgpu::util::{GpuProfiler, GpuProfilerError};
+
+use crate::common::{initialize_test, TestParameters};
+
+#[test]
+fn profiler_nested_scopes() {
+    initialize_test(
+        TestParameters::default().features(wgpu::Features::TIMESTAMP_QUERY),
+        |ctx| {
+            let mut profiler = GpuProfiler::new(&ctx.device, &ctx.queue, 2);
+            assert!(profiler.is_enabled());
+
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            profiler.begin_scope("frame", &mut encoder, &ctx.device);
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                profiler.begin_scope("compute", &mut pass, &ctx.device);
+                profiler.end_scope(&mut pass).unwrap();
+            }
+            profiler.end_scope(&mut encoder).unwrap();
+            profiler.resolve_queries(&mut encoder);
+            ctx.queue.submit(Some(encoder.finish()));
+            profiler.end_frame().unwrap();
+
+            ctx.device.poll(wgpu::Maintain::Wait);
+            let results = profiler.process_finished_frame().unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].label, "frame");
+            assert!(results[0].time.start <= results[0].time.end);
+            assert_eq!(results[0].nested_scopes.len(), 1);
+            assert_eq!(results[0].nested_scopes[0].label, "compute");
+            // All frames were processed.
+            assert!(profiler.process_finished_frame().is_none());
+        },
+    )
+}
+
+#[test]
+fn profiler_misuse() {
+    initialize_test(
+        TestParameters::default().features(wgpu::Features::TIMESTAMP_QUERY),
+        |ctx| {
+            let mut profiler = GpuProfiler::new(&ctx.device, &ctx.queue, 1);
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+            assert_eq!(
+                profiler.end_scope(&mut encoder),
+                Err(GpuProfilerError::NoOpenScope)
+            );
+
+            profiler.begin_scope("open", &mut encoder, &ctx.device);
+            assert_eq!(
+                profiler.end_frame(),
+                Err(GpuProfilerError::UnclosedScopes(1))
+            );
+            profiler.end_scope(&mut encoder).unwrap();
+            assert_eq!(
+                profiler.end_frame(),
+                Err(GpuProfilerError::UnresolvedQueries)
+            );
+            profiler.resolve_queries(&mut encoder);
+            ctx.queue.submit(Some(encoder.finish()));
+            profiler.end_frame().unwrap();
+
+            // A second frame doesn't fit while the first one is pending.
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            profiler.begin_scope("second", &mut encoder, &ctx.device);
+            profiler.end_scope(&mut encoder).unwrap();
+            profiler.resolve_queries(&mut encoder);
+            ctx.queue.submit(Some(encoder.finish()));
+            assert_eq!(
+                profiler.end_frame(),
+                Err(GpuProfilerError::PendingFrameQueueFull)
+            );
+        },
+    )
+}
+
+#[test]
+fn profiler_disabled() {
+    initialize_test(TestParameters::default(), |ctx| {
+        if ctx
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return;
+        }
+        let mut profiler = GpuProfiler::new(&ctx.device, &ctx.queue, 1);
+        assert!(!profiler.is_enabled());
+
+        // Scopes are still tracked, but nothing is ever produced.
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        profiler.begin_scope("frame", &mut encoder, &ctx.device);
+        profiler.end_scope(&mut encoder).unwrap();
+        profiler.resolve_queries(&mut encoder);
+        ctx.queue.submit(Some(encoder.finish()));
+        profiler.end_frame().unwrap();
+        ctx.device.poll(wgpu::Maintain::Wait);
+        assert!(profiler.process_finished_frame().is_none());
+    })
+}
@@ -0,0 +1,273 @@
+use std::num::NonZeroU64;
+
+use crate::common::{initialize_test, TestParameters, TestingContext};
+
+const SHADER: &str = "
+    struct PushConstants {
+        value: u32,
+    };
+    var<push_constant> pc: PushConstants;
+
+    @group(0) @binding(0)
+    var<storage, read_write> output: array<u32, 2>;
+
+    @stage(compute) @workgroup_size(1)
+    fn write_first() {
+        output[0] = pc.value;
+    }
+
+    @stage(compute) @workgroup_size(1)
+    fn write_second() {
+        output[1] = pc.value;
+    }
+";
+
+/// Reads one more push constant word than [`SHADER`], so its pipeline layout
+/// has a wider range.
+const WIDE_SHADER: &str = "
+    struct PushConstants {
+        value: u32,
+        extra: u32,
+    };
+    var<push_constant> pc: PushConstants;
+
+    @group(0) @binding(0)
+    var<storage, read_write> output: array<u32, 2>;
+
+    @stage(compute) @workgroup_size(1)
+    fn write_second() {
+        output[1] = pc.value * 100u + pc.extra;
+    }
+";
+
+fn push_constant_parameters() -> TestParameters {
+    TestParameters::default()
+        .downlevel_flags(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        .features(wgpu::Features::PUSH_CONSTANTS)
+        .limits(wgpu::Limits {
+            max_push_constant_size: 8,
+            ..wgpu::Limits::downlevel_defaults()
+        })
+}
+
+enum Step {
+    SetPushConstant(u32),
+    SetPushConstantAt(u32, u32),
+    SetFirstPipeline,
+    SetSecondPipeline,
+    SetWidePipeline,
+    Dispatch,
+}
+
+struct PushConstantsTest {
+    first: wgpu::ComputePipeline,
+    second: wgpu::ComputePipeline,
+    wide: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    output: wgpu::Buffer,
+}
+
+impl PushConstantsTest {
+    fn new(ctx: &TestingContext) -> Self {
+        let module = ctx
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            });
+        let bind_group_layout =
+            ctx.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: NonZeroU64::new(8),
+                        },
+                        count: None,
+                    }],
+                });
+        let layout = ctx
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::COMPUTE,
+                    range: 0..4,
+                }],
+            });
+        let wide_module = ctx
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(WIDE_SHADER.into()),
+            });
+        let wide_layout = ctx
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::COMPUTE,
+                    range: 0..8,
+                }],
+            });
+        let create_pipeline = |layout, module, entry_point| {
+            ctx.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: None,
+                    layout: Some(layout),
+                    module,
+                    entry_point,
+                    constants: &[],
+                    cache: None,
+                })
+        };
+        let output = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: output.as_entire_binding(),
+            }],
+        });
+        Self {
+            first: create_pipeline(&layout, &module, "write_first"),
+            second: create_pipeline(&layout, &module, "write_second"),
+            wide: create_pipeline(&wide_layout, &wide_module, "write_second"),
+            bind_group,
+            output,
+        }
+    }
+
+    /// Run `steps` in a compute pass and return the output buffer contents.
+    fn run(&self, ctx: &TestingContext, steps: &[Step]) -> Vec<u32> {
+        let readback = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 8,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            for step in steps {
+                match *step {
+                    Step::SetPushConstant(value) => {
+                        pass.set_push_constants(0, bytemuck::bytes_of(&value))
+                    }
+                    Step::SetPushConstantAt(offset, value) => {
+                        pass.set_push_constants(offset, bytemuck::bytes_of(&value))
+                    }
+                    Step::SetFirstPipeline => pass.set_pipeline(&self.first),
+                    Step::SetSecondPipeline => pass.set_pipeline(&self.second),
+                    Step::SetWidePipeline => pass.set_pipeline(&self.wide),
+                    Step::Dispatch => pass.dispatch(1, 1, 1),
+                }
+            }
+        }
+        encoder.copy_buffer_to_buffer(&self.output, 0, &readback, 0, 8);
+        ctx.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping).unwrap();
+        let mapped = slice.get_mapped_range();
+        bytemuck::cast_slice(&*mapped).to_vec()
+    }
+}
+
+#[test]
+fn push_constants_persist_across_pipelines() {
+    initialize_test(push_constant_parameters(), |ctx| {
+        let test = PushConstantsTest::new(&ctx);
+        // Values set before any pipeline are applied once one is set, and the
+        // second pipeline has a compatible layout, so it sees the same values.
+        let output = test.run(
+            &ctx,
+            &[
+                Step::SetPushConstant(7),
+                Step::SetFirstPipeline,
+                Step::Dispatch,
+                Step::SetSecondPipeline,
+                Step::Dispatch,
+            ],
+        );
+        assert_eq!(output, [7, 7]);
+
+        let output = test.run(
+            &ctx,
+            &[
+                Step::SetFirstPipeline,
+                Step::SetPushConstant(1),
+                Step::Dispatch,
+                Step::SetPushConstant(2),
+                Step::SetSecondPipeline,
+                Step::Dispatch,
+            ],
+        );
+        assert_eq!(output, [1, 2]);
+    })
+}
+
+#[test]
+fn push_constants_persist_across_layouts() {
+    initialize_test(push_constant_parameters(), |ctx| {
+        let test = PushConstantsTest::new(&ctx);
+        // The wide layout sees the word written under the narrow one, and the
+        // narrow layout sees the word written under the wide one once switched back.
+        let output = test.run(
+            &ctx,
+            &[
+                Step::SetFirstPipeline,
+                Step::SetPushConstant(1),
+                Step::Dispatch,
+                Step::SetWidePipeline,
+                Step::SetPushConstantAt(4, 9),
+                Step::Dispatch,
+                Step::SetPushConstant(2),
+                Step::SetFirstPipeline,
+                Step::Dispatch,
+            ],
+        );
+        assert_eq!(output, [2, 109]);
+    })
+}
+
+#[test]
+fn push_constants_missing() {
+    initialize_test(push_constant_parameters(), |ctx| {
+        let test = PushConstantsTest::new(&ctx);
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        test.run(&ctx, &[Step::SetFirstPipeline, Step::Dispatch]);
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_some());
+
+        // The wider range of a new layout needs its extra word written too.
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        test.run(
+            &ctx,
+            &[
+                Step::SetFirstPipeline,
+                Step::SetPushConstant(1),
+                Step::Dispatch,
+                Step::SetWidePipeline,
+                Step::Dispatch,
+            ],
+        );
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_some());
+    })
+}
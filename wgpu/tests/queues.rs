@@ -0,0 +1,216 @@
+use std::{
+    num::NonZeroU32,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use crate::common::{initialize_test, TestParameters};
+
+/// Keeps the GPU busy for a while, so that submissions are still running
+/// when the next one is made.
+const BUSY_SHADER: &str = "
+    @group(0) @binding(0)
+    var<storage, read_write> data: array<u32>;
+
+    @stage(compute) @workgroup_size(64)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        var value = data[id.x];
+        for (var i = 0u; i < 4096u; i = i + 1u) {
+            value = value * 1664525u + 1013904223u;
+        }
+        data[id.x] = value;
+    }
+";
+
+#[test]
+fn copies_on_extra_queues() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let (device, _queue, extra_queues) =
+            pollster::block_on(ctx.adapter.request_device_with_queues(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: ctx.device_features,
+                    limits: ctx.device_limits.clone(),
+                },
+                &[
+                    wgpu::QueueDescriptor {
+                        label: Some("compute"),
+                    },
+                    wgpu::QueueDescriptor {
+                        label: Some("transfer"),
+                    },
+                ],
+                None,
+            ))
+            .unwrap();
+        assert_eq!(extra_queues.len(), 2);
+
+        let destinations = extra_queues
+            .iter()
+            .enumerate()
+            .map(|(i, queue)| {
+                let src = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: 16,
+                    usage: wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                });
+                src.slice(..).get_mapped_range_mut().fill(i as u8 + 1);
+                src.unmap();
+                let dst = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: 16,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                encoder.copy_buffer_to_buffer(&src, 0, &dst, 0, 16);
+                queue.submit(Some(encoder.finish()));
+                dst
+            })
+            .collect::<Vec<_>>();
+
+        for (i, dst) in destinations.iter().enumerate() {
+            let slice = dst.slice(..);
+            let mapping = slice.map_async(wgpu::MapMode::Read);
+            device.poll(wgpu::Maintain::Wait);
+            pollster::block_on(mapping).unwrap();
+            assert_eq!(&*slice.get_mapped_range(), &[i as u8 + 1; 16]);
+        }
+    })
+}
+
+#[test]
+fn resources_in_use_on_other_queue() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let (device, queue, extra_queues) =
+            pollster::block_on(ctx.adapter.request_device_with_queues(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: ctx.device_features,
+                    limits: ctx.device_limits.clone(),
+                },
+                &[wgpu::QueueDescriptor {
+                    label: Some("compute"),
+                }],
+                None,
+            ))
+            .unwrap();
+        let extra_queue = &extra_queues[0];
+
+        const WORKGROUPS: u32 = 1024;
+        let busy = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: WORKGROUPS as u64 * 64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let texture_size = wgpu::Extent3d {
+            width: 64,
+            height: 64,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+        });
+        let other = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 64 * 64 * 4,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let buffer_layout = wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: NonZeroU32::new(64 * 4),
+            rows_per_image: None,
+        };
+
+        let module = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(BUSY_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            constants: &[],
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: busy.as_entire_binding(),
+            }],
+        });
+
+        let encode_busy_work = || {
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch(WORKGROUPS, 1, 1);
+            }
+            encoder.copy_buffer_to_texture(
+                wgpu::ImageCopyBuffer {
+                    buffer: &busy,
+                    layout: buffer_layout,
+                },
+                texture.as_image_copy(),
+                texture_size,
+            );
+            encoder.finish()
+        };
+        let read_buffer = || {
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            encoder.copy_buffer_to_buffer(&busy, 0, &other, 0, 16);
+            encoder.finish()
+        };
+        let read_texture = || {
+            let mut encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            encoder.copy_texture_to_buffer(
+                texture.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &other,
+                    layout: buffer_layout,
+                },
+                texture_size,
+            );
+            encoder.finish()
+        };
+
+        // Queues sharing a hardware queue execute in order, so resources
+        // can move between them freely.
+        let expect_in_use = |command_buffer: wgpu::CommandBuffer, message: &str| {
+            let result = catch_unwind(AssertUnwindSafe(|| queue.submit(Some(command_buffer))));
+            if extra_queue.is_independent() {
+                let panic = result.expect_err("using a busy resource should fail");
+                let panic_message = panic.downcast_ref::<String>().unwrap();
+                assert!(panic_message.contains(message), "{}", panic_message);
+            } else {
+                assert!(result.is_ok());
+            }
+        };
+
+        extra_queue.submit(Some(encode_busy_work()));
+        expect_in_use(read_buffer(), "buffer");
+        expect_in_use(read_texture(), "texture");
+
+        // Once the work is done, the resources can be used anywhere.
+        device.poll(wgpu::Maintain::Wait);
+        queue.submit(vec![read_buffer(), read_texture()]);
+        device.poll(wgpu::Maintain::Wait);
+    })
+}
@@ -0,0 +1,299 @@
+use crate::common::{initialize_test, TestParameters, TestingContext};
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+fn create_bundle(
+    ctx: &TestingContext,
+    record: impl FnOnce(&mut wgpu::RenderBundleEncoder),
+) -> wgpu::RenderBundle {
+    let mut encoder =
+        ctx.device
+            .create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+                label: None,
+                color_formats: &[FORMAT],
+                depth_stencil: None,
+                sample_count: 1,
+                multiview: None,
+            });
+    record(&mut encoder);
+    encoder.finish(&wgpu::RenderBundleDescriptor { label: None })
+}
+
+/// Execute `bundle` in an 8x8 pass, returning the validation error if any.
+fn execute_bundle(ctx: &TestingContext, bundle: &wgpu::RenderBundle) -> Option<wgpu::Error> {
+    let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations::default(),
+            }],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_stencil_reference(1);
+        pass.execute_bundles(Some(bundle));
+    }
+    ctx.queue.submit(Some(encoder.finish()));
+    pollster::block_on(ctx.device.pop_error_scope())
+}
+
+#[test]
+fn bundle_dynamic_state() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let bundle = create_bundle(&ctx, |encoder| {
+            encoder.set_viewport(0.0, 0.0, 4.0, 4.0, 0.0, 1.0);
+            encoder.set_scissor_rect(4, 4, 4, 4);
+            encoder.set_stencil_reference(2);
+        });
+        assert!(execute_bundle(&ctx, &bundle).is_none());
+    })
+}
+
+#[test]
+fn bundle_scissor_out_of_bounds() {
+    initialize_test(TestParameters::default(), |ctx| {
+        // Fits in the bundle, but not in the pass it is executed in.
+        let bundle = create_bundle(&ctx, |encoder| encoder.set_scissor_rect(4, 4, 8, 8));
+        assert!(execute_bundle(&ctx, &bundle).is_some());
+
+        // Overflows.
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = create_bundle(&ctx, |encoder| encoder.set_scissor_rect(u32::MAX, 0, 2, 2));
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_some());
+    })
+}
+
+const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+const SHADER: &str = "
+    @stage(vertex)
+    fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+        let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+        return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    }
+
+    @stage(fragment)
+    fn fs_red() -> @location(0) vec4<f32> {
+        return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+    }
+
+    @stage(fragment)
+    fn fs_green() -> @location(0) vec4<f32> {
+        return vec4<f32>(0.0, 1.0, 0.0, 1.0);
+    }
+";
+
+/// Create a pipeline drawing a full-screen triangle with the color of `fragment_entry_point`
+/// where the stencil value equals the stencil reference.
+fn create_stencil_pipeline(
+    ctx: &TestingContext,
+    module: &wgpu::ShaderModule,
+    fragment_entry_point: &str,
+) -> wgpu::RenderPipeline {
+    let stencil_face = wgpu::StencilFaceState {
+        compare: wgpu::CompareFunction::Equal,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: wgpu::StencilOperation::Keep,
+    };
+    ctx.device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module,
+                entry_point: "vs_main",
+                buffers: &[],
+                constants: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_STENCIL_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState {
+                    front: stencil_face,
+                    back: stencil_face,
+                    read_mask: 0xff,
+                    write_mask: 0,
+                },
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module,
+                entry_point: fragment_entry_point,
+                targets: &[wgpu::ColorTargetState {
+                    format: FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+                constants: &[],
+            }),
+            multiview: None,
+            cache: None,
+        })
+}
+
+#[test]
+fn bundle_dynamic_state_restored() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let module = ctx
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+            });
+        let red = create_stencil_pipeline(&ctx, &module, "fs_red");
+        let green = create_stencil_pipeline(&ctx, &module, "fs_green");
+
+        // Draws red in the top-left quadrant, leaving a stencil reference
+        // matching nothing behind.
+        let mut bundle_encoder =
+            ctx.device
+                .create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+                    label: None,
+                    color_formats: &[FORMAT],
+                    depth_stencil: Some(wgpu::RenderBundleDepthStencil {
+                        format: DEPTH_STENCIL_FORMAT,
+                        depth_read_only: true,
+                        stencil_read_only: true,
+                    }),
+                    sample_count: 1,
+                    multiview: None,
+                });
+        bundle_encoder.set_pipeline(&red);
+        bundle_encoder.set_viewport(0.0, 0.0, 4.0, 4.0, 0.0, 1.0);
+        bundle_encoder.set_scissor_rect(0, 0, 4, 4);
+        bundle_encoder.set_stencil_reference(1);
+        bundle_encoder.draw(0..3, 0..1);
+        bundle_encoder.set_stencil_reference(2);
+        let bundle = bundle_encoder.finish(&wgpu::RenderBundleDescriptor { label: None });
+
+        let size = wgpu::Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        };
+        let color = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+        let color_view = color.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_stencil = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_STENCIL_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        });
+        let depth_stencil_view = depth_stencil.create_view(&wgpu::TextureViewDescriptor::default());
+        let readback = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64 * 8,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_stencil_view,
+                    depth_ops: None,
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1),
+                        store: true,
+                    }),
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            // Only the bottom-right quadrant passes all of these.
+            pass.set_viewport(4.0, 4.0, 4.0, 4.0, 0.0, 1.0);
+            pass.set_scissor_rect(4, 4, 4, 4);
+            pass.set_stencil_reference(1);
+            pass.execute_bundles(Some(&bundle));
+            pass.set_pipeline(&green);
+            pass.draw(0..3, 0..1);
+        }
+        encoder.copy_texture_to_buffer(
+            color.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        ctx.queue.submit(Some(encoder.finish()));
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_none());
+
+        let slice = readback.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        ctx.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping).unwrap();
+        let data = slice.get_mapped_range();
+        for y in 0..8 {
+            for x in 0..8 {
+                let offset = (y * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT + x * 4) as usize;
+                let expected = match (x < 4, y < 4) {
+                    (true, true) => [255, 0, 0, 255],
+                    (false, false) => [0, 255, 0, 255],
+                    _ => [0, 0, 0, 255],
+                };
+                assert_eq!(
+                    &data[offset..offset + 4],
+                    &expected[..],
+                    "pixel ({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    })
+}
@@ -1,9 +1,25 @@
 // All files containing tests
 mod common;
 
+mod buffer_ranges;
 mod clear_texture;
+mod descriptor_table;
 mod device;
 mod example_wgsl;
 mod instance;
+mod layout_dedup;
+mod pipeline_async;
+mod pipeline_cache;
+mod pipeline_constants;
+mod poll;
+mod poller;
+mod profile;
+mod profiler;
+mod push_constants;
+mod queues;
+mod render_bundle;
+mod shader_compilation_info;
+mod timeline;
+mod timestamp_writes;
 mod vertex_indices;
 mod zero_init_texture_after_discard;
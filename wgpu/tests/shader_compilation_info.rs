@@ -0,0 +1,50 @@
+use crate::common::{initialize_test, TestParameters};
+
+const VALID_SHADER: &str = "
+    @stage(compute) @workgroup_size(1)
+    fn main() {}
+";
+
+const INVALID_SHADER: &str = "@stage(compute) @workgroup_size(1)
+fn main() { // é
+    let x: i32 = ;
+}
+";
+
+#[test]
+fn compilation_info() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let module = ctx
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(VALID_SHADER.into()),
+            });
+        let info = pollster::block_on(module.get_compilation_info());
+        assert!(info.messages.is_empty());
+
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = ctx
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(INVALID_SHADER.into()),
+            });
+        assert!(pollster::block_on(ctx.device.pop_error_scope()).is_some());
+
+        // Invalid modules still report why they failed.
+        let info = pollster::block_on(module.get_compilation_info());
+        assert!(info.has_errors());
+        // The error points at the `;`.
+        let message = &info.messages[0];
+        assert_eq!(message.line_num, 3);
+        assert_eq!(message.line_pos, 18);
+        assert_eq!(message.length, 1);
+        let offset = message.offset as usize;
+        assert_eq!(&INVALID_SHADER[offset..offset + 1], ";");
+        // The `é` takes two bytes, but a single UTF-16 code unit.
+        assert_eq!(message.utf16_offset, message.offset - 1);
+        assert_eq!(message.utf16_line_pos, 18);
+        assert_eq!(message.utf16_length, 1);
+    })
+}
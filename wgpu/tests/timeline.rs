@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use crate::common::{initialize_test, TestParameters};
+
+#[test]
+fn timeline_orders_queues() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let (device, queue, extra_queues) =
+            pollster::block_on(ctx.adapter.request_device_with_queues(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: ctx.device_features,
+                    limits: ctx.device_limits.clone(),
+                },
+                &[wgpu::QueueDescriptor { label: None }],
+                None,
+            ))
+            .unwrap();
+        let timeline = device.create_timeline(0);
+
+        let src = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        src.slice(..).get_mapped_range_mut().fill(3);
+        src.unmap();
+        let middle = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let dst = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&src, 0, &middle, 0, 16);
+        queue.submit(Some(encoder.finish()));
+        queue.signal_timeline(&timeline, 1);
+
+        let extra_queue = &extra_queues[0];
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&middle, 0, &dst, 0, 16);
+        extra_queue.wait_timeline(&timeline, 1);
+        extra_queue.submit(Some(encoder.finish()));
+        extra_queue.signal_timeline(&timeline, 2);
+
+        assert!(timeline.wait(2, Some(Duration::from_secs(5))));
+        assert_eq!(timeline.value(), 2);
+        // Nothing signals 3.
+        assert!(!timeline.wait(3, Some(Duration::from_millis(10))));
+
+        let slice = dst.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping).unwrap();
+        assert_eq!(&*slice.get_mapped_range(), &[3; 16]);
+    })
+}
+
+#[test]
+fn timeline_orders_devices() {
+    initialize_test(TestParameters::default(), |ctx| {
+        let (other_device, other_queue) = pollster::block_on(ctx.adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: ctx.device_features,
+                limits: ctx.device_limits.clone(),
+            },
+            None,
+        ))
+        .unwrap();
+        let timeline = ctx.device.create_timeline(0);
+
+        let src = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        src.slice(..).get_mapped_range_mut().fill(5);
+        src.unmap();
+        let dst = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&src, 0, &dst, 0, 16);
+        ctx.queue.submit(Some(encoder.finish()));
+        ctx.queue.signal_timeline(&timeline, 1);
+
+        // Fences of another device are waited for on the host when submitting.
+        other_queue.wait_timeline(&timeline, 1);
+        let encoder =
+            other_device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        other_queue.submit(Some(encoder.finish()));
+        assert_eq!(timeline.value(), 1);
+        other_queue.signal_timeline(&timeline, 2);
+        assert!(timeline.wait(2, None));
+
+        // The work of the first device is done, so polling is enough.
+        let slice = dst.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        ctx.device.poll(wgpu::Maintain::Poll);
+        pollster::block_on(mapping).unwrap();
+        assert_eq!(&*slice.get_mapped_range(), &[5; 16]);
+    })
+}
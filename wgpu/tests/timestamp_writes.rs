@@ -0,0 +1,73 @@
+use crate::common::{initialize_test, TestParameters};
+
+#[test]
+fn pass_timestamp_writes() {
+    initialize_test(
+        TestParameters::default().features(wgpu::Features::TIMESTAMP_QUERY),
+        |ctx| {
+            let query_set = ctx.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: None,
+                ty: wgpu::QueryType::Timestamp,
+                count: 4,
+            });
+            let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let readback_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 4 * 8,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = ctx
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: Some(wgpu::PassTimestampWrites {
+                    query_set: &query_set,
+                    beginning_of_pass_write_index: Some(0),
+                    end_of_pass_write_index: Some(1),
+                }),
+            });
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations::default(),
+                }],
+                depth_stencil_attachment: None,
+                timestamp_writes: Some(wgpu::PassTimestampWrites {
+                    query_set: &query_set,
+                    beginning_of_pass_write_index: Some(2),
+                    end_of_pass_write_index: Some(3),
+                }),
+                occlusion_query_set: None,
+            });
+            encoder.resolve_query_set(&query_set, 0..4, &readback_buffer, 0);
+            ctx.queue.submit(Some(encoder.finish()));
+
+            let slice = readback_buffer.slice(..);
+            let mapping = slice.map_async(wgpu::MapMode::Read);
+            ctx.device.poll(wgpu::Maintain::Wait);
+            pollster::block_on(mapping).unwrap();
+            let timestamps: Vec<u64> = bytemuck::cast_slice(&*slice.get_mapped_range()).to_vec();
+            // Each pass ends after it begins.
+            assert!(timestamps[0] <= timestamps[1]);
+            assert!(timestamps[2] <= timestamps[3]);
+        },
+    )
+}
@@ -64,6 +64,7 @@ fn pulling_common(
                 buffers: &[],
                 entry_point: "vs_main",
                 module: &shader,
+                constants: &[],
             },
             primitive: wgpu::PrimitiveState::default(),
             depth_stencil: None,
@@ -76,8 +77,10 @@ fn pulling_common(
                     blend: None,
                     write_mask: wgpu::ColorWrites::ALL,
                 }],
+                constants: &[],
             }),
             multiview: None,
+            cache: None,
         });
 
     let dummy = ctx
@@ -113,6 +116,8 @@ fn pulling_common(
         }],
         depth_stencil_attachment: None,
         label: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
     });
 
     rpass.set_pipeline(&pipeline);
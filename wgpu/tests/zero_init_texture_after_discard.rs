@@ -23,6 +23,8 @@ fn discarding_color_target_resets_texture_init_state_check_visible_on_copy_after
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             ctx.queue.submit([encoder.finish()]);
         }
@@ -58,6 +60,8 @@ fn discarding_color_target_resets_texture_init_state_check_visible_on_copy_in_sa
                     },
                 }],
                 depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             copy_texture_to_buffer(&mut encoder, &texture, &readback_buffer);
             ctx.queue.submit([encoder.finish()]);
@@ -97,6 +101,8 @@ fn discarding_depth_target_resets_texture_init_state_check_visible_on_copy_in_sa
                                 store: false, // discard!
                             }),
                         }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
                     });
                     copy_texture_to_buffer(&mut encoder, &texture, &readback_buffer);
                     ctx.queue.submit([encoder.finish()]);
@@ -133,6 +139,8 @@ fn discarding_either_depth_or_stencil_aspect() {
                         store: true,
                     }),
                 }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             ctx.queue.submit([encoder.finish()]);
         }
@@ -154,6 +162,8 @@ fn discarding_either_depth_or_stencil_aspect() {
                         store: false, // discard!
                     }),
                 }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
             });
             ctx.queue.submit([encoder.finish()]);
         }
@@ -231,6 +241,8 @@ fn create_white_texture_and_readback_buffer(
                     store: true,
                 }),
             }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
         });
         ctx.queue.submit([encoder.finish()]);
     } else {